@@ -0,0 +1,88 @@
+use allocator_api2::alloc::Allocator;
+
+use crate::FxBuildHasher;
+
+/// Type alias for a [`hashbrown`] hash map that uses the Fx hashing algorithm and
+/// allocates from `A`.
+pub type FxHashMapIn<K, V, A> = hashbrown::HashMap<K, V, FxBuildHasher, A>;
+
+/// Type alias for a [`hashbrown`] hash set that uses the Fx hashing algorithm and
+/// allocates from `A`.
+pub type FxHashSetIn<T, A> = hashbrown::HashSet<T, FxBuildHasher, A>;
+
+/// Type alias for an [`FxHashMapIn`] that allocates from a [`bumpalo::Bump`] arena.
+///
+/// ```
+/// use bumpalo::Bump;
+/// use rustc_hash::{hash_map_with_capacity_in, FxBumpMap};
+///
+/// let bump = Bump::new();
+/// let mut map: FxBumpMap<'_, u32, u32> = hash_map_with_capacity_in(16, &bump);
+/// map.insert(22, 44);
+/// assert_eq!(map[&22], 44);
+/// ```
+#[cfg(feature = "bumpalo")]
+pub type FxBumpMap<'bump, K, V> = FxHashMapIn<K, V, &'bump bumpalo::Bump>;
+
+/// Type alias for an [`FxHashSetIn`] that allocates from a [`bumpalo::Bump`] arena.
+#[cfg(feature = "bumpalo")]
+pub type FxBumpSet<'bump, T> = FxHashSetIn<T, &'bump bumpalo::Bump>;
+
+/// Creates an empty [`FxHashMapIn`] that allocates from `alloc`.
+pub fn hash_map_new_in<K, V, A: Allocator>(alloc: A) -> FxHashMapIn<K, V, A> {
+    FxHashMapIn::with_hasher_in(FxBuildHasher, alloc)
+}
+
+/// Creates an empty [`FxHashMapIn`] with room for at least `capacity` elements
+/// that allocates from `alloc`.
+pub fn hash_map_with_capacity_in<K, V, A: Allocator>(
+    capacity: usize,
+    alloc: A,
+) -> FxHashMapIn<K, V, A> {
+    FxHashMapIn::with_capacity_and_hasher_in(capacity, FxBuildHasher, alloc)
+}
+
+/// Creates an empty [`FxHashSetIn`] that allocates from `alloc`.
+pub fn hash_set_new_in<T, A: Allocator>(alloc: A) -> FxHashSetIn<T, A> {
+    FxHashSetIn::with_hasher_in(FxBuildHasher, alloc)
+}
+
+/// Creates an empty [`FxHashSetIn`] with room for at least `capacity` elements
+/// that allocates from `alloc`.
+pub fn hash_set_with_capacity_in<T, A: Allocator>(capacity: usize, alloc: A) -> FxHashSetIn<T, A> {
+    FxHashSetIn::with_capacity_and_hasher_in(capacity, FxBuildHasher, alloc)
+}
+
+#[cfg(test)]
+mod tests {
+    use allocator_api2::alloc::Global;
+
+    use crate::{hash_map_new_in, hash_set_with_capacity_in};
+
+    #[test]
+    fn global_allocator() {
+        let mut map = hash_map_new_in(Global);
+        map.insert(1_u32, 2_u32);
+        assert_eq!(map[&1], 2);
+
+        let mut set = hash_set_with_capacity_in(16, Global);
+        assert!(set.capacity() >= 16);
+        assert!(set.insert(1_u32));
+    }
+
+    #[cfg(feature = "bumpalo")]
+    #[test]
+    fn bump_allocator() {
+        use crate::{FxBumpMap, FxBumpSet};
+
+        let bump = bumpalo::Bump::new();
+        let mut map: FxBumpMap<'_, u32, u32> = hash_map_new_in(&bump);
+        map.extend((0..100).map(|i| (i, i)));
+        assert_eq!(map.len(), 100);
+        assert!(bump.allocated_bytes() > 0);
+
+        let mut set: FxBumpSet<'_, u32> = crate::hash_set_new_in(&bump);
+        set.insert(1);
+        assert!(set.contains(&1));
+    }
+}
@@ -0,0 +1,232 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+
+use crate::FxBuildHasher;
+
+/// A fixed-capacity hash map stored entirely inline, using the Fx hashing algorithm.
+///
+/// The map uses open addressing with linear probing over an array of `N`
+/// slots and never allocates, so it is usable without `alloc`. Inserting into
+/// a full map fails instead of growing. Lookups stay fast as long as the map
+/// is not close to full.
+///
+/// ```
+/// use rustc_hash::FxArrayMap;
+///
+/// let mut map = FxArrayMap::<u32, u32, 2>::new();
+/// assert_eq!(map.insert(1, 10), Ok(None));
+/// assert_eq!(map.insert(2, 20), Ok(None));
+/// assert_eq!(map.insert(3, 30), Err((3, 30)));
+/// assert_eq!(map.get(&2), Some(&20));
+/// ```
+#[derive(Clone)]
+pub struct FxArrayMap<K, V, const N: usize> {
+    slots: [Option<(K, V)>; N],
+    len: usize,
+}
+
+impl<K, V, const N: usize> FxArrayMap<K, V, N> {
+    /// Creates an empty `FxArrayMap`.
+    pub fn new() -> FxArrayMap<K, V, N> {
+        FxArrayMap {
+            slots: core::array::from_fn(|_| None),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of elements in the map.
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the map contains no elements.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the maximum number of elements the map can hold.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Removes all elements.
+    pub fn clear(&mut self) {
+        self.slots.iter_mut().for_each(|slot| *slot = None);
+        self.len = 0;
+    }
+
+    /// Returns an iterator over the keys and values, in unspecified order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.slots.iter().flatten().map(|(k, v)| (k, v))
+    }
+
+    /// Returns an iterator over the keys and mutable values, in unspecified order.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut V)> {
+        self.slots.iter_mut().flatten().map(|(k, v)| (&*k, v))
+    }
+}
+
+impl<K: Eq + Hash, V, const N: usize> FxArrayMap<K, V, N> {
+    fn home<Q: Hash + ?Sized>(key: &Q) -> usize {
+        (FxBuildHasher.hash_one(key) % N as u64) as usize
+    }
+
+    fn find<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        if self.len == 0 {
+            return None;
+        }
+
+        let mut i = Self::home(key);
+        for _ in 0..N {
+            match &self.slots[i] {
+                Some((k, _)) if k.borrow() == key => return Some(i),
+                Some(_) => i = (i + 1) % N,
+                None => return None,
+            }
+        }
+        None
+    }
+
+    /// Inserts a key-value pair, returning the previous value of the key.
+    ///
+    /// If the key is not present and the map is full, the pair is returned as the error.
+    pub fn insert(&mut self, key: K, value: V) -> Result<Option<V>, (K, V)> {
+        if N == 0 {
+            return Err((key, value));
+        }
+
+        let mut i = Self::home(&key);
+        for _ in 0..N {
+            match &mut self.slots[i] {
+                Some((k, v)) if *k == key => return Ok(Some(core::mem::replace(v, value))),
+                Some(_) => i = (i + 1) % N,
+                slot @ None => {
+                    *slot = Some((key, value));
+                    self.len += 1;
+                    return Ok(None);
+                }
+            }
+        }
+        Err((key, value))
+    }
+
+    /// Returns a reference to the value of `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let i = self.find(key)?;
+        self.slots[i].as_ref().map(|(_, v)| v)
+    }
+
+    /// Returns a mutable reference to the value of `key`.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let i = self.find(key)?;
+        self.slots[i].as_mut().map(|(_, v)| v)
+    }
+
+    /// Returns `true` if the map contains `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    /// Removes `key` from the map, returning its value.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let mut hole = self.find(key)?;
+        let (_, value) = self.slots[hole].take()?;
+        self.len -= 1;
+
+        // Shift later elements of the probe sequence back into the hole, so
+        // lookups never stop early at an empty slot. An element can move into
+        // the hole if its home slot does not lie cyclically in (hole, j].
+        let mut j = hole;
+        loop {
+            j = (j + 1) % N;
+            let Some((k, _)) = &self.slots[j] else {
+                break;
+            };
+            let home = Self::home(k);
+            let stays = if hole <= j {
+                hole < home && home <= j
+            } else {
+                hole < home || home <= j
+            };
+            if !stays {
+                self.slots[hole] = self.slots[j].take();
+                hole = j;
+            }
+        }
+        Some(value)
+    }
+}
+
+impl<K, V, const N: usize> Default for FxArrayMap<K, V, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, const N: usize> fmt::Debug for FxArrayMap<K, V, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FxArrayMap;
+
+    #[test]
+    fn insert_until_full() {
+        let mut map = FxArrayMap::<u32, u32, 8>::new();
+        for i in 0..8 {
+            assert_eq!(map.insert(i, i), Ok(None));
+        }
+        assert_eq!(map.insert(8, 8), Err((8, 8)));
+        assert_eq!(map.insert(3, 30), Ok(Some(3)));
+        assert_eq!(map.len(), 8);
+
+        let mut empty = FxArrayMap::<u32, u32, 0>::new();
+        assert_eq!(empty.insert(1, 1), Err((1, 1)));
+        assert_eq!(empty.get(&1), None);
+    }
+
+    #[test]
+    fn remove_keeps_probe_sequences_intact() {
+        // Use many more keys than slots over time to exercise wrap-around.
+        let mut map = FxArrayMap::<u32, u32, 7>::new();
+        for round in 0..50_u32 {
+            for i in 0..6 {
+                map.insert(round * 6 + i, i).unwrap();
+            }
+            for i in (0..6).step_by(2) {
+                assert_eq!(map.remove(&(round * 6 + i)), Some(i));
+            }
+            for i in 0..6 {
+                assert_eq!(map.contains_key(&(round * 6 + i)), i % 2 == 1);
+            }
+            for i in (1..6).step_by(2) {
+                assert_eq!(map.remove(&(round * 6 + i)), Some(i));
+            }
+            assert!(map.is_empty());
+        }
+    }
+}
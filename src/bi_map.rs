@@ -0,0 +1,233 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::Hash;
+
+use crate::FxTable;
+
+/// A bidirectional one-to-one map between left and right values, using the Fx
+/// hashing algorithm.
+///
+/// Each left value is associated with exactly one right value and vice versa.
+/// Both sides are stored in their own table, so values are cloned once on
+/// insertion.
+///
+/// ```
+/// use rustc_hash::FxBiMap;
+///
+/// let mut map = FxBiMap::default();
+/// map.insert("x", 0);
+/// map.insert("y", 1);
+/// assert_eq!(map.get_by_left("y"), Some(&1));
+/// assert_eq!(map.get_by_right(&0), Some(&"x"));
+/// ```
+#[derive(Clone)]
+pub struct FxBiMap<L, R> {
+    left: FxTable<L, R>,
+    right: FxTable<R, L>,
+}
+
+impl<L, R> FxBiMap<L, R> {
+    /// Creates an empty `FxBiMap`.
+    pub fn new() -> FxBiMap<L, R> {
+        Self::with_capacity(0)
+    }
+
+    /// Creates an empty `FxBiMap` with room for at least `capacity` pairs.
+    pub fn with_capacity(capacity: usize) -> FxBiMap<L, R> {
+        FxBiMap {
+            left: FxTable::with_capacity_and_hasher(capacity, Default::default()),
+            right: FxTable::with_capacity_and_hasher(capacity, Default::default()),
+        }
+    }
+
+    /// Returns the number of pairs.
+    pub fn len(&self) -> usize {
+        self.left.len()
+    }
+
+    /// Returns `true` if the map contains no pairs.
+    pub fn is_empty(&self) -> bool {
+        self.left.is_empty()
+    }
+
+    /// Removes all pairs.
+    pub fn clear(&mut self) {
+        self.left.clear();
+        self.right.clear();
+    }
+
+    /// Returns an iterator over all pairs.
+    pub fn iter(&self) -> impl Iterator<Item = (&L, &R)> {
+        self.left.iter()
+    }
+}
+
+impl<L: Eq + Hash + Clone, R: Eq + Hash + Clone> FxBiMap<L, R> {
+    /// Inserts the pair `(left, right)`, removing any pairs that contain either value.
+    ///
+    /// Returns the pairs that were removed, see [`BiMapOverwritten`].
+    pub fn insert(&mut self, left: L, right: R) -> BiMapOverwritten<L, R> {
+        let overwritten = match self.remove_by_left(&left) {
+            Some((l, r)) if r == right => BiMapOverwritten::Pair(l, r),
+            Some(by_left) => match self.remove_by_right(&right) {
+                Some(by_right) => BiMapOverwritten::Both(by_left, by_right),
+                None => BiMapOverwritten::Left(by_left.0, by_left.1),
+            },
+            None => match self.remove_by_right(&right) {
+                Some((l, r)) => BiMapOverwritten::Right(l, r),
+                None => BiMapOverwritten::Neither,
+            },
+        };
+        self.left.insert(left.clone(), right.clone());
+        self.right.insert(right, left);
+        overwritten
+    }
+
+    /// Inserts the pair `(left, right)` unless either value is already present,
+    /// in which case the pair is returned.
+    pub fn insert_no_overwrite(&mut self, left: L, right: R) -> Result<(), (L, R)> {
+        if self.left.contains_key(&left) || self.right.contains_key(&right) {
+            return Err((left, right));
+        }
+        self.left.insert(left.clone(), right.clone());
+        self.right.insert(right, left);
+        Ok(())
+    }
+
+    /// Returns the right value associated with `left`.
+    pub fn get_by_left<Q>(&self, left: &Q) -> Option<&R>
+    where
+        L: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.left.get(left)
+    }
+
+    /// Returns the left value associated with `right`.
+    pub fn get_by_right<Q>(&self, right: &Q) -> Option<&L>
+    where
+        R: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.right.get(right)
+    }
+
+    /// Returns `true` if `left` is present.
+    pub fn contains_left<Q>(&self, left: &Q) -> bool
+    where
+        L: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.left.contains_key(left)
+    }
+
+    /// Returns `true` if `right` is present.
+    pub fn contains_right<Q>(&self, right: &Q) -> bool
+    where
+        R: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.right.contains_key(right)
+    }
+
+    /// Removes and returns the pair containing `left`.
+    pub fn remove_by_left<Q>(&mut self, left: &Q) -> Option<(L, R)>
+    where
+        L: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let (l, r) = self.left.remove_entry(left)?;
+        self.right.remove(&r);
+        Some((l, r))
+    }
+
+    /// Removes and returns the pair containing `right`.
+    pub fn remove_by_right<Q>(&mut self, right: &Q) -> Option<(L, R)>
+    where
+        R: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let (r, l) = self.right.remove_entry(right)?;
+        self.left.remove(&l);
+        Some((l, r))
+    }
+}
+
+/// The pairs removed by [`FxBiMap::insert`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BiMapOverwritten<L, R> {
+    /// Neither value was present.
+    Neither,
+    /// The left value was present in this pair, the right value was not present.
+    Left(L, R),
+    /// The right value was present in this pair, the left value was not present.
+    Right(L, R),
+    /// The inserted pair itself was already present.
+    Pair(L, R),
+    /// The left value was present in the first pair and the right value in the second.
+    Both((L, R), (L, R)),
+}
+
+impl<L, R> Default for FxBiMap<L, R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<L: fmt::Debug, R: fmt::Debug> fmt::Debug for FxBiMap<L, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<L: Eq + Hash + Clone, R: Eq + Hash + Clone> Extend<(L, R)> for FxBiMap<L, R> {
+    fn extend<I: IntoIterator<Item = (L, R)>>(&mut self, iter: I) {
+        for (l, r) in iter {
+            self.insert(l, r);
+        }
+    }
+}
+
+impl<L: Eq + Hash + Clone, R: Eq + Hash + Clone> FromIterator<(L, R)> for FxBiMap<L, R> {
+    fn from_iter<I: IntoIterator<Item = (L, R)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BiMapOverwritten, FxBiMap};
+
+    #[test]
+    fn overwrite_semantics() {
+        let mut map = FxBiMap::new();
+        assert_eq!(map.insert(1_u32, 'a'), BiMapOverwritten::Neither);
+        assert_eq!(map.insert(2, 'b'), BiMapOverwritten::Neither);
+        assert_eq!(map.insert(1, 'a'), BiMapOverwritten::Pair(1, 'a'));
+        assert_eq!(map.insert(2, 'c'), BiMapOverwritten::Left(2, 'b'));
+        assert_eq!(map.insert(2, 'b'), BiMapOverwritten::Left(2, 'c'));
+        assert_eq!(map.insert(3, 'b'), BiMapOverwritten::Right(2, 'b'));
+        assert_eq!(map.insert(2, 'b'), BiMapOverwritten::Right(3, 'b'));
+
+        // Overlaps with both existing pairs.
+        assert_eq!(
+            map.insert(1, 'b'),
+            BiMapOverwritten::Both((1, 'a'), (2, 'b'))
+        );
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get_by_left(&1), Some(&'b'));
+        assert_eq!(map.get_by_right(&'b'), Some(&1));
+        assert!(!map.contains_right(&'a'));
+        assert!(!map.contains_left(&2));
+
+        assert_eq!(map.insert_no_overwrite(3, 'b'), Err((3, 'b')));
+        assert_eq!(map.insert_no_overwrite(3, 'c'), Ok(()));
+
+        assert_eq!(map.remove_by_right(&'c'), Some((3, 'c')));
+        assert_eq!(map.get_by_left(&3), None);
+        assert_eq!(map.remove_by_left(&1), Some((1, 'b')));
+        assert!(map.is_empty());
+    }
+}
@@ -0,0 +1,309 @@
+use core::hash::{BuildHasher, Hash};
+
+use bincode::de::{BorrowDecoder, Decoder};
+use bincode::enc::Encoder;
+use bincode::error::{DecodeError, EncodeError};
+use bincode::{BorrowDecode, Decode, Encode};
+
+use crate::presized::prealloc;
+use crate::{FxPresized, FxSeededState, FxWithSeed};
+
+// bincode's own impls encode `FxHashMap` and `FxHashSet` as their length
+// followed by their elements, and reserve room for all of them when decoding.
+// The impls for `FxPresized` encode the same way, so both can decode what the
+// other encoded, and also cover the `hashbrown` collections, which bincode
+// has no impls for. `FxWithSeed` encodes the seed before the same format, so
+// that maps with an `FxSeededState` can be decoded as well.
+
+impl Encode for FxSeededState {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        // The seed is stored as a `u64` so that it decodes on any platform.
+        (self.seed() as u64).encode(encoder)
+    }
+}
+
+impl<Context> Decode<Context> for FxSeededState {
+    fn decode<D: Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, DecodeError> {
+        let seed = u64::decode(decoder)?;
+        let seed = usize::try_from(seed).map_err(|_| DecodeError::OutsideUsizeRange(seed))?;
+        Ok(FxSeededState::with_seed(seed))
+    }
+}
+
+bincode::impl_borrow_decode!(FxSeededState);
+
+/// Encodes the length of a collection the way bincode does.
+fn encode_len<E: Encoder>(len: usize, encoder: &mut E) -> Result<(), EncodeError> {
+    (len as u64).encode(encoder)
+}
+
+/// Decodes the length of a collection the way bincode does.
+fn decode_len<D: Decoder>(decoder: &mut D) -> Result<usize, DecodeError> {
+    let len = u64::decode(decoder)?;
+    usize::try_from(len).map_err(|_| DecodeError::OutsideUsizeRange(len))
+}
+
+/// Decodes the entries of a map of type `$map<K, V, _>` with the given hasher,
+/// decoding every key and value with `$decode`.
+macro_rules! decode_map {
+    ($map:ident, $decoder:ident, $hasher:expr, $decode:path) => {{
+        let len = decode_len($decoder)?;
+        $decoder.claim_container_read::<(K, V)>(len)?;
+        let capacity = prealloc::<(K, V)>(Some(len));
+        let mut map = $map::with_capacity_and_hasher(capacity, $hasher);
+        for _ in 0..len {
+            // bincode claims the bytes of every entry twice otherwise.
+            $decoder.unclaim_bytes_read(core::mem::size_of::<(K, V)>());
+            let k = $decode($decoder)?;
+            let v = $decode($decoder)?;
+            map.insert(k, v);
+        }
+        map
+    }};
+}
+
+/// Decodes the elements of a set of type `$set<T, _>` with the given hasher,
+/// decoding every element with `$decode`.
+macro_rules! decode_set {
+    ($set:ident, $decoder:ident, $hasher:expr, $decode:path) => {{
+        let len = decode_len($decoder)?;
+        $decoder.claim_container_read::<T>(len)?;
+        let capacity = prealloc::<T>(Some(len));
+        let mut set = $set::with_capacity_and_hasher(capacity, $hasher);
+        for _ in 0..len {
+            $decoder.unclaim_bytes_read(core::mem::size_of::<T>());
+            set.insert($decode($decoder)?);
+        }
+        set
+    }};
+}
+
+macro_rules! impl_bincode {
+    ($map:ident, $set:ident) => {
+        fn encode_map<K: Encode, V: Encode, S, E: Encoder>(
+            map: &$map<K, V, S>,
+            encoder: &mut E,
+        ) -> Result<(), EncodeError> {
+            encode_len(map.len(), encoder)?;
+            map.iter().try_for_each(|(k, v)| {
+                k.encode(encoder)?;
+                v.encode(encoder)
+            })
+        }
+
+        fn encode_set<T: Encode, S, E: Encoder>(
+            set: &$set<T, S>,
+            encoder: &mut E,
+        ) -> Result<(), EncodeError> {
+            encode_len(set.len(), encoder)?;
+            set.iter().try_for_each(|t| t.encode(encoder))
+        }
+
+        impl<K: Encode, V: Encode, S> Encode for FxPresized<$map<K, V, S>> {
+            fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+                encode_map(&self.0, encoder)
+            }
+        }
+
+        impl<Context, K, V, S> Decode<Context> for FxPresized<$map<K, V, S>>
+        where
+            K: Decode<Context> + Eq + Hash,
+            V: Decode<Context>,
+            S: BuildHasher + Default,
+        {
+            fn decode<D: Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, DecodeError> {
+                Ok(FxPresized(decode_map!(
+                    $map,
+                    decoder,
+                    S::default(),
+                    Decode::decode
+                )))
+            }
+        }
+
+        impl<'de, Context, K, V, S> BorrowDecode<'de, Context> for FxPresized<$map<K, V, S>>
+        where
+            K: BorrowDecode<'de, Context> + Eq + Hash,
+            V: BorrowDecode<'de, Context>,
+            S: BuildHasher + Default,
+        {
+            fn borrow_decode<D: BorrowDecoder<'de, Context = Context>>(
+                decoder: &mut D,
+            ) -> Result<Self, DecodeError> {
+                let map = decode_map!($map, decoder, S::default(), BorrowDecode::borrow_decode);
+                Ok(FxPresized(map))
+            }
+        }
+
+        impl<T: Encode, S> Encode for FxPresized<$set<T, S>> {
+            fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+                encode_set(&self.0, encoder)
+            }
+        }
+
+        impl<Context, T, S> Decode<Context> for FxPresized<$set<T, S>>
+        where
+            T: Decode<Context> + Eq + Hash,
+            S: BuildHasher + Default,
+        {
+            fn decode<D: Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, DecodeError> {
+                Ok(FxPresized(decode_set!(
+                    $set,
+                    decoder,
+                    S::default(),
+                    Decode::decode
+                )))
+            }
+        }
+
+        impl<'de, Context, T, S> BorrowDecode<'de, Context> for FxPresized<$set<T, S>>
+        where
+            T: BorrowDecode<'de, Context> + Eq + Hash,
+            S: BuildHasher + Default,
+        {
+            fn borrow_decode<D: BorrowDecoder<'de, Context = Context>>(
+                decoder: &mut D,
+            ) -> Result<Self, DecodeError> {
+                let set = decode_set!($set, decoder, S::default(), BorrowDecode::borrow_decode);
+                Ok(FxPresized(set))
+            }
+        }
+
+        impl<K: Encode, V: Encode> Encode for FxWithSeed<&$map<K, V, FxSeededState>> {
+            fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+                self.0.hasher().encode(encoder)?;
+                encode_map(self.0, encoder)
+            }
+        }
+
+        impl<K: Encode, V: Encode> Encode for FxWithSeed<$map<K, V, FxSeededState>> {
+            fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+                FxWithSeed(&self.0).encode(encoder)
+            }
+        }
+
+        impl<Context, K, V> Decode<Context> for FxWithSeed<$map<K, V, FxSeededState>>
+        where
+            K: Decode<Context> + Eq + Hash,
+            V: Decode<Context>,
+        {
+            fn decode<D: Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, DecodeError> {
+                let state = FxSeededState::decode(decoder)?;
+                Ok(FxWithSeed(decode_map!(
+                    $map,
+                    decoder,
+                    state,
+                    Decode::decode
+                )))
+            }
+        }
+
+        impl<'de, Context, K, V> BorrowDecode<'de, Context>
+            for FxWithSeed<$map<K, V, FxSeededState>>
+        where
+            K: BorrowDecode<'de, Context> + Eq + Hash,
+            V: BorrowDecode<'de, Context>,
+        {
+            fn borrow_decode<D: BorrowDecoder<'de, Context = Context>>(
+                decoder: &mut D,
+            ) -> Result<Self, DecodeError> {
+                let state = FxSeededState::decode(decoder)?;
+                let map = decode_map!($map, decoder, state, BorrowDecode::borrow_decode);
+                Ok(FxWithSeed(map))
+            }
+        }
+
+        impl<T: Encode> Encode for FxWithSeed<&$set<T, FxSeededState>> {
+            fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+                self.0.hasher().encode(encoder)?;
+                encode_set(self.0, encoder)
+            }
+        }
+
+        impl<T: Encode> Encode for FxWithSeed<$set<T, FxSeededState>> {
+            fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+                FxWithSeed(&self.0).encode(encoder)
+            }
+        }
+
+        impl<Context, T> Decode<Context> for FxWithSeed<$set<T, FxSeededState>>
+        where
+            T: Decode<Context> + Eq + Hash,
+        {
+            fn decode<D: Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, DecodeError> {
+                let state = FxSeededState::decode(decoder)?;
+                Ok(FxWithSeed(decode_set!(
+                    $set,
+                    decoder,
+                    state,
+                    Decode::decode
+                )))
+            }
+        }
+
+        impl<'de, Context, T> BorrowDecode<'de, Context> for FxWithSeed<$set<T, FxSeededState>>
+        where
+            T: BorrowDecode<'de, Context> + Eq + Hash,
+        {
+            fn borrow_decode<D: BorrowDecoder<'de, Context = Context>>(
+                decoder: &mut D,
+            ) -> Result<Self, DecodeError> {
+                let state = FxSeededState::decode(decoder)?;
+                let set = decode_set!($set, decoder, state, BorrowDecode::borrow_decode);
+                Ok(FxWithSeed(set))
+            }
+        }
+    };
+}
+
+#[cfg(feature = "std")]
+mod std_impls {
+    use std::collections::{HashMap, HashSet};
+
+    use super::*;
+
+    impl_bincode!(HashMap, HashSet);
+}
+
+#[cfg(feature = "hashbrown")]
+mod hashbrown_impls {
+    use hashbrown::{HashMap, HashSet};
+
+    use super::*;
+
+    impl_bincode!(HashMap, HashSet);
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::collections::HashSet;
+    use std::string::{String, ToString};
+
+    use crate::{FxHashMap, FxPresized, FxSeededState, FxWithSeed};
+
+    #[test]
+    fn round_trip() {
+        let config = bincode::config::standard();
+
+        let map: FxHashMap<u32, String> = (0..1000).map(|i| (i, i.to_string())).collect();
+        let bytes = bincode::encode_to_vec(FxPresized(map.clone()), config).unwrap();
+        assert_eq!(bytes, bincode::encode_to_vec(&map, config).unwrap());
+        let (back, _): (FxPresized<FxHashMap<u32, String>>, _) =
+            bincode::decode_from_slice(&bytes, config).unwrap();
+        assert_eq!(back.0, map);
+
+        let names: FxHashMap<&str, u32> = map.iter().map(|(&i, s)| (s.as_str(), i)).collect();
+        let bytes = bincode::encode_to_vec(&names, config).unwrap();
+        let (back, _): (FxPresized<FxHashMap<&str, u32>>, _) =
+            bincode::borrow_decode_from_slice(&bytes, config).unwrap();
+        assert_eq!(back.0, names);
+
+        let mut set = HashSet::with_hasher(FxSeededState::with_seed(7));
+        set.extend(0..1000_u64);
+        let bytes = bincode::encode_to_vec(FxWithSeed(&set), config).unwrap();
+        let (FxWithSeed(back), _): (FxWithSeed<HashSet<u64, FxSeededState>>, _) =
+            bincode::decode_from_slice(&bytes, config).unwrap();
+        assert_eq!(back.hasher().seed(), 7);
+        assert_eq!(back, set);
+    }
+}
@@ -0,0 +1,296 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::Hash;
+use core::marker::PhantomData;
+
+use crate::sketch::{self, SketchMismatch, DEFAULT_FALSE_POSITIVE_RATE, DEFAULT_SEEDS};
+
+/// A Bloom filter using the Fx hashing algorithm.
+///
+/// A Bloom filter records a set of items in a fixed array of bits. Looking up
+/// an item that was inserted always succeeds, while an item that was not can
+/// be reported as present with a small probability, the false positive rate.
+/// The `k` bit positions of an item are derived by double hashing from two Fx
+/// hashes with different seeds, so inserting and querying hash the item only
+/// twice.
+///
+/// Filters with the same number of bits, number of hashes and seeds can be
+/// combined with [`union`](Self::union) and [`intersect`](Self::intersect).
+/// Filters created without explicit seeds all use the same seeds, which makes
+/// them combinable across processes.
+///
+/// A typical use is negative caching in front of a large map, where most
+/// lookups of missing keys are answered without touching the map:
+///
+/// ```
+/// use rustc_hash::{FxBloomFilter, FxHashMap};
+///
+/// let map: FxHashMap<u64, u64> = (0..10_000).map(|i| (i * 2, i)).collect();
+/// let filter: FxBloomFilter<u64> = map.keys().copied().collect();
+///
+/// let lookup = |key: u64| filter.contains(&key).then(|| map.get(&key)).flatten();
+/// assert_eq!(lookup(42), Some(&21));
+/// assert_eq!(lookup(43), None);
+/// ```
+pub struct FxBloomFilter<T: ?Sized> {
+    pub(crate) words: Vec<u64>,
+    pub(crate) num_bits: u64,
+    pub(crate) num_hashes: u32,
+    pub(crate) seeds: (usize, usize),
+    pub(crate) marker: PhantomData<fn(&T)>,
+}
+
+impl<T: ?Sized> FxBloomFilter<T> {
+    /// Creates an empty filter with `num_bits` bits and `num_hashes` hashes
+    /// per item.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_bits` or `num_hashes` is 0.
+    pub fn new(num_bits: u64, num_hashes: u32) -> FxBloomFilter<T> {
+        Self::with_seeds(num_bits, num_hashes, DEFAULT_SEEDS)
+    }
+
+    /// Creates an empty filter like [`new`](Self::new), deriving the hashes
+    /// from Fx hashes seeded with `seeds`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_bits` or `num_hashes` is 0, or if the seeds are equal.
+    pub fn with_seeds(num_bits: u64, num_hashes: u32, seeds: (usize, usize)) -> FxBloomFilter<T> {
+        assert!(num_bits > 0, "a Bloom filter needs at least one bit");
+        assert!(num_hashes > 0, "a Bloom filter needs at least one hash");
+        assert_ne!(seeds.0, seeds.1, "the seeds must be different");
+        let words = usize::try_from(num_bits.div_ceil(64)).expect("too many bits");
+        FxBloomFilter {
+            words: vec![0; words],
+            num_bits,
+            num_hashes,
+            seeds,
+            marker: PhantomData,
+        }
+    }
+
+    /// Creates an empty filter sized to hold `expected_items` items with a
+    /// false positive rate of at most `rate`.
+    ///
+    /// It uses the optimal `-log2(rate)` hashes and `-log2(rate) / ln(2)` bits
+    /// per item, about 9.6 bits for a rate of 1%.
+    ///
+    /// ```
+    /// use rustc_hash::FxBloomFilter;
+    ///
+    /// let filter = FxBloomFilter::<str>::with_false_positive_rate(1000, 0.01);
+    /// assert_eq!(filter.num_hashes(), 7);
+    /// assert_eq!(filter.num_bits(), 9586);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rate` is not between 0 and 1, exclusive.
+    pub fn with_false_positive_rate(expected_items: usize, rate: f64) -> FxBloomFilter<T> {
+        let (num_bits, num_hashes) = sketch::bloom_parameters(expected_items, rate);
+        Self::new(num_bits, num_hashes)
+    }
+
+    /// Returns the number of bits.
+    pub fn num_bits(&self) -> u64 {
+        self.num_bits
+    }
+
+    /// Returns the number of hashes per item.
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    /// Returns the seeds of the two hash functions.
+    pub fn seeds(&self) -> (usize, usize) {
+        self.seeds
+    }
+
+    /// Returns the number of bits that are set.
+    pub fn count_ones(&self) -> u64 {
+        self.words.iter().map(|w| u64::from(w.count_ones())).sum()
+    }
+
+    /// Returns `true` if no item was inserted.
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&w| w == 0)
+    }
+
+    /// Removes all items, keeping the parameters.
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    /// Inserts an item, returning `false` if it was possibly present already.
+    pub fn insert(&mut self, item: &T) -> bool
+    where
+        T: Hash,
+    {
+        self.insert_hashes(sketch::hash_pair(self.seeds, item))
+    }
+
+    /// Returns `false` if the item is definitely not in the filter and `true`
+    /// if it probably is.
+    pub fn contains(&self, item: &T) -> bool
+    where
+        T: Hash,
+    {
+        let hashes = sketch::hash_pair(self.seeds, item);
+        (0..self.num_hashes).all(|i| {
+            let (word, mask) = self.bit(sketch::nth_hash(hashes, i));
+            self.words[word] & mask != 0
+        })
+    }
+
+    /// Returns `true` if `other` has the same parameters, so that the filters
+    /// can be combined.
+    pub fn is_compatible(&self, other: &FxBloomFilter<T>) -> bool {
+        self.num_bits == other.num_bits
+            && self.num_hashes == other.num_hashes
+            && self.seeds == other.seeds
+    }
+
+    /// Adds the items of `other` to this filter.
+    ///
+    /// The result is the filter that inserting the items of both filters
+    /// would have produced.
+    pub fn union(&mut self, other: &FxBloomFilter<T>) -> Result<(), SketchMismatch> {
+        self.combine(other, |a, b| a | b)
+    }
+
+    /// Keeps only the bits that are also set in `other`.
+    ///
+    /// Items inserted into both filters are still found, while the false
+    /// positive rate is at most that of the filter holding the items of both.
+    pub fn intersect(&mut self, other: &FxBloomFilter<T>) -> Result<(), SketchMismatch> {
+        self.combine(other, |a, b| a & b)
+    }
+
+    fn combine(
+        &mut self,
+        other: &FxBloomFilter<T>,
+        op: impl Fn(u64, u64) -> u64,
+    ) -> Result<(), SketchMismatch> {
+        if !self.is_compatible(other) {
+            return Err(SketchMismatch);
+        }
+        for (a, &b) in self.words.iter_mut().zip(&other.words) {
+            *a = op(*a, b);
+        }
+        Ok(())
+    }
+
+    fn insert_hashes(&mut self, hashes: (u64, u64)) -> bool {
+        let mut new = false;
+        for i in 0..self.num_hashes {
+            let (word, mask) = self.bit(sketch::nth_hash(hashes, i));
+            new |= self.words[word] & mask == 0;
+            self.words[word] |= mask;
+        }
+        new
+    }
+
+    /// Returns the word index and mask of the bit selected by `hash`.
+    fn bit(&self, hash: u64) -> (usize, u64) {
+        let bit = hash % self.num_bits;
+        ((bit / 64) as usize, 1 << (bit % 64))
+    }
+}
+
+impl<T: Hash> FromIterator<T> for FxBloomFilter<T> {
+    /// Collects the items into a filter sized for their number and a false
+    /// positive rate of 1%.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> FxBloomFilter<T> {
+        let hashes: Vec<_> = iter
+            .into_iter()
+            .map(|item| sketch::hash_pair(DEFAULT_SEEDS, &item))
+            .collect();
+        let mut filter = Self::with_false_positive_rate(hashes.len(), DEFAULT_FALSE_POSITIVE_RATE);
+        for h in hashes {
+            filter.insert_hashes(h);
+        }
+        filter
+    }
+}
+
+impl<T: Hash> Extend<T> for FxBloomFilter<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.insert(&item);
+        }
+    }
+}
+
+impl<T: ?Sized> Clone for FxBloomFilter<T> {
+    fn clone(&self) -> Self {
+        FxBloomFilter {
+            words: self.words.clone(),
+            num_bits: self.num_bits,
+            num_hashes: self.num_hashes,
+            seeds: self.seeds,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> PartialEq for FxBloomFilter<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.is_compatible(other) && self.words == other.words
+    }
+}
+
+impl<T: ?Sized> Eq for FxBloomFilter<T> {}
+
+impl<T: ?Sized> fmt::Debug for FxBloomFilter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FxBloomFilter")
+            .field("num_bits", &self.num_bits)
+            .field("num_hashes", &self.num_hashes)
+            .field("count_ones", &self.count_ones())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FxBloomFilter, SketchMismatch};
+
+    #[test]
+    fn no_false_negatives() {
+        let mut filter = FxBloomFilter::with_false_positive_rate(10_000, 0.01);
+        for i in 0..10_000_u32 {
+            filter.insert(&i);
+        }
+        assert!((0..10_000).all(|i| filter.contains(&i)));
+
+        let false_positives = (10_000..110_000).filter(|i| filter.contains(i)).count();
+        assert!(false_positives < 1_500, "{false_positives} false positives");
+    }
+
+    #[test]
+    fn union_and_intersect() {
+        let mut a: FxBloomFilter<str> = FxBloomFilter::new(1 << 12, 4);
+        let mut b = a.clone();
+        assert!(a.insert("a"));
+        assert!(!a.insert("a"));
+        a.insert("both");
+        b.insert("b");
+        b.insert("both");
+
+        let mut union = a.clone();
+        union.union(&b).unwrap();
+        assert!(["a", "b", "both"].iter().all(|s| union.contains(s)));
+
+        a.intersect(&b).unwrap();
+        assert!(a.contains("both"));
+        assert!(!a.contains("a") && !a.contains("b"));
+
+        let other = FxBloomFilter::with_seeds(1 << 12, 4, (1, 2));
+        assert_eq!(a.union(&other), Err(SketchMismatch));
+        a.clear();
+        assert!(a.is_empty());
+    }
+}
@@ -0,0 +1,147 @@
+use borsh::io::{Error, ErrorKind, Read, Result, Write};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::FxSeededState;
+
+// `FxHashMap`, `FxHashSet` and their `hashbrown` counterparts are covered by
+// the impls borsh provides for all hash maps and sets, which write the
+// entries sorted by key. The collections below are written the same way: a
+// `u32` length followed by the entries in ascending order, so equal
+// collections always serialize to the same bytes whatever their hash order.
+
+impl BorshSerialize for FxSeededState {
+    fn serialize<W: Write>(&self, writer: &mut W) -> Result<()> {
+        // The seed is stored as a `u64` so that it reads back on any platform.
+        (self.seed() as u64).serialize(writer)
+    }
+}
+
+impl BorshDeserialize for FxSeededState {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> Result<Self> {
+        let seed = u64::deserialize_reader(reader)?;
+        let seed = usize::try_from(seed)
+            .map_err(|_| Error::new(ErrorKind::InvalidData, "the seed does not fit usize"))?;
+        Ok(FxSeededState::with_seed(seed))
+    }
+}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod collections {
+    use alloc::vec::Vec;
+    use core::hash::Hash;
+
+    use super::*;
+    use crate::{CounterOverflow, FrozenFxMap, FrozenFxSet, FxCounter};
+
+    impl<K: BorshSerialize + Ord, V: BorshSerialize> BorshSerialize for FrozenFxMap<K, V> {
+        fn serialize<W: Write>(&self, writer: &mut W) -> Result<()> {
+            let mut entries: Vec<(&K, &V)> = self.iter().collect();
+            entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+            entries.serialize(writer)
+        }
+    }
+
+    impl<K, V> BorshDeserialize for FrozenFxMap<K, V>
+    where
+        K: BorshDeserialize + Eq + Hash,
+        V: BorshDeserialize,
+    {
+        fn deserialize_reader<R: Read>(reader: &mut R) -> Result<Self> {
+            let entries = Vec::<(K, V)>::deserialize_reader(reader)?;
+            FrozenFxMap::try_from_iter(entries)
+                .map_err(|_| Error::new(ErrorKind::InvalidData, "no index found for the keys"))
+        }
+    }
+
+    impl<T: BorshSerialize + Ord> BorshSerialize for FrozenFxSet<T> {
+        fn serialize<W: Write>(&self, writer: &mut W) -> Result<()> {
+            let mut values: Vec<&T> = self.iter().collect();
+            values.sort_unstable();
+            values.serialize(writer)
+        }
+    }
+
+    impl<T: BorshDeserialize + Eq + Hash> BorshDeserialize for FrozenFxSet<T> {
+        fn deserialize_reader<R: Read>(reader: &mut R) -> Result<Self> {
+            let values = Vec::<T>::deserialize_reader(reader)?;
+            FrozenFxSet::try_from_iter(values)
+                .map_err(|_| Error::new(ErrorKind::InvalidData, "no index found for the keys"))
+        }
+    }
+
+    impl<T: BorshSerialize + Ord> BorshSerialize for FxCounter<T> {
+        fn serialize<W: Write>(&self, writer: &mut W) -> Result<()> {
+            let policy: u8 = match self.overflow() {
+                CounterOverflow::Saturate => 0,
+                CounterOverflow::Wrap => 1,
+                CounterOverflow::Panic => 2,
+            };
+            policy.serialize(writer)?;
+            let mut counts: Vec<(&T, u64)> = self.iter().collect();
+            counts.sort_unstable_by(|a, b| a.0.cmp(b.0));
+            counts.serialize(writer)
+        }
+    }
+
+    impl<T: BorshDeserialize + Eq + Hash> BorshDeserialize for FxCounter<T> {
+        fn deserialize_reader<R: Read>(reader: &mut R) -> Result<Self> {
+            let overflow = match u8::deserialize_reader(reader)? {
+                0 => CounterOverflow::Saturate,
+                1 => CounterOverflow::Wrap,
+                2 => CounterOverflow::Panic,
+                _ => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        "unknown counter overflow policy",
+                    ))
+                }
+            };
+            let mut counter = FxCounter::with_overflow(overflow);
+            for (item, count) in Vec::<(T, u64)>::deserialize_reader(reader)? {
+                counter.add_n(item, count);
+            }
+            Ok(counter)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::string::{String, ToString};
+    use std::vec::Vec;
+
+    use crate::{CounterOverflow, FrozenFxMap, FrozenFxSet, FxCounter, FxSeededState};
+
+    #[test]
+    fn round_trip() {
+        let state: FxSeededState =
+            borsh::from_slice(&borsh::to_vec(&FxSeededState::with_seed(7)).unwrap()).unwrap();
+        assert_eq!(state.seed(), 7);
+
+        let map: FrozenFxMap<u32, String> = (0..100).map(|i| (i, i.to_string())).collect();
+        let bytes = borsh::to_vec(&map).unwrap();
+        let back: FrozenFxMap<u32, String> = borsh::from_slice(&bytes).unwrap();
+        assert!((0..100).all(|i| back.get(&i) == Some(&i.to_string())));
+        // The entries are written in key order, whatever the order of the map.
+        let reversed: FrozenFxMap<u32, String> =
+            (0..100).rev().map(|i| (i, i.to_string())).collect();
+        assert_eq!(borsh::to_vec(&reversed).unwrap(), bytes);
+
+        let set: FrozenFxSet<u8> = [3, 1, 2].into_iter().collect();
+        assert_eq!(borsh::to_vec(&set).unwrap(), [3, 0, 0, 0, 1, 2, 3]);
+        let back: FrozenFxSet<u8> = borsh::from_slice(&[2, 0, 0, 0, 9, 4]).unwrap();
+        assert!(back.contains(&9) && back.contains(&4));
+
+        let mut counter = FxCounter::with_overflow(CounterOverflow::Wrap);
+        counter.extend("abracadabra".chars().map(|c| c as u8));
+        let back: FxCounter<u8> = borsh::from_slice(&borsh::to_vec(&counter).unwrap()).unwrap();
+        assert_eq!(back.overflow(), CounterOverflow::Wrap);
+        let mut counts: Vec<(u8, u64)> = back.iter().map(|(&c, n)| (c, n)).collect();
+        counts.sort_unstable();
+        assert_eq!(
+            counts,
+            [(b'a', 5), (b'b', 2), (b'c', 1), (b'd', 1), (b'r', 2)]
+        );
+        assert!(borsh::from_slice::<FxCounter<u8>>(&[3, 0, 0, 0, 0]).is_err());
+    }
+}
@@ -0,0 +1,171 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+
+use crate::FxBuildHasher;
+
+/// A lossy, fixed-size cache stored entirely inline, using the Fx hashing algorithm.
+///
+/// Every key has exactly one slot, chosen as its hash modulo `N`, and inserting
+/// a key overwrites whatever entry occupied that slot before. This makes the
+/// cache a good fit for memoization where recomputing an evicted value is
+/// cheap. It never allocates, so it is usable without `alloc`.
+///
+/// ```
+/// use rustc_hash::FxCache;
+///
+/// let mut squares = FxCache::<u64, u64, 64>::new();
+/// assert_eq!(*squares.get_or_insert_with(12, || 12 * 12), 144);
+/// assert_eq!(squares.get(&12), Some(&144));
+/// ```
+#[derive(Clone)]
+pub struct FxCache<K, V, const N: usize> {
+    slots: [Option<(K, V)>; N],
+}
+
+impl<K, V, const N: usize> FxCache<K, V, N> {
+    /// Creates an empty `FxCache`.
+    ///
+    /// Using a cache with zero slots is a compile-time error.
+    pub fn new() -> FxCache<K, V, N> {
+        const { assert!(N > 0, "an FxCache needs at least one slot") };
+        FxCache {
+            slots: core::array::from_fn(|_| None),
+        }
+    }
+
+    /// Returns the number of slots of the cache.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the number of occupied slots.
+    pub fn len(&self) -> usize {
+        self.slots.iter().filter(|slot| slot.is_some()).count()
+    }
+
+    /// Returns `true` if no slot is occupied.
+    pub fn is_empty(&self) -> bool {
+        self.slots.iter().all(|slot| slot.is_none())
+    }
+
+    /// Removes all entries.
+    pub fn clear(&mut self) {
+        self.slots.iter_mut().for_each(|slot| *slot = None);
+    }
+
+    /// Returns an iterator over the cached keys and values, in unspecified order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.slots.iter().flatten().map(|(k, v)| (k, v))
+    }
+
+    fn slot_of<Q: Hash + ?Sized>(key: &Q) -> usize {
+        (FxBuildHasher.hash_one(key) % N as u64) as usize
+    }
+}
+
+impl<K: Eq + Hash, V, const N: usize> FxCache<K, V, N> {
+    /// Returns a reference to the cached value of `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        match &self.slots[Self::slot_of(key)] {
+            Some((k, v)) if k.borrow() == key => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the cached value of `key`.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        match &mut self.slots[Self::slot_of(key)] {
+            Some((k, v)) if (*k).borrow() == key => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Caches a value for `key`, returning the entry that previously occupied
+    /// its slot. That entry has either the same key or was evicted.
+    pub fn insert(&mut self, key: K, value: V) -> Option<(K, V)> {
+        self.slots[Self::slot_of(&key)].replace((key, value))
+    }
+
+    /// Returns the cached value of `key`, computing and caching it with
+    /// `compute` first if it is missing.
+    pub fn get_or_insert_with(&mut self, key: K, compute: impl FnOnce() -> V) -> &mut V {
+        let slot = &mut self.slots[Self::slot_of(&key)];
+        if !matches!(slot, Some((k, _)) if *k == key) {
+            *slot = Some((key, compute()));
+        }
+        let (_, v) = slot.as_mut().unwrap();
+        v
+    }
+
+    /// Removes `key` from the cache, returning its value.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let slot = &mut self.slots[Self::slot_of(key)];
+        match slot {
+            Some((k, _)) if (*k).borrow() == key => slot.take().map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+impl<K, V, const N: usize> Default for FxCache<K, V, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, const N: usize> fmt::Debug for FxCache<K, V, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FxCache;
+
+    #[test]
+    fn overwrites_on_collision() {
+        let mut cache = FxCache::<u32, u32, 1>::new();
+        assert_eq!(cache.insert(1, 10), None);
+        assert_eq!(cache.insert(1, 11), Some((1, 10)));
+        assert_eq!(cache.insert(2, 20), Some((1, 11)));
+        assert_eq!(cache.get(&1), None);
+        assert_eq!(cache.get(&2), Some(&20));
+        assert_eq!(cache.len(), 1);
+
+        let mut computed = 0;
+        *cache.get_or_insert_with(3, || {
+            computed += 1;
+            30
+        }) += 1;
+        assert_eq!(*cache.get_or_insert_with(3, || unreachable!()), 31);
+        assert_eq!(computed, 1);
+        assert_eq!(cache.remove(&2), None);
+        assert_eq!(cache.remove(&3), Some(31));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn memoizes() {
+        let mut cache = FxCache::<u64, u64, 256>::new();
+        for n in (0..1000).chain(0..1000) {
+            let v = *cache.get_or_insert_with(n, || n * n);
+            assert_eq!(v, n * n);
+        }
+        assert!(cache.len() <= cache.capacity());
+        assert!(cache.iter().all(|(k, v)| k * k == *v));
+    }
+}
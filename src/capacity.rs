@@ -0,0 +1,119 @@
+use core::hash::BuildHasher;
+
+use crate::FxSeededState;
+
+/// A `with_capacity` constructor for hash collections with any default-constructible
+/// hasher, such as `FxHashMap`, `FxHashSet` and their random or counter-seeded variants.
+///
+/// The inherent `with_capacity` of `std`'s collections only exists for the
+/// default hasher. With this trait in scope, `FxHashMap::with_capacity(n)` is
+/// a shorthand for `FxHashMap::with_capacity_and_hasher(n, Default::default())`.
+///
+/// ```
+/// use rustc_hash::{FxHashMap, FxHashSet, FxWithCapacity};
+///
+/// let map: FxHashMap<u32, u32> = FxHashMap::with_capacity(100);
+/// assert!(map.capacity() >= 100);
+///
+/// let set = FxHashSet::<&str>::with_capacity(10);
+/// assert!(set.capacity() >= 10);
+/// ```
+pub trait FxWithCapacity: Sized {
+    /// Creates an empty collection with room for at least `capacity` elements
+    /// and a default hasher.
+    fn with_capacity(capacity: usize) -> Self;
+}
+
+/// A `with_capacity_and_seed` constructor for hash collections using
+/// [`FxSeededState`], which has no default seed.
+///
+/// ```
+/// use rustc_hash::{FxHashMapSeed, FxSeededWithCapacity};
+///
+/// let map: FxHashMapSeed<u32, u32> = FxHashMapSeed::with_capacity_and_seed(100, 7);
+/// assert!(map.capacity() >= 100);
+/// assert_eq!(map.hasher().seed(), 7);
+/// ```
+pub trait FxSeededWithCapacity: Sized {
+    /// Creates an empty collection with room for at least `capacity` elements
+    /// and an `FxSeededState` with the given `seed`.
+    fn with_capacity_and_seed(capacity: usize, seed: usize) -> Self;
+}
+
+#[cfg(feature = "std")]
+impl<K, V, S: BuildHasher + Default> FxWithCapacity for std::collections::HashMap<K, V, S> {
+    fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, S::default())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, S: BuildHasher + Default> FxWithCapacity for std::collections::HashSet<T, S> {
+    fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, S::default())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K, V> FxSeededWithCapacity for std::collections::HashMap<K, V, FxSeededState> {
+    fn with_capacity_and_seed(capacity: usize, seed: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, FxSeededState::with_seed(seed))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> FxSeededWithCapacity for std::collections::HashSet<T, FxSeededState> {
+    fn with_capacity_and_seed(capacity: usize, seed: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, FxSeededState::with_seed(seed))
+    }
+}
+
+#[cfg(feature = "hashbrown")]
+impl<K, V, S: BuildHasher + Default> FxWithCapacity for hashbrown::HashMap<K, V, S> {
+    fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, S::default())
+    }
+}
+
+#[cfg(feature = "hashbrown")]
+impl<T, S: BuildHasher + Default> FxWithCapacity for hashbrown::HashSet<T, S> {
+    fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, S::default())
+    }
+}
+
+#[cfg(feature = "hashbrown")]
+impl<K, V> FxSeededWithCapacity for hashbrown::HashMap<K, V, FxSeededState> {
+    fn with_capacity_and_seed(capacity: usize, seed: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, FxSeededState::with_seed(seed))
+    }
+}
+
+#[cfg(feature = "hashbrown")]
+impl<T> FxSeededWithCapacity for hashbrown::HashSet<T, FxSeededState> {
+    fn with_capacity_and_seed(capacity: usize, seed: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, FxSeededState::with_seed(seed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FxTable, FxWithCapacity};
+
+    #[test]
+    fn with_capacity() {
+        let map: FxTable<u32, u32> = FxTable::with_capacity(50);
+        assert!(map.capacity() >= 50);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn seeded_with_capacity() {
+        use crate::{FxSeededState, FxSeededWithCapacity};
+
+        let set: std::collections::HashSet<u32, FxSeededState> =
+            FxSeededWithCapacity::with_capacity_and_seed(20, 3);
+        assert!(set.capacity() >= 20);
+        assert_eq!(set.hasher().seed(), 3);
+    }
+}
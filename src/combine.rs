@@ -0,0 +1,66 @@
+use core::hash::Hasher;
+
+use crate::FxHasher;
+
+/// Combines two hashes into one with the Fx mixing step.
+///
+/// Combining hashes with XOR is a common mistake: it is commutative, so
+/// `(a, b)` and `(b, a)` collide, and it cancels, so every pair `(a, a)`
+/// combines to 0. This feeds both hashes through an [`FxHasher`] instead,
+/// which depends on their order and never cancels, so composite keys hashed
+/// from independently computed parts get well distributed hashes.
+///
+/// This is [`combine_all`] of the two hashes.
+///
+/// ```
+/// use rustc_hash::combine;
+///
+/// let (a, b) = (0x1234, 0x5678);
+/// assert_ne!(combine(a, b), combine(b, a));
+/// assert_ne!(combine(a, a), combine(b, b));
+/// ```
+pub fn combine(h1: u64, h2: u64) -> u64 {
+    combine_all([h1, h2])
+}
+
+/// Combines any number of hashes into one with the Fx mixing step.
+///
+/// The result depends on the order of the hashes and on their number, so
+/// that e.g. `[]` and `[0]` differ.
+///
+/// ```
+/// use rustc_hash::{combine, combine_all};
+///
+/// let parts = [0x1111_u64, 0x2222, 0x3333];
+/// assert_eq!(combine_all(parts), combine_all(parts.iter().copied()));
+/// assert_ne!(combine_all([]), combine_all([0]));
+/// assert_eq!(combine_all([1, 2]), combine(1, 2));
+/// ```
+pub fn combine_all<I: IntoIterator<Item = u64>>(hashes: I) -> u64 {
+    let mut hasher = FxHasher::default();
+    let mut len = 0_u64;
+    for hash in hashes {
+        hasher.write_u64(hash);
+        len += 1;
+    }
+    // The length goes last, as it is not known up front.
+    hasher.write_u64(len);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{combine, combine_all};
+
+    #[test]
+    fn combine_avoids_xor_pitfalls() {
+        for a in 0..100_u64 {
+            assert_ne!(combine(a, a), 0);
+            for b in (a + 1)..100 {
+                assert_ne!(combine(a, b), combine(b, a));
+            }
+        }
+        assert_ne!(combine_all([0, 0]), combine_all([0]));
+        assert_eq!(combine_all([7, 8]), combine(7, 8));
+    }
+}
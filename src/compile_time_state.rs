@@ -0,0 +1,56 @@
+use crate::FxHasher;
+
+/// Type alias for a hashmap using the `fx` hash algorithm with [`FxCompileTimeSeededState`].
+#[cfg(feature = "std")]
+pub type FxHashMapCompileTime<K, V> = std::collections::HashMap<K, V, FxCompileTimeSeededState>;
+
+/// Type alias for a hashmap using the `fx` hash algorithm with [`FxCompileTimeSeededState`].
+#[cfg(feature = "std")]
+pub type FxHashSetCompileTime<V> = std::collections::HashSet<V, FxCompileTimeSeededState>;
+
+/// [`FxCompileTimeSeededState`] is an alternative state for `HashMap` types, using a seed that
+/// is chosen at random when the crate is compiled.
+///
+/// Every build gets a different hash function, but no entropy is needed at runtime, which makes
+/// this usable on targets without a source of randomness. All instances within one build share
+/// the same seed.
+///
+/// ```
+/// # use std::collections::HashMap;
+/// use rustc_hash::FxCompileTimeSeededState;
+///
+/// let mut map = HashMap::with_hasher(FxCompileTimeSeededState);
+/// map.insert(15, 610);
+/// assert_eq!(map[&15], 610);
+/// ```
+#[derive(Copy, Clone, Default)]
+pub struct FxCompileTimeSeededState;
+
+impl FxCompileTimeSeededState {
+    /// The seed baked into this build.
+    pub const SEED: usize = const_random::const_random!(usize);
+}
+
+impl core::hash::BuildHasher for FxCompileTimeSeededState {
+    type Hasher = FxHasher;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        FxHasher::with_seed(Self::SEED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::hash::BuildHasher;
+
+    use crate::{FxCompileTimeSeededState, FxSeededState};
+
+    #[test]
+    fn matches_seeded_state_with_same_seed() {
+        let a = FxCompileTimeSeededState;
+        let b = FxSeededState::with_seed(FxCompileTimeSeededState::SEED);
+
+        assert_eq!(a.hash_one(42_u32), b.hash_one(42_u32));
+        assert_eq!(a.hash_one("uwu"), b.hash_one("uwu"));
+    }
+}
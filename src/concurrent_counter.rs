@@ -0,0 +1,178 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::Hash;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{FxHashMap, FxShardedMap};
+
+/// A concurrent counter of keys, sharded by the Fx hash of the key.
+///
+/// Counting a key that was seen before only takes the read lock of its shard
+/// and updates an atomic, so threads counting existing keys never block each
+/// other. Only the first occurrence of a key takes the shard's write lock.
+///
+/// ```
+/// use rustc_hash::FxConcurrentCounter;
+/// use std::thread;
+///
+/// let counter = FxConcurrentCounter::new();
+/// thread::scope(|s| {
+///     for _ in 0..4 {
+///         s.spawn(|| {
+///             for i in 0..100_u32 {
+///                 counter.increment(i % 3);
+///             }
+///         });
+///     }
+/// });
+/// assert_eq!(counter.get(&0), 136);
+/// assert_eq!(counter.total(), 400);
+///
+/// let counts = counter.drain();
+/// assert_eq!(counts[&1], 132);
+/// assert!(counter.is_empty());
+/// ```
+pub struct FxConcurrentCounter<K> {
+    counts: FxShardedMap<K, AtomicU64>,
+}
+
+impl<K> FxConcurrentCounter<K> {
+    /// Creates an empty `FxConcurrentCounter` with a shard count based on the
+    /// available parallelism.
+    pub fn new() -> FxConcurrentCounter<K> {
+        FxConcurrentCounter {
+            counts: FxShardedMap::new(),
+        }
+    }
+
+    /// Creates an empty `FxConcurrentCounter` with `shards` shards, rounded up
+    /// to a power of two.
+    pub fn with_shards(shards: usize) -> FxConcurrentCounter<K> {
+        FxConcurrentCounter {
+            counts: FxShardedMap::with_shards(shards),
+        }
+    }
+
+    /// Returns the number of distinct keys.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Returns `true` if no key has been counted.
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Returns the sum of all counts.
+    pub fn total(&self) -> u64 {
+        let mut total = 0_u64;
+        self.counts
+            .for_each(|_, c| total = total.wrapping_add(c.load(Ordering::Relaxed)));
+        total
+    }
+
+    /// Removes all keys and counts.
+    pub fn clear(&self) {
+        self.counts.clear();
+    }
+}
+
+impl<K: Eq + Hash> FxConcurrentCounter<K> {
+    /// Adds one to the count of `key`.
+    pub fn increment(&self, key: K) {
+        self.add(key, 1);
+    }
+
+    /// Adds `n` to the count of `key`, wrapping around on overflow.
+    pub fn add(&self, key: K, n: u64) {
+        let added = self
+            .counts
+            .get_with(&key, |c| c.fetch_add(n, Ordering::Relaxed));
+        if added.is_none() {
+            self.counts
+                .entry(key, |e| e.or_default().fetch_add(n, Ordering::Relaxed));
+        }
+    }
+
+    /// Returns the count of `key`, which is zero if it was never counted.
+    pub fn get<Q>(&self, key: &Q) -> u64
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.counts
+            .get_with(key, |c| c.load(Ordering::Relaxed))
+            .unwrap_or(0)
+    }
+
+    /// Returns a copy of all counts, taken one shard at a time.
+    pub fn snapshot(&self) -> FxHashMap<K, u64>
+    where
+        K: Clone,
+    {
+        let mut counts = FxHashMap::default();
+        self.counts.for_each(|k, c| {
+            counts.insert(k.clone(), c.load(Ordering::Relaxed));
+        });
+        counts
+    }
+
+    /// Removes all keys and returns their counts, taken one shard at a time.
+    ///
+    /// Increments racing with `drain` are either part of the result or of the
+    /// counter afterwards, so no count is lost.
+    pub fn drain(&self) -> FxHashMap<K, u64> {
+        self.counts
+            .drain()
+            .into_iter()
+            .map(|(k, c)| (k, c.into_inner()))
+            .collect()
+    }
+}
+
+impl<K> Default for FxConcurrentCounter<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: fmt::Debug> fmt::Debug for FxConcurrentCounter<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.counts.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use crate::FxConcurrentCounter;
+
+    #[test]
+    fn drain_while_counting() {
+        let counter = FxConcurrentCounter::with_shards(2);
+        let mut drained = 0;
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for i in 0..10_000_u32 {
+                        counter.add(i % 50, 2);
+                    }
+                });
+            }
+            for _ in 0..10 {
+                drained += counter.drain().values().sum::<u64>();
+            }
+        });
+        assert_eq!(drained + counter.total(), 80_000);
+
+        counter.clear();
+        assert!(counter.is_empty());
+
+        let counter = FxConcurrentCounter::default();
+        counter.increment("a");
+        assert_eq!(counter.get("a"), 1);
+        assert_eq!(counter.get("b"), 0);
+        assert_eq!(counter.snapshot().len(), 1);
+    }
+}
@@ -0,0 +1,238 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use core::sync::atomic::{AtomicU64, Ordering};
+use std::boxed::Box;
+use std::collections::hash_map::Entry;
+use std::vec::Vec;
+
+use crate::sketch;
+use crate::{FxBuildHasher, FxHashSet, FxShardedMap};
+
+/// The number of filter bits per stripe.
+const FILTER_BITS_PER_STRIPE: usize = 4096;
+
+/// A concurrent hash set for deduplication, made of lock-striped Fx hash sets.
+///
+/// Every element is assigned to a stripe by its Fx hash, and only that
+/// stripe is locked to insert it. A lock-free bit filter in front of the
+/// stripes answers most [`contains`](Self::contains) calls for absent elements
+/// without taking a lock at all. Removing elements does not clear their filter
+/// bits, so the fast path gets less effective after many removals.
+///
+/// ```
+/// use rustc_hash::FxConcurrentSet;
+/// use std::thread;
+///
+/// let seen = FxConcurrentSet::new();
+/// let firsts: usize = thread::scope(|s| {
+///     let handles: Vec<_> = (0..4)
+///         .map(|_| s.spawn(|| (0..100_u32).filter(|&i| seen.insert_if_absent(i)).count()))
+///         .collect();
+///     handles.into_iter().map(|h| h.join().unwrap()).sum()
+/// });
+/// assert_eq!(firsts, 100);
+/// assert!(seen.contains(&42));
+/// ```
+pub struct FxConcurrentSet<T> {
+    stripes: FxShardedMap<T, ()>,
+    filter: Box<[AtomicU64]>,
+}
+
+impl<T> FxConcurrentSet<T> {
+    /// Creates an empty `FxConcurrentSet` with a stripe count based on the
+    /// available parallelism.
+    pub fn new() -> FxConcurrentSet<T> {
+        Self::from_stripes(FxShardedMap::new())
+    }
+
+    /// Creates an empty `FxConcurrentSet` with `stripes` stripes, rounded up to
+    /// a power of two.
+    pub fn with_stripes(stripes: usize) -> FxConcurrentSet<T> {
+        Self::from_stripes(FxShardedMap::with_shards(stripes))
+    }
+
+    fn from_stripes(stripes: FxShardedMap<T, ()>) -> FxConcurrentSet<T> {
+        let words = stripes.shard_count() * FILTER_BITS_PER_STRIPE / 64;
+        FxConcurrentSet {
+            stripes,
+            filter: (0..words).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    /// Returns the number of stripes.
+    pub fn stripe_count(&self) -> usize {
+        self.stripes.shard_count()
+    }
+
+    /// Returns the number of elements in the set.
+    pub fn len(&self) -> usize {
+        self.stripes.len()
+    }
+
+    /// Returns `true` if the set contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.stripes.is_empty()
+    }
+
+    /// Removes all elements.
+    pub fn clear(&self) {
+        // Clearing the filter first keeps it conservative for elements
+        // inserted concurrently: they either end up cleared from their stripe
+        // too, or they set their bit again after the filter was cleared.
+        for word in self.filter.iter() {
+            word.store(0, Ordering::Relaxed);
+        }
+        self.stripes.clear();
+    }
+
+    /// Returns the filter word and bit for a hashed element.
+    fn filter_bit<Q: Hash + ?Sized>(&self, value: &Q) -> (&AtomicU64, u64) {
+        // The tables of the stripes take their bucket index from the low bits
+        // of the hash, so we take the filter bit from the high half of the
+        // mixed hash.
+        let hash = sketch::mix(FxBuildHasher.hash_one(value));
+        let bit = (hash >> 32) as usize % (self.filter.len() * 64);
+        (&self.filter[bit / 64], 1 << (bit % 64))
+    }
+}
+
+impl<T: Eq + Hash> FxConcurrentSet<T> {
+    /// Adds `value` to the set if it is not present yet, locking only the
+    /// stripe that owns it.
+    ///
+    /// Returns `true` if this call inserted the value.
+    pub fn insert_if_absent(&self, value: T) -> bool {
+        let (word, bit) = self.filter_bit(&value);
+        self.stripes.entry(value, |e| match e {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(e) => {
+                word.fetch_or(bit, Ordering::Release);
+                e.insert(());
+                true
+            }
+        })
+    }
+
+    /// Returns `true` if the set contains `value`.
+    ///
+    /// Values whose filter bit is unset are known to be absent without locking.
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (word, bit) = self.filter_bit(value);
+        word.load(Ordering::Acquire) & bit != 0 && self.stripes.contains_key(value)
+    }
+
+    /// Removes `value`, returning `true` if it was present.
+    pub fn remove<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.stripes.remove(value).is_some()
+    }
+
+    /// Returns a copy of all elements, taken one stripe at a time.
+    pub fn snapshot(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let mut values = Vec::new();
+        self.stripes.for_each(|t, _| values.push(t.clone()));
+        values
+    }
+
+    /// Consumes the set and returns its stripes merged into a single `FxHashSet`.
+    pub fn into_set(self) -> FxHashSet<T> {
+        self.stripes.into_inner().into_keys().collect()
+    }
+}
+
+impl<T> Default for FxConcurrentSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Eq + Hash> FromIterator<T> for FxConcurrentSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let set = Self::new();
+        for t in iter {
+            set.insert_if_absent(t);
+        }
+        set
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for FxConcurrentSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut set = f.debug_set();
+        self.stripes.for_each(|t, _| {
+            set.entry(t);
+        });
+        set.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::Ordering;
+    use std::thread;
+
+    use crate::FxConcurrentSet;
+
+    #[test]
+    fn dedup_across_threads() {
+        let set = FxConcurrentSet::with_stripes(3);
+        assert_eq!(set.stripe_count(), 4);
+
+        let inserted: usize = thread::scope(|s| {
+            let handles: std::vec::Vec<_> = (0..4_u32)
+                .map(|t| {
+                    let set = &set;
+                    s.spawn(move || (0..1000).filter(|i| set.insert_if_absent(i + t)).count())
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).sum()
+        });
+        assert_eq!(inserted, 1003);
+        assert_eq!(set.len(), 1003);
+        assert!((0..1003).all(|i| set.contains(&i)));
+        assert!(!set.contains(&1003));
+
+        assert!(set.remove(&0));
+        assert!(!set.contains(&0));
+        assert!(set.insert_if_absent(0));
+
+        set.clear();
+        assert!(set.is_empty());
+        assert!(!set.contains(&1));
+    }
+
+    #[test]
+    fn filter_bits_are_spread() {
+        let set = FxConcurrentSet::with_stripes(1);
+        for i in 0..1000_u32 {
+            set.insert_if_absent(i);
+        }
+        let bits: u32 = set
+            .filter
+            .iter()
+            .map(|w| w.load(Ordering::Relaxed).count_ones())
+            .sum();
+        // 1000 random bits out of 4096 are expected to hit about 887 of them.
+        assert!(bits > 800, "{bits} filter bits set");
+    }
+
+    #[test]
+    fn conversions() {
+        let set: FxConcurrentSet<&str> = ["a", "b", "a"].into_iter().collect();
+        let mut values = set.snapshot();
+        values.sort_unstable();
+        assert_eq!(values, ["a", "b"]);
+        assert_eq!(set.into_set().len(), 2);
+    }
+}
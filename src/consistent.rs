@@ -0,0 +1,297 @@
+/// Assigns a key to one of `num_buckets` buckets with jump consistent
+/// hashing, given its Fx hash.
+///
+/// When the number of buckets grows from `n` to `n + 1`, only the keys that
+/// move to the new bucket change buckets, about `1 / (n + 1)` of them, and
+/// the keys are spread evenly over the buckets. This makes it a good choice
+/// for assigning keys to shards numbered `0..n`; buckets can only be added
+/// or removed at the end, see [`FxHashRing`] for removing arbitrary nodes.
+///
+/// Every process that assigns a key must pass the same hash for it, so hash
+/// keys with an unseeded `FxHasher` rather than with the hasher of a map,
+/// which may be seeded, e.g. per map with the `shuffle-debug` feature or
+/// per process with `FxRandomState`.
+///
+/// ```
+/// use rustc_hash::{fx_jump_hash, FxHasher};
+/// use std::hash::{Hash, Hasher};
+///
+/// let mut hasher = FxHasher::default();
+/// "user-42".hash(&mut hasher);
+/// let hash = hasher.finish();
+/// let shard = fx_jump_hash(hash, 10);
+/// assert!(shard < 10);
+/// // Growing to 11 shards keeps the key in place or moves it to the new one.
+/// assert!([shard, 10].contains(&fx_jump_hash(hash, 11)));
+/// ```
+///
+/// # Panics
+///
+/// Panics if `num_buckets` is 0.
+pub fn fx_jump_hash(key_hash: u64, num_buckets: u32) -> u32 {
+    assert!(num_buckets > 0, "there must be at least one bucket");
+    let mut key = key_hash;
+    let mut bucket = 0_u64;
+    let mut next = 0_u64;
+    while next < u64::from(num_buckets) {
+        bucket = next;
+        key = key.wrapping_mul(2862933555777941757).wrapping_add(1);
+        let scale = (1_u64 << 31) as f64 / ((key >> 33) + 1) as f64;
+        next = ((bucket + 1) as f64 * scale) as u64;
+    }
+    bucket as u32
+}
+
+/// Picks the node a key belongs to with rendezvous, or highest random
+/// weight, hashing.
+///
+/// Every node gets a weight for the key, derived from the unseeded Fx hashes
+/// of the key and the node, and the node with the highest weight wins.
+/// Removing any node only moves the keys it had, spread evenly over the other
+/// nodes, and the result does not depend on the order of the nodes, so every
+/// process with the same nodes picks the same one. A lookup
+/// takes time linear in the number of nodes, which suits small clusters;
+/// see [`FxHashRing`] for many nodes.
+///
+/// Returns `None` if there are no nodes.
+///
+/// ```
+/// use rustc_hash::fx_rendezvous;
+///
+/// let nodes = ["db-1", "db-2", "db-3", "db-4"];
+/// let node = fx_rendezvous("tenant-7", &nodes).unwrap();
+///
+/// // Removing another node leaves the key where it was.
+/// let rest: Vec<_> = nodes.iter().copied().filter(|n| n == node || *n != "db-2").collect();
+/// assert_eq!(fx_rendezvous("tenant-7", &rest), Some(node));
+/// ```
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub fn fx_rendezvous<'a, K, N>(key: &K, nodes: &'a [N]) -> Option<&'a N>
+where
+    K: core::hash::Hash + ?Sized,
+    N: core::hash::Hash,
+{
+    let key_hash = crate::stable_hash(key);
+    let weight = |node: &N| {
+        let seed = crate::stable_hash(node);
+        crate::sketch::mix(key_hash ^ crate::sketch::mix(seed))
+    };
+    nodes.iter().max_by_key(|node| weight(node))
+}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use ring::FxHashRing;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod ring {
+    use alloc::vec::Vec;
+    use core::hash::Hash;
+
+    use crate::{sketch, stable_hash};
+
+    /// The number of virtual nodes per unit of weight of rings created with
+    /// [`FxHashRing::default`].
+    const DEFAULT_VIRTUAL_NODES: u32 = 160;
+
+    /// A consistent hash ring using the Fx hashing algorithm.
+    ///
+    /// Every node is placed at several points of a ring of 64-bit hashes, its
+    /// virtual nodes, and a key belongs to the node of the first point at or
+    /// after the hash of the key. Adding or removing a node only moves the
+    /// keys between it and the other nodes, and a node with twice the weight
+    /// gets twice the virtual nodes and about twice the keys. Points are
+    /// derived from the unseeded Fx hashes of the nodes, so every process with
+    /// the same nodes builds the same ring.
+    ///
+    /// ```
+    /// use rustc_hash::FxHashRing;
+    ///
+    /// let mut ring = FxHashRing::default();
+    /// ring.add_node("cache-a", 1);
+    /// ring.add_node("cache-b", 1);
+    /// ring.add_node("cache-c", 2);
+    ///
+    /// let node = *ring.node_for("session-1234").unwrap();
+    /// ring.remove_node(if node == "cache-a" { &"cache-b" } else { &"cache-a" });
+    /// assert_eq!(ring.node_for("session-1234"), Some(&node));
+    /// ```
+    #[derive(Clone, Debug)]
+    pub struct FxHashRing<N> {
+        // The virtual nodes, sorted by their point on the ring.
+        points: Vec<(u64, N)>,
+        nodes: Vec<(N, u32)>,
+        virtual_nodes: u32,
+    }
+
+    impl<N> FxHashRing<N> {
+        /// Creates an empty ring placing `virtual_nodes` points per unit of
+        /// weight of every node.
+        ///
+        /// More virtual nodes spread the keys more evenly at the cost of
+        /// memory and lookup time.
+        ///
+        /// # Panics
+        ///
+        /// Panics if `virtual_nodes` is 0.
+        pub fn new(virtual_nodes: u32) -> FxHashRing<N> {
+            assert!(virtual_nodes > 0, "nodes need at least one point");
+            FxHashRing {
+                points: Vec::new(),
+                nodes: Vec::new(),
+                virtual_nodes,
+            }
+        }
+
+        /// Returns the number of virtual nodes per unit of weight.
+        pub fn virtual_nodes(&self) -> u32 {
+            self.virtual_nodes
+        }
+
+        /// Returns the number of nodes.
+        pub fn len(&self) -> usize {
+            self.nodes.len()
+        }
+
+        /// Returns `true` if the ring has no nodes.
+        pub fn is_empty(&self) -> bool {
+            self.nodes.is_empty()
+        }
+
+        /// Returns an iterator over the nodes and their weights, in the order
+        /// they were added.
+        pub fn nodes(&self) -> impl Iterator<Item = (&N, u32)> {
+            self.nodes.iter().map(|(n, w)| (n, *w))
+        }
+    }
+
+    impl<N: Hash + Eq + Clone> FxHashRing<N> {
+        /// Adds a node with the given weight, replacing its previous weight
+        /// if it is already on the ring.
+        ///
+        /// A node with weight 0 gets no keys.
+        pub fn add_node(&mut self, node: N, weight: u32) {
+            self.remove_node(&node);
+            let count = weight.saturating_mul(self.virtual_nodes);
+            self.points.reserve(count as usize);
+            for i in 0..count {
+                let point = sketch::mix(stable_hash(&(&node, i)));
+                self.points.push((point, node.clone()));
+            }
+            // Ties between points of different nodes are broken by the order
+            // of insertion, so they are rare but possible.
+            self.points.sort_by_key(|&(point, _)| point);
+            self.nodes.push((node, weight));
+        }
+
+        /// Removes a node, returning `false` if it was not on the ring.
+        ///
+        /// Its keys are spread over the remaining nodes.
+        pub fn remove_node(&mut self, node: &N) -> bool {
+            let Some(i) = self.nodes.iter().position(|(n, _)| n == node) else {
+                return false;
+            };
+            self.nodes.remove(i);
+            self.points.retain(|(_, n)| n != node);
+            true
+        }
+
+        /// Returns the node a key belongs to, or `None` if the ring has no
+        /// virtual nodes.
+        pub fn node_for<K: Hash + ?Sized>(&self, key: &K) -> Option<&N> {
+            let hash = sketch::mix(stable_hash(key));
+            let i = self.points.partition_point(|&(point, _)| point < hash);
+            // Past the last point the ring wraps around to the first.
+            let (_, node) = self.points.get(i).or_else(|| self.points.first())?;
+            Some(node)
+        }
+    }
+
+    impl<N> Default for FxHashRing<N> {
+        /// Creates an empty ring with 160 virtual nodes per unit of weight.
+        fn default() -> Self {
+            Self::new(DEFAULT_VIRTUAL_NODES)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::hash::BuildHasher;
+
+    use super::fx_jump_hash;
+    use crate::FxBuildHasher;
+
+    #[test]
+    fn jump_hash_moves_few_keys() {
+        let mut counts = [0_u32; 10];
+        let mut moved = 0;
+        for key in 0..100_000_u64 {
+            let hash = FxBuildHasher.hash_one(key);
+            let bucket = fx_jump_hash(hash, 10);
+            counts[bucket as usize] += 1;
+            let grown = fx_jump_hash(hash, 11);
+            if grown != bucket {
+                assert_eq!(grown, 10);
+                moved += 1;
+            }
+        }
+        assert!(
+            counts.iter().all(|&c| (9_000..11_000).contains(&c)),
+            "{counts:?}"
+        );
+        assert!((8_000..10_200).contains(&moved), "{moved} keys moved");
+        assert_eq!(fx_jump_hash(12345, 1), 0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn ring_remaps_only_removed_node() {
+        use crate::{FxHashMap, FxHashRing};
+
+        let mut ring = FxHashRing::new(100);
+        for (node, weight) in [("a", 1), ("b", 1), ("c", 2)] {
+            ring.add_node(node, weight);
+        }
+        let before: FxHashMap<u32, &str> = (0..10_000)
+            .map(|key| (key, *ring.node_for(&key).unwrap()))
+            .collect();
+        let share = before.values().filter(|&&n| n == "c").count();
+        assert!((4_000..6_000).contains(&share), "{share} keys on c");
+
+        assert!(ring.remove_node(&"b"));
+        assert!(!ring.remove_node(&"b"));
+        for (key, node) in &before {
+            let now = *ring.node_for(key).unwrap();
+            if *node != "b" {
+                assert_eq!(now, *node);
+            }
+        }
+        assert_eq!(ring.len(), 2);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn rendezvous_remaps_only_removed_node() {
+        use std::vec::Vec;
+
+        use crate::fx_rendezvous;
+
+        let nodes: Vec<u32> = (0..8).collect();
+        let before: Vec<u32> = (0..10_000_u32)
+            .map(|key| *fx_rendezvous(&key, &nodes).unwrap())
+            .collect();
+        for node in &nodes {
+            let share = before.iter().filter(|&n| n == node).count();
+            assert!((1_000..1_500).contains(&share), "{share} keys on {node}");
+        }
+
+        let rest: Vec<u32> = nodes.iter().copied().filter(|&n| n != 3).collect();
+        for (key, &node) in before.iter().enumerate() {
+            let now = *fx_rendezvous(&(key as u32), &rest).unwrap();
+            if node != 3 {
+                assert_eq!(now, node);
+            }
+        }
+        assert_eq!(fx_rendezvous::<_, u32>(&1, &[]), None);
+    }
+}
@@ -0,0 +1,156 @@
+use core::hash::{BuildHasher, Hash};
+
+/// Conversion of a hash collection into its Fx-hashed counterpart.
+///
+/// `std`'s `HashMap` and `HashSet` with any hasher convert into `FxHashMap`
+/// and `FxHashSet`, and `hashbrown`'s into `FxHashMapAlloc` and
+/// `FxHashSetAlloc`. The entries are moved into a collection that reserved
+/// room for all of them up front.
+///
+/// ```
+/// use std::collections::HashMap;
+/// use rustc_hash::{FxHashMap, IntoFx};
+///
+/// let map: HashMap<&str, u32> = HashMap::from([("a", 1), ("b", 2)]);
+/// let map: FxHashMap<&str, u32> = map.into_fx();
+/// assert_eq!(map["b"], 2);
+/// ```
+pub trait IntoFx {
+    /// The Fx-hashed collection.
+    type Fx;
+
+    /// Moves the entries into the Fx-hashed collection.
+    fn into_fx(self) -> Self::Fx;
+}
+
+/// Conversion of a borrowed hash collection into its Fx-hashed counterpart,
+/// cloning the entries.
+///
+/// See [`IntoFx`] for the supported collections.
+///
+/// ```
+/// use std::collections::HashSet;
+/// use rustc_hash::ToFx;
+///
+/// let set: HashSet<String> = ["a".to_string()].into_iter().collect();
+/// let fx = set.to_fx();
+/// assert!(fx.contains("a") && set.contains("a"));
+/// ```
+pub trait ToFx {
+    /// The Fx-hashed collection.
+    type Fx;
+
+    /// Clones the entries into the Fx-hashed collection.
+    fn to_fx(&self) -> Self::Fx;
+}
+
+#[cfg(feature = "std")]
+impl<K: Eq + Hash, V, S: BuildHasher> IntoFx for std::collections::HashMap<K, V, S> {
+    type Fx = crate::FxHashMap<K, V>;
+
+    fn into_fx(self) -> Self::Fx {
+        let mut map = Self::Fx::with_capacity_and_hasher(self.len(), Default::default());
+        map.extend(self);
+        map
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Eq + Hash, S: BuildHasher> IntoFx for std::collections::HashSet<T, S> {
+    type Fx = crate::FxHashSet<T>;
+
+    fn into_fx(self) -> Self::Fx {
+        let mut set = Self::Fx::with_capacity_and_hasher(self.len(), Default::default());
+        set.extend(self);
+        set
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: Clone + Eq + Hash, V: Clone, S: BuildHasher> ToFx for std::collections::HashMap<K, V, S> {
+    type Fx = crate::FxHashMap<K, V>;
+
+    fn to_fx(&self) -> Self::Fx {
+        let mut map = Self::Fx::with_capacity_and_hasher(self.len(), Default::default());
+        map.extend(self.iter().map(|(k, v)| (k.clone(), v.clone())));
+        map
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Clone + Eq + Hash, S: BuildHasher> ToFx for std::collections::HashSet<T, S> {
+    type Fx = crate::FxHashSet<T>;
+
+    fn to_fx(&self) -> Self::Fx {
+        let mut set = Self::Fx::with_capacity_and_hasher(self.len(), Default::default());
+        set.extend(self.iter().cloned());
+        set
+    }
+}
+
+#[cfg(feature = "hashbrown")]
+impl<K: Eq + Hash, V, S: BuildHasher> IntoFx for hashbrown::HashMap<K, V, S> {
+    type Fx = crate::FxHashMapAlloc<K, V>;
+
+    fn into_fx(self) -> Self::Fx {
+        let mut map = Self::Fx::with_capacity_and_hasher(self.len(), Default::default());
+        map.extend(self);
+        map
+    }
+}
+
+#[cfg(feature = "hashbrown")]
+impl<T: Eq + Hash, S: BuildHasher> IntoFx for hashbrown::HashSet<T, S> {
+    type Fx = crate::FxHashSetAlloc<T>;
+
+    fn into_fx(self) -> Self::Fx {
+        let mut set = Self::Fx::with_capacity_and_hasher(self.len(), Default::default());
+        set.extend(self);
+        set
+    }
+}
+
+#[cfg(feature = "hashbrown")]
+impl<K: Clone + Eq + Hash, V: Clone, S: BuildHasher> ToFx for hashbrown::HashMap<K, V, S> {
+    type Fx = crate::FxHashMapAlloc<K, V>;
+
+    fn to_fx(&self) -> Self::Fx {
+        let mut map = Self::Fx::with_capacity_and_hasher(self.len(), Default::default());
+        map.extend(self.iter().map(|(k, v)| (k.clone(), v.clone())));
+        map
+    }
+}
+
+#[cfg(feature = "hashbrown")]
+impl<T: Clone + Eq + Hash, S: BuildHasher> ToFx for hashbrown::HashSet<T, S> {
+    type Fx = crate::FxHashSetAlloc<T>;
+
+    fn to_fx(&self) -> Self::Fx {
+        let mut set = Self::Fx::with_capacity_and_hasher(self.len(), Default::default());
+        set.extend(self.iter().cloned());
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))]
+    use hashbrown::HashMap;
+    #[cfg(feature = "std")]
+    use std::collections::HashMap;
+
+    use crate::{FxSeededState, IntoFx, ToFx};
+
+    #[test]
+    fn converts() {
+        let mut map = HashMap::with_hasher(FxSeededState::with_seed(5));
+        map.extend((0..100).map(|i| (i, i * 2)));
+
+        let copy = map.to_fx();
+        let moved = map.clone().into_fx();
+        assert_eq!(copy, moved);
+        assert_eq!(moved.len(), 100);
+        assert!(moved.capacity() >= 100);
+        assert!(map.iter().all(|(k, v)| moved.get(k) == Some(v)));
+    }
+}
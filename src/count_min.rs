@@ -0,0 +1,295 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use core::marker::PhantomData;
+
+use crate::sketch::{self, SketchMismatch, DEFAULT_SEEDS};
+use crate::FxSeededState;
+
+/// A Count-Min sketch using the Fx hashing algorithm.
+///
+/// It estimates how often every item occurred in a stream in a fixed amount
+/// of memory: `depth` rows of `width` counters, where every row hashes the
+/// item with its own Fx seed and counts it in one of its counters. The
+/// estimate of an item is the smallest of its counters, which is never less
+/// than its true count and, with probability `1 - e^-depth`, exceeds it by at
+/// most `e / width` times the total count.
+///
+/// With conservative update, adding an item only raises its counters as far
+/// as needed for its estimate to grow by the added count, which makes the
+/// estimates of other items considerably tighter. Sketches built this way
+/// can still be merged, but their merged estimates are less tight than if
+/// all items had been added to one sketch.
+///
+/// ```
+/// use rustc_hash::FxCountMinSketch;
+///
+/// let mut hits = FxCountMinSketch::with_error(0.001, 0.01);
+/// for path in ["/", "/about", "/", "/", "/login"] {
+///     hits.add(path, 1);
+/// }
+/// assert!(hits.estimate("/") >= 3);
+/// assert_eq!(hits.estimate("/missing"), 0);
+/// assert_eq!(hits.total(), 5);
+/// ```
+pub struct FxCountMinSketch<T: ?Sized> {
+    pub(crate) counters: Vec<u64>,
+    pub(crate) width: u64,
+    pub(crate) depth: u32,
+    pub(crate) seed: usize,
+    pub(crate) conservative: bool,
+    pub(crate) total: u64,
+    pub(crate) marker: PhantomData<fn(&T)>,
+}
+
+impl<T: ?Sized> FxCountMinSketch<T> {
+    /// Creates an empty sketch with `depth` rows of `width` counters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` or `depth` is 0.
+    pub fn new(width: u64, depth: u32) -> FxCountMinSketch<T> {
+        Self::with_seed(width, depth, DEFAULT_SEEDS.0)
+    }
+
+    /// Creates an empty sketch like [`new`](Self::new), deriving the seeds of
+    /// the rows from `seed`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` or `depth` is 0.
+    pub fn with_seed(width: u64, depth: u32, seed: usize) -> FxCountMinSketch<T> {
+        assert!(width > 0, "a Count-Min sketch needs at least one column");
+        assert!(depth > 0, "a Count-Min sketch needs at least one row");
+        let len = width.checked_mul(u64::from(depth));
+        let len = len.and_then(|n| usize::try_from(n).ok());
+        FxCountMinSketch {
+            counters: vec![0; len.expect("too many counters")],
+            width,
+            depth,
+            seed,
+            conservative: false,
+            total: 0,
+            marker: PhantomData,
+        }
+    }
+
+    /// Creates an empty sketch whose estimates exceed the true counts by at
+    /// most `epsilon` times the total count, with probability `1 - delta`.
+    ///
+    /// It uses `e / epsilon` columns and `ln(1 / delta)` rows, rounded up.
+    ///
+    /// ```
+    /// use rustc_hash::FxCountMinSketch;
+    ///
+    /// let sketch = FxCountMinSketch::<str>::with_error(0.001, 0.01);
+    /// assert_eq!(sketch.width(), 2719);
+    /// assert_eq!(sketch.depth(), 5);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `epsilon` or `delta` is not between 0 and 1, exclusive.
+    pub fn with_error(epsilon: f64, delta: f64) -> FxCountMinSketch<T> {
+        assert!(
+            epsilon > 0.0 && epsilon < 1.0 && delta > 0.0 && delta < 1.0,
+            "the error bounds must be between 0 and 1"
+        );
+        let width = sketch::ceil(core::f64::consts::E / epsilon);
+        let depth = sketch::ceil(-sketch::log2(delta) * core::f64::consts::LN_2);
+        Self::new(width, depth as u32)
+    }
+
+    /// Returns the number of counters per row.
+    pub fn width(&self) -> u64 {
+        self.width
+    }
+
+    /// Returns the number of rows.
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    /// Returns the seed that the seeds of the rows are derived from.
+    pub fn seed(&self) -> usize {
+        self.seed
+    }
+
+    /// Returns `true` if the sketch uses conservative update.
+    pub fn conservative_update(&self) -> bool {
+        self.conservative
+    }
+
+    /// Enables or disables conservative update for the items added from now
+    /// on.
+    pub fn set_conservative_update(&mut self, enabled: bool) {
+        self.conservative = enabled;
+    }
+
+    /// Returns the sum of all added counts.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Returns `true` if nothing was added.
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    /// Resets all counts, keeping the parameters.
+    pub fn clear(&mut self) {
+        self.counters.fill(0);
+        self.total = 0;
+    }
+
+    /// Adds `count` occurrences of an item, saturating at `u64::MAX`.
+    pub fn add(&mut self, item: &T, count: u64)
+    where
+        T: Hash,
+    {
+        self.total = self.total.saturating_add(count);
+        if self.conservative {
+            let target = self.estimate(item).saturating_add(count);
+            for row in 0..self.depth {
+                let index = self.index(row, item);
+                self.counters[index] = self.counters[index].max(target);
+            }
+        } else {
+            for row in 0..self.depth {
+                let index = self.index(row, item);
+                self.counters[index] = self.counters[index].saturating_add(count);
+            }
+        }
+    }
+
+    /// Returns an upper bound of the number of occurrences of an item.
+    pub fn estimate(&self, item: &T) -> u64
+    where
+        T: Hash,
+    {
+        (0..self.depth)
+            .map(|row| self.counters[self.index(row, item)])
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Returns `true` if `other` has the same parameters, so that the
+    /// sketches can be merged.
+    pub fn is_compatible(&self, other: &FxCountMinSketch<T>) -> bool {
+        self.width == other.width && self.depth == other.depth && self.seed == other.seed
+    }
+
+    /// Adds the counts of `other` to this sketch.
+    pub fn merge(&mut self, other: &FxCountMinSketch<T>) -> Result<(), SketchMismatch> {
+        if !self.is_compatible(other) {
+            return Err(SketchMismatch);
+        }
+        for (a, &b) in self.counters.iter_mut().zip(&other.counters) {
+            *a = a.saturating_add(b);
+        }
+        self.total = self.total.saturating_add(other.total);
+        Ok(())
+    }
+
+    /// Returns the index of the counter of an item in `row`.
+    fn index(&self, row: u32, item: &T) -> usize
+    where
+        T: Hash,
+    {
+        let hash = FxSeededState::with_seed(self.row_seed(row)).hash_one(item);
+        let column = sketch::mix(hash) % self.width;
+        (u64::from(row) * self.width + column) as usize
+    }
+
+    fn row_seed(&self, row: u32) -> usize {
+        (self.seed as u64 ^ sketch::mix(u64::from(row) + 1)) as usize
+    }
+}
+
+impl<T: Hash> Extend<T> for FxCountMinSketch<T> {
+    /// Adds one occurrence of every item.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.add(&item, 1);
+        }
+    }
+}
+
+impl<T: ?Sized> Clone for FxCountMinSketch<T> {
+    fn clone(&self) -> Self {
+        FxCountMinSketch {
+            counters: self.counters.clone(),
+            width: self.width,
+            depth: self.depth,
+            seed: self.seed,
+            conservative: self.conservative,
+            total: self.total,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> PartialEq for FxCountMinSketch<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.is_compatible(other)
+            && self.conservative == other.conservative
+            && self.total == other.total
+            && self.counters == other.counters
+    }
+}
+
+impl<T: ?Sized> Eq for FxCountMinSketch<T> {}
+
+impl<T: ?Sized> fmt::Debug for FxCountMinSketch<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FxCountMinSketch")
+            .field("width", &self.width)
+            .field("depth", &self.depth)
+            .field("conservative_update", &self.conservative)
+            .field("total", &self.total)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FxCountMinSketch, SketchMismatch};
+
+    #[test]
+    fn estimates_bound_counts() {
+        for conservative in [false, true] {
+            let mut sketch = FxCountMinSketch::with_error(0.01, 0.01);
+            sketch.set_conservative_update(conservative);
+            // Item `i` occurs `i % 100` times.
+            for i in 0..10_000_u32 {
+                sketch.add(&i, u64::from(i % 100));
+            }
+            let slack = (0.01 * sketch.total() as f64) as u64;
+            let within = (0..10_000_u32)
+                .filter(|i| {
+                    let (estimate, count) = (sketch.estimate(i), u64::from(i % 100));
+                    assert!(estimate >= count);
+                    estimate <= count + slack
+                })
+                .count();
+            assert!(within >= 9_900, "{within} estimates within bounds");
+        }
+    }
+
+    #[test]
+    fn merge() {
+        let mut a = FxCountMinSketch::new(64, 4);
+        let mut b = a.clone();
+        a.add("x", 2);
+        a.add("y", 1);
+        b.add("x", 1);
+        b.add("z", 1);
+        a.merge(&b).unwrap();
+        assert!(a.estimate("x") >= 3 && a.estimate("z") >= 1);
+        assert_eq!(a.total(), 5);
+
+        let other = FxCountMinSketch::with_seed(64, 4, 1);
+        assert_eq!(a.merge(&other), Err(SketchMismatch));
+    }
+}
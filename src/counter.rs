@@ -0,0 +1,198 @@
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::Reverse;
+use core::fmt;
+use core::hash::Hash;
+
+use crate::FxTable;
+
+/// What an [`FxCounter`] does when a count would exceed `u64::MAX`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum CounterOverflow {
+    /// Counts stay at `u64::MAX`.
+    #[default]
+    Saturate,
+    /// Counts wrap around to zero.
+    Wrap,
+    /// Adding panics.
+    Panic,
+}
+
+/// A map counting the occurrences of items, using the Fx hashing algorithm.
+///
+/// ```
+/// use rustc_hash::FxCounter;
+///
+/// let counter: FxCounter<char> = "abracadabra".chars().collect();
+/// assert_eq!(counter.get(&'a'), 5);
+/// assert_eq!(counter.get(&'z'), 0);
+/// assert_eq!(counter.most_common(1), [(&'a', 5)]);
+/// ```
+#[derive(Clone)]
+pub struct FxCounter<T> {
+    counts: FxTable<T, u64>,
+    overflow: CounterOverflow,
+}
+
+impl<T> FxCounter<T> {
+    /// Creates an empty `FxCounter` that saturates on overflow.
+    pub fn new() -> FxCounter<T> {
+        Self::with_overflow(CounterOverflow::Saturate)
+    }
+
+    /// Creates an empty `FxCounter` with the given overflow policy.
+    pub fn with_overflow(overflow: CounterOverflow) -> FxCounter<T> {
+        FxCounter {
+            counts: FxTable::default(),
+            overflow,
+        }
+    }
+
+    /// Returns the overflow policy of this counter.
+    pub fn overflow(&self) -> CounterOverflow {
+        self.overflow
+    }
+
+    /// Returns the number of distinct items.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Returns `true` if no items were counted.
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Returns the sum of all counts, saturating at `u64::MAX`.
+    pub fn total(&self) -> u64 {
+        self.counts.values().fold(0, |a, &b| a.saturating_add(b))
+    }
+
+    /// Removes all items.
+    pub fn clear(&mut self) {
+        self.counts.clear();
+    }
+
+    /// Returns an iterator over the items and their counts.
+    pub fn iter(&self) -> impl Iterator<Item = (&T, u64)> {
+        self.counts.iter().map(|(t, &n)| (t, n))
+    }
+
+    /// Returns the `n` items with the highest counts, in descending order.
+    ///
+    /// The order of items with equal counts is unspecified.
+    pub fn most_common(&self, n: usize) -> Vec<(&T, u64)> {
+        let mut items: Vec<_> = self.iter().collect();
+        if n < items.len() {
+            items.select_nth_unstable_by_key(n, |&(_, c)| Reverse(c));
+            items.truncate(n);
+        }
+        items.sort_unstable_by_key(|&(_, c)| Reverse(c));
+        items
+    }
+}
+
+impl<T: Eq + Hash> FxCounter<T> {
+    /// Counts one occurrence of `item` and returns its new count.
+    pub fn add(&mut self, item: T) -> u64 {
+        self.add_n(item, 1)
+    }
+
+    /// Counts `n` occurrences of `item` and returns its new count.
+    ///
+    /// # Panics
+    ///
+    /// Panics on overflow if the policy is [`CounterOverflow::Panic`].
+    pub fn add_n(&mut self, item: T, n: u64) -> u64 {
+        let count = self.counts.entry(item).or_insert(0);
+        *count = match self.overflow {
+            CounterOverflow::Saturate => count.saturating_add(n),
+            CounterOverflow::Wrap => count.wrapping_add(n),
+            CounterOverflow::Panic => count.checked_add(n).expect("FxCounter overflowed"),
+        };
+        *count
+    }
+
+    /// Returns the count of `item`, which is zero if it was never added.
+    pub fn get<Q>(&self, item: &Q) -> u64
+    where
+        T: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.counts.get(item).copied().unwrap_or(0)
+    }
+
+    /// Removes `item` and returns its count.
+    pub fn remove<Q>(&mut self, item: &Q) -> u64
+    where
+        T: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.counts.remove(item).unwrap_or(0)
+    }
+}
+
+impl<T> Default for FxCounter<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for FxCounter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Eq + Hash> Extend<T> for FxCounter<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.add(item);
+        }
+    }
+}
+
+impl<T: Eq + Hash> FromIterator<T> for FxCounter<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut counter = Self::new();
+        counter.extend(iter);
+        counter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CounterOverflow, FxCounter};
+
+    #[test]
+    fn counts_and_most_common() {
+        let mut counter: FxCounter<u32> = [1, 2, 2, 3, 3, 3].into_iter().collect();
+        assert_eq!(counter.len(), 3);
+        assert_eq!(counter.total(), 6);
+        assert_eq!(counter.add(1), 2);
+        assert_eq!(counter.most_common(1), [(&3, 3)]);
+        assert_eq!(counter.most_common(10).len(), 3);
+        assert_eq!(counter.most_common(0), []);
+        assert_eq!(counter.remove(&3), 3);
+        assert_eq!(counter.get(&3), 0);
+    }
+
+    #[test]
+    fn overflow_policies() {
+        let mut counter = FxCounter::new();
+        counter.add_n(1_u32, u64::MAX);
+        assert_eq!(counter.add(1), u64::MAX);
+
+        let mut counter = FxCounter::with_overflow(CounterOverflow::Wrap);
+        counter.add_n(1_u32, u64::MAX);
+        assert_eq!(counter.add(1), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn overflow_panics() {
+        let mut counter = FxCounter::with_overflow(CounterOverflow::Panic);
+        counter.add_n(1_u32, u64::MAX);
+        counter.add(1);
+    }
+}
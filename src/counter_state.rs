@@ -0,0 +1,91 @@
+use core::hash::Hasher;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::FxHasher;
+
+/// Type alias for a hashmap using the `fx` hash algorithm with [`FxCounterSeededState`].
+#[cfg(feature = "std")]
+pub type FxHashMapCounter<K, V> = std::collections::HashMap<K, V, FxCounterSeededState>;
+
+/// Type alias for a hashmap using the `fx` hash algorithm with [`FxCounterSeededState`].
+#[cfg(feature = "std")]
+pub type FxHashSetCounter<V> = std::collections::HashSet<V, FxCounterSeededState>;
+
+/// [`FxCounterSeededState`] is an alternative state for `HashMap` types, giving every instance
+/// a different seed without needing a source of randomness.
+///
+/// Seeds are derived from a global counter, so they differ between instances but are the same
+/// across runs of a program that creates its states in the same order. Use `FxRandomState`
+/// (behind the `rand` feature) if seeds should be unpredictable.
+///
+/// ```
+/// use rustc_hash::FxCounterSeededState;
+///
+/// let a = FxCounterSeededState::new();
+/// let b = FxCounterSeededState::new();
+/// assert_ne!(a.seed(), b.seed());
+/// ```
+#[derive(Clone)]
+pub struct FxCounterSeededState {
+    seed: usize,
+}
+
+impl FxCounterSeededState {
+    /// Constructs a new `FxCounterSeededState` with the next seed from the global counter.
+    pub fn new() -> FxCounterSeededState {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+
+        // Hashing the counter is a bijection, so seeds stay distinct until the
+        // counter wraps, while neighbouring states get unrelated seeds. The
+        // hasher is seeded so that the first state doesn't get the same seed
+        // as `FxBuildHasher`.
+        let mut hasher = FxHasher::with_seed(crate::SEED1 as usize);
+        hasher.write_usize(n);
+        FxCounterSeededState {
+            seed: hasher.finish() as usize,
+        }
+    }
+
+    /// Returns the seed used by this `FxCounterSeededState`.
+    pub const fn seed(&self) -> usize {
+        self.seed
+    }
+}
+
+impl core::hash::BuildHasher for FxCounterSeededState {
+    type Hasher = FxHasher;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        FxHasher::with_seed(self.seed)
+    }
+}
+
+impl Default for FxCounterSeededState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FxCounterSeededState;
+
+    #[test]
+    fn cloned_counter_states_are_equal() {
+        let a = FxCounterSeededState::new();
+        let b = a.clone();
+
+        assert_eq!(a.seed, b.seed);
+    }
+
+    #[test]
+    fn counter_states_are_different() {
+        let a = FxCounterSeededState::new();
+        let b = FxCounterSeededState::new();
+
+        assert_ne!(a.seed, b.seed);
+        assert_ne!(a.seed, 0);
+    }
+}
@@ -0,0 +1,365 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::Hash;
+use core::marker::PhantomData;
+
+use crate::sketch::{self, DEFAULT_FALSE_POSITIVE_RATE, DEFAULT_SEEDS};
+use crate::FxBloomFilter;
+
+/// The width of the counters of an [`FxCountingBloomFilter`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum CounterWidth {
+    /// 4-bit counters, packed two per byte, saturating at 15.
+    #[default]
+    Four,
+    /// 8-bit counters, saturating at 255.
+    Eight,
+}
+
+impl CounterWidth {
+    /// Returns the number of bits per counter.
+    pub const fn bits(self) -> u32 {
+        match self {
+            CounterWidth::Four => 4,
+            CounterWidth::Eight => 8,
+        }
+    }
+
+    /// Returns the largest count, at which counters saturate.
+    pub const fn max(self) -> u8 {
+        match self {
+            CounterWidth::Four => 15,
+            CounterWidth::Eight => 255,
+        }
+    }
+}
+
+/// A counting Bloom filter using the Fx hashing algorithm, supporting removal.
+///
+/// It works like an [`FxBloomFilter`] whose bits are replaced with small
+/// counters: inserting an item increments its `k` counters and removing it
+/// decrements them, so the filter follows a changing set without being
+/// rebuilt. 4-bit counters take four times the memory of a Bloom filter with
+/// the same false positive rate and are enough for most uses, 8-bit counters
+/// suit items that are inserted many times.
+///
+/// A counter that reaches its maximum saturates and is never decremented
+/// again, as its true count is unknown. Removing an item that was never
+/// inserted can cause false negatives for other items that share its
+/// counters.
+///
+/// ```
+/// use rustc_hash::FxCountingBloomFilter;
+///
+/// let mut filter = FxCountingBloomFilter::with_false_positive_rate(1000, 0.01);
+/// filter.insert("session-1");
+/// filter.insert("session-2");
+/// assert!(filter.remove("session-1"));
+/// assert!(!filter.contains("session-1"));
+/// assert!(filter.contains("session-2"));
+/// ```
+pub struct FxCountingBloomFilter<T: ?Sized> {
+    pub(crate) counters: Vec<u8>,
+    pub(crate) num_counters: u64,
+    pub(crate) num_hashes: u32,
+    pub(crate) width: CounterWidth,
+    pub(crate) seeds: (usize, usize),
+    pub(crate) marker: PhantomData<fn(&T)>,
+}
+
+impl<T: ?Sized> FxCountingBloomFilter<T> {
+    /// Creates an empty filter with `num_counters` 4-bit counters and
+    /// `num_hashes` hashes per item.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_counters` or `num_hashes` is 0.
+    pub fn new(num_counters: u64, num_hashes: u32) -> FxCountingBloomFilter<T> {
+        Self::with_options(num_counters, num_hashes, CounterWidth::Four, DEFAULT_SEEDS)
+    }
+
+    /// Creates an empty filter with counters of the given width, deriving the
+    /// hashes from Fx hashes seeded with `seeds`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_counters` or `num_hashes` is 0, or if the seeds are
+    /// equal.
+    pub fn with_options(
+        num_counters: u64,
+        num_hashes: u32,
+        width: CounterWidth,
+        seeds: (usize, usize),
+    ) -> FxCountingBloomFilter<T> {
+        assert!(
+            num_counters > 0,
+            "a Bloom filter needs at least one counter"
+        );
+        assert!(num_hashes > 0, "a Bloom filter needs at least one hash");
+        assert_ne!(seeds.0, seeds.1, "the seeds must be different");
+        let bytes = usize::try_from(counter_bytes(num_counters, width)).expect("too many counters");
+        FxCountingBloomFilter {
+            counters: vec![0; bytes],
+            num_counters,
+            num_hashes,
+            width,
+            seeds,
+            marker: PhantomData,
+        }
+    }
+
+    /// Creates an empty filter with 4-bit counters sized to hold
+    /// `expected_items` items with a false positive rate of at most `rate`.
+    ///
+    /// The number of counters and hashes are those of
+    /// [`FxBloomFilter::with_false_positive_rate`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rate` is not between 0 and 1, exclusive.
+    pub fn with_false_positive_rate(expected_items: usize, rate: f64) -> FxCountingBloomFilter<T> {
+        let (num_counters, num_hashes) = sketch::bloom_parameters(expected_items, rate);
+        Self::new(num_counters, num_hashes)
+    }
+
+    /// Returns the number of counters.
+    pub fn num_counters(&self) -> u64 {
+        self.num_counters
+    }
+
+    /// Returns the number of hashes per item.
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    /// Returns the width of the counters.
+    pub fn counter_width(&self) -> CounterWidth {
+        self.width
+    }
+
+    /// Returns the seeds of the two hash functions.
+    pub fn seeds(&self) -> (usize, usize) {
+        self.seeds
+    }
+
+    /// Returns `true` if no item is in the filter.
+    pub fn is_empty(&self) -> bool {
+        self.counters.iter().all(|&c| c == 0)
+    }
+
+    /// Removes all items, keeping the parameters.
+    pub fn clear(&mut self) {
+        self.counters.fill(0);
+    }
+
+    /// Inserts an item.
+    pub fn insert(&mut self, item: &T)
+    where
+        T: Hash,
+    {
+        self.insert_hashes(sketch::hash_pair(self.seeds, item));
+    }
+
+    /// Removes an item, returning `false` if it was definitely not in the
+    /// filter, in which case the filter is unchanged.
+    pub fn remove(&mut self, item: &T) -> bool
+    where
+        T: Hash,
+    {
+        let hashes = sketch::hash_pair(self.seeds, item);
+        if self.min_count(hashes) == 0 {
+            return false;
+        }
+        for i in 0..self.num_hashes {
+            let index = self.index(sketch::nth_hash(hashes, i));
+            let count = self.get(index);
+            if count != self.width.max() {
+                self.set(index, count - 1);
+            }
+        }
+        true
+    }
+
+    /// Returns `false` if the item is definitely not in the filter and `true`
+    /// if it probably is.
+    pub fn contains(&self, item: &T) -> bool
+    where
+        T: Hash,
+    {
+        self.estimate_count(item) > 0
+    }
+
+    /// Returns an upper bound of the number of times the item was inserted
+    /// and not removed, up to the maximum count of the counters.
+    pub fn estimate_count(&self, item: &T) -> u8
+    where
+        T: Hash,
+    {
+        self.min_count(sketch::hash_pair(self.seeds, item))
+    }
+
+    /// Returns a Bloom filter holding the same items, with one bit for every
+    /// counter.
+    pub fn to_bloom_filter(&self) -> FxBloomFilter<T> {
+        let mut filter = FxBloomFilter::with_seeds(self.num_counters, self.num_hashes, self.seeds);
+        for index in 0..self.num_counters {
+            if self.get(index) != 0 {
+                filter.words[(index / 64) as usize] |= 1 << (index % 64);
+            }
+        }
+        filter
+    }
+
+    fn insert_hashes(&mut self, hashes: (u64, u64)) {
+        for i in 0..self.num_hashes {
+            let index = self.index(sketch::nth_hash(hashes, i));
+            let count = self.get(index);
+            if count != self.width.max() {
+                self.set(index, count + 1);
+            }
+        }
+    }
+
+    fn min_count(&self, hashes: (u64, u64)) -> u8 {
+        (0..self.num_hashes)
+            .map(|i| self.get(self.index(sketch::nth_hash(hashes, i))))
+            .min()
+            .unwrap_or(0)
+    }
+
+    fn index(&self, hash: u64) -> u64 {
+        hash % self.num_counters
+    }
+
+    fn get(&self, index: u64) -> u8 {
+        match self.width {
+            CounterWidth::Four => self.counters[(index / 2) as usize] >> (index % 2 * 4) & 0xf,
+            CounterWidth::Eight => self.counters[index as usize],
+        }
+    }
+
+    fn set(&mut self, index: u64, count: u8) {
+        match self.width {
+            CounterWidth::Four => {
+                let shift = index % 2 * 4;
+                let byte = &mut self.counters[(index / 2) as usize];
+                *byte = *byte & !(0xf << shift) | count << shift;
+            }
+            CounterWidth::Eight => self.counters[index as usize] = count,
+        }
+    }
+}
+
+/// Returns the number of bytes holding `num_counters` counters.
+pub(crate) fn counter_bytes(num_counters: u64, width: CounterWidth) -> u64 {
+    match width {
+        CounterWidth::Four => num_counters.div_ceil(2),
+        CounterWidth::Eight => num_counters,
+    }
+}
+
+impl<T: Hash> FromIterator<T> for FxCountingBloomFilter<T> {
+    /// Collects the items into a filter with 4-bit counters sized for their
+    /// number and a false positive rate of 1%.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> FxCountingBloomFilter<T> {
+        let hashes: Vec<_> = iter
+            .into_iter()
+            .map(|item| sketch::hash_pair(DEFAULT_SEEDS, &item))
+            .collect();
+        let mut filter = Self::with_false_positive_rate(hashes.len(), DEFAULT_FALSE_POSITIVE_RATE);
+        for h in hashes {
+            filter.insert_hashes(h);
+        }
+        filter
+    }
+}
+
+impl<T: Hash> Extend<T> for FxCountingBloomFilter<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.insert(&item);
+        }
+    }
+}
+
+impl<T: ?Sized> Clone for FxCountingBloomFilter<T> {
+    fn clone(&self) -> Self {
+        FxCountingBloomFilter {
+            counters: self.counters.clone(),
+            num_counters: self.num_counters,
+            num_hashes: self.num_hashes,
+            width: self.width,
+            seeds: self.seeds,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> PartialEq for FxCountingBloomFilter<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.num_counters == other.num_counters
+            && self.num_hashes == other.num_hashes
+            && self.width == other.width
+            && self.seeds == other.seeds
+            && self.counters == other.counters
+    }
+}
+
+impl<T: ?Sized> Eq for FxCountingBloomFilter<T> {}
+
+impl<T: ?Sized> fmt::Debug for FxCountingBloomFilter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FxCountingBloomFilter")
+            .field("num_counters", &self.num_counters)
+            .field("num_hashes", &self.num_hashes)
+            .field("counter_width", &self.width)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CounterWidth, FxCountingBloomFilter};
+
+    #[test]
+    fn insert_and_remove() {
+        for width in [CounterWidth::Four, CounterWidth::Eight] {
+            let mut filter = FxCountingBloomFilter::with_options(20_001, 7, width, (1, 2));
+            for i in 0..1000_u32 {
+                filter.insert(&i);
+            }
+            filter.insert(&0);
+            assert_eq!(filter.estimate_count(&0), 2);
+            for i in 0..500 {
+                assert!(filter.remove(&i));
+            }
+            assert!((500..1000).all(|i| filter.contains(&i)));
+            assert!(filter.contains(&0));
+            let remaining = (0..500).filter(|i| filter.contains(i)).count();
+            assert!(remaining < 20, "{remaining} removed items still found");
+
+            let bloom = filter.to_bloom_filter();
+            assert!((500..1000).all(|i| bloom.contains(&i)));
+
+            filter.remove(&0);
+            for i in 500..1000 {
+                filter.remove(&i);
+            }
+            assert!(filter.is_empty());
+        }
+    }
+
+    #[test]
+    fn saturated_counters_stick() {
+        let mut filter = FxCountingBloomFilter::new(64, 1);
+        for _ in 0..20 {
+            filter.insert("hot");
+        }
+        assert_eq!(filter.estimate_count("hot"), 15);
+        for _ in 0..20 {
+            filter.remove("hot");
+        }
+        assert!(filter.contains("hot"));
+    }
+}
@@ -0,0 +1,513 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::Hash;
+use core::marker::PhantomData;
+
+use crate::sketch::{self, DEFAULT_FALSE_POSITIVE_RATE, DEFAULT_SEEDS};
+
+/// The number of fingerprints in a bucket.
+pub(crate) const BUCKET_SIZE: u64 = 4;
+
+/// The number of fingerprints relocated before an insertion gives up.
+const MAX_KICKS: u32 = 500;
+
+/// The load factor up to which insertions are expected to succeed.
+const MAX_LOAD: f64 = 0.95;
+
+/// An error returned when inserting into a full [`FxCuckooFilter`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CuckooFilterFull;
+
+impl fmt::Display for CuckooFilterFull {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the cuckoo filter is full")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CuckooFilterFull {}
+
+/// A cuckoo filter using the Fx hashing algorithm.
+///
+/// A cuckoo filter stores a short fingerprint of every item in one of two
+/// buckets of four slots, so like a Bloom filter it answers membership
+/// queries with false positives but no false negatives. Unlike a Bloom
+/// filter it supports removal, and below a false positive rate of about 3% it
+/// takes less space. Both the bucket and the fingerprint of an item are
+/// derived from two Fx hashes with different seeds, and the second bucket is
+/// derived from the first and the fingerprint alone, so fingerprints can be
+/// moved between their buckets to make room.
+///
+/// The false positive rate is about `8 / 2^fingerprint_bits`. Insertions
+/// start failing when the filter is around 95% full; one fingerprint that
+/// could not be placed is kept aside, so a failed insertion never loses an
+/// item, but all further insertions fail until an item is removed.
+///
+/// ```
+/// use rustc_hash::FxCuckooFilter;
+///
+/// let mut filter = FxCuckooFilter::with_false_positive_rate(1000, 0.001);
+/// filter.insert("alice").unwrap();
+/// filter.insert("bob").unwrap();
+/// assert!(filter.contains("alice"));
+/// assert!(filter.remove("alice"));
+/// assert!(!filter.contains("alice"));
+/// assert_eq!(filter.len(), 1);
+/// ```
+pub struct FxCuckooFilter<T: ?Sized> {
+    pub(crate) words: Vec<u64>,
+    pub(crate) num_buckets: u64,
+    pub(crate) fingerprint_bits: u32,
+    pub(crate) len: u64,
+    pub(crate) victim: Option<(u64, u32)>,
+    pub(crate) seeds: (usize, usize),
+    pub(crate) marker: PhantomData<fn(&T)>,
+}
+
+impl<T: ?Sized> FxCuckooFilter<T> {
+    /// Creates an empty filter with room for at least `capacity` fingerprints
+    /// of `fingerprint_bits` bits.
+    ///
+    /// The number of buckets is rounded up to a power of two.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fingerprint_bits` is not between 1 and 32.
+    pub fn new(capacity: usize, fingerprint_bits: u32) -> FxCuckooFilter<T> {
+        Self::with_seeds(capacity, fingerprint_bits, DEFAULT_SEEDS)
+    }
+
+    /// Creates an empty filter like [`new`](Self::new), deriving buckets and
+    /// fingerprints from Fx hashes seeded with `seeds`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fingerprint_bits` is not between 1 and 32, or if the seeds
+    /// are equal.
+    pub fn with_seeds(
+        capacity: usize,
+        fingerprint_bits: u32,
+        seeds: (usize, usize),
+    ) -> FxCuckooFilter<T> {
+        let num_buckets = (capacity as u64).div_ceil(BUCKET_SIZE).max(1);
+        Self::with_buckets(num_buckets.next_power_of_two(), fingerprint_bits, seeds)
+    }
+
+    fn with_buckets(
+        num_buckets: u64,
+        fingerprint_bits: u32,
+        seeds: (usize, usize),
+    ) -> FxCuckooFilter<T> {
+        assert!(
+            (1..=32).contains(&fingerprint_bits),
+            "fingerprints must have between 1 and 32 bits"
+        );
+        assert_ne!(seeds.0, seeds.1, "the seeds must be different");
+        let words =
+            usize::try_from(slot_words(num_buckets, fingerprint_bits)).expect("too many buckets");
+        FxCuckooFilter {
+            words: vec![0; words],
+            num_buckets,
+            fingerprint_bits,
+            len: 0,
+            victim: None,
+            seeds,
+            marker: PhantomData,
+        }
+    }
+
+    /// Creates an empty filter sized to hold `expected_items` items with a
+    /// false positive rate of at most `rate`.
+    ///
+    /// It uses fingerprints of `log2(8 / rate)` bits, rounded up, and enough
+    /// buckets to stay below 95% load.
+    ///
+    /// ```
+    /// use rustc_hash::FxCuckooFilter;
+    ///
+    /// let filter = FxCuckooFilter::<str>::with_false_positive_rate(1000, 0.01);
+    /// assert_eq!(filter.fingerprint_bits(), 10);
+    /// assert_eq!(filter.capacity(), 2048);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rate` is not between 0 and 1, exclusive.
+    pub fn with_false_positive_rate(expected_items: usize, rate: f64) -> FxCuckooFilter<T> {
+        assert!(
+            rate > 0.0 && rate < 1.0,
+            "the false positive rate must be between 0 and 1"
+        );
+        let bits = sketch::ceil(3.0 - sketch::log2(rate)).min(32) as u32;
+        let capacity = sketch::ceil(expected_items as f64 / MAX_LOAD);
+        Self::new(usize::try_from(capacity).expect("too many items"), bits)
+    }
+
+    /// Returns the number of items in the filter.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if no item is in the filter.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of fingerprints the filter has room for.
+    pub fn capacity(&self) -> u64 {
+        self.num_buckets * BUCKET_SIZE
+    }
+
+    /// Returns the fraction of the capacity that is used.
+    pub fn load_factor(&self) -> f64 {
+        self.len as f64 / self.capacity() as f64
+    }
+
+    /// Returns the number of buckets, a power of two.
+    pub fn num_buckets(&self) -> u64 {
+        self.num_buckets
+    }
+
+    /// Returns the number of bits per fingerprint.
+    pub fn fingerprint_bits(&self) -> u32 {
+        self.fingerprint_bits
+    }
+
+    /// Returns the seeds of the two hash functions.
+    pub fn seeds(&self) -> (usize, usize) {
+        self.seeds
+    }
+
+    /// Removes all items, keeping the parameters.
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+        self.len = 0;
+        self.victim = None;
+    }
+
+    /// Inserts an item.
+    ///
+    /// Inserting an item twice stores two fingerprints, so that it is still
+    /// found after being removed once.
+    pub fn insert(&mut self, item: &T) -> Result<(), CuckooFilterFull>
+    where
+        T: Hash,
+    {
+        self.insert_hashes(sketch::hash_pair(self.seeds, item))
+    }
+
+    /// Returns `false` if the item is definitely not in the filter and `true`
+    /// if it probably is.
+    pub fn contains(&self, item: &T) -> bool
+    where
+        T: Hash,
+    {
+        let (index, fingerprint) = self.locate(sketch::hash_pair(self.seeds, item));
+        let alt = self.alt_index(index, fingerprint);
+        self.find(index, fingerprint).is_some()
+            || self.find(alt, fingerprint).is_some()
+            || self
+                .victim
+                .is_some_and(|(i, f)| f == fingerprint && (i == index || i == alt))
+    }
+
+    /// Removes an item, returning `false` if it was definitely not in the
+    /// filter, in which case the filter is unchanged.
+    ///
+    /// Removing an item that was never inserted can remove another item with
+    /// the same fingerprint.
+    pub fn remove(&mut self, item: &T) -> bool
+    where
+        T: Hash,
+    {
+        let (index, fingerprint) = self.locate(sketch::hash_pair(self.seeds, item));
+        let alt = self.alt_index(index, fingerprint);
+        if let Some(slot) = self
+            .find(index, fingerprint)
+            .or_else(|| self.find(alt, fingerprint))
+        {
+            self.set(slot, 0);
+        } else if self
+            .victim
+            .is_some_and(|(i, f)| f == fingerprint && (i == index || i == alt))
+        {
+            self.victim = None;
+        } else {
+            return false;
+        }
+        self.len -= 1;
+        // There is a free slot now, so try to place the fingerprint that was
+        // kept aside.
+        if let Some((i, f)) = self.victim.take() {
+            self.place(i, f);
+        }
+        true
+    }
+
+    /// Shrinks the filter to the fewest buckets that hold its items.
+    ///
+    /// The number of buckets is halved while the items fit in at most 95% of
+    /// the smaller capacity. Fingerprints keep their bucket modulo the new
+    /// number of buckets, so the items are moved without being hashed again,
+    /// and the false positive rate grows only by the fewer buckets compared.
+    pub fn shrink_to_fit(&mut self) {
+        while self.num_buckets > 1 && self.len as f64 <= (self.capacity() / 2) as f64 * MAX_LOAD {
+            match self.halved() {
+                Some(smaller) => *self = smaller,
+                None => break,
+            }
+        }
+    }
+
+    /// Returns the filter with half the buckets, or `None` if the
+    /// fingerprints do not fit.
+    fn halved(&self) -> Option<FxCuckooFilter<T>> {
+        let mut smaller =
+            Self::with_buckets(self.num_buckets / 2, self.fingerprint_bits, self.seeds);
+        let mask = smaller.num_buckets - 1;
+        let fingerprints = (0..self.capacity())
+            .map(|slot| (slot / BUCKET_SIZE, self.get(slot)))
+            .filter(|&(_, f)| f != 0)
+            .chain(self.victim);
+        for (index, fingerprint) in fingerprints {
+            if !smaller.place(index & mask, fingerprint) {
+                return None;
+            }
+        }
+        smaller.len = self.len;
+        Some(smaller)
+    }
+
+    /// Returns the number of stored fingerprints, including the one kept
+    /// aside.
+    #[cfg(feature = "serde")]
+    pub(crate) fn stored(&self) -> u64 {
+        let slots = (0..self.capacity()).filter(|&slot| self.get(slot) != 0);
+        slots.count() as u64 + u64::from(self.victim.is_some())
+    }
+
+    fn insert_hashes(&mut self, hashes: (u64, u64)) -> Result<(), CuckooFilterFull> {
+        if self.victim.is_some() {
+            return Err(CuckooFilterFull);
+        }
+        let (index, fingerprint) = self.locate(hashes);
+        self.place(index, fingerprint);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Stores a fingerprint in bucket `index` or its alternate bucket,
+    /// relocating other fingerprints as needed.
+    ///
+    /// Returns `false` if it gave up, keeping the last displaced fingerprint
+    /// aside.
+    fn place(&mut self, mut index: u64, mut fingerprint: u32) -> bool {
+        let alt = self.alt_index(index, fingerprint);
+        if self.put(index, fingerprint) || self.put(alt, fingerprint) {
+            return true;
+        }
+        // The relocations only need to be unpredictable enough to avoid
+        // cycles, so a xorshift seeded from the fingerprint will do.
+        let mut state = (index ^ u64::from(fingerprint).rotate_left(32)) | 1;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        if next() & 1 == 0 {
+            index = alt;
+        }
+        for _ in 0..MAX_KICKS {
+            let slot = index * BUCKET_SIZE + next() % BUCKET_SIZE;
+            let evicted = self.get(slot);
+            self.set(slot, fingerprint);
+            fingerprint = evicted;
+            index = self.alt_index(index, fingerprint);
+            if self.put(index, fingerprint) {
+                return true;
+            }
+        }
+        self.victim = Some((index, fingerprint));
+        false
+    }
+
+    /// Stores a fingerprint in a free slot of bucket `index`, returning
+    /// `false` if it is full.
+    fn put(&mut self, index: u64, fingerprint: u32) -> bool {
+        match self.find(index, 0) {
+            Some(slot) => {
+                self.set(slot, fingerprint);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the slot of bucket `index` holding `fingerprint`.
+    fn find(&self, index: u64, fingerprint: u32) -> Option<u64> {
+        let first = index * BUCKET_SIZE;
+        (first..first + BUCKET_SIZE).find(|&slot| self.get(slot) == fingerprint)
+    }
+
+    /// Returns the first bucket and the non-zero fingerprint of an item.
+    fn locate(&self, (h1, h2): (u64, u64)) -> (u64, u32) {
+        let fingerprint = (h2 >> (64 - self.fingerprint_bits)) as u32;
+        (h1 & (self.num_buckets - 1), fingerprint.max(1))
+    }
+
+    /// Returns the other bucket of a fingerprint stored in bucket `index`.
+    ///
+    /// The buckets are related by XOR with a hash of the fingerprint, so each
+    /// is the alternate of the other.
+    fn alt_index(&self, index: u64, fingerprint: u32) -> u64 {
+        let hash = u64::from(fingerprint)
+            .wrapping_mul(0xf1357aea2e62a9c5)
+            .rotate_left(26);
+        (index ^ hash) & (self.num_buckets - 1)
+    }
+
+    fn get(&self, slot: u64) -> u32 {
+        let bit = slot * u64::from(self.fingerprint_bits);
+        let (word, offset) = ((bit / 64) as usize, bit % 64);
+        let mut value = self.words[word] >> offset;
+        if offset + u64::from(self.fingerprint_bits) > 64 {
+            value |= self.words[word + 1] << (64 - offset);
+        }
+        (value & self.fingerprint_mask()) as u32
+    }
+
+    fn set(&mut self, slot: u64, fingerprint: u32) {
+        let bit = slot * u64::from(self.fingerprint_bits);
+        let (word, offset) = ((bit / 64) as usize, bit % 64);
+        let mask = self.fingerprint_mask();
+        let value = u64::from(fingerprint);
+        self.words[word] = self.words[word] & !(mask << offset) | value << offset;
+        if offset + u64::from(self.fingerprint_bits) > 64 {
+            let shift = 64 - offset;
+            self.words[word + 1] = self.words[word + 1] & !(mask >> shift) | value >> shift;
+        }
+    }
+
+    fn fingerprint_mask(&self) -> u64 {
+        (1 << self.fingerprint_bits) - 1
+    }
+}
+
+/// Returns the number of words holding the fingerprints of `num_buckets`
+/// buckets.
+pub(crate) fn slot_words(num_buckets: u64, fingerprint_bits: u32) -> u64 {
+    (num_buckets * BUCKET_SIZE * u64::from(fingerprint_bits)).div_ceil(64)
+}
+
+impl<T: Hash> FromIterator<T> for FxCuckooFilter<T> {
+    /// Collects the items into a filter sized for their number and a false
+    /// positive rate of 1%.
+    ///
+    /// If the items do not fit, the filter is built again with twice the
+    /// buckets.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> FxCuckooFilter<T> {
+        let hashes: Vec<_> = iter
+            .into_iter()
+            .map(|item| sketch::hash_pair(DEFAULT_SEEDS, &item))
+            .collect();
+        let mut filter = Self::with_false_positive_rate(hashes.len(), DEFAULT_FALSE_POSITIVE_RATE);
+        while hashes.iter().any(|&h| filter.insert_hashes(h).is_err()) {
+            filter = Self::with_buckets(
+                filter.num_buckets * 2,
+                filter.fingerprint_bits,
+                DEFAULT_SEEDS,
+            );
+        }
+        filter
+    }
+}
+
+impl<T: ?Sized> Clone for FxCuckooFilter<T> {
+    fn clone(&self) -> Self {
+        FxCuckooFilter {
+            words: self.words.clone(),
+            num_buckets: self.num_buckets,
+            fingerprint_bits: self.fingerprint_bits,
+            len: self.len,
+            victim: self.victim,
+            seeds: self.seeds,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> PartialEq for FxCuckooFilter<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.num_buckets == other.num_buckets
+            && self.fingerprint_bits == other.fingerprint_bits
+            && self.seeds == other.seeds
+            && self.len == other.len
+            && self.victim == other.victim
+            && self.words == other.words
+    }
+}
+
+impl<T: ?Sized> Eq for FxCuckooFilter<T> {}
+
+impl<T: ?Sized> fmt::Debug for FxCuckooFilter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FxCuckooFilter")
+            .field("len", &self.len)
+            .field("num_buckets", &self.num_buckets)
+            .field("fingerprint_bits", &self.fingerprint_bits)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CuckooFilterFull, FxCuckooFilter};
+
+    #[test]
+    fn insert_remove_and_shrink() {
+        let mut filter = FxCuckooFilter::with_seeds(8192, 12, (1, 2));
+        for i in 0..7000_u32 {
+            filter.insert(&i).unwrap();
+        }
+        assert!((0..7000).all(|i| filter.contains(&i)));
+        let false_positives = (7000..107_000).filter(|i| filter.contains(i)).count();
+        assert!(false_positives < 500, "{false_positives} false positives");
+
+        for i in 0..6000 {
+            assert!(filter.remove(&i));
+        }
+        assert_eq!(filter.len(), 1000);
+        filter.shrink_to_fit();
+        assert_eq!(filter.capacity(), 2048);
+        assert!((6000..7000).all(|i| filter.contains(&i)));
+
+        filter.clear();
+        assert!(filter.is_empty() && !filter.contains(&6500));
+    }
+
+    #[test]
+    fn full_filter_keeps_items() {
+        // Odd fingerprint widths straddle word boundaries.
+        let mut filter = FxCuckooFilter::new(64, 7);
+        let mut inserted = 0_u32;
+        while filter.insert(&inserted).is_ok() {
+            inserted += 1;
+        }
+        assert!(inserted > 48, "only {inserted} items fit");
+        assert_eq!(filter.insert(&u32::MAX), Err(CuckooFilterFull));
+        assert!((0..inserted).all(|i| filter.contains(&i)));
+
+        assert!(filter.remove(&0));
+        assert!(filter.insert(&u32::MAX).is_ok());
+        assert!((1..inserted).all(|i| filter.contains(&i)));
+    }
+
+    #[test]
+    fn collect() {
+        let filter: FxCuckooFilter<u64> = (0..10_000).collect();
+        assert_eq!(filter.len(), 10_000);
+        assert!((0..10_000).all(|i| filter.contains(&i)));
+    }
+}
@@ -0,0 +1,47 @@
+use core::hash::Hash;
+
+use crate::FxBuildHasher;
+
+/// Type alias for a [`dashmap::DashMap`] that uses the Fx hashing algorithm.
+///
+/// The hasher is used both for selecting a shard and for the lookup within it.
+///
+/// ```
+/// use rustc_hash::FxDashMap;
+///
+/// let map: FxDashMap<u32, u32> = FxDashMap::default();
+/// map.insert(22, 44);
+/// assert_eq!(*map.get(&22).unwrap(), 44);
+/// ```
+pub type FxDashMap<K, V> = dashmap::DashMap<K, V, FxBuildHasher>;
+
+/// Type alias for a [`dashmap::DashSet`] that uses the Fx hashing algorithm.
+pub type FxDashSet<T> = dashmap::DashSet<T, FxBuildHasher>;
+
+/// Creates an empty [`FxDashMap`] with room for at least `capacity` elements.
+pub fn dash_map_with_capacity<K: Eq + Hash, V>(capacity: usize) -> FxDashMap<K, V> {
+    FxDashMap::with_capacity_and_hasher(capacity, FxBuildHasher)
+}
+
+/// Creates an empty [`FxDashSet`] with room for at least `capacity` elements.
+pub fn dash_set_with_capacity<T: Eq + Hash>(capacity: usize) -> FxDashSet<T> {
+    FxDashSet::with_capacity_and_hasher(capacity, FxBuildHasher)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{dash_map_with_capacity, dash_set_with_capacity};
+
+    #[test]
+    fn with_capacity() {
+        let map = dash_map_with_capacity(64);
+        assert!(map.capacity() >= 64);
+        map.insert(1_u32, 2_u32);
+        assert_eq!(*map.get(&1).unwrap(), 2);
+
+        let set = dash_set_with_capacity(64);
+        assert!(set.capacity() >= 64);
+        assert!(set.insert(1_u32));
+        assert!(!set.insert(1_u32));
+    }
+}
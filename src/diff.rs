@@ -0,0 +1,189 @@
+use alloc::vec::Vec;
+
+/// The differences between two maps, borrowing from both.
+///
+/// The entries in each list are in unspecified order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapDiff<'a, K, V> {
+    /// The entries of keys only in the new map.
+    pub added: Vec<(&'a K, &'a V)>,
+    /// The entries of keys only in the old map.
+    pub removed: Vec<(&'a K, &'a V)>,
+    /// The keys in both maps with different values, with the old and the new
+    /// value.
+    pub changed: Vec<(&'a K, &'a V, &'a V)>,
+}
+
+impl<K, V> MapDiff<'_, K, V> {
+    /// Returns `true` if the maps have equal entries.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Maps that [`fx_diff`] can compare.
+pub trait FxDiffExt<K, V> {
+    /// Returns the differences from `self` to `new`.
+    fn diff<'a>(&'a self, new: &'a Self) -> MapDiff<'a, K, V>;
+}
+
+/// Returns the keys added to, removed from and changed in `new` compared to
+/// `old`.
+///
+/// Every key of `old` is looked up in `new` once. The keys of `new` are only
+/// looked up in `old` if that did not already find all of them, so comparing
+/// maps that only differ in values or lost keys walks a single table.
+/// `FxPrehashedMap`s are compared with their stored hashes and hash no keys.
+///
+/// ```
+/// use rustc_hash::{fx_diff, FxHashMap};
+///
+/// let old: FxHashMap<&str, u32> = [("a", 1), ("b", 2), ("c", 3)].into_iter().collect();
+/// let new: FxHashMap<&str, u32> = [("b", 2), ("c", 30), ("d", 4)].into_iter().collect();
+/// let diff = fx_diff(&old, &new);
+/// assert_eq!(diff.added, [(&"d", &4)]);
+/// assert_eq!(diff.removed, [(&"a", &1)]);
+/// assert_eq!(diff.changed, [(&"c", &3, &30)]);
+/// ```
+pub fn fx_diff<'a, K, V, M: FxDiffExt<K, V>>(old: &'a M, new: &'a M) -> MapDiff<'a, K, V> {
+    old.diff(new)
+}
+
+/// Diffs two maps given a lookup of keys of `old` in `new` and of keys of
+/// `new` in `old`.
+fn diff_with<'a, K: 'a, V: PartialEq + 'a>(
+    old: impl Iterator<Item = (&'a K, &'a V)>,
+    new: impl Iterator<Item = (&'a K, &'a V)>,
+    new_len: usize,
+    in_new: impl Fn(&'a K) -> Option<&'a V>,
+    in_old: impl Fn(&'a K) -> bool,
+) -> MapDiff<'a, K, V> {
+    let mut diff = MapDiff {
+        added: Vec::new(),
+        removed: Vec::new(),
+        changed: Vec::new(),
+    };
+    let mut shared = 0;
+    for (k, v) in old {
+        match in_new(k) {
+            Some(new_v) => {
+                shared += 1;
+                if v != new_v {
+                    diff.changed.push((k, v, new_v));
+                }
+            }
+            None => diff.removed.push((k, v)),
+        }
+    }
+    if shared < new_len {
+        diff.added.reserve(new_len - shared);
+        diff.added.extend(new.filter(|(k, _)| !in_old(k)));
+    }
+    diff
+}
+
+macro_rules! impl_diff {
+    ($map:ident) => {
+        impl<K: Eq + Hash, V: PartialEq, S: BuildHasher> FxDiffExt<K, V> for $map<K, V, S> {
+            fn diff<'a>(&'a self, new: &'a Self) -> MapDiff<'a, K, V> {
+                diff_with(
+                    self.iter(),
+                    new.iter(),
+                    new.len(),
+                    |k| new.get(k),
+                    |k| self.contains_key(k),
+                )
+            }
+        }
+    };
+}
+
+#[cfg(feature = "std")]
+mod std_impls {
+    use core::hash::{BuildHasher, Hash};
+    use std::collections::HashMap;
+
+    use super::*;
+
+    impl_diff!(HashMap);
+}
+
+#[cfg(feature = "hashbrown")]
+mod hashbrown_impls {
+    use core::hash::{BuildHasher, Hash};
+
+    use hashbrown::HashMap;
+
+    use super::*;
+    use crate::FxPrehashedMap;
+
+    impl_diff!(HashMap);
+
+    impl<K: Eq + Hash, V: PartialEq> FxDiffExt<K, V> for FxPrehashedMap<K, V> {
+        fn diff<'a>(&'a self, new: &'a Self) -> MapDiff<'a, K, V> {
+            let mut diff = MapDiff {
+                added: Vec::new(),
+                removed: Vec::new(),
+                changed: Vec::new(),
+            };
+            let mut shared = 0;
+            for (hash, k, v) in self.iter_with_hash() {
+                match new.get_with_hash(hash, k) {
+                    Some(new_v) => {
+                        shared += 1;
+                        if v != new_v {
+                            diff.changed.push((k, v, new_v));
+                        }
+                    }
+                    None => diff.removed.push((k, v)),
+                }
+            }
+            if shared < new.len() {
+                diff.added.extend(
+                    new.iter_with_hash()
+                        .filter(|&(hash, k, _)| !self.contains_key_with_hash(hash, k))
+                        .map(|(_, k, v)| (k, v)),
+                );
+            }
+            diff
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{fx_diff, FxTable};
+
+    #[test]
+    fn finds_all_differences() {
+        let old: FxTable<u32, u32> = (0..1000).map(|i| (i, i)).collect();
+        let new: FxTable<u32, u32> = (500..1500).map(|i| (i, i % 700)).collect();
+        let diff = fx_diff(&old, &new);
+
+        let mut added: Vec<u32> = diff.added.iter().map(|(k, _)| **k).collect();
+        added.sort_unstable();
+        assert_eq!(added, (1000..1500).collect::<Vec<_>>());
+        assert_eq!(diff.removed.len(), 500);
+        assert!(diff.removed.iter().all(|(k, _)| **k < 500));
+        assert_eq!(diff.changed.len(), 300);
+        assert!(diff.changed.iter().all(|(k, a, b)| **k >= 700 && a != b));
+
+        assert!(fx_diff(&old, &old).is_empty());
+        assert_eq!(fx_diff(&new, &old).added.len(), 500);
+    }
+
+    #[cfg(feature = "hashbrown")]
+    #[test]
+    fn prehashed() {
+        use crate::FxPrehashedMap;
+
+        let old: FxPrehashedMap<u32, u32> = (0..100).map(|i| (i, i)).collect();
+        let new: FxPrehashedMap<u32, u32> = (50..150).map(|i| (i, i / 2)).collect();
+        let diff = fx_diff(&old, &new);
+        assert_eq!(diff.added.len(), 50);
+        assert_eq!(diff.removed.len(), 50);
+        assert_eq!(diff.changed.len(), 50);
+    }
+}
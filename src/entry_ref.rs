@@ -0,0 +1,94 @@
+use alloc::borrow::ToOwned;
+use core::hash::Hash;
+
+use hashbrown::Equivalent;
+
+use crate::{FxHashMapAlloc, FxMapState};
+
+/// Type alias for a [`hashbrown`] entry with a borrowed key, as returned by
+/// [`FxHashMapAlloc::entry_ref`](hashbrown::HashMap::entry_ref).
+///
+/// The borrowed key is only turned into an owned key when a vacant entry is
+/// filled, which avoids allocating e.g. a `String` for keys that are present.
+pub type FxEntryRef<'a, 'b, K, Q, V> = hashbrown::hash_map::EntryRef<'a, 'b, K, Q, V, FxMapState>;
+
+/// Borrowed-key helpers for [`FxHashMapAlloc`].
+///
+/// These are shortcuts over `entry_ref` that only clone the key when it is
+/// inserted. `hashbrown` no longer offers a raw entry API; use
+/// `FxPrehashedMap` to look up keys by a precomputed hash instead.
+///
+/// ```
+/// use rustc_hash::{FxHashMapAlloc, FxHashMapAllocExt};
+///
+/// let mut counts: FxHashMapAlloc<String, u32> = FxHashMapAlloc::default();
+/// for word in "a b a".split(' ') {
+///     *counts.get_or_default_ref(word) += 1;
+/// }
+/// assert_eq!(counts["a"], 2);
+/// ```
+pub trait FxHashMapAllocExt<K, V> {
+    /// Returns the value of `key`, inserting the result of `default` with an
+    /// owned copy of `key` first if the key is missing.
+    fn get_or_insert_with_ref<Q>(&mut self, key: &Q, default: impl FnOnce() -> V) -> &mut V
+    where
+        Q: Hash + Equivalent<K> + ToOwned<Owned = K> + ?Sized;
+
+    /// Returns the value of `key`, inserting `V::default()` with an owned
+    /// copy of `key` first if the key is missing.
+    fn get_or_default_ref<Q>(&mut self, key: &Q) -> &mut V
+    where
+        Q: Hash + Equivalent<K> + ToOwned<Owned = K> + ?Sized,
+        V: Default,
+    {
+        self.get_or_insert_with_ref(key, V::default)
+    }
+
+    /// Inserts a value for a borrowed key, returning the previous value.
+    ///
+    /// The key is only cloned if it is not present yet.
+    fn insert_ref<Q>(&mut self, key: &Q, value: V) -> Option<V>
+    where
+        Q: Hash + Equivalent<K> + ToOwned<Owned = K> + ?Sized;
+}
+
+impl<K: Eq + Hash, V> FxHashMapAllocExt<K, V> for FxHashMapAlloc<K, V> {
+    fn get_or_insert_with_ref<Q>(&mut self, key: &Q, default: impl FnOnce() -> V) -> &mut V
+    where
+        Q: Hash + Equivalent<K> + ToOwned<Owned = K> + ?Sized,
+    {
+        self.entry_ref(key).or_insert_with(default)
+    }
+
+    fn insert_ref<Q>(&mut self, key: &Q, value: V) -> Option<V>
+    where
+        Q: Hash + Equivalent<K> + ToOwned<Owned = K> + ?Sized,
+    {
+        match self.entry_ref(key) {
+            FxEntryRef::Occupied(mut e) => Some(e.insert(value)),
+            FxEntryRef::Vacant(e) => {
+                e.insert(value);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use crate::{FxHashMapAlloc, FxHashMapAllocExt};
+
+    #[test]
+    fn borrowed_keys() {
+        let mut map: FxHashMapAlloc<String, u32> = FxHashMapAlloc::default();
+        assert_eq!(*map.get_or_insert_with_ref("a", || 1), 1);
+        assert_eq!(*map.get_or_insert_with_ref("a", || 2), 1);
+        assert_eq!(map.insert_ref("a", 3), Some(1));
+        assert_eq!(map.insert_ref("b", 4), None);
+        *map.get_or_default_ref("c") += 5;
+        assert_eq!(map.len(), 3);
+        assert_eq!(map["c"], 5);
+    }
+}
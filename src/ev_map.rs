@@ -0,0 +1,185 @@
+use core::hash::Hash;
+
+use evmap::ShallowCopy;
+
+use crate::FxBuildHasher;
+
+/// Type alias for an [`evmap::ReadHandle`] that uses the Fx hashing algorithm.
+pub type FxEvMapReadHandle<K, V, M = ()> = evmap::ReadHandle<K, V, M, FxBuildHasher>;
+
+/// Type alias for an [`evmap::ReadHandleFactory`] that uses the Fx hashing algorithm.
+pub type FxEvMapReadHandleFactory<K, V, M = ()> = evmap::ReadHandleFactory<K, V, M, FxBuildHasher>;
+
+/// Type alias for an [`evmap::WriteHandle`] that uses the Fx hashing algorithm.
+pub type FxEvMapWriteHandle<K, V, M = ()> = evmap::WriteHandle<K, V, M, FxBuildHasher>;
+
+/// An eventually consistent multi-value map using the Fx hashing algorithm,
+/// backed by [`evmap`].
+///
+/// Reads go through [`FxEvMapReadHandle`]s and never block. Writes are only
+/// visible to readers after [`refresh`](Self::refresh), which makes this a good
+/// fit for very read-heavy workloads with a single writer.
+///
+/// ```
+/// use rustc_hash::FxEvMap;
+///
+/// let mut map = FxEvMap::new();
+/// let reader = map.reader();
+///
+/// map.insert("a", 1);
+/// assert_eq!(reader.get_one("a").map(|v| *v), None);
+///
+/// map.refresh();
+/// assert_eq!(reader.get_one("a").map(|v| *v), Some(1));
+/// ```
+pub struct FxEvMap<K, V, M = ()>
+where
+    K: Eq + Hash + Clone,
+    V: Eq + Hash + ShallowCopy,
+    M: 'static + Clone,
+{
+    write: FxEvMapWriteHandle<K, V, M>,
+    factory: FxEvMapReadHandleFactory<K, V, M>,
+}
+
+impl<K, V> FxEvMap<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Eq + Hash + ShallowCopy,
+{
+    /// Creates an empty `FxEvMap`.
+    pub fn new() -> FxEvMap<K, V> {
+        Self::from_options(evmap::Options::default().with_hasher(FxBuildHasher))
+    }
+
+    /// Creates an empty `FxEvMap` with room for at least `capacity` keys.
+    pub fn with_capacity(capacity: usize) -> FxEvMap<K, V> {
+        Self::from_options(
+            evmap::Options::default()
+                .with_hasher(FxBuildHasher)
+                .with_capacity(capacity),
+        )
+    }
+}
+
+impl<K, V, M> FxEvMap<K, V, M>
+where
+    K: Eq + Hash + Clone,
+    V: Eq + Hash + ShallowCopy,
+    M: 'static + Clone,
+{
+    /// Creates an empty `FxEvMap` with `meta` as its initial meta value.
+    pub fn with_meta(meta: M) -> FxEvMap<K, V, M> {
+        Self::from_options(
+            evmap::Options::default()
+                .with_hasher(FxBuildHasher)
+                .with_meta(meta),
+        )
+    }
+
+    fn from_options(options: evmap::Options<M, FxBuildHasher>) -> FxEvMap<K, V, M> {
+        let (read, write) = options.construct();
+        FxEvMap {
+            write,
+            factory: read.factory(),
+        }
+    }
+
+    /// Returns a new read handle.
+    ///
+    /// Read handles are `Send` but not `Sync`, so every reading thread needs
+    /// its own. Use [`read_factory`](Self::read_factory) to create them on
+    /// other threads.
+    pub fn reader(&self) -> FxEvMapReadHandle<K, V, M> {
+        self.factory.handle()
+    }
+
+    /// Returns a `Sync` factory for read handles.
+    pub fn read_factory(&self) -> FxEvMapReadHandleFactory<K, V, M> {
+        self.factory.clone()
+    }
+
+    /// Returns the write handle, for operations not exposed by `FxEvMap` itself.
+    pub fn writer(&mut self) -> &mut FxEvMapWriteHandle<K, V, M> {
+        &mut self.write
+    }
+
+    /// Adds `value` to the values of `key`.
+    pub fn insert(&mut self, key: K, value: V) -> &mut Self {
+        self.write.insert(key, value);
+        self
+    }
+
+    /// Replaces all values of `key` with `value`.
+    pub fn update(&mut self, key: K, value: V) -> &mut Self {
+        self.write.update(key, value);
+        self
+    }
+
+    /// Removes one occurrence of `value` from the values of `key`.
+    ///
+    /// The key is kept even if this removes its last value; use
+    /// [`empty`](Self::empty) to remove the key itself.
+    pub fn remove(&mut self, key: K, value: V) -> &mut Self {
+        self.write.remove(key, value);
+        self
+    }
+
+    /// Removes `key` and all of its values.
+    pub fn empty(&mut self, key: K) -> &mut Self {
+        self.write.empty(key);
+        self
+    }
+
+    /// Publishes all pending writes to the readers.
+    pub fn refresh(&mut self) -> &mut Self {
+        self.write.refresh();
+        self
+    }
+
+    /// Consumes the map and returns its underlying handles.
+    pub fn into_handles(self) -> (FxEvMapReadHandle<K, V, M>, FxEvMapWriteHandle<K, V, M>) {
+        (self.factory.handle(), self.write)
+    }
+}
+
+impl<K, V> Default for FxEvMap<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Eq + Hash + ShallowCopy,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use crate::FxEvMap;
+
+    #[test]
+    fn readers_on_other_threads() {
+        let mut map = FxEvMap::with_capacity(16);
+        let factory = map.read_factory();
+
+        map.insert(1_u32, 1_u32).insert(1, 2).insert(2, 3).refresh();
+        thread::spawn(move || {
+            let reader = factory.handle();
+            assert_eq!(reader.len(), 2);
+            assert_eq!(reader.get(&1).map(|v| v.len()), Some(2));
+        })
+        .join()
+        .unwrap();
+
+        map.update(1, 5).empty(2).refresh();
+        let reader = map.reader();
+        assert_eq!(reader.get_one(&1).map(|v| *v), Some(5));
+        assert!(!reader.contains_key(&2));
+
+        let (reader, mut writer) = map.into_handles();
+        writer.remove(1, 5).refresh();
+        assert!(!reader.contains_value(&1, &5));
+    }
+}
@@ -0,0 +1,62 @@
+use core::hash::Hash;
+
+use crate::FxBuildHasher;
+
+/// Type alias for a [`flurry::HashMap`] that uses the Fx hashing algorithm.
+///
+/// ```
+/// use rustc_hash::FxFlurryMap;
+///
+/// let map: FxFlurryMap<u32, u32> = FxFlurryMap::default();
+/// let guard = map.guard();
+/// map.insert(22, 44, &guard);
+/// assert_eq!(map.get(&22, &guard), Some(&44));
+/// ```
+pub type FxFlurryMap<K, V> = flurry::HashMap<K, V, FxBuildHasher>;
+
+/// Type alias for a [`flurry::HashSet`] that uses the Fx hashing algorithm.
+pub type FxFlurrySet<T> = flurry::HashSet<T, FxBuildHasher>;
+
+/// Type alias for a pinned reference to an [`FxFlurryMap`], which carries its own guard.
+pub type FxFlurryMapRef<'map, K, V> = flurry::HashMapRef<'map, K, V, FxBuildHasher>;
+
+/// Creates an empty [`FxFlurryMap`] with room for at least `capacity` elements.
+pub fn flurry_map_with_capacity<K, V>(capacity: usize) -> FxFlurryMap<K, V> {
+    FxFlurryMap::with_capacity_and_hasher(capacity, FxBuildHasher)
+}
+
+/// Creates an [`FxFlurryMap`] from the key-value pairs of `iter`, inserting them
+/// all under a single guard.
+pub fn flurry_map_from_iter<K, V, I>(iter: I) -> FxFlurryMap<K, V>
+where
+    K: 'static + Sync + Send + Clone + Hash + Ord,
+    V: 'static + Sync + Send,
+    I: IntoIterator<Item = (K, V)>,
+{
+    let iter = iter.into_iter();
+    let map = flurry_map_with_capacity(iter.size_hint().0);
+    {
+        let guard = map.guard();
+        for (k, v) in iter {
+            map.insert(k, v, &guard);
+        }
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{flurry_map_from_iter, flurry_map_with_capacity, FxFlurryMapRef};
+
+    #[test]
+    fn constructors() {
+        let map = flurry_map_from_iter((0..100_u32).map(|i| (i, i * 2)));
+        let pinned: FxFlurryMapRef<'_, u32, u32> = map.pin();
+        assert_eq!(pinned.len(), 100);
+        assert_eq!(pinned.get(&7), Some(&14));
+
+        let map = flurry_map_with_capacity::<u32, u32>(64);
+        assert!(map.pin().insert(1, 2).is_none());
+        assert_eq!(map.pin().get(&1), Some(&2));
+    }
+}
@@ -0,0 +1,141 @@
+use core::hash::{BuildHasher, Hash};
+
+use crate::FxSeededState;
+
+/// A `from_array` constructor for hash collections with any default-constructible
+/// hasher, such as `FxHashMap`, `FxHashSet` and their random or counter-seeded variants.
+///
+/// `std`'s `From<[T; N]>` implementations only exist for the default hasher.
+/// Like them, `from_array` reserves room for all elements up front and later
+/// elements win over earlier ones with an equal key.
+///
+/// ```
+/// use rustc_hash::{FxFromArray, FxHashMap, FxHashSet};
+///
+/// let map = FxHashMap::from_array([(1, "a"), (2, "b")]);
+/// assert_eq!(map[&2], "b");
+///
+/// let set = FxHashSet::from_array(["x", "y", "x"]);
+/// assert_eq!(set.len(), 2);
+/// ```
+pub trait FxFromArray<T>: Sized {
+    /// Creates a collection containing the elements of `array` and a default hasher.
+    fn from_array<const N: usize>(array: [T; N]) -> Self;
+}
+
+/// A `from_array_and_seed` constructor for hash collections using
+/// [`FxSeededState`], which has no default seed.
+///
+/// ```
+/// use rustc_hash::{FxHashSetSeed, FxSeededFromArray};
+///
+/// let set = FxHashSetSeed::from_array_and_seed([1, 2, 3], 7);
+/// assert!(set.contains(&2));
+/// assert_eq!(set.hasher().seed(), 7);
+/// ```
+pub trait FxSeededFromArray<T>: Sized {
+    /// Creates a collection containing the elements of `array` and an
+    /// `FxSeededState` with the given `seed`.
+    fn from_array_and_seed<const N: usize>(array: [T; N], seed: usize) -> Self;
+}
+
+#[cfg(feature = "std")]
+impl<K: Eq + Hash, V, S: BuildHasher + Default> FxFromArray<(K, V)>
+    for std::collections::HashMap<K, V, S>
+{
+    fn from_array<const N: usize>(array: [(K, V); N]) -> Self {
+        let mut map = Self::with_capacity_and_hasher(N, S::default());
+        map.extend(array);
+        map
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Eq + Hash, S: BuildHasher + Default> FxFromArray<T> for std::collections::HashSet<T, S> {
+    fn from_array<const N: usize>(array: [T; N]) -> Self {
+        let mut set = Self::with_capacity_and_hasher(N, S::default());
+        set.extend(array);
+        set
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: Eq + Hash, V> FxSeededFromArray<(K, V)> for std::collections::HashMap<K, V, FxSeededState> {
+    fn from_array_and_seed<const N: usize>(array: [(K, V); N], seed: usize) -> Self {
+        let mut map = Self::with_capacity_and_hasher(N, FxSeededState::with_seed(seed));
+        map.extend(array);
+        map
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Eq + Hash> FxSeededFromArray<T> for std::collections::HashSet<T, FxSeededState> {
+    fn from_array_and_seed<const N: usize>(array: [T; N], seed: usize) -> Self {
+        let mut set = Self::with_capacity_and_hasher(N, FxSeededState::with_seed(seed));
+        set.extend(array);
+        set
+    }
+}
+
+#[cfg(feature = "hashbrown")]
+impl<K: Eq + Hash, V, S: BuildHasher + Default> FxFromArray<(K, V)>
+    for hashbrown::HashMap<K, V, S>
+{
+    fn from_array<const N: usize>(array: [(K, V); N]) -> Self {
+        let mut map = Self::with_capacity_and_hasher(N, S::default());
+        map.extend(array);
+        map
+    }
+}
+
+#[cfg(feature = "hashbrown")]
+impl<T: Eq + Hash, S: BuildHasher + Default> FxFromArray<T> for hashbrown::HashSet<T, S> {
+    fn from_array<const N: usize>(array: [T; N]) -> Self {
+        let mut set = Self::with_capacity_and_hasher(N, S::default());
+        set.extend(array);
+        set
+    }
+}
+
+#[cfg(feature = "hashbrown")]
+impl<K: Eq + Hash, V> FxSeededFromArray<(K, V)> for hashbrown::HashMap<K, V, FxSeededState> {
+    fn from_array_and_seed<const N: usize>(array: [(K, V); N], seed: usize) -> Self {
+        let mut map = Self::with_capacity_and_hasher(N, FxSeededState::with_seed(seed));
+        map.extend(array);
+        map
+    }
+}
+
+#[cfg(feature = "hashbrown")]
+impl<T: Eq + Hash> FxSeededFromArray<T> for hashbrown::HashSet<T, FxSeededState> {
+    fn from_array_and_seed<const N: usize>(array: [T; N], seed: usize) -> Self {
+        let mut set = Self::with_capacity_and_hasher(N, FxSeededState::with_seed(seed));
+        set.extend(array);
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FxFromArray, FxTable};
+
+    #[test]
+    fn from_array() {
+        let map = FxTable::from_array([(1, 'a'), (2, 'b'), (1, 'c')]);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&1), Some(&'c'));
+
+        let empty = FxTable::<u32, u32>::from_array([]);
+        assert!(empty.is_empty());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn seeded_from_array() {
+        use crate::{FxHashMapSeed, FxSeededFromArray};
+
+        let map = FxHashMapSeed::from_array_and_seed([(1, 2)], 3);
+        assert_eq!(map[&1], 2);
+        assert_eq!(map.hasher().seed(), 3);
+    }
+}
@@ -0,0 +1,45 @@
+use crate::FxBuildHasher;
+
+/// Type alias for an [`elsa::FrozenMap`] that uses the Fx hashing algorithm.
+///
+/// ```
+/// use rustc_hash::FxFrozenMap;
+///
+/// let map: FxFrozenMap<u32, String> = FxFrozenMap::default();
+/// let a = map.insert(1, "a".to_string());
+/// map.insert(2, "b".to_string());
+/// assert_eq!(a, "a");
+/// ```
+pub type FxFrozenMap<K, V> = elsa::FrozenMap<K, V, FxBuildHasher>;
+
+/// Type alias for an [`elsa::FrozenIndexMap`] that uses the Fx hashing algorithm.
+pub type FxFrozenIndexMap<K, V> = elsa::FrozenIndexMap<K, V, FxBuildHasher>;
+
+/// Type alias for an [`elsa::FrozenIndexSet`] that uses the Fx hashing algorithm.
+///
+/// This is also the set to use when insertion order is not needed, as `elsa`
+/// has no unordered frozen set.
+pub type FxFrozenIndexSet<T> = elsa::FrozenIndexSet<T, FxBuildHasher>;
+
+#[cfg(test)]
+mod tests {
+    use std::boxed::Box;
+
+    use crate::{FxFrozenIndexMap, FxFrozenIndexSet};
+
+    #[test]
+    fn references_stay_valid() {
+        let set: FxFrozenIndexSet<Box<str>> = FxFrozenIndexSet::default();
+        let a = set.insert("a".into());
+        let b = set.insert("b".into());
+        assert_eq!(set.insert("a".into()), "a");
+        assert_eq!((a, b), ("a", "b"));
+        assert_eq!(set.get_index_of("b"), Some(1));
+
+        let map: FxFrozenIndexMap<u32, Box<u32>> = FxFrozenIndexMap::default();
+        let one = map.insert(1, Box::new(10));
+        map.insert(2, Box::new(20));
+        assert_eq!(*one, 10);
+        assert_eq!(map.get(&2), Some(&20));
+    }
+}
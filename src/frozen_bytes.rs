@@ -0,0 +1,367 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::frozen_map::{DisplacementIndex, FrozenBuildError};
+use crate::phf::slot_with;
+use crate::{FrozenFxMap, FxTable};
+
+/// The magic bytes at the start of every encoded map.
+const MAGIC: [u8; 8] = *b"FXFROZEN";
+
+/// The current version of the format.
+const VERSION: u16 = 1;
+
+/// The byte order marker of little-endian files, the only one written.
+const LITTLE_ENDIAN: u8 = 0;
+
+/// The size of the header in bytes.
+const HEADER_LEN: usize = 48;
+
+/// An error returned when bytes are not a valid encoded [`FrozenFxMap`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FrozenFormatError {
+    /// The bytes do not start with the magic bytes of the format.
+    BadMagic,
+    /// The format version is not supported by this version of the crate.
+    UnsupportedVersion(u16),
+    /// The byte order of the bytes is not supported.
+    UnsupportedByteOrder(u8),
+    /// The map was written on a platform with a different pointer width,
+    /// which hashes keys differently.
+    PointerWidthMismatch(u8),
+    /// The bytes are shorter or longer than the header says, or an entry
+    /// points outside of the data section.
+    Truncated,
+}
+
+impl fmt::Display for FrozenFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrozenFormatError::BadMagic => f.write_str("not an encoded frozen map"),
+            FrozenFormatError::UnsupportedVersion(v) => {
+                write!(f, "unsupported frozen map version {v}")
+            }
+            FrozenFormatError::UnsupportedByteOrder(o) => {
+                write!(f, "unsupported frozen map byte order {o}")
+            }
+            FrozenFormatError::PointerWidthMismatch(w) => {
+                write!(f, "frozen map was written with {w}-byte pointers")
+            }
+            FrozenFormatError::Truncated => f.write_str("frozen map is truncated or corrupt"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FrozenFormatError {}
+
+impl<K: AsRef<[u8]>, V: AsRef<[u8]>> FrozenFxMap<K, V> {
+    /// Encodes the map into a byte buffer that [`FrozenFxMapView`] can read
+    /// without deserializing it, e.g. after memory-mapping a file.
+    ///
+    /// The buffer starts with a 48-byte header:
+    ///
+    /// | offset | size | content                                      |
+    /// |--------|------|----------------------------------------------|
+    /// | 0      | 8    | magic bytes `FXFROZEN`                       |
+    /// | 8      | 2    | format version, currently 1                  |
+    /// | 10     | 1    | byte order, 0 for little-endian              |
+    /// | 11     | 1    | pointer width in bytes of the writer         |
+    /// | 12     | 4    | number of entries `o` left out of the index  |
+    /// | 16     | 8    | hash seed                                    |
+    /// | 24     | 8    | number of entries `n`                        |
+    /// | 32     | 8    | number of displacement buckets `b`           |
+    /// | 40     | 8    | size of the data section `d`                 |
+    ///
+    /// It is followed by `b` displacement pairs of two `u32`, `n` pairs of
+    /// `u64` holding the end offsets of every key and value in the data
+    /// section, and the `d` bytes of the data section with every key directly
+    /// followed by its value. All integers are little-endian, and every section
+    /// starts at a multiple of 8 bytes. The last `o` entries are the ones whose
+    /// hashes collide with another entry for every seed, which lookups scan.
+    ///
+    /// If several keys have the same bytes, the last one wins.
+    ///
+    /// Returns an error if no index is found for the keys, see
+    /// [`FrozenBuildError`].
+    pub fn to_bytes(&self) -> Result<Vec<u8>, FrozenBuildError> {
+        let mut unique = FxTable::default();
+        for (i, k) in self.keys().iter().enumerate() {
+            unique.insert(k.as_ref(), i);
+        }
+        let entries: Vec<(&[u8], &[u8])> = unique
+            .into_iter()
+            .map(|(k, i)| (k, self.values()[i].as_ref()))
+            .collect();
+        let keys: Vec<&[u8]> = entries.iter().map(|&(k, _)| k).collect();
+        let (index, slots) = DisplacementIndex::build(&keys)?;
+        let overflow = (entries.len() - index.len as usize) as u32;
+
+        let data_len: usize = entries.iter().map(|(k, v)| k.len() + v.len()).sum();
+        let mut out = Vec::with_capacity(
+            HEADER_LEN + index.displacements.len() * 8 + entries.len() * 16 + data_len,
+        );
+        out.extend_from_slice(&MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        out.push(LITTLE_ENDIAN);
+        out.push(core::mem::size_of::<usize>() as u8);
+        out.extend_from_slice(&overflow.to_le_bytes());
+        for n in [
+            index.seed,
+            entries.len(),
+            index.displacements.len(),
+            data_len,
+        ] {
+            out.extend_from_slice(&(n as u64).to_le_bytes());
+        }
+
+        for &(d1, d2) in index.displacements.iter() {
+            out.extend_from_slice(&d1.to_le_bytes());
+            out.extend_from_slice(&d2.to_le_bytes());
+        }
+        let mut end = 0;
+        for &i in &slots {
+            let (k, v) = entries[i];
+            out.extend_from_slice(&((end + k.len()) as u64).to_le_bytes());
+            end += k.len() + v.len();
+            out.extend_from_slice(&(end as u64).to_le_bytes());
+        }
+        for &i in &slots {
+            let (k, v) = entries[i];
+            out.extend_from_slice(k);
+            out.extend_from_slice(v);
+        }
+        Ok(out)
+    }
+}
+
+/// A read-only view of a [`FrozenFxMap`] encoded with
+/// [`to_bytes`](FrozenFxMap::to_bytes), borrowing keys and values from the
+/// encoded bytes.
+///
+/// Loading a view validates the header and the entry offsets, which takes time
+/// proportional to the number of entries but never copies keys or values.
+///
+/// ```
+/// use rustc_hash::{FrozenFxMap, FrozenFxMapView};
+///
+/// let map: FrozenFxMap<&str, &str> = [("fx", "fast"), ("sip", "safe")].into_iter().collect();
+/// let bytes = map.to_bytes().unwrap();
+///
+/// let view = FrozenFxMapView::new(&bytes).unwrap();
+/// assert_eq!(view.get(b"fx"), Some(&b"fast"[..]));
+/// assert_eq!(view.get(b"aes"), None);
+/// ```
+#[derive(Clone, Copy)]
+pub struct FrozenFxMapView<'a> {
+    seed: usize,
+    len: u32,
+    indexed: u32,
+    displacements: &'a [u8],
+    ends: &'a [u8],
+    data: &'a [u8],
+}
+
+impl<'a> FrozenFxMapView<'a> {
+    /// Validates `bytes` and returns a view of the encoded map.
+    pub fn new(bytes: &'a [u8]) -> Result<FrozenFxMapView<'a>, FrozenFormatError> {
+        let header = bytes
+            .get(..HEADER_LEN)
+            .ok_or(FrozenFormatError::Truncated)?;
+        if header[..8] != MAGIC {
+            return Err(FrozenFormatError::BadMagic);
+        }
+        let version = u16::from_le_bytes([header[8], header[9]]);
+        if version != VERSION {
+            return Err(FrozenFormatError::UnsupportedVersion(version));
+        }
+        if header[10] != LITTLE_ENDIAN {
+            return Err(FrozenFormatError::UnsupportedByteOrder(header[10]));
+        }
+        if usize::from(header[11]) != core::mem::size_of::<usize>() {
+            return Err(FrozenFormatError::PointerWidthMismatch(header[11]));
+        }
+
+        let overflow = read_u32(header, 12) as usize;
+        let field = |i: usize| usize::try_from(read_u64(header, 16 + i * 8));
+        let truncated = |_| FrozenFormatError::Truncated;
+        let seed = field(0).map_err(truncated)?;
+        let len = field(1).map_err(truncated)?;
+        let buckets = field(2).map_err(truncated)?;
+        let data_len = field(3).map_err(truncated)?;
+
+        let sizes = [buckets.checked_mul(8), len.checked_mul(16), Some(data_len)];
+        let mut sections = [&bytes[..0]; 3];
+        let mut rest = &bytes[HEADER_LEN..];
+        for (section, size) in sections.iter_mut().zip(sizes) {
+            let size = size.ok_or(FrozenFormatError::Truncated)?;
+            if size > rest.len() {
+                return Err(FrozenFormatError::Truncated);
+            }
+            (*section, rest) = rest.split_at(size);
+        }
+        if !rest.is_empty() || (len > 0 && buckets == 0) || overflow > len {
+            return Err(FrozenFormatError::Truncated);
+        }
+        let [displacements, ends, data] = sections;
+
+        // Checking the offsets once makes every later lookup infallible.
+        let mut start = 0;
+        for i in 0..len * 2 {
+            let end = read_u64(ends, i * 8);
+            if end < start || end > data_len as u64 {
+                return Err(FrozenFormatError::Truncated);
+            }
+            start = end;
+        }
+
+        let len = u32::try_from(len).map_err(|_| FrozenFormatError::Truncated)?;
+        Ok(FrozenFxMapView {
+            seed,
+            len,
+            indexed: len - overflow as u32,
+            displacements,
+            ends,
+            data,
+        })
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the key and value in `slot`.
+    fn entry(&self, slot: usize) -> (&'a [u8], &'a [u8]) {
+        let start = match slot {
+            0 => 0,
+            _ => read_u64(self.ends, slot * 16 - 8) as usize,
+        };
+        let key_end = read_u64(self.ends, slot * 16) as usize;
+        let value_end = read_u64(self.ends, slot * 16 + 8) as usize;
+        (&self.data[start..key_end], &self.data[key_end..value_end])
+    }
+
+    /// Returns the value of `key`.
+    pub fn get(&self, key: &[u8]) -> Option<&'a [u8]> {
+        let buckets = self.displacements.len() / 8;
+        let slot = slot_with(self.seed, self.indexed, buckets, key, |b| {
+            let d1 = read_u32(self.displacements, b * 8);
+            let d2 = read_u32(self.displacements, b * 8 + 4);
+            (d1, d2)
+        });
+        if let Some((k, v)) = slot.map(|slot| self.entry(slot)) {
+            if k == key {
+                return Some(v);
+            }
+        }
+        // The entries left out of the index follow the indexed ones.
+        (self.indexed as usize..self.len())
+            .map(|slot| self.entry(slot))
+            .find_map(|(k, v)| (k == key).then_some(v))
+    }
+
+    /// Returns `true` if the map contains `key`.
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns an iterator over the keys and values, in unspecified order.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (&'a [u8], &'a [u8])> + '_ {
+        (0..self.len()).map(|slot| self.entry(slot))
+    }
+}
+
+impl fmt::Debug for FrozenFxMapView<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+fn read_u64(bytes: &[u8], at: usize) -> u64 {
+    let mut buf = [0; 8];
+    buf.copy_from_slice(&bytes[at..at + 8]);
+    u64::from_le_bytes(buf)
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    let mut buf = [0; 4];
+    buf.copy_from_slice(&bytes[at..at + 4]);
+    u32::from_le_bytes(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    use crate::{FrozenFormatError, FrozenFxMap, FrozenFxMapView};
+
+    #[test]
+    fn round_trip() {
+        let map: FrozenFxMap<String, Vec<u8>> = (0..1000_u32)
+            .map(|i| (format!("key{i}"), i.to_le_bytes().to_vec()))
+            .collect();
+        let bytes = map.to_bytes().unwrap();
+        let view = FrozenFxMapView::new(&bytes).unwrap();
+        assert_eq!(view.len(), 1000);
+        for i in 0..1000_u32 {
+            let key = format!("key{i}");
+            assert_eq!(view.get(key.as_bytes()), Some(&i.to_le_bytes()[..]));
+        }
+        assert!(!view.contains_key(b"key1000"));
+        assert_eq!(
+            view.iter().map(|(k, v)| k.len() + v.len()).sum::<usize>(),
+            9_890
+        );
+
+        let empty = FrozenFxMap::<&str, &str>::default().to_bytes().unwrap();
+        let view = FrozenFxMapView::new(&empty).unwrap();
+        assert!(view.is_empty());
+        assert_eq!(view.get(b""), None);
+    }
+
+    #[test]
+    fn rejects_bad_bytes() {
+        let map: FrozenFxMap<&str, &str> = [("a", "b")].into_iter().collect();
+        let bytes = map.to_bytes().unwrap();
+
+        let err = |bytes: &[u8]| FrozenFxMapView::new(bytes).unwrap_err();
+        assert_eq!(err(&bytes[..bytes.len() - 1]), FrozenFormatError::Truncated);
+        assert_eq!(err(&bytes[..10]), FrozenFormatError::Truncated);
+
+        let mut bad = bytes.clone();
+        bad[0] = b'X';
+        assert_eq!(err(&bad), FrozenFormatError::BadMagic);
+
+        let mut bad = bytes.clone();
+        bad[8] = 2;
+        assert_eq!(err(&bad), FrozenFormatError::UnsupportedVersion(2));
+
+        let mut bad = bytes.clone();
+        bad[10] = 1;
+        assert_eq!(err(&bad), FrozenFormatError::UnsupportedByteOrder(1));
+
+        let mut bad = bytes.clone();
+        bad[11] = 3;
+        assert_eq!(err(&bad), FrozenFormatError::PointerWidthMismatch(3));
+
+        // More entries are left out of the index than there are entries.
+        let mut bad = bytes.clone();
+        bad[12] = 2;
+        assert_eq!(err(&bad), FrozenFormatError::Truncated);
+
+        // The end offset of the only value points past the data section.
+        let mut bad = bytes.clone();
+        let at = bad.len() - 2 - 8;
+        bad[at] = 9;
+        assert_eq!(err(&bad), FrozenFormatError::Truncated);
+    }
+}
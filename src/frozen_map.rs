@@ -0,0 +1,454 @@
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+
+use crate::phf::{slot_with, Split};
+use crate::{FxSeededState, FxTable};
+
+/// The average number of keys per displacement bucket.
+const KEYS_PER_BUCKET: usize = 4;
+
+/// The number of seeds tried before giving up on building an index.
+const MAX_SEEDS: usize = 16;
+
+/// The number of displacement candidates tried per key, and per key of a
+/// single bucket, before trying the next seed. Most indexes need a few
+/// hundred per key in total, and at most a few dozen per key for one bucket.
+const CANDIDATES_PER_KEY: u64 = 2048;
+const BUCKET_CANDIDATES_PER_KEY: u64 = 256;
+
+/// An error returned when no displacement index was found for a set of keys
+/// within the search limits.
+///
+/// This is extremely unlikely for keys with well-behaved `Hash` impls.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrozenBuildError;
+
+impl fmt::Display for FrozenBuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("no displacement index found for the keys")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FrozenBuildError {}
+
+/// A hash-and-displace index mapping `len` distinct keys to the slots
+/// `0..len`.
+///
+/// Keys are hashed with a seeded Fx hasher and grouped into buckets. Every
+/// bucket stores a pair of displacements chosen at build time so that the keys
+/// of all buckets land in distinct slots.
+///
+/// Keys whose hashes can never be separated, which happens for multi-word keys
+/// whatever the seed as the Fx hash is linear in them, are left out of the
+/// index: they are stored after the `len` indexed keys, and lookups that miss
+/// their slot scan them.
+#[derive(Clone)]
+pub(crate) struct DisplacementIndex {
+    pub(crate) seed: usize,
+    pub(crate) displacements: Box<[(u32, u32)]>,
+    pub(crate) len: u32,
+}
+
+impl DisplacementIndex {
+    /// Builds an index over `keys`, which must be distinct.
+    ///
+    /// Returns the index and the positions in `keys` of the key of every slot,
+    /// followed by the positions of the keys left out of the index.
+    pub(crate) fn build<T: Hash>(
+        keys: &[T],
+    ) -> Result<(DisplacementIndex, Vec<usize>), FrozenBuildError> {
+        if u32::try_from(keys.len()).is_err() {
+            return Err(FrozenBuildError);
+        }
+        let buckets = keys.len().div_ceil(KEYS_PER_BUCKET).max(1);
+        (0..MAX_SEEDS)
+            .find_map(|seed| Self::try_build(keys, seed, buckets))
+            .ok_or(FrozenBuildError)
+    }
+
+    fn try_build<T: Hash>(
+        keys: &[T],
+        seed: usize,
+        buckets: usize,
+    ) -> Option<(DisplacementIndex, Vec<usize>)> {
+        let state = FxSeededState::with_seed(seed);
+        let splits: Vec<Split> = keys.iter().map(|k| Split::new(state.hash_one(k))).collect();
+
+        // Keys with equal splits in the same bucket land in the same slot for
+        // every displacement, so all but one of them are left out.
+        let mut by_split: Vec<usize> = (0..keys.len()).collect();
+        by_split.sort_unstable_by_key(|&i| splits[i].key(buckets));
+        let mut overflow = Vec::new();
+        for pair in by_split.windows(2) {
+            if splits[pair[0]].key(buckets) == splits[pair[1]].key(buckets) {
+                overflow.push(pair[1]);
+            }
+        }
+        let mut members = vec![Vec::new(); buckets];
+        let mut left_out = vec![false; keys.len()];
+        for &i in &overflow {
+            left_out[i] = true;
+        }
+        for (i, split) in splits.iter().enumerate() {
+            if !left_out[i] {
+                members[split.bucket as usize % buckets].push(i);
+            }
+        }
+        let len = (keys.len() - overflow.len()) as u32;
+
+        // Placing the largest buckets first, while most slots are still free,
+        // makes it much more likely to find displacements for all of them.
+        let mut order: Vec<usize> = (0..buckets).collect();
+        order.sort_unstable_by_key(|&b| core::cmp::Reverse(members[b].len()));
+
+        let mut displacements = vec![(0, 0); buckets].into_boxed_slice();
+        let mut slots = vec![usize::MAX; len as usize];
+        // Slots tried for the current candidate are marked with its generation,
+        // which avoids clearing a scratch buffer for every candidate.
+        let mut tried = vec![0_u64; len as usize];
+        let mut generation = 0_u64;
+        let budget = CANDIDATES_PER_KEY * u64::from(len) + (1 << 20);
+        let bucket_budget = BUCKET_CANDIDATES_PER_KEY * u64::from(len) + (1 << 16);
+
+        'buckets: for b in order {
+            let bucket = &members[b];
+            if bucket.is_empty() {
+                continue;
+            }
+            let first = generation;
+            let candidates = (0..len).flat_map(|d1| (0..len).map(move |d2| (d1, d2)));
+            'candidates: for displacement in candidates {
+                generation += 1;
+                if generation > budget || generation - first > bucket_budget {
+                    return None;
+                }
+                for &i in bucket {
+                    let slot = splits[i].slot(displacement, len) as usize;
+                    if slots[slot] != usize::MAX || tried[slot] == generation {
+                        continue 'candidates;
+                    }
+                    tried[slot] = generation;
+                }
+                for &i in bucket {
+                    slots[splits[i].slot(displacement, len) as usize] = i;
+                }
+                displacements[b] = displacement;
+                continue 'buckets;
+            }
+            return None;
+        }
+
+        slots.extend(overflow);
+        let index = DisplacementIndex {
+            seed,
+            displacements,
+            len,
+        };
+        Some((index, slots))
+    }
+
+    /// Returns the slot of a key, which is only meaningful for keys the index
+    /// was built from.
+    pub(crate) fn slot<Q: Hash + ?Sized>(&self, key: &Q) -> Option<usize> {
+        slot_with(self.seed, self.len, self.displacements.len(), key, |b| {
+            self.displacements[b]
+        })
+    }
+}
+
+/// An immutable hash map optimized for lookups, using the Fx hashing algorithm.
+///
+/// The keys and values are stored in two flat arrays without any empty slots,
+/// and a small displacement index with one entry per four keys maps every key
+/// directly to its slot. A lookup is a single hash, an index access and one key
+/// comparison. Building the map takes longer than building an `FxHashMap`.
+///
+/// With the `serde` feature, the map is serialized in this layout, so
+/// deserializing it fills the arrays directly and only hashes every key once
+/// to check that it is in its slot, instead of building the index again.
+///
+/// ```
+/// use rustc_hash::FrozenFxMap;
+///
+/// let map: FrozenFxMap<&str, u32> = [("one", 1), ("two", 2), ("three", 3)]
+///     .into_iter()
+///     .collect();
+/// assert_eq!(map.get("two"), Some(&2));
+/// assert_eq!(map.get("four"), None);
+/// assert_eq!(map.len(), 3);
+/// ```
+#[derive(Clone)]
+pub struct FrozenFxMap<K, V> {
+    pub(crate) index: DisplacementIndex,
+    /// The keys, each in its slot of `index`.
+    pub(crate) keys: Box<[K]>,
+    pub(crate) values: Box<[V]>,
+}
+
+impl<K, V> FrozenFxMap<K, V> {
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Returns the keys, in unspecified order.
+    pub fn keys(&self) -> &[K] {
+        &self.keys
+    }
+
+    /// Returns the values, in the order of [`keys`](Self::keys).
+    pub fn values(&self) -> &[V] {
+        &self.values
+    }
+
+    /// Returns an iterator over the keys and values, in unspecified order.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (&K, &V)> {
+        self.keys.iter().zip(self.values.iter())
+    }
+}
+
+impl<K: Eq + Hash, V> FrozenFxMap<K, V> {
+    /// Returns the slot of `key` if the map contains it.
+    fn find<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let slot = self.index.slot(key)?;
+        if self.keys[slot].borrow() == key {
+            return Some(slot);
+        }
+        // The keys left out of the index follow the indexed ones.
+        (self.index.len as usize..self.keys.len()).find(|&i| self.keys[i].borrow() == key)
+    }
+
+    /// Returns a reference to the value of `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.find(key).map(|slot| &self.values[slot])
+    }
+
+    /// Returns the stored key and the value of `key`.
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.find(key)
+            .map(|slot| (&self.keys[slot], &self.values[slot]))
+    }
+
+    /// Returns `true` if the map contains `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+}
+
+impl<K: Eq + Hash, V> FrozenFxMap<K, V> {
+    /// Builds the map from key-value pairs like [`collect`](Iterator::collect),
+    /// returning an error instead of panicking if no index is found.
+    pub fn try_from_iter<I: IntoIterator<Item = (K, V)>>(
+        iter: I,
+    ) -> Result<FrozenFxMap<K, V>, FrozenBuildError> {
+        let mut map = FxTable::default();
+        map.extend(iter);
+        let (keys, values): (Vec<K>, Vec<V>) = map.into_iter().unzip();
+
+        let (index, slots) = DisplacementIndex::build(&keys)?;
+        let mut keys: Vec<Option<K>> = keys.into_iter().map(Some).collect();
+        let mut values: Vec<Option<V>> = values.into_iter().map(Some).collect();
+        Ok(FrozenFxMap {
+            index,
+            keys: slots.iter().filter_map(|&i| keys[i].take()).collect(),
+            values: slots.iter().filter_map(|&i| values[i].take()).collect(),
+        })
+    }
+}
+
+impl<K: Eq + Hash, V> FromIterator<(K, V)> for FrozenFxMap<K, V> {
+    /// Builds the map from key-value pairs. Like `collect` into a hash map,
+    /// later pairs win over earlier pairs with an equal key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no index is found, see [`FrozenBuildError`].
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self::try_from_iter(iter).expect("failed to build a frozen map")
+    }
+}
+
+impl<K, V> Default for FrozenFxMap<K, V> {
+    fn default() -> Self {
+        FrozenFxMap {
+            index: DisplacementIndex {
+                seed: 0,
+                displacements: Box::new([]),
+                len: 0,
+            },
+            keys: Box::new([]),
+            values: Box::new([]),
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for FrozenFxMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// An immutable hash set optimized for lookups, using the Fx hashing algorithm.
+///
+/// See [`FrozenFxMap`] for details.
+///
+/// ```
+/// use rustc_hash::FrozenFxSet;
+///
+/// let keywords: FrozenFxSet<&str> = ["fn", "let", "match"].into_iter().collect();
+/// assert!(keywords.contains("let"));
+/// assert!(!keywords.contains("var"));
+/// ```
+#[derive(Clone, Default)]
+pub struct FrozenFxSet<T> {
+    pub(crate) map: FrozenFxMap<T, ()>,
+}
+
+impl<T> FrozenFxSet<T> {
+    /// Returns the number of elements in the set.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the set contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns the elements, in unspecified order.
+    pub fn as_slice(&self) -> &[T] {
+        self.map.keys()
+    }
+
+    /// Returns an iterator over the elements, in unspecified order.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &T> {
+        self.map.keys().iter()
+    }
+}
+
+impl<T: Eq + Hash> FrozenFxSet<T> {
+    /// Returns `true` if the set contains `value`.
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.map.contains_key(value)
+    }
+
+    /// Returns the stored element equal to `value`.
+    pub fn get<Q>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.map.get_key_value(value).map(|(t, _)| t)
+    }
+}
+
+impl<T: Eq + Hash> FrozenFxSet<T> {
+    /// Builds the set from elements like [`collect`](Iterator::collect),
+    /// returning an error instead of panicking if no index is found.
+    pub fn try_from_iter<I: IntoIterator<Item = T>>(
+        iter: I,
+    ) -> Result<FrozenFxSet<T>, FrozenBuildError> {
+        let map = FrozenFxMap::try_from_iter(iter.into_iter().map(|t| (t, ())))?;
+        Ok(FrozenFxSet { map })
+    }
+}
+
+impl<T: Eq + Hash> FromIterator<T> for FrozenFxSet<T> {
+    /// # Panics
+    ///
+    /// Panics if no index is found, see [`FrozenBuildError`].
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::try_from_iter(iter).expect("failed to build a frozen set")
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for FrozenFxSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{FrozenFxMap, FrozenFxSet};
+
+    #[test]
+    fn lookups() {
+        let map: FrozenFxMap<u32, u32> = (0..10_000).map(|i| (i * 7, i)).collect();
+        assert_eq!(map.len(), 10_000);
+        assert!((0..10_000).all(|i| map.get(&(i * 7)) == Some(&i)));
+        assert!((0..10_000).all(|i| !map.contains_key(&(i * 7 + 1))));
+
+        let mut values: Vec<u32> = map.values().to_vec();
+        values.sort_unstable();
+        assert!(values.iter().copied().eq(0..10_000));
+    }
+
+    #[test]
+    fn colliding_hashes() {
+        // The Fx hashes of these keys are equal for every seed.
+        let k = crate::K;
+        let keys = [(0_usize, k), (1, 0), (2, 0_usize.wrapping_sub(k))];
+        let map: FrozenFxMap<(usize, usize), usize> =
+            keys.iter().enumerate().map(|(i, &k)| (k, i)).collect();
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.index.len, 1);
+        for (i, k) in keys.iter().enumerate() {
+            assert_eq!(map.get(k), Some(&i));
+        }
+        assert_eq!(map.get(&(3, 0)), None);
+
+        let set: FrozenFxSet<(usize, usize)> = keys.iter().copied().chain([(5, 6)]).collect();
+        assert!(keys.iter().all(|k| set.contains(k)));
+        assert!(set.contains(&(5, 6)));
+    }
+
+    #[test]
+    fn small_and_duplicate() {
+        let map: FrozenFxMap<&str, u32> = [("a", 1), ("a", 2)].into_iter().collect();
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get_key_value("a"), Some((&"a", &2)));
+
+        let empty: FrozenFxSet<u32> = FrozenFxSet::default();
+        assert!(!empty.contains(&0));
+        let empty: FrozenFxSet<u32> = core::iter::empty().collect();
+        assert!(empty.is_empty());
+        assert!(!empty.contains(&0));
+
+        let set: FrozenFxSet<u32> = [5].into_iter().collect();
+        assert_eq!(set.get(&5), Some(&5));
+        assert_eq!(set.iter().len(), 1);
+    }
+}
@@ -0,0 +1,115 @@
+use core::cell::Cell;
+
+use critical_section::Mutex;
+
+use crate::FxHasher;
+
+static GLOBAL_SEED: Mutex<Cell<Option<usize>>> = Mutex::new(Cell::new(None));
+
+/// Sets the process-wide seed used by [`FxGlobalSeededState`].
+///
+/// The seed can only be set once. If it was already set, the existing seed is
+/// returned as the error.
+///
+/// Access to the seed is guarded by a [critical section], so this may be called
+/// from interrupt handlers on targets without `std`.
+///
+/// [critical section]: https://docs.rs/critical-section
+pub fn set_global_seed(seed: usize) -> Result<(), usize> {
+    critical_section::with(|cs| {
+        let cell = GLOBAL_SEED.borrow(cs);
+        match cell.get() {
+            Some(existing) => Err(existing),
+            None => {
+                cell.set(Some(seed));
+                Ok(())
+            }
+        }
+    })
+}
+
+/// Returns the process-wide seed, if it was set.
+pub fn global_seed() -> Option<usize> {
+    critical_section::with(|cs| GLOBAL_SEED.borrow(cs).get())
+}
+
+/// Returns the process-wide seed, initializing it with `init` if it was not set yet.
+///
+/// `init` runs inside the critical section, so it should be short, e.g. reading
+/// a hardware RNG register.
+pub fn global_seed_or_init(init: impl FnOnce() -> usize) -> usize {
+    critical_section::with(|cs| {
+        let cell = GLOBAL_SEED.borrow(cs);
+        match cell.get() {
+            Some(seed) => seed,
+            None => {
+                let seed = init();
+                cell.set(Some(seed));
+                seed
+            }
+        }
+    })
+}
+
+/// [`FxGlobalSeededState`] is an alternative state for `HashMap` types, using the process-wide
+/// seed set through [`set_global_seed`] or [`global_seed_or_init`].
+#[derive(Clone)]
+pub struct FxGlobalSeededState {
+    seed: usize,
+}
+
+impl FxGlobalSeededState {
+    /// Constructs a new `FxGlobalSeededState` using the process-wide seed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the process-wide seed was not set yet.
+    pub fn new() -> FxGlobalSeededState {
+        Self::try_new().expect("the global seed was not set")
+    }
+
+    /// Constructs a new `FxGlobalSeededState` using the process-wide seed, or returns `None` if
+    /// it was not set yet.
+    pub fn try_new() -> Option<FxGlobalSeededState> {
+        global_seed().map(|seed| FxGlobalSeededState { seed })
+    }
+}
+
+impl core::hash::BuildHasher for FxGlobalSeededState {
+    type Hasher = FxHasher;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        FxHasher::with_seed(self.seed)
+    }
+}
+
+impl Default for FxGlobalSeededState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::hash::BuildHasher;
+
+    use crate::{
+        global_seed, global_seed_or_init, set_global_seed, FxGlobalSeededState, FxSeededState,
+    };
+
+    // The seed is global, so everything is checked in a single test.
+    #[test]
+    fn global_seed_is_set_once() {
+        assert_eq!(global_seed(), None);
+        assert!(FxGlobalSeededState::try_new().is_none());
+
+        assert_eq!(global_seed_or_init(|| 7), 7);
+        assert_eq!(global_seed_or_init(|| 8), 7);
+        assert_eq!(set_global_seed(9), Err(7));
+        assert_eq!(global_seed(), Some(7));
+
+        let a = FxGlobalSeededState::new();
+        let b = FxSeededState::with_seed(7);
+        assert_eq!(a.hash_one(42_u32), b.hash_one(42_u32));
+    }
+}
@@ -0,0 +1,57 @@
+use crate::FxBuildHasher;
+
+/// Type alias for a [`petgraph::graphmap::GraphMap`] that uses the Fx hashing algorithm.
+pub type FxGraphMap<N, E, Ty> = petgraph::graphmap::GraphMap<N, E, Ty, FxBuildHasher>;
+
+/// Type alias for an undirected [`FxGraphMap`].
+pub type FxUnGraphMap<N, E> = FxGraphMap<N, E, petgraph::Undirected>;
+
+/// Type alias for a directed [`FxGraphMap`].
+///
+/// ```
+/// use rustc_hash::FxDiGraphMap;
+///
+/// let graph = FxDiGraphMap::<u32, ()>::from_edges([(1, 2), (2, 3)]);
+/// assert!(graph.contains_edge(1, 2));
+/// assert!(!graph.contains_edge(2, 1));
+/// ```
+pub type FxDiGraphMap<N, E> = FxGraphMap<N, E, petgraph::Directed>;
+
+/// Creates an empty [`FxGraphMap`] with preallocated space for `nodes` nodes and `edges` edges.
+pub fn graph_map_with_capacity<N, E, Ty>(nodes: usize, edges: usize) -> FxGraphMap<N, E, Ty>
+where
+    N: petgraph::graphmap::NodeTrait,
+    Ty: petgraph::EdgeType,
+{
+    FxGraphMap::with_capacity_and_hasher(nodes, edges, FxBuildHasher)
+}
+
+/// Creates an [`FxGraphMap`] from an iterator of edges, adding nodes as needed.
+///
+/// Items may be `(a, b)` pairs, using `E::default()` as the weight, or `(a, b, weight)` triples.
+pub fn graph_map_from_edges<N, E, Ty, I>(iterable: I) -> FxGraphMap<N, E, Ty>
+where
+    N: petgraph::graphmap::NodeTrait,
+    Ty: petgraph::EdgeType,
+    I: IntoIterator,
+    I::Item: petgraph::IntoWeightedEdge<E, NodeId = N>,
+{
+    FxGraphMap::from_edges(iterable)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{graph_map_from_edges, graph_map_with_capacity, FxDiGraphMap, FxUnGraphMap};
+
+    #[test]
+    fn graph_maps() {
+        let mut graph: FxUnGraphMap<u32, u32> = graph_map_with_capacity(4, 4);
+        graph.add_edge(1, 2, 10);
+        graph.add_edge(2, 3, 20);
+        assert_eq!(graph.edge_weight(2, 1), Some(&10));
+
+        let graph: FxDiGraphMap<u32, u32> = graph_map_from_edges([(1, 2, 1), (2, 3, 1), (1, 3, 5)]);
+        let costs = petgraph::algo::dijkstra(&graph, 1, Some(3), |e| *e.2);
+        assert_eq!(costs[&3], 2);
+    }
+}
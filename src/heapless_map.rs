@@ -0,0 +1,45 @@
+use hash32::BuildHasherDefault;
+
+use crate::FxHasher;
+
+/// Type alias for a fixed-capacity [`heapless::IndexMap`] that uses the Fx hashing algorithm.
+///
+/// Like all `heapless` maps, the capacity `N` must be a power of two greater than one.
+///
+/// ```
+/// use rustc_hash::FxHeaplessIndexMap;
+///
+/// let mut map = FxHeaplessIndexMap::<u32, u32, 4>::new();
+/// map.insert(22, 44).unwrap();
+/// assert_eq!(map.get(&22), Some(&44));
+/// ```
+pub type FxHeaplessIndexMap<K, V, const N: usize> =
+    heapless::IndexMap<K, V, BuildHasherDefault<FxHasher>, N>;
+
+/// Type alias for a fixed-capacity [`heapless::IndexSet`] that uses the Fx hashing algorithm.
+///
+/// Like all `heapless` sets, the capacity `N` must be a power of two greater than one.
+pub type FxHeaplessIndexSet<T, const N: usize> =
+    heapless::IndexSet<T, BuildHasherDefault<FxHasher>, N>;
+
+#[cfg(test)]
+mod tests {
+    use crate::{FxHeaplessIndexMap, FxHeaplessIndexSet};
+
+    #[test]
+    fn fails_when_full() {
+        let mut map = FxHeaplessIndexMap::<u32, u32, 4>::new();
+        for i in 0..4 {
+            assert_eq!(map.insert(i, i), Ok(None));
+        }
+        assert_eq!(map.insert(4, 4), Err((4, 4)));
+        assert_eq!(map.insert(3, 6), Ok(Some(3)));
+        assert!(map.keys().copied().eq(0..4));
+
+        let mut set = FxHeaplessIndexSet::<u32, 2>::new();
+        assert_eq!(set.insert(1), Ok(true));
+        assert_eq!(set.insert(1), Ok(false));
+        assert_eq!(set.insert(2), Ok(true));
+        assert_eq!(set.insert(3), Err(3));
+    }
+}
@@ -0,0 +1,258 @@
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::Reverse;
+use core::fmt;
+use core::hash::Hash;
+
+use crate::FxTable;
+
+/// Tracks the most frequent keys of a stream in bounded memory, using the Fx
+/// hashing algorithm.
+///
+/// This is the Space-Saving algorithm: it counts at most `capacity` keys, and
+/// a new key that arrives when all are taken replaces the key with the
+/// smallest count, inheriting that count as its possible overestimate. Every
+/// count is thus an upper bound of the true count, exceeding it by at most
+/// the reported error, and every key occurring more than `total / capacity`
+/// times is tracked.
+///
+/// ```
+/// use rustc_hash::FxHeavyHitters;
+///
+/// let mut hot = FxHeavyHitters::new(3);
+/// for key in ["a", "b", "a", "c", "a", "d", "b", "a"] {
+///     hot.add(key);
+/// }
+/// let top = hot.top(1);
+/// assert_eq!(top[0].0, "a");
+/// assert!(top[0].1 - top[0].2 <= 4 && 4 <= top[0].1);
+/// ```
+#[derive(Clone)]
+pub struct FxHeavyHitters<K> {
+    // A binary min-heap by count, so that the key to replace is the first.
+    entries: Vec<Entry<K>>,
+    positions: FxTable<K, usize>,
+    capacity: usize,
+    total: u64,
+}
+
+#[derive(Clone)]
+struct Entry<K> {
+    key: K,
+    count: u64,
+    error: u64,
+}
+
+impl<K> FxHeavyHitters<K> {
+    /// Creates an empty tracker counting at most `capacity` keys.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is 0.
+    pub fn new(capacity: usize) -> FxHeavyHitters<K> {
+        assert!(capacity > 0, "a heavy hitters tracker needs room for a key");
+        FxHeavyHitters {
+            entries: Vec::with_capacity(capacity),
+            positions: FxTable::with_capacity_and_hasher(capacity, Default::default()),
+            capacity,
+            total: 0,
+        }
+    }
+
+    /// Returns the maximum number of keys counted.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of keys counted.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if nothing was added.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the sum of all added counts, saturating at `u64::MAX`.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Removes all keys.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.positions.clear();
+        self.total = 0;
+    }
+
+    /// Returns an iterator over the counted keys with their counts and
+    /// errors, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, u64, u64)> {
+        self.entries.iter().map(|e| (&e.key, e.count, e.error))
+    }
+
+    /// Returns the `k` keys with the highest counts with their counts and
+    /// errors, in descending order of count.
+    ///
+    /// The true count of every key is between its count minus its error and
+    /// its count. The order of keys with equal counts is unspecified.
+    pub fn top(&self, k: usize) -> Vec<(K, u64, u64)>
+    where
+        K: Clone,
+    {
+        let mut entries: Vec<_> = self.iter().collect();
+        if k < entries.len() {
+            entries.select_nth_unstable_by_key(k, |&(_, c, _)| Reverse(c));
+            entries.truncate(k);
+        }
+        entries.sort_unstable_by_key(|&(_, c, _)| Reverse(c));
+        entries
+            .into_iter()
+            .map(|(key, count, error)| (key.clone(), count, error))
+            .collect()
+    }
+}
+
+impl<K: Eq + Hash + Clone> FxHeavyHitters<K> {
+    /// Counts one occurrence of `key` and returns its new count.
+    pub fn add(&mut self, key: K) -> u64 {
+        self.add_n(key, 1)
+    }
+
+    /// Counts `n` occurrences of `key` and returns its new count, saturating
+    /// at `u64::MAX`.
+    ///
+    /// If the tracker is full and `key` is not counted yet, it replaces the
+    /// key with the smallest count.
+    pub fn add_n(&mut self, key: K, n: u64) -> u64 {
+        self.total = self.total.saturating_add(n);
+        let i = match self.positions.get(&key) {
+            Some(&i) => {
+                self.entries[i].count = self.entries[i].count.saturating_add(n);
+                i
+            }
+            None if self.entries.len() < self.capacity => {
+                let i = self.entries.len();
+                self.positions.insert(key.clone(), i);
+                self.entries.push(Entry {
+                    key,
+                    count: n,
+                    error: 0,
+                });
+                self.sift_up(i)
+            }
+            None => {
+                let min = self.entries[0].count;
+                let old = core::mem::replace(
+                    &mut self.entries[0],
+                    Entry {
+                        key: key.clone(),
+                        count: min.saturating_add(n),
+                        error: min,
+                    },
+                );
+                self.positions.remove(&old.key);
+                self.positions.insert(key, 0);
+                0
+            }
+        };
+        let count = self.entries[i].count;
+        self.sift_down(i);
+        count
+    }
+
+    /// Returns the count and error of `key`, or `None` if it is not counted.
+    pub fn get<Q>(&self, key: &Q) -> Option<(u64, u64)>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let entry = &self.entries[*self.positions.get(key)?];
+        Some((entry.count, entry.error))
+    }
+
+    fn sift_up(&mut self, mut i: usize) -> usize {
+        while i > 0 {
+            let parent = (i - 1) / 2;
+            if self.entries[parent].count <= self.entries[i].count {
+                break;
+            }
+            self.swap(i, parent);
+            i = parent;
+        }
+        i
+    }
+
+    fn sift_down(&mut self, mut i: usize) {
+        loop {
+            let mut smallest = i;
+            for child in [2 * i + 1, 2 * i + 2] {
+                if child < self.entries.len()
+                    && self.entries[child].count < self.entries[smallest].count
+                {
+                    smallest = child;
+                }
+            }
+            if smallest == i {
+                return;
+            }
+            self.swap(i, smallest);
+            i = smallest;
+        }
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.entries.swap(a, b);
+        *self.positions.get_mut(&self.entries[a].key).unwrap() = a;
+        *self.positions.get_mut(&self.entries[b].key).unwrap() = b;
+    }
+}
+
+impl<K: fmt::Debug> fmt::Debug for FxHeavyHitters<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Eq + Hash + Clone> Extend<K> for FxHeavyHitters<K> {
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        for key in iter {
+            self.add(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FxHeavyHitters;
+
+    #[test]
+    fn finds_frequent_keys() {
+        let mut hot = FxHeavyHitters::new(50);
+        // Key `k` below 10 occurs in every `k + 1`-th of 1000 rounds, among
+        // 5000 keys occurring once.
+        for round in 0..1000_u32 {
+            for k in 0..10 {
+                if round % (k + 1) == 0 {
+                    hot.add(k);
+                }
+            }
+            for noise in 0..5 {
+                hot.add(1000 + round * 5 + noise);
+            }
+        }
+        assert_eq!(hot.len(), 50);
+        let top = hot.top(3);
+        assert_eq!([top[0].0, top[1].0, top[2].0], [0, 1, 2]);
+        for (key, count, error) in top {
+            let exact = u64::from(999 / (key + 1) + 1);
+            assert!(count - error <= exact && exact <= count);
+        }
+        assert_eq!(hot.get(&0), Some((1000, 0)));
+        assert_eq!(hot.get(&999), None);
+
+        hot.clear();
+        assert!(hot.is_empty() && hot.total() == 0);
+    }
+}
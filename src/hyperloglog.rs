@@ -0,0 +1,249 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::Hash;
+use core::marker::PhantomData;
+
+use crate::sketch::{self, SketchMismatch, DEFAULT_SEEDS};
+
+/// The precision of sketches collected from iterators.
+const DEFAULT_PRECISION: u8 = 14;
+
+/// The smallest and the largest supported precision.
+pub(crate) const PRECISIONS: core::ops::RangeInclusive<u8> = 4..=18;
+
+/// A HyperLogLog cardinality estimator using the Fx hashing algorithm.
+///
+/// It estimates the number of distinct items in a stream in a fixed amount of
+/// memory: `2^precision` one-byte registers, each holding the longest run of
+/// leading zeros seen in the hashes of the items that select it. The
+/// relative standard error is `1.04 / sqrt(2^precision)`, about 0.8% for the
+/// default precision of 14, which takes 16 KiB. Small cardinalities are
+/// estimated by linear counting of the empty registers instead, which is
+/// accurate where the raw estimate is biased.
+///
+/// Sketches with the same precision and seeds can be merged, e.g. to count
+/// the distinct items over several shards. Sketches created without explicit
+/// seeds all use the same seeds, which makes them mergeable across
+/// processes.
+///
+/// ```
+/// use rustc_hash::FxHyperLogLog;
+///
+/// let mut users = FxHyperLogLog::new(12);
+/// for i in 0..100_000_u32 {
+///     users.insert(&(i % 20_000));
+/// }
+/// let estimate = users.estimate();
+/// assert!((19_000.0..21_000.0).contains(&estimate));
+/// ```
+pub struct FxHyperLogLog<T: ?Sized> {
+    pub(crate) registers: Vec<u8>,
+    pub(crate) precision: u8,
+    pub(crate) seeds: (usize, usize),
+    pub(crate) marker: PhantomData<fn(&T)>,
+}
+
+impl<T: ?Sized> FxHyperLogLog<T> {
+    /// Creates an empty sketch with `2^precision` registers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `precision` is not between 4 and 18.
+    pub fn new(precision: u8) -> FxHyperLogLog<T> {
+        Self::with_seeds(precision, DEFAULT_SEEDS)
+    }
+
+    /// Creates an empty sketch like [`new`](Self::new), selecting registers
+    /// and ranks with Fx hashes seeded with `seeds`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `precision` is not between 4 and 18, or if the seeds are
+    /// equal.
+    pub fn with_seeds(precision: u8, seeds: (usize, usize)) -> FxHyperLogLog<T> {
+        assert!(
+            PRECISIONS.contains(&precision),
+            "the precision must be between 4 and 18"
+        );
+        assert_ne!(seeds.0, seeds.1, "the seeds must be different");
+        FxHyperLogLog {
+            registers: vec![0; 1 << precision],
+            precision,
+            seeds,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the precision, the binary logarithm of the number of
+    /// registers.
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    /// Returns the seeds of the two hash functions.
+    pub fn seeds(&self) -> (usize, usize) {
+        self.seeds
+    }
+
+    /// Returns the relative standard error of the estimates.
+    pub fn relative_error(&self) -> f64 {
+        let mut sqrt_m = (1_u64 << (self.precision / 2)) as f64;
+        if self.precision % 2 == 1 {
+            sqrt_m *= core::f64::consts::SQRT_2;
+        }
+        1.04 / sqrt_m
+    }
+
+    /// Returns `true` if no item was inserted.
+    pub fn is_empty(&self) -> bool {
+        self.registers.iter().all(|&r| r == 0)
+    }
+
+    /// Removes all items, keeping the parameters.
+    pub fn clear(&mut self) {
+        self.registers.fill(0);
+    }
+
+    /// Inserts an item, returning `true` if the sketch changed.
+    pub fn insert(&mut self, item: &T) -> bool
+    where
+        T: Hash,
+    {
+        self.insert_hashes(sketch::hash_pair(self.seeds, item))
+    }
+
+    /// Returns the estimated number of distinct items inserted.
+    pub fn estimate(&self) -> f64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.precision {
+            4 => 0.673,
+            5 => 0.697,
+            6 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let mut sum = 0.0;
+        let mut zeros = 0_u32;
+        for &r in &self.registers {
+            // 2^-r, exactly.
+            sum += f64::from_bits((1023 - u64::from(r)) << 52);
+            zeros += u32::from(r == 0);
+        }
+        let raw = alpha * m * m / sum;
+        if raw <= 2.5 * m && zeros > 0 {
+            // Linear counting, m * ln(m / zeros).
+            m * sketch::log2(m / f64::from(zeros)) * core::f64::consts::LN_2
+        } else {
+            raw
+        }
+    }
+
+    /// Returns `true` if `other` has the same parameters, so that the
+    /// sketches can be merged.
+    pub fn is_compatible(&self, other: &FxHyperLogLog<T>) -> bool {
+        self.precision == other.precision && self.seeds == other.seeds
+    }
+
+    /// Adds the items of `other` to this sketch.
+    ///
+    /// The result is the sketch that inserting the items of both sketches
+    /// would have produced.
+    pub fn merge(&mut self, other: &FxHyperLogLog<T>) -> Result<(), SketchMismatch> {
+        if !self.is_compatible(other) {
+            return Err(SketchMismatch);
+        }
+        for (a, &b) in self.registers.iter_mut().zip(&other.registers) {
+            *a = (*a).max(b);
+        }
+        Ok(())
+    }
+
+    fn insert_hashes(&mut self, (h1, h2): (u64, u64)) -> bool {
+        let index = (sketch::mix(h1) >> (64 - self.precision)) as usize;
+        // Setting the lowest bit caps the rank at 64.
+        let rank = (sketch::mix(h2) | 1).leading_zeros() as u8 + 1;
+        let register = &mut self.registers[index];
+        if rank > *register {
+            *register = rank;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl<T: Hash> FromIterator<T> for FxHyperLogLog<T> {
+    /// Collects the items into a sketch with a precision of 14.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> FxHyperLogLog<T> {
+        let mut sketch = Self::new(DEFAULT_PRECISION);
+        sketch.extend(iter);
+        sketch
+    }
+}
+
+impl<T: Hash> Extend<T> for FxHyperLogLog<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.insert(&item);
+        }
+    }
+}
+
+impl<T: ?Sized> Clone for FxHyperLogLog<T> {
+    fn clone(&self) -> Self {
+        FxHyperLogLog {
+            registers: self.registers.clone(),
+            precision: self.precision,
+            seeds: self.seeds,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> PartialEq for FxHyperLogLog<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.is_compatible(other) && self.registers == other.registers
+    }
+}
+
+impl<T: ?Sized> Eq for FxHyperLogLog<T> {}
+
+impl<T: ?Sized> fmt::Debug for FxHyperLogLog<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FxHyperLogLog")
+            .field("precision", &self.precision)
+            .field("estimate", &self.estimate())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FxHyperLogLog, SketchMismatch};
+
+    #[test]
+    fn estimates_within_error() {
+        for precision in [4, 10, 14] {
+            let mut sketch = FxHyperLogLog::with_seeds(precision, (1, 2));
+            assert_eq!(sketch.estimate(), 0.0);
+            let bound = 4.0 * sketch.relative_error();
+            for n in [10_u64, 1_000, 100_000] {
+                sketch.clear();
+                sketch.extend(0..n);
+                let error = (sketch.estimate() - n as f64).abs() / n as f64;
+                assert!(error < bound, "p={precision} n={n}: error {error}");
+            }
+        }
+    }
+
+    #[test]
+    fn merge() {
+        let mut a: FxHyperLogLog<u32> = (0..60_000).collect();
+        let b: FxHyperLogLog<u32> = (40_000..100_000).collect();
+        a.merge(&b).unwrap();
+        assert_eq!(a, (0..100_000).collect());
+
+        let other = FxHyperLogLog::new(10);
+        assert_eq!(a.merge(&other), Err(SketchMismatch));
+    }
+}
@@ -0,0 +1,288 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+
+use hashbrown::HashTable;
+
+use crate::FxBuildHasher;
+
+/// The number of buckets of the old table migrated by every mutating operation.
+const MIGRATE_BUCKETS: usize = 8;
+
+/// A hash map using the Fx hashing algorithm that grows by rehashing
+/// incrementally instead of all at once.
+///
+/// When the table is full, a table of twice the capacity is allocated and the
+/// entries of the old table are moved over a few buckets at a time by each
+/// following mutating operation. This bounds the latency of a single insert,
+/// at the cost of lookups probing both tables while a migration is in progress.
+/// Allocating the new table is still proportional to its size, but that is
+/// much cheaper than rehashing every entry.
+///
+/// ```
+/// use rustc_hash::FxIncrementalMap;
+///
+/// let mut map = FxIncrementalMap::new();
+/// for i in 0..1000_u32 {
+///     map.insert(i, i * 2);
+/// }
+/// assert_eq!(map.len(), 1000);
+/// assert_eq!(map.get(&500), Some(&1000));
+/// ```
+#[derive(Clone)]
+pub struct FxIncrementalMap<K, V> {
+    table: HashTable<(K, V)>,
+    old: HashTable<(K, V)>,
+    cursor: usize,
+}
+
+impl<K, V> FxIncrementalMap<K, V> {
+    /// Creates an empty `FxIncrementalMap`.
+    pub fn new() -> FxIncrementalMap<K, V> {
+        Self::with_capacity(0)
+    }
+
+    /// Creates an empty `FxIncrementalMap` with room for at least `capacity`
+    /// entries before the first migration.
+    pub fn with_capacity(capacity: usize) -> FxIncrementalMap<K, V> {
+        FxIncrementalMap {
+            table: HashTable::with_capacity(capacity),
+            old: HashTable::new(),
+            cursor: 0,
+        }
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.table.len() + self.old.len()
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if entries of a previous table are still being migrated.
+    pub fn is_migrating(&self) -> bool {
+        !self.old.is_empty()
+    }
+
+    /// Removes all entries, keeping the allocated capacity of the current table.
+    pub fn clear(&mut self) {
+        self.table.clear();
+        self.old = HashTable::new();
+        self.cursor = 0;
+    }
+
+    /// Returns an iterator over the keys and values, in unspecified order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.table
+            .iter()
+            .chain(self.old.iter())
+            .map(|(k, v)| (k, v))
+    }
+
+    /// Returns an iterator over the keys and mutable values, in unspecified order.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut V)> {
+        self.table
+            .iter_mut()
+            .chain(self.old.iter_mut())
+            .map(|(k, v)| (&*k, v))
+    }
+}
+
+impl<K: Eq + Hash, V> FxIncrementalMap<K, V> {
+    /// Returns a reference to the value of `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let hash = FxBuildHasher.hash_one(key);
+        let eq = |(k, _): &(K, V)| k.borrow() == key;
+        let (_, v) = self
+            .table
+            .find(hash, eq)
+            .or_else(|| self.old.find(hash, eq))?;
+        Some(v)
+    }
+
+    /// Returns a mutable reference to the value of `key`.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.migrate();
+        let hash = FxBuildHasher.hash_one(key);
+        let eq = |(k, _): &(K, V)| k.borrow() == key;
+        let (_, v) = match self.table.find_mut(hash, eq) {
+            Some(entry) => entry,
+            None => self.old.find_mut(hash, eq)?,
+        };
+        Some(v)
+    }
+
+    /// Returns `true` if the map contains `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Inserts a key-value pair, returning the previous value of the key.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.migrate();
+        let hash = FxBuildHasher.hash_one(&key);
+        let eq = |(k, _): &(K, V)| *k == key;
+        if let Some((_, v)) = self.table.find_mut(hash, eq) {
+            return Some(core::mem::replace(v, value));
+        }
+        if let Some((_, v)) = self.old.find_mut(hash, eq) {
+            return Some(core::mem::replace(v, value));
+        }
+
+        if self.table.len() == self.table.capacity() {
+            self.grow();
+        }
+        self.table.insert_unique(hash, (key, value), Self::hasher);
+        None
+    }
+
+    /// Removes `key`, returning its value.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.migrate();
+        let hash = FxBuildHasher.hash_one(key);
+        let eq = |(k, _): &(K, V)| k.borrow() == key;
+        let entry = match self.table.find_entry(hash, eq) {
+            Ok(entry) => entry,
+            Err(_) => self.old.find_entry(hash, eq).ok()?,
+        };
+        let ((_, v), _) = entry.remove();
+        Some(v)
+    }
+
+    /// Moves all remaining entries of the previous table into the current one.
+    pub fn finish_migration(&mut self) {
+        while self.is_migrating() {
+            self.migrate();
+        }
+    }
+
+    fn hasher((k, _): &(K, V)) -> u64 {
+        FxBuildHasher.hash_one(k)
+    }
+
+    /// Replaces the full table with one of twice the capacity, which the
+    /// entries of the full table are migrated to over the next operations.
+    fn grow(&mut self) {
+        // The new table has room for all entries of the old one, and the old
+        // table has at most `capacity * 8 / 7` buckets, so the migration always
+        // completes long before the new table is full again. Finishing it here
+        // is only a safeguard.
+        self.finish_migration();
+        let capacity = (self.table.capacity() * 2).max(4);
+        self.old = core::mem::replace(&mut self.table, HashTable::with_capacity(capacity));
+        self.cursor = 0;
+    }
+
+    fn migrate(&mut self) {
+        if !self.is_migrating() {
+            return;
+        }
+
+        let end = (self.cursor + MIGRATE_BUCKETS).min(self.old.num_buckets());
+        for i in self.cursor..end {
+            if let Ok(entry) = self.old.get_bucket_entry(i) {
+                let (entry, _) = entry.remove();
+                let hash = Self::hasher(&entry);
+                self.table.insert_unique(hash, entry, Self::hasher);
+            }
+        }
+        self.cursor = end;
+
+        if self.old.is_empty() {
+            self.old = HashTable::new();
+            self.cursor = 0;
+        }
+    }
+}
+
+impl<K, V> Default for FxIncrementalMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for FxIncrementalMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Eq + Hash, V> Extend<(K, V)> for FxIncrementalMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<K: Eq + Hash, V> FromIterator<(K, V)> for FxIncrementalMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FxIncrementalMap;
+
+    #[test]
+    fn migrates_while_growing() {
+        let mut map = FxIncrementalMap::with_capacity(100);
+        let capacity = map.table.capacity();
+        for i in 0..capacity as u32 {
+            map.insert(i, i);
+        }
+        assert!(!map.is_migrating());
+
+        map.insert(u32::MAX, 0);
+        assert!(map.is_migrating());
+        assert_eq!(map.len(), capacity + 1);
+        for i in 0..capacity as u32 {
+            assert_eq!(map.get(&i), Some(&i));
+        }
+
+        assert_eq!(map.insert(0, 10), Some(0));
+        assert_eq!(map.remove(&1), Some(1));
+        *map.get_mut(&2).unwrap() = 20;
+        map.finish_migration();
+        assert!(!map.is_migrating());
+        assert_eq!(map.len(), capacity);
+        assert_eq!(map.get(&0), Some(&10));
+        assert_eq!(map.get(&2), Some(&20));
+        assert!(!map.contains_key(&1));
+    }
+
+    #[test]
+    fn never_migrates_everything_at_once() {
+        let mut map = FxIncrementalMap::new();
+        for i in 0..100_000_u32 {
+            map.insert(i, i);
+            assert!(map.old.len() <= map.table.capacity() - map.table.len());
+        }
+        assert_eq!(
+            map.iter().map(|(_, v)| *v as u64).sum::<u64>(),
+            4_999_950_000
+        );
+    }
+}
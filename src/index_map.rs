@@ -0,0 +1,47 @@
+use crate::FxBuildHasher;
+
+/// Type alias for an [`indexmap::IndexMap`] that uses the Fx hashing algorithm.
+///
+/// ```
+/// use rustc_hash::FxIndexMap;
+///
+/// let mut map: FxIndexMap<&str, u32> = FxIndexMap::default();
+/// map.insert("b", 2);
+/// map.insert("a", 1);
+/// assert_eq!(map.keys().copied().collect::<Vec<_>>(), ["b", "a"]);
+/// ```
+pub type FxIndexMap<K, V> = indexmap::IndexMap<K, V, FxBuildHasher>;
+
+/// Type alias for an [`indexmap::IndexSet`] that uses the Fx hashing algorithm.
+pub type FxIndexSet<T> = indexmap::IndexSet<T, FxBuildHasher>;
+
+/// Entry type of [`FxIndexMap`].
+pub type FxIndexMapEntry<'a, K, V> = indexmap::map::Entry<'a, K, V>;
+
+/// Occupied entry type of [`FxIndexMap`].
+pub type FxIndexMapOccupiedEntry<'a, K, V> = indexmap::map::OccupiedEntry<'a, K, V>;
+
+/// Vacant entry type of [`FxIndexMap`].
+pub type FxIndexMapVacantEntry<'a, K, V> = indexmap::map::VacantEntry<'a, K, V>;
+
+#[cfg(test)]
+mod tests {
+    use crate::{FxIndexMap, FxIndexMapEntry, FxIndexSet};
+
+    #[test]
+    fn preserves_insertion_order() {
+        let mut map = FxIndexMap::default();
+        for i in (0..16_u32).rev() {
+            map.insert(i, i * 2);
+        }
+
+        match map.entry(3) {
+            FxIndexMapEntry::Occupied(entry) => assert_eq!(*entry.get(), 6),
+            FxIndexMapEntry::Vacant(_) => unreachable!(),
+        }
+        assert!(map.keys().copied().eq((0..16).rev()));
+
+        let set: FxIndexSet<u32> = [3, 1, 2, 1].into_iter().collect();
+        assert!(set.iter().copied().eq([3, 1, 2]));
+    }
+}
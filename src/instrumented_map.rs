@@ -0,0 +1,365 @@
+use core::borrow::Borrow;
+use core::cell::Cell;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+
+use hashbrown::HashTable;
+
+use crate::FxBuildHasher;
+
+/// The default probe length above which a hit counts as a long probe.
+const DEFAULT_PROBE_LENGTH_THRESHOLD: u64 = 16;
+
+/// The number of collisions per lookup above which the statistics look
+/// adversarial. A well distributed hash has far less than one.
+#[cfg(feature = "tracing")]
+const ADVERSARIAL_COLLISIONS_PER_LOOKUP: f64 = 1.0;
+
+/// Statistics recorded by an [`FxInstrumentedMap`].
+///
+/// Every operation that looks up a key, including `insert` and `remove`,
+/// counts as a lookup.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FxMapStats {
+    /// The number of lookups.
+    pub lookups: u64,
+    /// The number of lookups that found their key.
+    pub hits: u64,
+    /// The number of stored keys compared with the key of a lookup.
+    pub comparisons: u64,
+    /// The number of comparisons with a stored key that was not equal. The
+    /// table only compares keys whose hashes share some bits, so these are
+    /// partial hash collisions.
+    pub collisions: u64,
+    /// The sum of the probe lengths of all hits.
+    pub total_probe_length: u64,
+    /// The longest probe length of a hit.
+    pub max_probe_length: u64,
+    /// The number of hits with a probe length above the threshold of the map.
+    pub long_probes: u64,
+    /// The number of times the table grew.
+    pub resizes: u64,
+}
+
+impl FxMapStats {
+    /// Returns the average probe length of the hits, or 0 if there were none.
+    ///
+    /// The probe length of a key is the distance between the bucket its hash
+    /// points to and the bucket it is stored in. It stays close to zero for a
+    /// well distributed hash.
+    pub fn average_probe_length(&self) -> f64 {
+        if self.hits == 0 {
+            0.0
+        } else {
+            self.total_probe_length as f64 / self.hits as f64
+        }
+    }
+
+    /// Returns the average number of collisions per lookup, or 0 if there were
+    /// no lookups.
+    pub fn collisions_per_lookup(&self) -> f64 {
+        if self.lookups == 0 {
+            0.0
+        } else {
+            self.collisions as f64 / self.lookups as f64
+        }
+    }
+}
+
+/// A hash map using the Fx hashing algorithm that records how well the hash
+/// distributes its keys.
+///
+/// The map uses the same table as `std`'s `HashMap`, so the [statistics]
+/// reflect how an `FxHashMap` would behave for the same operations. Recording
+/// them costs a little time on every lookup, so this type is meant for
+/// diagnosing a workload rather than for production use. The statistics are
+/// kept in a `Cell`, so the map is not `Sync`.
+///
+/// With the `tracing` feature, the map also emits events: a `debug` event
+/// when it grows, a `warn` event when a hit sets a new maximum probe length
+/// above the threshold, and a `warn` event when it grows while the lookups
+/// so far averaged more than one collision.
+///
+/// [statistics]: FxMapStats
+///
+/// ```
+/// use rustc_hash::FxInstrumentedMap;
+///
+/// let mut map = FxInstrumentedMap::new();
+/// for i in 0..1000_u64 {
+///     map.insert(i << 32, i);
+/// }
+/// assert_eq!(map.get(&(5 << 32)), Some(&5));
+///
+/// let stats = map.stats();
+/// assert_eq!(stats.lookups, 1001);
+/// assert_eq!(stats.hits, 1);
+/// assert!(stats.resizes > 0);
+/// assert!(stats.average_probe_length() < 8.0);
+/// ```
+#[derive(Clone)]
+pub struct FxInstrumentedMap<K, V> {
+    table: HashTable<(K, V)>,
+    stats: Cell<FxMapStats>,
+    probe_length_threshold: u64,
+}
+
+impl<K, V> FxInstrumentedMap<K, V> {
+    /// Creates an empty `FxInstrumentedMap`.
+    pub fn new() -> FxInstrumentedMap<K, V> {
+        FxInstrumentedMap {
+            table: HashTable::new(),
+            stats: Cell::default(),
+            probe_length_threshold: DEFAULT_PROBE_LENGTH_THRESHOLD,
+        }
+    }
+
+    /// Creates an empty `FxInstrumentedMap` with room for at least `capacity` entries.
+    pub fn with_capacity(capacity: usize) -> FxInstrumentedMap<K, V> {
+        FxInstrumentedMap {
+            table: HashTable::with_capacity(capacity),
+            stats: Cell::default(),
+            probe_length_threshold: DEFAULT_PROBE_LENGTH_THRESHOLD,
+        }
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    /// Returns the number of entries the map can hold without growing.
+    pub fn capacity(&self) -> usize {
+        self.table.capacity()
+    }
+
+    /// Removes all entries, keeping the statistics.
+    pub fn clear(&mut self) {
+        self.table.clear();
+    }
+
+    /// Returns the statistics recorded so far.
+    pub fn stats(&self) -> FxMapStats {
+        self.stats.get()
+    }
+
+    /// Resets the statistics, returning the ones recorded so far.
+    pub fn reset_stats(&self) -> FxMapStats {
+        self.stats.take()
+    }
+
+    /// Sets the probe length above which a hit counts as a long probe. The
+    /// default is 16.
+    pub fn set_probe_length_threshold(&mut self, threshold: u64) {
+        self.probe_length_threshold = threshold;
+    }
+
+    /// Returns an iterator over the keys and values, in unspecified order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.table.iter().map(|(k, v)| (k, v))
+    }
+
+    /// Returns an iterator over the keys and mutable values, in unspecified order.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut V)> {
+        self.table.iter_mut().map(|(k, v)| (&*k, v))
+    }
+}
+
+impl<K: Eq + Hash, V> FxInstrumentedMap<K, V> {
+    /// Returns the hash and the bucket of `key`, recording the lookup.
+    fn find<Q>(&self, key: &Q) -> (u64, Option<usize>)
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let hash = FxBuildHasher.hash_one(key);
+        let mut comparisons = 0;
+        let bucket = self.table.find_bucket_index(hash, |(k, _)| {
+            comparisons += 1;
+            k.borrow() == key
+        });
+
+        let mut stats = self.stats.get();
+        stats.lookups += 1;
+        stats.comparisons += comparisons;
+        stats.collisions += comparisons - u64::from(bucket.is_some());
+        if let Some(bucket) = bucket {
+            // The table takes the home bucket from the low bits of the hash.
+            let mask = self.table.num_buckets() - 1;
+            let probe_length = (bucket.wrapping_sub(hash as usize) & mask) as u64;
+            stats.hits += 1;
+            stats.total_probe_length += probe_length;
+            if probe_length > self.probe_length_threshold {
+                stats.long_probes += 1;
+                #[cfg(feature = "tracing")]
+                if probe_length > stats.max_probe_length {
+                    tracing::warn!(
+                        probe_length,
+                        threshold = self.probe_length_threshold,
+                        len = self.table.len(),
+                        "long probe sequence in FxInstrumentedMap"
+                    );
+                }
+            }
+            stats.max_probe_length = stats.max_probe_length.max(probe_length);
+        }
+        self.stats.set(stats);
+        (hash, bucket)
+    }
+
+    /// Returns a reference to the value of `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let (_, v) = self.table.get_bucket(self.find(key).1?)?;
+        Some(v)
+    }
+
+    /// Returns a mutable reference to the value of `key`.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let bucket = self.find(key).1?;
+        let (_, v) = self.table.get_bucket_mut(bucket)?;
+        Some(v)
+    }
+
+    /// Returns `true` if the map contains `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.find(key).1.is_some()
+    }
+
+    /// Inserts a key-value pair, returning the previous value of the key.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let (hash, bucket) = self.find(&key);
+        if let Some((_, v)) = bucket.and_then(|b| self.table.get_bucket_mut(b)) {
+            return Some(core::mem::replace(v, value));
+        }
+        let buckets = self.table.num_buckets();
+        self.table
+            .insert_unique(hash, (key, value), |(k, _)| FxBuildHasher.hash_one(k));
+        if self.table.num_buckets() > buckets {
+            let mut stats = self.stats.get();
+            stats.resizes += 1;
+            self.stats.set(stats);
+            #[cfg(feature = "tracing")]
+            {
+                tracing::debug!(
+                    buckets = self.table.num_buckets(),
+                    len = self.table.len(),
+                    "FxInstrumentedMap resized"
+                );
+                if stats.collisions_per_lookup() > ADVERSARIAL_COLLISIONS_PER_LOOKUP {
+                    tracing::warn!(
+                        collisions = stats.collisions,
+                        lookups = stats.lookups,
+                        "hash collisions in FxInstrumentedMap look adversarial"
+                    );
+                }
+            }
+        }
+        None
+    }
+
+    /// Removes `key`, returning its value.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let bucket = self.find(key).1?;
+        let ((_, v), _) = self.table.get_bucket_entry(bucket).ok()?.remove();
+        Some(v)
+    }
+}
+
+impl<K, V> Default for FxInstrumentedMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for FxInstrumentedMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Eq + Hash, V> Extend<(K, V)> for FxInstrumentedMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<K: Eq + Hash, V> FromIterator<(K, V)> for FxInstrumentedMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::hash::{Hash, Hasher};
+
+    use crate::{FxInstrumentedMap, FxMapStats};
+
+    /// A key whose hash ignores its value, so all keys collide.
+    #[derive(PartialEq, Eq)]
+    struct Colliding(u32);
+
+    impl Hash for Colliding {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            0_u32.hash(state);
+        }
+    }
+
+    #[test]
+    fn records_operations() {
+        let mut map: FxInstrumentedMap<u32, u32> = FxInstrumentedMap::with_capacity(100);
+        map.extend((0..100).map(|i| (i, i)));
+        assert_eq!(map.get(&7), Some(&7));
+        assert_eq!(map.get(&1000), None);
+        *map.get_mut(&8).unwrap() += 1;
+        assert_eq!(map.remove(&8), Some(9));
+
+        let stats = map.reset_stats();
+        assert_eq!(stats.lookups, 104);
+        assert_eq!(stats.hits, 3);
+        assert_eq!(stats.resizes, 0);
+        assert_eq!(stats.long_probes, 0);
+        assert_eq!(map.stats(), FxMapStats::default());
+        assert_eq!(map.len(), 99);
+    }
+
+    #[test]
+    fn detects_collisions() {
+        let mut map = FxInstrumentedMap::new();
+        map.extend((0..50).map(|i| (Colliding(i), i)));
+        map.reset_stats();
+
+        assert!((0..50).all(|i| map.get(&Colliding(i)) == Some(&i)));
+        let stats = map.stats();
+        assert_eq!(stats.hits, 50);
+        assert!(stats.collisions_per_lookup() > 10.0);
+        assert!(stats.average_probe_length() > 10.0);
+        assert!(stats.max_probe_length >= 49);
+        assert!(stats.long_probes > 0);
+    }
+}
@@ -0,0 +1,275 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::FxTable;
+
+/// The number of keys the dense region may cover regardless of the map's length.
+const MIN_DENSE: usize = 64;
+
+/// A map keyed by mostly dense `u32` IDs, such as entity IDs or node indices.
+///
+/// Keys below a bound are stored directly at their index in a vector, so
+/// looking them up needs neither hashing nor probing. Keys beyond the bound go
+/// into a hash table using the Fx hashing algorithm. The dense region grows
+/// towards larger keys as long as at least half of it stays in use, and moves
+/// overflowing keys it now covers out of the hash table.
+///
+/// ```
+/// use rustc_hash::FxIntMap;
+///
+/// let mut map = FxIntMap::new();
+/// for id in 0..1000 {
+///     map.insert(id, id * 2);
+/// }
+/// map.insert(u32::MAX, 0);
+///
+/// assert_eq!(map.get(500), Some(&1000));
+/// assert_eq!(map.get(u32::MAX), Some(&0));
+/// assert_eq!(map.len(), 1001);
+/// ```
+#[derive(Clone)]
+pub struct FxIntMap<V> {
+    dense: Vec<Option<V>>,
+    dense_len: usize,
+    overflow: FxTable<u32, V>,
+}
+
+impl<V> FxIntMap<V> {
+    /// Creates an empty `FxIntMap`.
+    pub fn new() -> FxIntMap<V> {
+        FxIntMap {
+            dense: Vec::new(),
+            dense_len: 0,
+            overflow: FxTable::default(),
+        }
+    }
+
+    /// Creates an empty `FxIntMap` whose dense region covers the keys `0..capacity`.
+    pub fn with_capacity(capacity: usize) -> FxIntMap<V> {
+        let mut map = Self::new();
+        map.dense.resize_with(capacity, || None);
+        map
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.dense_len + self.overflow.len()
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of keys covered by the dense region.
+    pub fn dense_capacity(&self) -> usize {
+        self.dense.len()
+    }
+
+    /// Removes all entries, keeping the allocated memory.
+    pub fn clear(&mut self) {
+        self.dense.iter_mut().for_each(|v| *v = None);
+        self.dense_len = 0;
+        self.overflow.clear();
+    }
+
+    /// Returns a reference to the value of `key`.
+    pub fn get(&self, key: u32) -> Option<&V> {
+        match self.dense.get(key as usize) {
+            Some(slot) => slot.as_ref(),
+            None => self.overflow.get(&key),
+        }
+    }
+
+    /// Returns a mutable reference to the value of `key`.
+    pub fn get_mut(&mut self, key: u32) -> Option<&mut V> {
+        match self.dense.get_mut(key as usize) {
+            Some(slot) => slot.as_mut(),
+            None => self.overflow.get_mut(&key),
+        }
+    }
+
+    /// Returns `true` if the map contains `key`.
+    pub fn contains_key(&self, key: u32) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Inserts a key-value pair, returning the previous value of the key.
+    pub fn insert(&mut self, key: u32, value: V) -> Option<V> {
+        let index = key as usize;
+        if index >= self.dense.len() {
+            self.grow_to(index);
+        }
+        match self.dense.get_mut(index) {
+            Some(slot) => {
+                let old = slot.replace(value);
+                if old.is_none() {
+                    self.dense_len += 1;
+                }
+                old
+            }
+            None => self.overflow.insert(key, value),
+        }
+    }
+
+    /// Returns the value of `key`, inserting the result of `default` first if
+    /// the key is missing.
+    pub fn get_or_insert_with(&mut self, key: u32, default: impl FnOnce() -> V) -> &mut V {
+        if !self.contains_key(key) {
+            self.insert(key, default());
+        }
+        self.get_mut(key).unwrap()
+    }
+
+    /// Removes `key`, returning its value.
+    pub fn remove(&mut self, key: u32) -> Option<V> {
+        match self.dense.get_mut(key as usize) {
+            Some(slot) => {
+                let old = slot.take();
+                if old.is_some() {
+                    self.dense_len -= 1;
+                }
+                old
+            }
+            None => self.overflow.remove(&key),
+        }
+    }
+
+    /// Returns an iterator over the keys and values. Keys in the dense region
+    /// come first and in ascending order, followed by the others in
+    /// unspecified order.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &V)> {
+        let dense = self.dense.iter().enumerate();
+        dense
+            .filter_map(|(k, v)| Some((k as u32, v.as_ref()?)))
+            .chain(self.overflow.iter().map(|(&k, v)| (k, v)))
+    }
+
+    /// Returns an iterator over the keys and mutable values, in the order of
+    /// [`iter`](Self::iter).
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (u32, &mut V)> {
+        let dense = self.dense.iter_mut().enumerate();
+        dense
+            .filter_map(|(k, v)| Some((k as u32, v.as_mut()?)))
+            .chain(self.overflow.iter_mut().map(|(&k, v)| (k, v)))
+    }
+
+    /// Returns an iterator over the keys, in the order of [`iter`](Self::iter).
+    pub fn keys(&self) -> impl Iterator<Item = u32> + '_ {
+        self.iter().map(|(k, _)| k)
+    }
+
+    /// Returns an iterator over the values, in the order of [`iter`](Self::iter).
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, v)| v)
+    }
+
+    /// Extends the dense region to cover `index` if it would stay at least
+    /// half full, and moves the overflowing keys it now covers into it.
+    fn grow_to(&mut self, index: usize) {
+        let limit = MIN_DENSE.max(2 * (self.len() + 1));
+        if index >= limit {
+            return;
+        }
+        // Grow at least geometrically so moving keys out of the overflow
+        // table is amortized over many insertions.
+        let new_len = (index + 1).max(2 * self.dense.len()).min(limit);
+        self.dense.resize_with(new_len, || None);
+        if self.overflow.keys().any(|&k| (k as usize) < new_len) {
+            let overflow = core::mem::take(&mut self.overflow);
+            for (k, v) in overflow {
+                match self.dense.get_mut(k as usize) {
+                    Some(slot) => {
+                        *slot = Some(v);
+                        self.dense_len += 1;
+                    }
+                    None => {
+                        self.overflow.insert(k, v);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<V> Default for FxIntMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V: fmt::Debug> fmt::Debug for FxIntMap<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<V> Extend<(u32, V)> for FxIntMap<V> {
+    fn extend<I: IntoIterator<Item = (u32, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<V> FromIterator<(u32, V)> for FxIntMap<V> {
+    fn from_iter<I: IntoIterator<Item = (u32, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{FxIntMap, FxTable};
+
+    #[test]
+    fn matches_hash_map() {
+        let mut map = FxIntMap::new();
+        let mut reference = FxTable::default();
+        let mut state = 1_u64;
+        for i in 0..50_000_u64 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+            // Mostly dense keys, with an occasional far away one.
+            let key = if ((state >> 16) as u8) < 16 {
+                (state >> 32) as u32
+            } else {
+                (state >> 40) as u32 % 4096
+            };
+            if state >> 62 != 0 {
+                assert_eq!(map.insert(key, i), reference.insert(key, i));
+            } else {
+                assert_eq!(map.remove(key), reference.remove(&key));
+            }
+            assert_eq!(map.len(), reference.len());
+        }
+        assert!(reference.iter().all(|(&k, v)| map.get(k) == Some(v)));
+        assert_eq!(map.iter().count(), reference.len());
+        assert!(map.dense_capacity() >= 4096);
+    }
+
+    #[test]
+    fn overflow_moves_into_dense_region() {
+        let mut map: FxIntMap<u32> = [(100, 1), (5_000_000, 2)].into_iter().collect();
+        assert_eq!(map.dense_capacity(), 0);
+
+        map.extend((0..100).map(|k| (k, k)));
+        assert!(map.dense_capacity() > 100);
+        assert_eq!(map.get(100), Some(&1));
+        assert_eq!(map.len(), 102);
+
+        *map.get_or_insert_with(7, || 0) += 1;
+        assert_eq!(map.get(7), Some(&8));
+
+        let keys: Vec<u32> = map.keys().collect();
+        assert_eq!(keys.last(), Some(&5_000_000));
+        assert!(keys[..101].iter().copied().eq(0..101));
+
+        map.clear();
+        assert!(map.is_empty());
+        assert!(!map.contains_key(5_000_000));
+    }
+}
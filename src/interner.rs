@@ -0,0 +1,487 @@
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cell::{Cell, OnceCell, RefCell};
+use core::fmt;
+use core::hash::{BuildHasher, Hash, Hasher};
+use core::ops::Deref;
+
+use crate::slab_map::{locate, FIRST_CHUNK_LEN};
+use crate::{FxBuildHasher, FxTable};
+
+/// A handle to a string interned in an [`FxInterner`].
+///
+/// Symbols are 4 bytes large and compare and hash as integers. They are
+/// numbered from 0 in the order their strings were interned, and only mean
+/// something to the interner that created them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// Returns the number of the symbol.
+    pub fn as_u32(self) -> u32 {
+        self.0
+    }
+}
+
+/// A string interner using the Fx hashing algorithm.
+///
+/// Interning a string returns a [`Symbol`], which is the same for equal
+/// strings, so strings can be compared and hashed by their symbols and
+/// stored only once. Interned strings are never freed.
+///
+/// Every string is stored once, in the list mapping symbols to strings, and
+/// looked up through an index of the hashes of the strings, like the values of
+/// an [`FxTypedInterner`]. `'static` strings are interned without copying by
+/// [`intern_static`](Self::intern_static) and
+/// [`from_static`](Self::from_static), e.g. for keywords known in advance.
+///
+/// ```
+/// use rustc_hash::FxInterner;
+///
+/// let mut interner = FxInterner::from_static(&["fn", "let"]);
+/// let name = interner.intern("main");
+/// assert_eq!(interner.intern("main"), name);
+/// assert_eq!(interner.resolve(name), "main");
+/// assert_eq!(interner.get("let").map(|s| s.as_u32()), Some(1));
+/// ```
+#[derive(Clone)]
+pub struct FxInterner {
+    /// Maps the hash of a string to the symbol of the last string interned
+    /// with that hash, which links to the earlier ones.
+    heads: FxTable<u64, usize>,
+    strings: Vec<Entry<Cow<'static, str>>>,
+}
+
+impl FxInterner {
+    /// Creates an empty `FxInterner`.
+    pub fn new() -> FxInterner {
+        Self::with_capacity(0)
+    }
+
+    /// Creates an empty `FxInterner` with room for at least `capacity`
+    /// strings.
+    pub fn with_capacity(capacity: usize) -> FxInterner {
+        FxInterner {
+            heads: FxTable::with_capacity_and_hasher(capacity, Default::default()),
+            strings: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Creates an interner with the given strings interned in order, so that
+    /// the symbol of the first distinct string is 0, and so on.
+    ///
+    /// The strings are not copied.
+    pub fn from_static(strings: &[&'static str]) -> FxInterner {
+        let mut interner = Self::with_capacity(strings.len());
+        for s in strings {
+            interner.intern_static(s);
+        }
+        interner
+    }
+
+    /// Returns the number of interned strings.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Returns `true` if no strings are interned.
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Reserves room for at least `additional` more strings.
+    pub fn reserve(&mut self, additional: usize) {
+        self.heads.reserve(additional);
+        self.strings.reserve(additional);
+    }
+
+    /// Returns the symbol of `s`, interning a copy of it if needed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `u32::MAX` strings are interned already.
+    pub fn intern(&mut self, s: &str) -> Symbol {
+        let hash = FxBuildHasher.hash_one(s);
+        match self.find(hash, s) {
+            Some(symbol) => symbol,
+            None => self.insert(hash, Cow::Owned(s.into())),
+        }
+    }
+
+    /// Returns the symbol of `s`, interning it without copying if needed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `u32::MAX` strings are interned already.
+    pub fn intern_static(&mut self, s: &'static str) -> Symbol {
+        let hash = FxBuildHasher.hash_one(s);
+        match self.find(hash, s) {
+            Some(symbol) => symbol,
+            None => self.insert(hash, Cow::Borrowed(s)),
+        }
+    }
+
+    /// Returns the symbol of `s` if it is interned.
+    pub fn get(&self, s: &str) -> Option<Symbol> {
+        self.find(FxBuildHasher.hash_one(s), s)
+    }
+
+    /// Returns the string of `symbol`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `symbol` was not created by this interner.
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        self.try_resolve(symbol)
+            .expect("the symbol is from another interner")
+    }
+
+    /// Returns the string of `symbol`, or `None` if it was not created by
+    /// this interner.
+    pub fn try_resolve(&self, symbol: Symbol) -> Option<&str> {
+        self.strings.get(symbol.0 as usize).map(|s| &*s.value)
+    }
+
+    /// Returns an iterator over the symbols and their strings, in the order
+    /// they were interned.
+    pub fn iter(&self) -> impl Iterator<Item = (Symbol, &str)> {
+        (self.strings.iter().enumerate()).map(|(i, s)| (Symbol(i as u32), &*s.value))
+    }
+
+    fn find(&self, hash: u64, s: &str) -> Option<Symbol> {
+        let mut index = self.heads.get(&hash).copied()?;
+        loop {
+            let entry = &self.strings[index];
+            if entry.value == s {
+                return Some(Symbol(index as u32));
+            }
+            // Strings with equal Fx hashes are linked, newest first.
+            index = entry.next.get();
+            if index == usize::MAX {
+                return None;
+            }
+        }
+    }
+
+    fn insert(&mut self, hash: u64, s: Cow<'static, str>) -> Symbol {
+        let index = self.strings.len();
+        let symbol = u32::try_from(index)
+            .ok()
+            .filter(|&n| n < u32::MAX)
+            .map(Symbol)
+            .expect("too many interned strings");
+        let head = self.heads.insert(hash, index);
+        self.strings.push(Entry {
+            value: s,
+            next: Cell::new(head.unwrap_or(usize::MAX)),
+        });
+        symbol
+    }
+}
+
+impl Default for FxInterner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for FxInterner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.strings.iter().map(|s| &s.value))
+            .finish()
+    }
+}
+
+impl<'a> Extend<&'a str> for FxInterner {
+    /// Interns every string.
+    fn extend<I: IntoIterator<Item = &'a str>>(&mut self, iter: I) {
+        for s in iter {
+            self.intern(s);
+        }
+    }
+}
+
+/// A handle to a value interned in an [`FxTypedInterner`].
+///
+/// Handles are references to their value that compare and hash by its
+/// address, without looking at the value itself, as equal values are interned
+/// only once.
+pub struct Interned<'a, T>(&'a T);
+
+impl<'a, T> Interned<'a, T> {
+    /// Returns the interned value.
+    pub fn get(self) -> &'a T {
+        self.0
+    }
+}
+
+impl<T> Clone for Interned<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Interned<'_, T> {}
+
+impl<T> Deref for Interned<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.0
+    }
+}
+
+impl<T> PartialEq for Interned<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        core::ptr::eq(self.0, other.0)
+    }
+}
+
+impl<T> Eq for Interned<'_, T> {}
+
+impl<T> Hash for Interned<'_, T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        core::ptr::hash(self.0, state);
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Interned<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// An interned value and the index of the next value with the same hash.
+#[derive(Clone)]
+struct Entry<T> {
+    value: T,
+    next: Cell<usize>,
+}
+
+/// A chunk of the arena of an [`FxTypedInterner`], allocated on first use.
+type Chunk<T> = OnceCell<Box<[OnceCell<Entry<T>>]>>;
+
+/// An interner for values of any type, using the Fx hashing algorithm.
+///
+/// Interning a value returns an [`Interned`] handle, which is the same for
+/// equal values, so that comparing handles is a pointer comparison. Values are
+/// interned through a shared reference and stored in an append-only arena,
+/// whose chunks are allocated with their final size like those of
+/// [`FxSlabMap`](crate::FxSlabMap), so they never move or are freed while the
+/// interner is borrowed, and handles are plain references to them.
+///
+/// ```
+/// use rustc_hash::FxTypedInterner;
+///
+/// #[derive(Debug, PartialEq, Eq, Hash)]
+/// enum Ty {
+///     Int,
+///     Tuple(Vec<u8>),
+/// }
+///
+/// let types = FxTypedInterner::new();
+/// let a = types.intern(Ty::Tuple(vec![1, 2]));
+/// let b = types.intern(Ty::Tuple(vec![1, 2]));
+/// assert_eq!(a, b);
+/// assert_ne!(a, types.intern(Ty::Int));
+/// assert_eq!(*b, Ty::Tuple(vec![1, 2]));
+/// assert_eq!(types.len(), 2);
+/// ```
+pub struct FxTypedInterner<T> {
+    /// Maps the hash of a value to the index of the last value interned with
+    /// that hash, which links to the earlier ones.
+    heads: RefCell<FxTable<u64, usize>>,
+    /// The chunks of the arena, chunk `i` holding `FIRST_CHUNK_LEN << i`
+    /// values.
+    chunks: [Chunk<T>; usize::BITS as usize],
+    len: Cell<usize>,
+}
+
+impl<T> FxTypedInterner<T> {
+    /// Creates an empty `FxTypedInterner`.
+    pub fn new() -> FxTypedInterner<T> {
+        Self::with_capacity(0)
+    }
+
+    /// Creates an empty `FxTypedInterner` with room for at least `capacity`
+    /// values in its index.
+    pub fn with_capacity(capacity: usize) -> FxTypedInterner<T> {
+        FxTypedInterner {
+            heads: RefCell::new(FxTable::with_capacity_and_hasher(
+                capacity,
+                Default::default(),
+            )),
+            chunks: core::array::from_fn(|_| OnceCell::new()),
+            len: Cell::new(0),
+        }
+    }
+
+    /// Returns the number of interned values.
+    pub fn len(&self) -> usize {
+        self.len.get()
+    }
+
+    /// Returns `true` if no values are interned.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over the handles of the interned values, in the
+    /// order they were interned.
+    pub fn iter(&self) -> impl Iterator<Item = Interned<'_, T>> {
+        (0..self.len()).map(|i| Interned(&self.entry(i).value))
+    }
+
+    fn entry(&self, index: usize) -> &Entry<T> {
+        let (chunk, offset) = locate(index);
+        self.chunks[chunk]
+            .get()
+            .and_then(|chunk| chunk[offset].get())
+            .expect("the index points to an interned value")
+    }
+
+    /// Stores a value at the end of the arena, returning its index.
+    fn push(&self, entry: Entry<T>) -> usize {
+        let index = self.len.get();
+        let (chunk, offset) = locate(index);
+        let chunk = self.chunks[chunk].get_or_init(|| {
+            (0..FIRST_CHUNK_LEN << chunk)
+                .map(|_| OnceCell::new())
+                .collect()
+        });
+        if chunk[offset].set(entry).is_err() {
+            unreachable!("the slot after the last value is empty");
+        }
+        self.len.set(index + 1);
+        index
+    }
+}
+
+impl<T: Hash + Eq> FxTypedInterner<T> {
+    /// Reserves room for at least `additional` more values in the index.
+    pub fn reserve(&mut self, additional: usize) {
+        self.heads.get_mut().reserve(additional);
+    }
+
+    /// Returns the handle of `value`, interning it if needed.
+    pub fn intern(&self, value: T) -> Interned<'_, T> {
+        let hash = FxBuildHasher.hash_one(&value);
+        if let Some(interned) = self.find(hash, &value) {
+            return interned;
+        }
+        let head = self.heads.borrow().get(&hash).copied();
+        let index = self.push(Entry {
+            value,
+            next: Cell::new(head.unwrap_or(usize::MAX)),
+        });
+        self.heads.borrow_mut().insert(hash, index);
+        Interned(&self.entry(index).value)
+    }
+
+    /// Returns the handle of `value` if it is interned.
+    pub fn get(&self, value: &T) -> Option<Interned<'_, T>> {
+        self.find(FxBuildHasher.hash_one(value), value)
+    }
+
+    fn find(&self, hash: u64, value: &T) -> Option<Interned<'_, T>> {
+        let mut index = self.heads.borrow().get(&hash).copied()?;
+        loop {
+            let entry = self.entry(index);
+            if entry.value == *value {
+                return Some(Interned(&entry.value));
+            }
+            // Values with equal Fx hashes are linked, newest first.
+            index = entry.next.get();
+            if index == usize::MAX {
+                return None;
+            }
+        }
+    }
+}
+
+impl<T> Default for FxTypedInterner<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for FxTypedInterner<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Hash + Eq> Extend<T> for FxTypedInterner<T> {
+    /// Interns every value.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.intern(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+    use alloc::vec::Vec;
+
+    use crate::{FxInterner, FxTypedInterner};
+
+    #[test]
+    fn interns_strings() {
+        let mut interner = FxInterner::from_static(&["a", "b", "a"]);
+        assert_eq!(interner.len(), 2);
+        let names: Vec<_> = (0..100).map(|i| format!("name{}", i % 50)).collect();
+        let symbols: Vec<_> = names.iter().map(|n| interner.intern(n)).collect();
+        assert_eq!(interner.len(), 52);
+        for (name, &symbol) in names.iter().zip(&symbols) {
+            assert_eq!(interner.resolve(symbol), name);
+        }
+        assert_eq!(symbols[0], symbols[50]);
+        assert_eq!(symbols[0].as_u32(), 2);
+
+        assert_eq!(interner.intern_static("b").as_u32(), 1);
+        assert_eq!(interner.get("c"), None);
+        let other = FxInterner::new().intern("x");
+        assert_eq!(FxInterner::new().try_resolve(other), None);
+        let strings: Vec<_> = interner.iter().map(|(_, s)| s).take(3).collect();
+        assert_eq!(strings, ["a", "b", "name0"]);
+    }
+
+    #[test]
+    fn interns_values() {
+        let interner = FxTypedInterner::new();
+        let a = interner.intern((1, "x"));
+        let b = interner.intern((2, "y"));
+        assert_eq!(interner.intern((1, "x")), a);
+        assert_ne!(a, b);
+        assert_eq!(*a, (1, "x"));
+        assert_eq!(interner.get(&(2, "y")), Some(b));
+        assert_eq!(interner.get(&(3, "z")), None);
+
+        // Handles stay valid while the arena grows.
+        let handles: Vec<_> = (0..1000).map(|i| interner.intern((i % 100, "x"))).collect();
+        assert_eq!(interner.len(), 101);
+        assert!(handles.iter().all(|h| interner.get(h.get()) == Some(*h)));
+        let values: Vec<_> = interner.iter().map(|v| v.get().0).collect();
+        assert_eq!(values[..3], [1, 2, 0]);
+
+        let mut interner = interner;
+        interner.extend([(7, "y"), (7, "y")]);
+        assert_eq!(interner.len(), 102);
+    }
+
+    #[test]
+    fn colliding_hashes() {
+        // The Fx hashes of these values are equal.
+        let interner = FxTypedInterner::new();
+        let a = interner.intern((0_usize, crate::K));
+        let b = interner.intern((1, 0));
+        assert_ne!(a, b);
+        assert_eq!(interner.get(&(0, crate::K)), Some(a));
+        assert_eq!(interner.get(&(1, 0)), Some(b));
+        assert_eq!(interner.get(&(2, 0)), None);
+    }
+}
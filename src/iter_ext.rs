@@ -0,0 +1,82 @@
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+use crate::{FxCounter, FxTable};
+
+/// The most groups reserved up front by
+/// [`collect_grouped`](FxIteratorExt::collect_grouped), since the number of
+/// distinct keys may be much smaller than the number of items.
+const MAX_RESERVED_GROUPS: usize = 1024;
+
+/// Collecting iterators into Fx-hashed maps.
+///
+/// ```
+/// use rustc_hash::{FxHashMap, FxIteratorExt};
+///
+/// let words = ["apple", "avocado", "banana", "blueberry", "cherry"];
+/// let by_letter: FxHashMap<char, Vec<&str>> = words
+///     .iter()
+///     .map(|w| (w.chars().next().unwrap(), *w))
+///     .collect_grouped();
+/// assert_eq!(by_letter[&'b'], ["banana", "blueberry"]);
+///
+/// let lengths = words.iter().map(|w| w.len()).collect_counts();
+/// assert_eq!(lengths.get(&6), 2);
+/// ```
+pub trait FxIteratorExt: Iterator + Sized {
+    /// Groups the values of key-value pairs by key, keeping the values of
+    /// every key in iteration order.
+    ///
+    /// The groups are built in an Fx-hashed map and then moved into a map of
+    /// the caller's choice, e.g. an `FxHashMap` or an `FxHashMapAlloc`, which
+    /// hashes every distinct key once more.
+    fn collect_grouped<K, V, M>(self) -> M
+    where
+        Self: Iterator<Item = (K, V)>,
+        K: Eq + Hash,
+        M: Default + Extend<(K, Vec<V>)>,
+    {
+        let groups = group(self);
+        let mut map = M::default();
+        map.extend(groups);
+        map
+    }
+
+    /// Counts the occurrences of every item.
+    fn collect_counts(self) -> FxCounter<Self::Item>
+    where
+        Self::Item: Eq + Hash,
+    {
+        self.collect()
+    }
+}
+
+impl<I: Iterator> FxIteratorExt for I {}
+
+fn group<K: Eq + Hash, V>(iter: impl Iterator<Item = (K, V)>) -> FxTable<K, Vec<V>> {
+    let groups = iter.size_hint().0.min(MAX_RESERVED_GROUPS);
+    let mut map = FxTable::with_capacity_and_hasher(groups, Default::default());
+    for (k, v) in iter {
+        map.entry(k).or_insert_with(Vec::new).push(v);
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{FxIteratorExt, FxTable};
+
+    #[test]
+    fn groups_and_counts() {
+        let grouped: FxTable<u32, Vec<u32>> = (0..100_u32).map(|i| (i % 3, i)).collect_grouped();
+        assert_eq!(grouped.len(), 3);
+        assert_eq!(grouped[&1].len(), 33);
+        assert!(grouped[&2].windows(2).all(|w| w[0] < w[1]));
+
+        let counts = (0..100_u32).map(|i| i % 3).collect_counts();
+        assert_eq!(counts.get(&0), 34);
+        assert_eq!(counts.total(), 100);
+    }
+}
@@ -0,0 +1,266 @@
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::Hash;
+
+use crate::FxTable;
+
+/// A hash map using the Fx hashing algorithm that can roll back changes to an
+/// earlier snapshot.
+///
+/// While a snapshot is open, every change logs how to undo it. Snapshots nest
+/// and must be closed in reverse order of being taken, either by
+/// [`rollback_to`](Self::rollback_to), which undoes all changes since the
+/// snapshot, or by [`commit`](Self::commit), which keeps them. Once the
+/// outermost snapshot is closed the log is dropped, so a map without open
+/// snapshots logs nothing.
+///
+/// This is modeled after `SnapshotMap` in rustc. Values are cloned when they
+/// are overwritten or removed, and keys when they are inserted.
+///
+/// ```
+/// use rustc_hash::FxJournaledMap;
+///
+/// let mut map = FxJournaledMap::new();
+/// map.insert("x", 1);
+///
+/// let mark = map.snapshot();
+/// map.insert("x", 2);
+/// map.insert("y", 3);
+/// map.rollback_to(mark);
+///
+/// assert_eq!(map.get("x"), Some(&1));
+/// assert_eq!(map.get("y"), None);
+/// ```
+#[derive(Clone)]
+pub struct FxJournaledMap<K, V> {
+    map: FxTable<K, V>,
+    undo_log: Vec<Undo<K, V>>,
+    open_snapshots: usize,
+}
+
+/// A snapshot of an [`FxJournaledMap`], to be passed to its
+/// [`rollback_to`](FxJournaledMap::rollback_to) or
+/// [`commit`](FxJournaledMap::commit).
+#[must_use = "a snapshot must be rolled back or committed"]
+#[derive(Debug)]
+pub struct Mark {
+    undo_len: usize,
+    /// The number of snapshots that were open before this one.
+    depth: usize,
+}
+
+/// How to undo a change.
+#[derive(Clone)]
+enum Undo<K, V> {
+    /// The key was inserted and is to be removed.
+    Inserted(K),
+    /// The key had this value before it was overwritten or removed.
+    Overwritten(K, V),
+}
+
+impl<K, V> FxJournaledMap<K, V> {
+    /// Creates an empty `FxJournaledMap`.
+    pub fn new() -> FxJournaledMap<K, V> {
+        FxJournaledMap {
+            map: FxTable::default(),
+            undo_log: Vec::new(),
+            open_snapshots: 0,
+        }
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns `true` if a snapshot is open.
+    pub fn in_snapshot(&self) -> bool {
+        self.open_snapshots > 0
+    }
+
+    /// Returns an iterator over the keys and values, in unspecified order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.map.iter()
+    }
+
+    /// Opens a snapshot, starting to log changes if none was open.
+    pub fn snapshot(&mut self) -> Mark {
+        let mark = Mark {
+            undo_len: self.undo_log.len(),
+            depth: self.open_snapshots,
+        };
+        self.open_snapshots += 1;
+        mark
+    }
+
+    /// Closes the snapshot `mark`, keeping the changes made since it was taken.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a snapshot taken after `mark` is still open.
+    pub fn commit(&mut self, mark: Mark) {
+        self.close(&mark);
+        if self.open_snapshots == 0 {
+            self.undo_log.clear();
+        }
+    }
+
+    fn close(&mut self, mark: &Mark) {
+        // Marks are not `Clone` and are consumed when closed, so the only
+        // open mark with the innermost depth is the innermost snapshot.
+        assert!(
+            mark.depth + 1 == self.open_snapshots && mark.undo_len <= self.undo_log.len(),
+            "snapshots must be closed in reverse order"
+        );
+        self.open_snapshots -= 1;
+    }
+}
+
+impl<K: Eq + Hash, V> FxJournaledMap<K, V> {
+    /// Closes the snapshot `mark`, undoing the changes made since it was taken.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a snapshot taken after `mark` is still open.
+    pub fn rollback_to(&mut self, mark: Mark) {
+        self.close(&mark);
+        for undo in self.undo_log.drain(mark.undo_len..).rev() {
+            match undo {
+                Undo::Inserted(k) => {
+                    self.map.remove(&k);
+                }
+                Undo::Overwritten(k, v) => {
+                    self.map.insert(k, v);
+                }
+            }
+        }
+        if self.open_snapshots == 0 {
+            self.undo_log.clear();
+        }
+    }
+
+    /// Returns a reference to the value of `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.map.get(key)
+    }
+
+    /// Returns `true` if the map contains `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.map.contains_key(key)
+    }
+
+    /// Removes all entries, logging them if a snapshot is open.
+    pub fn clear(&mut self) {
+        if self.in_snapshot() {
+            let removed = self.map.drain().map(|(k, v)| Undo::Overwritten(k, v));
+            self.undo_log.extend(removed);
+        } else {
+            self.map.clear();
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash, V: Clone> FxJournaledMap<K, V> {
+    /// Inserts a key-value pair, returning the previous value of the key.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if !self.in_snapshot() {
+            return self.map.insert(key, value);
+        }
+        let old = self.map.insert(key.clone(), value);
+        self.undo_log.push(match &old {
+            Some(old) => Undo::Overwritten(key, old.clone()),
+            None => Undo::Inserted(key),
+        });
+        old
+    }
+
+    /// Removes `key`, returning its value.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let (k, v) = self.map.remove_entry(key)?;
+        if self.in_snapshot() {
+            self.undo_log.push(Undo::Overwritten(k, v.clone()));
+        }
+        Some(v)
+    }
+}
+
+impl<K, V> Default for FxJournaledMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for FxJournaledMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.map.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FxJournaledMap;
+
+    #[test]
+    fn nested_snapshots() {
+        let mut map = FxJournaledMap::new();
+        map.insert(1, "a");
+        map.insert(2, "b");
+
+        let outer = map.snapshot();
+        map.insert(1, "c");
+        map.remove(&2);
+
+        let inner = map.snapshot();
+        map.insert(3, "d");
+        map.clear();
+        map.insert(4, "e");
+        map.rollback_to(inner);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(&1), Some(&"c"));
+
+        let inner = map.snapshot();
+        map.insert(5, "f");
+        map.commit(inner);
+        assert!(map.in_snapshot());
+
+        map.rollback_to(outer);
+        assert!(!map.in_snapshot());
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&1), Some(&"a"));
+        assert_eq!(map.get(&2), Some(&"b"));
+
+        let mark = map.snapshot();
+        map.insert(6, "g");
+        map.commit(mark);
+        assert_eq!(map.get(&6), Some(&"g"));
+        assert!(map.undo_log.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn close_out_of_order() {
+        let mut map = FxJournaledMap::new();
+        let outer = map.snapshot();
+        let _inner = map.snapshot();
+        map.insert(1, "a");
+        map.commit(outer);
+    }
+}
@@ -0,0 +1,273 @@
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cell::Cell;
+use core::fmt;
+use core::hash::Hash;
+
+use crate::FxTable;
+
+/// A hash map that remembers the entry of its last lookup, using the Fx
+/// hashing algorithm.
+///
+/// Entries are stored in a vector and a hash table maps every key to its
+/// position. A lookup first compares the key with the entry found by the
+/// previous lookup and only hashes the key and probes the table if they
+/// differ, which pays off when the same key is queried many times in a row.
+/// Removing an entry forgets the remembered position, so a stale position is
+/// never used.
+///
+/// The remembered position is kept in a `Cell`, so the map is not `Sync`.
+///
+/// ```
+/// use rustc_hash::FxLastLookupMap;
+///
+/// let mut map = FxLastLookupMap::new();
+/// map.insert("x", 1);
+/// map.insert("y", 2);
+///
+/// // The second and third lookups of "y" do not hash the key.
+/// assert_eq!(map.get("y"), Some(&2));
+/// assert_eq!(map.get("y"), Some(&2));
+/// assert_eq!(map.get("y"), Some(&2));
+/// assert_eq!(map.remove("y"), Some(2));
+/// assert_eq!(map.get("y"), None);
+/// ```
+#[derive(Clone)]
+pub struct FxLastLookupMap<K, V> {
+    entries: Vec<(K, V)>,
+    index: FxTable<K, usize>,
+    last: Cell<Option<usize>>,
+}
+
+impl<K, V> FxLastLookupMap<K, V> {
+    /// Creates an empty `FxLastLookupMap`.
+    pub fn new() -> FxLastLookupMap<K, V> {
+        FxLastLookupMap {
+            entries: Vec::new(),
+            index: FxTable::default(),
+            last: Cell::new(None),
+        }
+    }
+
+    /// Creates an empty `FxLastLookupMap` with room for at least `capacity` entries.
+    pub fn with_capacity(capacity: usize) -> FxLastLookupMap<K, V> {
+        FxLastLookupMap {
+            entries: Vec::with_capacity(capacity),
+            index: FxTable::with_capacity_and_hasher(capacity, Default::default()),
+            last: Cell::new(None),
+        }
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes all entries.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.index.clear();
+        self.last.set(None);
+    }
+
+    /// Returns an iterator over the keys and values, in unspecified order.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (&K, &V)> {
+        self.entries.iter().map(|(k, v)| (k, v))
+    }
+
+    /// Returns an iterator over the keys and mutable values, in unspecified order.
+    pub fn iter_mut(&mut self) -> impl ExactSizeIterator<Item = (&K, &mut V)> {
+        self.entries.iter_mut().map(|(k, v)| (&*k, v))
+    }
+
+    /// Returns an iterator over the keys, in unspecified order.
+    pub fn keys(&self) -> impl ExactSizeIterator<Item = &K> {
+        self.entries.iter().map(|(k, _)| k)
+    }
+
+    /// Returns an iterator over the values, in unspecified order.
+    pub fn values(&self) -> impl ExactSizeIterator<Item = &V> {
+        self.entries.iter().map(|(_, v)| v)
+    }
+}
+
+impl<K: Eq + Hash, V> FxLastLookupMap<K, V> {
+    /// Returns the position of `key`, trying the remembered position first.
+    fn find<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        if let Some(i) = self.last.get() {
+            if self.entries[i].0.borrow() == key {
+                return Some(i);
+            }
+        }
+        let i = *self.index.get(key)?;
+        self.last.set(Some(i));
+        Some(i)
+    }
+
+    /// Returns a reference to the value of `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.find(key).map(|i| &self.entries[i].1)
+    }
+
+    /// Returns the stored key and the value of `key`.
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.find(key).map(|i| {
+            let (k, v) = &self.entries[i];
+            (k, v)
+        })
+    }
+
+    /// Returns a mutable reference to the value of `key`.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let i = self.find(key)?;
+        Some(&mut self.entries[i].1)
+    }
+
+    /// Returns `true` if the map contains `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    /// Removes `key`, returning its value.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let i = self.index.remove(key)?;
+        self.last.set(None);
+        let (_, value) = self.entries.swap_remove(i);
+        if let Some((moved, _)) = self.entries.get(i) {
+            *self.index.get_mut::<K>(moved).unwrap() = i;
+        }
+        Some(value)
+    }
+}
+
+impl<K: Clone + Eq + Hash, V> FxLastLookupMap<K, V> {
+    /// Inserts a key-value pair, returning the previous value of the key.
+    ///
+    /// The key is stored twice, in the entry and in the hash table, so it is
+    /// cloned if it is not present yet.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(v) = self.get_mut(&key) {
+            return Some(core::mem::replace(v, value));
+        }
+        let i = self.entries.len();
+        self.index.insert(key.clone(), i);
+        self.entries.push((key, value));
+        self.last.set(Some(i));
+        None
+    }
+}
+
+impl<K, V> Default for FxLastLookupMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for FxLastLookupMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Clone + Eq + Hash, V> Extend<(K, V)> for FxLastLookupMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash, V> FromIterator<(K, V)> for FxLastLookupMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+    use core::hash::{Hash, Hasher};
+
+    use crate::{FxLastLookupMap, FxTable};
+
+    /// A key that counts how often it is hashed.
+    #[derive(Clone, PartialEq, Eq)]
+    struct Counted<'a>(u32, &'a Cell<u32>);
+
+    impl Hash for Counted<'_> {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.1.set(self.1.get() + 1);
+            self.0.hash(state);
+        }
+    }
+
+    #[test]
+    fn repeated_lookups_skip_hashing() {
+        let hashed = Cell::new(0);
+        let mut map: FxLastLookupMap<_, _> = (0..10).map(|i| (Counted(i, &hashed), i)).collect();
+        let key = Counted(3, &hashed);
+        hashed.set(0);
+        for _ in 0..100 {
+            assert_eq!(map.get(&key), Some(&3));
+        }
+        assert_eq!(hashed.get(), 1);
+
+        // Removing another key moves the last entry, which must not leave a
+        // stale position behind.
+        assert_eq!(map.remove(&Counted(0, &hashed)), Some(0));
+        assert_eq!(map.get(&Counted(9, &hashed)), Some(&9));
+        assert_eq!(map.get(&key), Some(&3));
+        assert_eq!(map.remove(&key), Some(3));
+        assert_eq!(map.get(&key), None);
+        assert_eq!(map.len(), 8);
+    }
+
+    #[test]
+    fn matches_hash_map() {
+        let mut map = FxLastLookupMap::new();
+        let mut reference = FxTable::default();
+        let mut state = 1_u64;
+        for i in 0..20_000_u64 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+            let key = (state >> 40) as u32 % 500;
+            match state >> 62 {
+                0 => assert_eq!(map.remove(&key), reference.remove(&key)),
+                1 => assert_eq!(map.insert(key, i), reference.insert(key, i)),
+                _ => assert_eq!(map.get(&key), reference.get(&key)),
+            }
+        }
+        assert_eq!(map.len(), reference.len());
+        assert!(reference.iter().all(|(k, v)| map.get(k) == Some(v)));
+    }
+}
@@ -20,12 +20,246 @@
 #[cfg(feature = "std")]
 extern crate std;
 
-#[cfg(feature = "rand")]
-extern crate rand;
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+extern crate alloc;
 
 #[cfg(feature = "rand")]
 mod random_state;
 
+#[cfg(all(feature = "shuffle-debug", debug_assertions))]
+mod shuffle;
+
+#[cfg(feature = "const-random")]
+mod compile_time_state;
+
+#[cfg(target_has_atomic = "ptr")]
+mod counter_state;
+
+#[cfg(feature = "critical-section")]
+mod global_seed;
+
+#[cfg(feature = "indexmap")]
+mod index_map;
+
+#[cfg(feature = "dashmap")]
+mod dash_map;
+
+#[cfg(feature = "lru")]
+mod lru_cache;
+
+#[cfg(feature = "rayon")]
+mod parallel;
+
+#[cfg(any(feature = "im", feature = "im-rc"))]
+mod persistent;
+
+#[cfg(feature = "flurry")]
+mod flurry_map;
+
+#[cfg(feature = "scc")]
+mod scc_map;
+
+#[cfg(feature = "papaya")]
+mod papaya_map;
+
+#[cfg(feature = "weak-table")]
+mod weak_map;
+
+#[cfg(feature = "priority-queue")]
+mod priority;
+
+#[cfg(feature = "moka")]
+mod moka_cache;
+
+#[cfg(feature = "quick_cache")]
+mod quickcache;
+
+#[cfg(feature = "elsa")]
+mod frozen;
+
+#[cfg(feature = "heapless")]
+mod heapless_map;
+
+#[cfg(feature = "petgraph")]
+mod graph_map;
+
+#[cfg(feature = "arc-swap")]
+mod snapshot_map;
+
+#[cfg(feature = "evmap")]
+mod ev_map;
+
+#[cfg(feature = "hashbrown")]
+mod prehashed_map;
+
+#[cfg(feature = "hashbrown")]
+mod entry_ref;
+
+#[cfg(feature = "hashlink")]
+mod linked_map;
+
+#[cfg(any(
+    feature = "serde",
+    feature = "speedy",
+    feature = "musli",
+    feature = "bincode"
+))]
+mod presized;
+
+#[cfg(feature = "serde")]
+mod serde_impls;
+
+#[cfg(feature = "speedy")]
+mod speedy_impls;
+
+#[cfg(feature = "musli")]
+mod musli_impls;
+
+#[cfg(feature = "bincode")]
+mod bincode_impls;
+
+#[cfg(feature = "rkyv")]
+mod rkyv_impls;
+
+#[cfg(feature = "borsh")]
+mod borsh_impls;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod multi_map;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod bi_map;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod counter;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod small_map;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod frozen_map;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod frozen_bytes;
+
+#[cfg(feature = "std")]
+mod sharded_map;
+
+#[cfg(all(feature = "std", target_has_atomic = "64"))]
+mod concurrent_counter;
+
+#[cfg(all(feature = "std", target_has_atomic = "64"))]
+mod concurrent_set;
+
+#[cfg(feature = "hashbrown")]
+mod incremental_map;
+
+#[cfg(feature = "hashbrown")]
+mod instrumented_map;
+
+#[cfg(feature = "hashbrown")]
+mod load_factor_map;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod raw_map;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod int_map;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod last_lookup_map;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod journaled_map;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod scope_map;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod union_find;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod slab_map;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod capacity;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod from_array;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod convert;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod iter_ext;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod sorted;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod set_ops;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod partition;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod diff;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod macros;
+
+// Only `phf` uses the module without `std` or `hashbrown`.
+#[cfg_attr(not(any(feature = "std", feature = "hashbrown")), allow(dead_code))]
+mod sketch;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod bloom;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod counting_bloom;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod cuckoo;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod fuse;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod hyperloglog;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod count_min;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod minhash;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod simhash;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod heavy_hitters;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod unordered;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod interner;
+
+mod array_map;
+
+mod cache;
+
+mod phf;
+
+mod consistent;
+
+mod combine;
+
+mod persist;
+
+#[cfg(feature = "allocator-api2")]
+mod alloc_map;
+
 mod seeded_state;
 
 use core::default::Default;
@@ -33,18 +267,311 @@ use core::hash::{BuildHasher, Hasher};
 #[cfg(feature = "std")]
 use std::collections::{HashMap, HashSet};
 
+/// The state of the map and set aliases below: `FxBuildHasher`, or a state
+/// seeded per map in debug builds with the `shuffle-debug` feature.
+#[cfg(all(
+    any(feature = "std", feature = "hashbrown"),
+    not(all(feature = "shuffle-debug", debug_assertions))
+))]
+type FxMapState = FxBuildHasher;
+#[cfg(all(
+    any(feature = "std", feature = "hashbrown"),
+    feature = "shuffle-debug",
+    debug_assertions
+))]
+type FxMapState = shuffle::FxShuffledState;
+
+#[cfg(all(feature = "shuffle-debug", debug_assertions))]
+pub use shuffle::FxShuffledState;
+
 /// Type alias for a hash map that uses the Fx hashing algorithm.
 #[cfg(feature = "std")]
-pub type FxHashMap<K, V> = HashMap<K, V, FxBuildHasher>;
+pub type FxHashMap<K, V> = HashMap<K, V, FxMapState>;
 
 /// Type alias for a hash set that uses the Fx hashing algorithm.
 #[cfg(feature = "std")]
-pub type FxHashSet<V> = HashSet<V, FxBuildHasher>;
+pub type FxHashSet<V> = HashSet<V, FxMapState>;
+
+/// Type alias for a [`hashbrown`] hash map that uses the Fx hashing algorithm.
+///
+/// Unlike `FxHashMap` this only requires `alloc`, so it is available in `no_std` builds.
+#[cfg(feature = "hashbrown")]
+pub type FxHashMapAlloc<K, V> = hashbrown::HashMap<K, V, FxMapState>;
+
+/// Type alias for a [`hashbrown`] hash set that uses the Fx hashing algorithm.
+///
+/// Unlike `FxHashSet` this only requires `alloc`, so it is available in `no_std` builds.
+#[cfg(feature = "hashbrown")]
+pub type FxHashSetAlloc<V> = hashbrown::HashSet<V, FxMapState>;
+
+/// The hash map backing the collections of this crate, which are available
+/// with either `std` or `hashbrown`.
+#[cfg(feature = "std")]
+type FxTable<K, V> = FxHashMap<K, V>;
+#[cfg(all(not(feature = "std"), feature = "hashbrown"))]
+type FxTable<K, V> = FxHashMapAlloc<K, V>;
 
 #[cfg(feature = "rand")]
 pub use random_state::{FxHashMapRand, FxHashSetRand, FxRandomState};
 
-pub use seeded_state::FxSeededState;
+#[cfg(feature = "const-random")]
+pub use compile_time_state::FxCompileTimeSeededState;
+#[cfg(all(feature = "const-random", feature = "std"))]
+pub use compile_time_state::{FxHashMapCompileTime, FxHashSetCompileTime};
+
+#[cfg(target_has_atomic = "ptr")]
+pub use counter_state::FxCounterSeededState;
+#[cfg(all(target_has_atomic = "ptr", feature = "std"))]
+pub use counter_state::{FxHashMapCounter, FxHashSetCounter};
+
+#[cfg(feature = "critical-section")]
+pub use global_seed::{global_seed, global_seed_or_init, set_global_seed, FxGlobalSeededState};
+
+#[cfg(feature = "indexmap")]
+pub use index_map::{
+    FxIndexMap, FxIndexMapEntry, FxIndexMapOccupiedEntry, FxIndexMapVacantEntry, FxIndexSet,
+};
+
+#[cfg(feature = "dashmap")]
+pub use dash_map::{dash_map_with_capacity, dash_set_with_capacity, FxDashMap, FxDashSet};
+
+#[cfg(feature = "lru")]
+pub use lru_cache::{lru_cache_unbounded, lru_cache_with_capacity, FxLruCache};
+
+#[cfg(feature = "rayon")]
+pub use parallel::{merge_all, par_decode_fx, par_encode_chunks, par_extend_fx, ParallelCollectFx};
+
+#[cfg(feature = "im")]
+pub use persistent::{FxImHashMap, FxImHashMapSeed, FxImHashSet, FxImHashSetSeed};
+#[cfg(feature = "im-rc")]
+pub use persistent::{FxImRcHashMap, FxImRcHashMapSeed, FxImRcHashSet, FxImRcHashSetSeed};
+
+#[cfg(feature = "flurry")]
+pub use flurry_map::{
+    flurry_map_from_iter, flurry_map_with_capacity, FxFlurryMap, FxFlurryMapRef, FxFlurrySet,
+};
+
+#[cfg(feature = "scc")]
+pub use scc_map::{
+    scc_hash_index_with_capacity, scc_hash_map_with_capacity, FxSccHashIndex, FxSccHashMap,
+    FxSccHashSet,
+};
+
+#[cfg(feature = "papaya")]
+pub use papaya_map::{
+    papaya_map_builder, papaya_map_with_capacity, FxPapayaMap, FxPapayaMapBuilder, FxPapayaSet,
+};
+
+#[cfg(feature = "weak-table")]
+pub use weak_map::{FxWeakHashSet, FxWeakKeyHashMap, FxWeakValueHashMap, FxWeakWeakHashMap};
+
+#[cfg(feature = "priority-queue")]
+pub use priority::{
+    double_priority_queue_with_capacity, priority_queue_with_capacity, FxDoublePriorityQueue,
+    FxPriorityQueue,
+};
+
+#[cfg(feature = "moka")]
+pub use moka_cache::{moka_cache, FxMokaCache, FxMokaSegmentedCache, MokaCacheBuilderExt};
+#[cfg(feature = "moka-future")]
+pub use moka_cache::{moka_future_cache, FxMokaFutureCache};
+
+#[cfg(feature = "quick_cache")]
+pub use quickcache::{
+    quick_cache_with_capacity, quick_cache_with_weighter, quick_unsync_cache_with_capacity,
+    FxQuickCache, FxQuickCacheWeighted, FxQuickUnsyncCache,
+};
+
+#[cfg(feature = "elsa")]
+pub use frozen::{FxFrozenIndexMap, FxFrozenIndexSet, FxFrozenMap};
+
+#[cfg(feature = "heapless")]
+pub use heapless_map::{FxHeaplessIndexMap, FxHeaplessIndexSet};
+
+#[cfg(feature = "petgraph")]
+pub use graph_map::{
+    graph_map_from_edges, graph_map_with_capacity, FxDiGraphMap, FxGraphMap, FxUnGraphMap,
+};
+
+#[cfg(feature = "arc-swap")]
+pub use snapshot_map::FxSnapshotMap;
+
+#[cfg(feature = "evmap")]
+pub use ev_map::{FxEvMap, FxEvMapReadHandle, FxEvMapReadHandleFactory, FxEvMapWriteHandle};
+
+#[cfg(feature = "hashbrown")]
+pub use prehashed_map::FxPrehashedMap;
+
+#[cfg(feature = "hashbrown")]
+pub use entry_ref::{FxEntryRef, FxHashMapAllocExt};
+
+#[cfg(feature = "hashlink")]
+pub use linked_map::{
+    linked_hash_map_with_capacity, linked_hash_set_with_capacity, FxLinkedHashMap,
+    FxLinkedHashMapRawEntryMut, FxLinkedHashSet,
+};
+
+#[cfg(any(
+    feature = "serde",
+    feature = "speedy",
+    feature = "musli",
+    feature = "bincode"
+))]
+pub use presized::FxPresized;
+
+#[cfg(any(feature = "serde", feature = "bincode"))]
+pub use presized::FxWithSeed;
+
+#[cfg(all(feature = "serde", any(feature = "std", feature = "hashbrown")))]
+pub use serde_impls::FxMapLoader;
+#[cfg(feature = "serde")]
+pub use serde_impls::{deserialize_presized, FxRefill, SortedSer};
+
+#[cfg(feature = "rkyv")]
+pub use rkyv_impls::{ArchivedFxHashMap, ArchivedFxHashSet, ArchivedFxSeededState};
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use multi_map::FxMultiMap;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use bi_map::{BiMapOverwritten, FxBiMap};
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use counter::{CounterOverflow, FxCounter};
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use small_map::{FxSmallMap, FxSmallSet};
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use frozen_map::{FrozenBuildError, FrozenFxMap, FrozenFxSet};
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use frozen_bytes::{FrozenFormatError, FrozenFxMapView};
+
+#[cfg(feature = "std")]
+pub use sharded_map::FxShardedMap;
+
+#[cfg(all(feature = "std", target_has_atomic = "64"))]
+pub use concurrent_counter::FxConcurrentCounter;
+
+#[cfg(all(feature = "std", target_has_atomic = "64"))]
+pub use concurrent_set::FxConcurrentSet;
+
+#[cfg(feature = "hashbrown")]
+pub use incremental_map::FxIncrementalMap;
+
+#[cfg(feature = "hashbrown")]
+pub use instrumented_map::{FxInstrumentedMap, FxMapStats};
+
+#[cfg(feature = "hashbrown")]
+pub use load_factor_map::FxLoadFactorMap;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use raw_map::FxRawMap;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use int_map::FxIntMap;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use last_lookup_map::FxLastLookupMap;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use journaled_map::{FxJournaledMap, Mark};
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use scope_map::FxScopeMap;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use union_find::FxUnionFind;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use slab_map::{FxSlabKey, FxSlabMap};
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use capacity::{FxSeededWithCapacity, FxWithCapacity};
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use from_array::{FxFromArray, FxSeededFromArray};
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use convert::{IntoFx, ToFx};
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use iter_ext::FxIteratorExt;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use sorted::{FxSortedExt, SortedDebug};
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use set_ops::FxSetOpsExt;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use partition::FxPartitionExt;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use diff::{fx_diff, FxDiffExt, MapDiff};
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use sketch::SketchMismatch;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use bloom::FxBloomFilter;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use counting_bloom::{CounterWidth, FxCountingBloomFilter};
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use cuckoo::{CuckooFilterFull, FxCuckooFilter};
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use fuse::{FingerprintWidth, FxBinaryFuseBuilder, FxBinaryFuseFilter};
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use hyperloglog::FxHyperLogLog;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use count_min::FxCountMinSketch;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use minhash::{jaccard_estimate, FxMinHash, MinHashSignature};
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use simhash::{hamming_distance, FxSimHash};
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use heavy_hitters::FxHeavyHitters;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use unordered::{hash_unordered, FxIncrementalFingerprint, FxUnorderedHasher};
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use interner::{FxInterner, FxTypedInterner, Interned, Symbol};
+
+pub use array_map::FxArrayMap;
+
+pub use cache::FxCache;
+
+pub use phf::FxPhfMap;
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use phf::{DisplayFxPhfMap, FxPhfBuilder};
+
+pub use persist::{PersistError, PersistedHash};
+
+pub use consistent::fx_jump_hash;
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use consistent::{fx_rendezvous, FxHashRing};
+
+pub use combine::{combine, combine_all};
+
+#[cfg(feature = "allocator-api2")]
+pub use alloc_map::{
+    hash_map_new_in, hash_map_with_capacity_in, hash_set_new_in, hash_set_with_capacity_in,
+    FxHashMapIn, FxHashSetIn,
+};
+#[cfg(feature = "bumpalo")]
+pub use alloc_map::{FxBumpMap, FxBumpSet};
+
+pub use seeded_state::{derive_seed, FxSeededState};
 #[cfg(feature = "std")]
 pub use seeded_state::{FxHashMapSeed, FxHashSetSeed};
 
@@ -313,12 +840,46 @@ impl BuildHasher for FxBuildHasher {
     }
 }
 
+/// Hashes `bytes` with the Fx hashing algorithm after folding `tag` into the
+/// hasher state.
+///
+/// This can be used to namespace fingerprints: the same bytes hashed under
+/// different tags do not collide systematically, and different inputs only
+/// collide across tags by chance.
+///
+/// ```
+/// use rustc_hash::hash_with_tag;
+///
+/// assert_eq!(hash_with_tag(1, b"uwu"), hash_with_tag(1, b"uwu"));
+/// assert_ne!(hash_with_tag(1, b"uwu"), hash_with_tag(2, b"uwu"));
+/// ```
+#[inline]
+pub fn hash_with_tag(tag: u64, bytes: &[u8]) -> u64 {
+    let mut hasher = FxHasher::default();
+    hasher.write_u64(tag);
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+/// Hashes `value` with an unseeded Fx hasher.
+///
+/// The hash is the same in every process and does not depend on the state of
+/// any map, which the maps of this crate seed per instance with the
+/// `shuffle-debug` feature. Use this for hashes that are persisted or compared
+/// across processes.
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub(crate) fn stable_hash<T: core::hash::Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = FxHasher::default();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
 #[cfg(test)]
 mod tests {
     #[cfg(not(any(target_pointer_width = "64", target_pointer_width = "32")))]
     compile_error!("The test suite only supports 64 bit and 32 bit usize");
 
-    use crate::{FxBuildHasher, FxHasher};
+    use crate::{hash_with_tag, FxBuildHasher, FxHasher};
     use core::hash::{BuildHasher, Hash, Hasher};
 
     macro_rules! test_hash {
@@ -456,4 +1017,23 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn tags_actually_different() {
+        for tag in 0..64 {
+            assert_ne!(hash_with_tag(tag, b"uwu"), hash_with_tag(tag + 1, b"uwu"));
+            assert_ne!(hash_with_tag(tag, b""), hash_with_tag(tag + 1, b""));
+        }
+    }
+
+    #[cfg(feature = "hashbrown")]
+    #[test]
+    fn hashbrown_aliases() {
+        let mut map = crate::FxHashMapAlloc::default();
+        map.insert(22, 44);
+        assert_eq!(map[&22], 44);
+
+        let set: crate::FxHashSetAlloc<u32> = [1, 2, 3].into_iter().collect();
+        assert!(set.contains(&2));
+    }
 }
@@ -0,0 +1,81 @@
+use core::hash::Hash;
+
+use crate::FxBuildHasher;
+
+/// Type alias for a [`hashlink::LinkedHashMap`] that uses the Fx hashing algorithm.
+///
+/// The map keeps its entries in insertion order and removes the oldest one
+/// with `pop_front` in constant time. Its `raw_entry` and `raw_entry_mut`
+/// builders look up entries by a precomputed hash, which is the value of
+/// `FxBuildHasher.hash_one(key)`.
+///
+/// ```
+/// use core::hash::BuildHasher;
+/// use rustc_hash::{linked_hash_map_with_capacity, FxBuildHasher, FxLinkedHashMapRawEntryMut};
+///
+/// let mut map = linked_hash_map_with_capacity(4);
+/// map.insert("b", 2);
+/// map.insert("a", 1);
+///
+/// let hash = FxBuildHasher.hash_one("c");
+/// match map.raw_entry_mut().from_key_hashed_nocheck(hash, "c") {
+///     FxLinkedHashMapRawEntryMut::Occupied(_) => unreachable!(),
+///     FxLinkedHashMapRawEntryMut::Vacant(entry) => {
+///         entry.insert_hashed_nocheck(hash, "c", 3);
+///     }
+/// }
+///
+/// assert_eq!(map.pop_front(), Some(("b", 2)));
+/// assert_eq!(map.keys().copied().collect::<Vec<_>>(), ["a", "c"]);
+/// ```
+pub type FxLinkedHashMap<K, V> = hashlink::LinkedHashMap<K, V, FxBuildHasher>;
+
+/// Type alias for a [`hashlink::LinkedHashSet`] that uses the Fx hashing algorithm.
+pub type FxLinkedHashSet<T> = hashlink::LinkedHashSet<T, FxBuildHasher>;
+
+/// Type alias for the entry returned by the `raw_entry_mut` builder of an
+/// [`FxLinkedHashMap`].
+pub type FxLinkedHashMapRawEntryMut<'a, K, V> =
+    hashlink::linked_hash_map::RawEntryMut<'a, K, V, FxBuildHasher>;
+
+/// Creates an empty [`FxLinkedHashMap`] with room for at least `capacity` entries.
+pub fn linked_hash_map_with_capacity<K: Eq + Hash, V>(capacity: usize) -> FxLinkedHashMap<K, V> {
+    FxLinkedHashMap::with_capacity_and_hasher(capacity, FxBuildHasher)
+}
+
+/// Creates an empty [`FxLinkedHashSet`] with room for at least `capacity` elements.
+pub fn linked_hash_set_with_capacity<T: Eq + Hash>(capacity: usize) -> FxLinkedHashSet<T> {
+    FxLinkedHashSet::with_capacity_and_hasher(capacity, FxBuildHasher)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::hash::BuildHasher;
+
+    use crate::{
+        linked_hash_map_with_capacity, linked_hash_set_with_capacity, FxBuildHasher,
+        FxLinkedHashSet,
+    };
+
+    #[test]
+    fn insertion_order() {
+        let mut map = linked_hash_map_with_capacity(8);
+        for i in [5_u32, 1, 3] {
+            map.insert(i, i * 10);
+        }
+        map.to_back(&5);
+        assert!(map.keys().copied().eq([1, 3, 5]));
+
+        let hash = FxBuildHasher.hash_one(3_u32);
+        assert_eq!(
+            map.raw_entry().from_key_hashed_nocheck(hash, &3),
+            Some((&3, &30))
+        );
+        assert_eq!(map.pop_front(), Some((1, 10)));
+
+        let mut set: FxLinkedHashSet<&str> = linked_hash_set_with_capacity(2);
+        set.insert("x");
+        set.insert("y");
+        assert_eq!(set.pop_front(), Some("x"));
+    }
+}
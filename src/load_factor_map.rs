@@ -0,0 +1,252 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::Hash;
+
+use crate::{FxHashMapAlloc, FxMapState};
+
+/// The load factor `hashbrown` grows at, in percent rounded down.
+const HASHBROWN_MAX_LOAD_PERCENT: usize = 87;
+
+/// The load factor `hashbrown` grows at, in permille.
+const HASHBROWN_MAX_LOAD_PERMILLE: usize = 875;
+
+/// A [`hashbrown`] hash map using the Fx hashing algorithm that grows at a
+/// configurable load factor.
+///
+/// `hashbrown` fills its tables up to 87.5% before growing. With a hash that
+/// mixes as little as Fx, probe sequences get long well before that for some
+/// key sets. This map reserves enough room before every insertion that at most
+/// `max_load_percent` percent of the buckets are in use, trading memory for
+/// shorter probe sequences. All other operations behave like those of an
+/// `FxHashMapAlloc`, which [`as_map`](Self::as_map) gives access to.
+///
+/// ```
+/// use rustc_hash::FxLoadFactorMap;
+///
+/// let mut map = FxLoadFactorMap::with_max_load_percent(50);
+/// for i in 0..1000 {
+///     map.insert(i, i);
+/// }
+/// assert_eq!(map.get(&10), Some(&10));
+/// assert!(map.as_map().capacity() >= 1000 * 87 / 50);
+/// ```
+#[derive(Clone)]
+pub struct FxLoadFactorMap<K, V> {
+    map: FxHashMapAlloc<K, V>,
+    max_load_percent: usize,
+}
+
+impl<K, V> FxLoadFactorMap<K, V> {
+    /// Creates an empty `FxLoadFactorMap` that grows at 75% load.
+    pub fn new() -> FxLoadFactorMap<K, V> {
+        Self::with_max_load_percent(75)
+    }
+
+    /// Creates an empty `FxLoadFactorMap` that grows when more than
+    /// `max_load_percent` percent of its buckets are in use.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_load_percent` is 0 or larger than 87, the load factor
+    /// of `hashbrown` itself.
+    pub fn with_max_load_percent(max_load_percent: usize) -> FxLoadFactorMap<K, V> {
+        assert!(
+            (1..=HASHBROWN_MAX_LOAD_PERCENT).contains(&max_load_percent),
+            "the maximum load factor must be between 1% and 87%"
+        );
+        FxLoadFactorMap {
+            map: FxHashMapAlloc::default(),
+            max_load_percent,
+        }
+    }
+
+    /// Returns the maximum load factor of the map, in percent.
+    pub fn max_load_percent(&self) -> usize {
+        self.max_load_percent
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Removes all entries, keeping the allocated memory.
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    /// Returns the underlying map.
+    pub fn as_map(&self) -> &FxHashMapAlloc<K, V> {
+        &self.map
+    }
+
+    /// Returns the underlying map, which no longer grows early.
+    pub fn into_map(self) -> FxHashMapAlloc<K, V> {
+        self.map
+    }
+
+    /// Returns an iterator over the keys and values, in unspecified order.
+    pub fn iter(&self) -> hashbrown::hash_map::Iter<'_, K, V> {
+        self.map.iter()
+    }
+
+    /// Returns an iterator over the keys and mutable values, in unspecified order.
+    pub fn iter_mut(&mut self) -> hashbrown::hash_map::IterMut<'_, K, V> {
+        self.map.iter_mut()
+    }
+
+    /// Returns the `hashbrown` capacity needed to hold `len` entries at the
+    /// maximum load factor.
+    fn capacity_for(&self, len: usize) -> usize {
+        len.saturating_mul(HASHBROWN_MAX_LOAD_PERMILLE)
+            .div_ceil(self.max_load_percent * 10)
+    }
+}
+
+impl<K: Eq + Hash, V> FxLoadFactorMap<K, V> {
+    /// Changes the maximum load factor and rebuilds the table to match it.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as
+    /// [`with_max_load_percent`](Self::with_max_load_percent).
+    pub fn set_max_load_percent(&mut self, max_load_percent: usize) {
+        let map = core::mem::take(&mut self.map);
+        *self = Self::with_max_load_percent(max_load_percent);
+        self.reserve(map.len());
+        self.map.extend(map);
+    }
+
+    /// Reserves room for at least `additional` more entries at the maximum
+    /// load factor.
+    pub fn reserve(&mut self, additional: usize) {
+        let capacity = self.capacity_for(self.map.len().saturating_add(additional));
+        if capacity > self.map.capacity() {
+            self.map.reserve(capacity - self.map.len());
+        }
+    }
+
+    /// Returns a reference to the value of `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.map.get(key)
+    }
+
+    /// Returns a mutable reference to the value of `key`.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.map.get_mut(key)
+    }
+
+    /// Returns `true` if the map contains `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.map.contains_key(key)
+    }
+
+    /// Inserts a key-value pair, returning the previous value of the key.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.reserve(1);
+        self.map.insert(key, value)
+    }
+
+    /// Returns the entry of `key`, with room for inserting it.
+    pub fn entry(&mut self, key: K) -> hashbrown::hash_map::Entry<'_, K, V, FxMapState> {
+        self.reserve(1);
+        self.map.entry(key)
+    }
+
+    /// Removes `key`, returning its value.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.map.remove(key)
+    }
+
+    /// Keeps only the entries for which `f` returns `true`.
+    pub fn retain(&mut self, f: impl FnMut(&K, &mut V) -> bool) {
+        self.map.retain(f);
+    }
+}
+
+impl<K, V> Default for FxLoadFactorMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for FxLoadFactorMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.map.fmt(f)
+    }
+}
+
+impl<K: Eq + Hash, V> Extend<(K, V)> for FxLoadFactorMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<K: Eq + Hash, V> FromIterator<(K, V)> for FxLoadFactorMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FxLoadFactorMap;
+
+    #[test]
+    fn stays_below_load_factor() {
+        for percent in [25, 50, 75, 87] {
+            let mut map = FxLoadFactorMap::with_max_load_percent(percent);
+            for i in 0..5000_u32 {
+                map.insert(i, i);
+                // `hashbrown`'s capacity is 87.5% of its buckets.
+                let buckets = map.as_map().capacity() * 8 / 7;
+                assert!(map.len() * 100 <= buckets * percent + 100);
+            }
+        }
+    }
+
+    #[test]
+    fn rebuilds() {
+        let mut map: FxLoadFactorMap<u32, u32> = (0..1000).map(|i| (i, i)).collect();
+        let capacity = map.as_map().capacity();
+        map.set_max_load_percent(30);
+        assert_eq!(map.max_load_percent(), 30);
+        assert!(map.as_map().capacity() > capacity);
+        assert!((0..1000).all(|i| map.get(&i) == Some(&i)));
+        *map.entry(5).or_default() += 1;
+        assert_eq!(map.remove(&5), Some(6));
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_full_load() {
+        FxLoadFactorMap::<u32, u32>::with_max_load_percent(90);
+    }
+}
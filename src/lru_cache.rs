@@ -0,0 +1,58 @@
+use core::hash::Hash;
+use core::num::NonZeroUsize;
+
+use crate::FxBuildHasher;
+
+/// Type alias for an [`lru::LruCache`] that uses the Fx hashing algorithm.
+///
+/// ```
+/// use std::num::NonZeroUsize;
+/// use rustc_hash::lru_cache_with_capacity;
+///
+/// let mut cache = lru_cache_with_capacity(NonZeroUsize::new(2).unwrap());
+/// cache.put(1, "one");
+/// cache.put(2, "two");
+/// cache.put(3, "three");
+/// assert_eq!(cache.get(&1), None);
+/// assert_eq!(cache.get(&3), Some(&"three"));
+/// ```
+pub type FxLruCache<K, V> = lru::LruCache<K, V, FxBuildHasher>;
+
+/// Creates an empty [`FxLruCache`] that holds at most `capacity` elements.
+pub fn lru_cache_with_capacity<K: Eq + Hash, V>(capacity: NonZeroUsize) -> FxLruCache<K, V> {
+    FxLruCache::with_hasher(capacity, FxBuildHasher)
+}
+
+/// Creates an empty [`FxLruCache`] without an upper bound on the number of elements.
+pub fn lru_cache_unbounded<K: Eq + Hash, V>() -> FxLruCache<K, V> {
+    FxLruCache::unbounded_with_hasher(FxBuildHasher)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::num::NonZeroUsize;
+
+    use crate::{lru_cache_unbounded, lru_cache_with_capacity};
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = lru_cache_with_capacity(NonZeroUsize::new(2).unwrap());
+        cache.put(1_u32, 1_u32);
+        cache.put(2, 2);
+        assert_eq!(cache.get(&1), Some(&1));
+        cache.put(3, 3);
+
+        assert_eq!(cache.get(&2), None);
+        assert_eq!(cache.get(&1), Some(&1));
+        assert_eq!(cache.get(&3), Some(&3));
+    }
+
+    #[test]
+    fn unbounded_never_evicts() {
+        let mut cache = lru_cache_unbounded();
+        for i in 0..1024_u32 {
+            cache.put(i, i);
+        }
+        assert_eq!(cache.len(), 1024);
+    }
+}
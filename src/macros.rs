@@ -0,0 +1,143 @@
+/// Builds the map of [`fx_map!`](crate::fx_map) and
+/// [`fx_map_alloc!`](crate::fx_map_alloc), given its type.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __fx_map {
+    (@unit $_key:expr) => {
+        ()
+    };
+    ($map:ty;) => {
+        <$map as ::core::default::Default>::default()
+    };
+    ($map:ty; $($key:expr => $value:expr),+ $(,)?) => {{
+        let capacity = [$($crate::__fx_map!(@unit $key)),+].len();
+        let mut map = <$map>::with_capacity_and_hasher(capacity, ::core::default::Default::default());
+        $(map.insert($key, $value);)+
+        map
+    }};
+}
+
+/// Builds the set of [`fx_set!`](crate::fx_set) and
+/// [`fx_set_alloc!`](crate::fx_set_alloc), given its type.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __fx_set {
+    (@unit $_value:expr) => {
+        ()
+    };
+    ($set:ty;) => {
+        <$set as ::core::default::Default>::default()
+    };
+    ($set:ty; $($value:expr),+ $(,)?) => {{
+        let capacity = [$($crate::__fx_set!(@unit $value)),+].len();
+        let mut set = <$set>::with_capacity_and_hasher(capacity, ::core::default::Default::default());
+        $(set.insert($value);)+
+        set
+    }};
+}
+
+/// Creates an `FxHashMap` containing the given key-value pairs.
+///
+/// The map reserves room for all pairs up front. Like `insert`, later pairs
+/// win over earlier pairs with an equal key. Use
+/// [`fx_map_alloc!`](crate::fx_map_alloc) for an `FxHashMapAlloc`.
+///
+/// ```
+/// use rustc_hash::{fx_map, FxHashMap};
+///
+/// let map: FxHashMap<&str, u32> = fx_map! {
+///     "one" => 1,
+///     "two" => 2,
+/// };
+/// assert_eq!(map["two"], 2);
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! fx_map {
+    ($($tt:tt)*) => {
+        $crate::__fx_map!($crate::FxHashMap<_, _>; $($tt)*)
+    };
+}
+
+/// Creates an `FxHashSet` containing the given elements.
+///
+/// The set reserves room for all elements up front. Use
+/// [`fx_set_alloc!`](crate::fx_set_alloc) for an `FxHashSetAlloc`.
+///
+/// ```
+/// use rustc_hash::{fx_set, FxHashSet};
+///
+/// let set: FxHashSet<u32> = fx_set![1, 2, 3];
+/// assert!(set.contains(&2));
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! fx_set {
+    ($($tt:tt)*) => {
+        $crate::__fx_set!($crate::FxHashSet<_>; $($tt)*)
+    };
+}
+
+/// Creates an `FxHashMapAlloc` containing the given key-value pairs, like
+/// [`fx_map!`](crate::fx_map) does for `FxHashMap`.
+///
+/// ```
+/// use rustc_hash::{fx_map_alloc, FxHashMapAlloc};
+///
+/// let map: FxHashMapAlloc<&str, u32> = fx_map_alloc! { "one" => 1 };
+/// assert_eq!(map["one"], 1);
+/// ```
+#[cfg(feature = "hashbrown")]
+#[macro_export]
+macro_rules! fx_map_alloc {
+    ($($tt:tt)*) => {
+        $crate::__fx_map!($crate::FxHashMapAlloc<_, _>; $($tt)*)
+    };
+}
+
+/// Creates an `FxHashSetAlloc` containing the given elements, like
+/// [`fx_set!`](crate::fx_set) does for `FxHashSet`.
+///
+/// ```
+/// use rustc_hash::{fx_set_alloc, FxHashSetAlloc};
+///
+/// let set: FxHashSetAlloc<u32> = fx_set_alloc![1, 2, 3];
+/// assert!(set.contains(&2));
+/// ```
+#[cfg(feature = "hashbrown")]
+#[macro_export]
+macro_rules! fx_set_alloc {
+    ($($tt:tt)*) => {
+        $crate::__fx_set!($crate::FxHashSetAlloc<_>; $($tt)*)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "std")]
+    #[test]
+    fn literals() {
+        let map = fx_map! { 1 => "a", 2 => "b", 1 => "c" };
+        assert_eq!(map.len(), 2);
+        assert_eq!(map[&1], "c");
+        assert!(map.capacity() >= 3);
+
+        let set = fx_set!["x", "y",];
+        assert_eq!(set.len(), 2);
+
+        let mut empty = fx_map! {};
+        empty.insert(0_u8, ());
+        let empty: crate::FxHashSet<u8> = fx_set![];
+        assert!(empty.is_empty());
+    }
+
+    #[cfg(feature = "hashbrown")]
+    #[test]
+    fn alloc_literals() {
+        let map = fx_map_alloc! { 1 => "a", 1 => "b" };
+        assert_eq!(map[&1], "b");
+
+        let set: crate::FxHashSetAlloc<u8> = fx_set_alloc![];
+        assert!(set.is_empty());
+    }
+}
@@ -0,0 +1,166 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::hash::{BuildHasher, Hash};
+
+use crate::sketch::{self, SketchMismatch, DEFAULT_SEEDS};
+use crate::FxSeededState;
+
+/// Computes MinHash signatures of sets using the Fx hashing algorithm.
+///
+/// The signature of a set holds, for each of `k` random permutations of the
+/// hash values, the smallest permuted hash of its items. Two sets agree on a
+/// position with probability equal to their Jaccard similarity, the size of
+/// their intersection divided by the size of their union, so
+/// [`jaccard_estimate`] estimates it with a standard error of at most
+/// `0.5 / sqrt(k)`.
+///
+/// Every item is hashed once with a seeded Fx hasher. Permutation `i` XORs
+/// that hash with `(seed + i)` times the Fx multiplier and applies a bijective
+/// mixing step, so it is a bijection of the 64-bit hash values.
+///
+/// ```
+/// use rustc_hash::{jaccard_estimate, FxMinHash};
+///
+/// let minhash = FxMinHash::new(256);
+/// let a = minhash.signature("the quick brown fox jumps over the lazy dog".split(' '));
+/// let b = minhash.signature("the quick brown fox jumps over the lazy cat".split(' '));
+/// let similarity = jaccard_estimate(&a, &b).unwrap();
+/// assert!((0.6..0.9).contains(&similarity));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FxMinHash {
+    num_hashes: usize,
+    seed: usize,
+}
+
+impl FxMinHash {
+    /// Creates a MinHash with `num_hashes` permutations.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_hashes` is 0.
+    pub fn new(num_hashes: usize) -> FxMinHash {
+        Self::with_seed(num_hashes, DEFAULT_SEEDS.0)
+    }
+
+    /// Creates a MinHash like [`new`](Self::new), deriving the item hashes
+    /// and the permutations from `seed`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_hashes` is 0.
+    pub fn with_seed(num_hashes: usize, seed: usize) -> FxMinHash {
+        assert!(num_hashes > 0, "a MinHash needs at least one permutation");
+        FxMinHash { num_hashes, seed }
+    }
+
+    /// Returns the number of permutations, the length of the signatures.
+    pub fn num_hashes(&self) -> usize {
+        self.num_hashes
+    }
+
+    /// Returns the seed that the hashes are derived from.
+    pub fn seed(&self) -> usize {
+        self.seed
+    }
+
+    /// Returns the signature of the empty set, to which items can be added
+    /// with [`MinHashSignature::insert`].
+    pub fn empty_signature(&self) -> MinHashSignature {
+        MinHashSignature {
+            mins: vec![u64::MAX; self.num_hashes],
+            seed: self.seed,
+        }
+    }
+
+    /// Returns the signature of the set of `items`.
+    pub fn signature<I>(&self, items: I) -> MinHashSignature
+    where
+        I: IntoIterator,
+        I::Item: Hash,
+    {
+        let mut signature = self.empty_signature();
+        for item in items {
+            signature.insert(&item);
+        }
+        signature
+    }
+}
+
+/// The MinHash signature of a set, created by [`FxMinHash`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MinHashSignature {
+    pub(crate) mins: Vec<u64>,
+    pub(crate) seed: usize,
+}
+
+impl MinHashSignature {
+    /// Adds an item to the set.
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) {
+        let hash = FxSeededState::with_seed(self.seed).hash_one(item);
+        for (i, min) in self.mins.iter_mut().enumerate() {
+            let step = (self.seed as u64).wrapping_add(i as u64);
+            let permuted = sketch::mix(hash ^ step.wrapping_mul(0xf1357aea2e62a9c5));
+            *min = (*min).min(permuted);
+        }
+    }
+
+    /// Returns the smallest permuted hash of every permutation.
+    pub fn mins(&self) -> &[u64] {
+        &self.mins
+    }
+
+    /// Returns `true` if `other` was computed with the same permutations, so
+    /// that the signatures can be compared.
+    pub fn is_compatible(&self, other: &MinHashSignature) -> bool {
+        self.mins.len() == other.mins.len() && self.seed == other.seed
+    }
+
+    /// Adds the items of the set of `other`, making this the signature of the
+    /// union of both sets.
+    pub fn merge(&mut self, other: &MinHashSignature) -> Result<(), SketchMismatch> {
+        if !self.is_compatible(other) {
+            return Err(SketchMismatch);
+        }
+        for (a, &b) in self.mins.iter_mut().zip(&other.mins) {
+            *a = (*a).min(b);
+        }
+        Ok(())
+    }
+}
+
+/// Estimates the Jaccard similarity of two sets from their MinHash
+/// signatures, as the fraction of permutations on which they agree.
+///
+/// Returns an error if the signatures were computed with different
+/// permutations.
+pub fn jaccard_estimate(a: &MinHashSignature, b: &MinHashSignature) -> Result<f64, SketchMismatch> {
+    if !a.is_compatible(b) {
+        return Err(SketchMismatch);
+    }
+    let equal = a.mins.iter().zip(&b.mins).filter(|(x, y)| x == y).count();
+    Ok(equal as f64 / a.mins.len() as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{jaccard_estimate, FxMinHash, SketchMismatch};
+
+    #[test]
+    fn estimates_similarity() {
+        let minhash = FxMinHash::with_seed(512, 1);
+        // The sets share 500 of 1500 items.
+        let a = minhash.signature(0..1000_u32);
+        let b = minhash.signature(500..1500_u32);
+        let estimate = jaccard_estimate(&a, &b).unwrap();
+        assert!((estimate - 1.0 / 3.0).abs() < 0.07, "estimate {estimate}");
+        assert_eq!(jaccard_estimate(&a, &a), Ok(1.0));
+
+        let mut union = a.clone();
+        union.merge(&b).unwrap();
+        assert_eq!(union, minhash.signature(0..1500_u32));
+
+        let other = FxMinHash::new(512).signature(0..1000_u32);
+        assert_eq!(jaccard_estimate(&a, &other), Err(SketchMismatch));
+    }
+}
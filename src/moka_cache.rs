@@ -0,0 +1,129 @@
+use core::hash::Hash;
+use std::collections::hash_map::RandomState;
+
+use crate::FxBuildHasher;
+
+/// Type alias for a [`moka::sync::Cache`] that uses the Fx hashing algorithm.
+///
+/// ```
+/// use rustc_hash::{FxMokaCache, MokaCacheBuilderExt};
+///
+/// let cache: FxMokaCache<u32, u32> = moka::sync::Cache::builder()
+///     .max_capacity(100)
+///     .build_fx();
+/// cache.insert(22, 44);
+/// assert_eq!(cache.get(&22), Some(44));
+/// ```
+pub type FxMokaCache<K, V> = moka::sync::Cache<K, V, FxBuildHasher>;
+
+/// Type alias for a [`moka::sync::SegmentedCache`] that uses the Fx hashing algorithm.
+pub type FxMokaSegmentedCache<K, V> = moka::sync::SegmentedCache<K, V, FxBuildHasher>;
+
+/// Type alias for a [`moka::future::Cache`] that uses the Fx hashing algorithm.
+#[cfg(feature = "moka-future")]
+pub type FxMokaFutureCache<K, V> = moka::future::Cache<K, V, FxBuildHasher>;
+
+/// Extension trait for finishing `moka` cache builders with [`FxBuildHasher`].
+///
+/// The builders of `moka` only pick the hasher in their final step, so all of
+/// their other configuration is available as usual before calling
+/// [`build_fx`](MokaCacheBuilderExt::build_fx).
+pub trait MokaCacheBuilderExt {
+    /// The cache type built by this builder.
+    type Cache;
+
+    /// Builds the cache using [`FxBuildHasher`].
+    fn build_fx(self) -> Self::Cache;
+}
+
+impl<K, V> MokaCacheBuilderExt
+    for moka::sync::CacheBuilder<K, V, moka::sync::Cache<K, V, RandomState>>
+where
+    K: Eq + Hash + Send + Sync + 'static,
+    V: Clone + Send + Sync + 'static,
+{
+    type Cache = FxMokaCache<K, V>;
+
+    fn build_fx(self) -> Self::Cache {
+        self.build_with_hasher(FxBuildHasher)
+    }
+}
+
+impl<K, V> MokaCacheBuilderExt
+    for moka::sync::CacheBuilder<K, V, moka::sync::SegmentedCache<K, V, RandomState>>
+where
+    K: Eq + Hash + Send + Sync + 'static,
+    V: Clone + Send + Sync + 'static,
+{
+    type Cache = FxMokaSegmentedCache<K, V>;
+
+    fn build_fx(self) -> Self::Cache {
+        self.build_with_hasher(FxBuildHasher)
+    }
+}
+
+#[cfg(feature = "moka-future")]
+impl<K, V> MokaCacheBuilderExt
+    for moka::future::CacheBuilder<K, V, moka::future::Cache<K, V, RandomState>>
+where
+    K: Eq + Hash + Send + Sync + 'static,
+    V: Clone + Send + Sync + 'static,
+{
+    type Cache = FxMokaFutureCache<K, V>;
+
+    fn build_fx(self) -> Self::Cache {
+        self.build_with_hasher(FxBuildHasher)
+    }
+}
+
+/// Creates an [`FxMokaCache`] that holds up to `max_capacity` entries.
+pub fn moka_cache<K, V>(max_capacity: u64) -> FxMokaCache<K, V>
+where
+    K: Eq + Hash + Send + Sync + 'static,
+    V: Clone + Send + Sync + 'static,
+{
+    moka::sync::Cache::builder()
+        .max_capacity(max_capacity)
+        .build_fx()
+}
+
+/// Creates an [`FxMokaFutureCache`] that holds up to `max_capacity` entries.
+#[cfg(feature = "moka-future")]
+pub fn moka_future_cache<K, V>(max_capacity: u64) -> FxMokaFutureCache<K, V>
+where
+    K: Eq + Hash + Send + Sync + 'static,
+    V: Clone + Send + Sync + 'static,
+{
+    moka::future::Cache::builder()
+        .max_capacity(max_capacity)
+        .build_fx()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{moka_cache, MokaCacheBuilderExt};
+
+    #[test]
+    fn sync_caches() {
+        let cache = moka_cache(16);
+        cache.insert(1_u32, 2_u32);
+        assert_eq!(cache.get(&1), Some(2));
+        assert_eq!(cache.policy().max_capacity(), Some(16));
+
+        let cache = moka::sync::SegmentedCache::builder(4)
+            .max_capacity(16)
+            .build_fx();
+        cache.insert(1_u32, 2_u32);
+        assert_eq!(cache.get(&1), Some(2));
+    }
+
+    #[cfg(feature = "moka-future")]
+    #[test]
+    fn future_cache() {
+        let cache = crate::moka_future_cache(16);
+        futures_executor::block_on(async {
+            cache.insert(1_u32, 2_u32).await;
+            assert_eq!(cache.get(&1).await, Some(2));
+        });
+    }
+}
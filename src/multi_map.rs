@@ -0,0 +1,216 @@
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::Hash;
+
+use crate::FxTable;
+
+/// A map from keys to one or more values, using the Fx hashing algorithm.
+///
+/// Values of a key are kept in insertion order. Keys without values are
+/// removed, so every key in the map has at least one value.
+///
+/// ```
+/// use rustc_hash::FxMultiMap;
+///
+/// let mut map = FxMultiMap::default();
+/// map.insert("a", 1);
+/// map.insert("a", 2);
+/// map.insert("b", 3);
+/// assert_eq!(map.get_all("a"), &[1, 2]);
+///
+/// assert!(map.remove_one("a", &1));
+/// assert_eq!(map.get_all("a"), &[2]);
+/// ```
+#[derive(Clone)]
+pub struct FxMultiMap<K, V> {
+    map: FxTable<K, Vec<V>>,
+    values: usize,
+}
+
+impl<K, V> FxMultiMap<K, V> {
+    /// Creates an empty `FxMultiMap`.
+    pub fn new() -> FxMultiMap<K, V> {
+        Self::with_capacity(0)
+    }
+
+    /// Creates an empty `FxMultiMap` with room for at least `capacity` keys.
+    pub fn with_capacity(capacity: usize) -> FxMultiMap<K, V> {
+        FxMultiMap {
+            map: FxTable::with_capacity_and_hasher(capacity, Default::default()),
+            values: 0,
+        }
+    }
+
+    /// Returns the number of distinct keys.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns the total number of values over all keys.
+    pub fn values_len(&self) -> usize {
+        self.values
+    }
+
+    /// Returns `true` if the map contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Removes all keys and values.
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.values = 0;
+    }
+
+    /// Returns an iterator over the keys and all their values.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &[V])> {
+        self.map.iter().map(|(k, v)| (k, v.as_slice()))
+    }
+
+    /// Returns an iterator over the keys and each of their values.
+    pub fn iter_flat(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.map
+            .iter()
+            .flat_map(|(k, v)| v.iter().map(move |v| (k, v)))
+    }
+
+    /// Returns an iterator over the distinct keys.
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.map.keys()
+    }
+}
+
+impl<K: Eq + Hash, V> FxMultiMap<K, V> {
+    /// Adds `value` to the values of `key`.
+    pub fn insert(&mut self, key: K, value: V) {
+        self.map.entry(key).or_default().push(value);
+        self.values += 1;
+    }
+
+    /// Returns all values of `key`, which is empty if the key is not present.
+    pub fn get_all<Q>(&self, key: &Q) -> &[V]
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.map.get(key).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the first value of `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.get_all(key).first()
+    }
+
+    /// Returns `true` if `key` has at least one value.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.map.contains_key(key)
+    }
+
+    /// Removes the first occurrence of `value` from the values of `key`.
+    ///
+    /// Returns `true` if a value was removed.
+    pub fn remove_one<Q>(&mut self, key: &Q, value: &V) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+        V: PartialEq,
+    {
+        let Some(values) = self.map.get_mut(key) else {
+            return false;
+        };
+        let Some(i) = values.iter().position(|v| v == value) else {
+            return false;
+        };
+
+        values.remove(i);
+        if values.is_empty() {
+            self.map.remove(key);
+        }
+        self.values -= 1;
+        true
+    }
+
+    /// Removes `key` and returns all of its values.
+    pub fn remove_all<Q>(&mut self, key: &Q) -> Option<Vec<V>>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let values = self.map.remove(key)?;
+        self.values -= values.len();
+        Some(values)
+    }
+}
+
+impl<K, V> Default for FxMultiMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for FxMultiMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Eq + Hash, V> Extend<(K, V)> for FxMultiMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<K: Eq + Hash, V> FromIterator<(K, V)> for FxMultiMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FxMultiMap;
+
+    #[test]
+    fn insert_and_remove() {
+        let mut map: FxMultiMap<u32, u32> = [(1, 1), (1, 2), (2, 3), (1, 2)].into_iter().collect();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.values_len(), 4);
+        assert_eq!(map.get_all(&1), &[1, 2, 2]);
+        assert_eq!(map.get(&2), Some(&3));
+        assert!(map.get_all(&3).is_empty());
+
+        assert!(map.remove_one(&1, &2));
+        assert!(!map.remove_one(&1, &5));
+        assert_eq!(map.get_all(&1), &[1, 2]);
+
+        assert!(map.remove_one(&2, &3));
+        assert!(!map.contains_key(&2));
+        assert_eq!(map.values_len(), 2);
+
+        assert_eq!(map.remove_all(&1), Some([1, 2].into()));
+        assert!(map.is_empty());
+        assert_eq!(map.values_len(), 0);
+    }
+
+    #[test]
+    fn iterators() {
+        let map: FxMultiMap<u32, u32> = [(1, 1), (1, 2), (2, 3)].into_iter().collect();
+        let mut flat: alloc::vec::Vec<_> = map.iter_flat().map(|(k, v)| (*k, *v)).collect();
+        flat.sort();
+        assert_eq!(flat, [(1, 1), (1, 2), (2, 3)]);
+        assert_eq!(map.iter().map(|(_, v)| v.len()).sum::<usize>(), 3);
+    }
+}
@@ -0,0 +1,165 @@
+use core::hash::{BuildHasher, Hash};
+
+use musli::de::{MapDecoder, SequenceDecoder};
+use musli::en::{MapEncoder, SequenceEncoder};
+use musli::{Allocator, Context, Decode, Decoder, Encode, Encoder};
+
+use crate::presized::prealloc;
+use crate::{FxPresized, FxSeededState};
+
+// musli's own impls encode `FxHashMap` and `FxHashSet` like any other hash map
+// or set, and reserve at most 1 MiB worth of elements when decoding them. The
+// impls for `FxPresized` encode the same way, so both can decode what the
+// other encoded, and also cover the `hashbrown` collections.
+
+impl<M> Encode<M> for FxSeededState {
+    type Encode = Self;
+
+    fn encode<E: Encoder<Mode = M>>(&self, encoder: E) -> Result<(), E::Error> {
+        // The seed is stored as a `u64` so that it decodes on any platform.
+        encoder.encode_u64(self.seed() as u64)
+    }
+
+    fn as_encode(&self) -> &Self {
+        self
+    }
+}
+
+impl<'de, M, A: Allocator> Decode<'de, M, A> for FxSeededState {
+    fn decode<D>(decoder: D) -> Result<Self, D::Error>
+    where
+        D: Decoder<'de, Mode = M, Allocator = A>,
+    {
+        let cx = decoder.cx();
+        let seed = decoder.decode_u64()?;
+        let seed = usize::try_from(seed).map_err(|_| cx.message("the seed does not fit usize"))?;
+        Ok(FxSeededState::with_seed(seed))
+    }
+}
+
+macro_rules! impl_presized {
+    ($map:ident, $set:ident) => {
+        impl<M, K, V, S> Encode<M> for FxPresized<$map<K, V, S>>
+        where
+            K: Encode<M>,
+            V: Encode<M>,
+        {
+            type Encode = Self;
+
+            fn encode<E: Encoder<Mode = M>>(&self, encoder: E) -> Result<(), E::Error> {
+                encoder.encode_map_fn(self.0.len(), |map| {
+                    self.0.iter().try_for_each(|(k, v)| map.insert_entry(k, v))
+                })
+            }
+
+            fn as_encode(&self) -> &Self {
+                self
+            }
+        }
+
+        impl<'de, M, A, K, V, S> Decode<'de, M, A> for FxPresized<$map<K, V, S>>
+        where
+            A: Allocator,
+            K: Decode<'de, M, A> + Eq + Hash,
+            V: Decode<'de, M, A>,
+            S: BuildHasher + Default,
+        {
+            fn decode<D>(decoder: D) -> Result<Self, D::Error>
+            where
+                D: Decoder<'de, Mode = M, Allocator = A>,
+            {
+                decoder.decode_map(|access| {
+                    let capacity = prealloc::<(K, V)>(access.size_hint().into_option());
+                    let mut map = $map::with_capacity_and_hasher(capacity, S::default());
+                    while let Some((k, v)) = access.entry()? {
+                        map.insert(k, v);
+                    }
+                    Ok(FxPresized(map))
+                })
+            }
+        }
+
+        impl<M, T, S> Encode<M> for FxPresized<$set<T, S>>
+        where
+            T: Encode<M>,
+        {
+            type Encode = Self;
+
+            fn encode<E: Encoder<Mode = M>>(&self, encoder: E) -> Result<(), E::Error> {
+                encoder.encode_sequence_fn(self.0.len(), |seq| {
+                    self.0.iter().try_for_each(|t| seq.push(t))
+                })
+            }
+
+            fn as_encode(&self) -> &Self {
+                self
+            }
+        }
+
+        impl<'de, M, A, T, S> Decode<'de, M, A> for FxPresized<$set<T, S>>
+        where
+            A: Allocator,
+            T: Decode<'de, M, A> + Eq + Hash,
+            S: BuildHasher + Default,
+        {
+            fn decode<D>(decoder: D) -> Result<Self, D::Error>
+            where
+                D: Decoder<'de, Mode = M, Allocator = A>,
+            {
+                decoder.decode_sequence(|seq| {
+                    let capacity = prealloc::<T>(seq.size_hint().into_option());
+                    let mut set = $set::with_capacity_and_hasher(capacity, S::default());
+                    while let Some(t) = seq.try_decode_next()? {
+                        set.insert(t.decode()?);
+                    }
+                    Ok(FxPresized(set))
+                })
+            }
+        }
+    };
+}
+
+#[cfg(feature = "std")]
+mod std_impls {
+    use std::collections::{HashMap, HashSet};
+
+    use super::*;
+
+    impl_presized!(HashMap, HashSet);
+}
+
+#[cfg(feature = "hashbrown")]
+mod hashbrown_impls {
+    use hashbrown::{HashMap, HashSet};
+
+    use super::*;
+
+    impl_presized!(HashMap, HashSet);
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::string::{String, ToString};
+
+    use crate::{FxHashMap, FxHashSet, FxPresized, FxSeededState};
+
+    #[test]
+    fn round_trip() {
+        let map: FxHashMap<u32, String> = (0..1000).map(|i| (i, i.to_string())).collect();
+        let bytes = musli::storage::to_vec(&FxPresized(map.clone())).unwrap();
+        assert_eq!(bytes, musli::storage::to_vec(&map).unwrap());
+        let back: FxPresized<FxHashMap<u32, String>> = musli::storage::from_slice(&bytes).unwrap();
+        assert_eq!(back.0, map);
+        assert!(back.0.capacity() < 2 * map.len());
+
+        let set: FxHashSet<u64> = (0..1000).collect();
+        let bytes = musli::wire::to_vec(&set).unwrap();
+        let back: FxPresized<FxHashSet<u64>> = musli::wire::from_slice(&bytes).unwrap();
+        assert_eq!(back.0, set);
+
+        let state = FxSeededState::with_seed(0xdead_beef);
+        let bytes = musli::storage::to_vec(&state).unwrap();
+        let back: FxSeededState = musli::storage::from_slice(&bytes).unwrap();
+        assert_eq!(back.seed(), 0xdead_beef);
+    }
+}
@@ -0,0 +1,48 @@
+use crate::FxBuildHasher;
+
+/// Type alias for a [`papaya::HashMap`] that uses the Fx hashing algorithm.
+///
+/// ```
+/// use rustc_hash::FxPapayaMap;
+///
+/// let map: FxPapayaMap<u32, u32> = FxPapayaMap::default();
+/// let map = map.pin();
+/// map.insert(22, 44);
+/// assert_eq!(map.get(&22), Some(&44));
+/// ```
+pub type FxPapayaMap<K, V> = papaya::HashMap<K, V, FxBuildHasher>;
+
+/// Type alias for a [`papaya::HashSet`] that uses the Fx hashing algorithm.
+pub type FxPapayaSet<T> = papaya::HashSet<T, FxBuildHasher>;
+
+/// Type alias for a [`papaya::HashMapBuilder`] that builds [`FxPapayaMap`]s.
+pub type FxPapayaMapBuilder<K, V> = papaya::HashMapBuilder<K, V, FxBuildHasher>;
+
+/// Returns a builder for an [`FxPapayaMap`], to configure e.g. its resize mode.
+pub fn papaya_map_builder<K, V>() -> FxPapayaMapBuilder<K, V> {
+    papaya::HashMap::builder().hasher(FxBuildHasher)
+}
+
+/// Creates an empty [`FxPapayaMap`] with room for at least `capacity` elements.
+pub fn papaya_map_with_capacity<K, V>(capacity: usize) -> FxPapayaMap<K, V> {
+    FxPapayaMap::with_capacity_and_hasher(capacity, FxBuildHasher)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{papaya_map_builder, papaya_map_with_capacity};
+
+    #[test]
+    fn constructors() {
+        let map = papaya_map_with_capacity(64);
+        map.pin().insert(1_u32, 2_u32);
+        assert_eq!(map.pin().get(&1), Some(&2));
+
+        let map = papaya_map_builder()
+            .capacity(64)
+            .resize_mode(papaya::ResizeMode::Blocking)
+            .build();
+        map.pin().insert(1_u32, 2_u32);
+        assert_eq!(map.pin().get(&1), Some(&2));
+    }
+}
@@ -0,0 +1,315 @@
+use core::hash::{BuildHasher, Hash};
+use std::collections::hash_map::Entry;
+use std::vec::Vec;
+
+use rayon::prelude::*;
+
+use crate::{sketch, FxBuildHasher, FxHashMap, FxHashSet, FxPartitionExt, FxShardedMap};
+
+/// Extension methods for collecting parallel iterators into Fx collections.
+///
+/// Items are first distributed over a number of shards by hash, each shard is
+/// deduplicated on its own thread, and only the already deduplicated shards are
+/// merged into the final collection. Like `collect`, later items win over
+/// earlier items with an equal key.
+///
+/// ```
+/// use rayon::prelude::*;
+/// use rustc_hash::ParallelCollectFx;
+///
+/// let map = (0..1000_u32).into_par_iter().map(|i| (i % 10, i)).par_collect_fx();
+/// assert_eq!(map.len(), 10);
+/// assert_eq!(map[&3], 993);
+/// ```
+pub trait ParallelCollectFx: ParallelIterator {
+    /// Collects the key-value pairs of this iterator into an [`FxHashMap`].
+    fn par_collect_fx<K, V>(self) -> FxHashMap<K, V>
+    where
+        Self: ParallelIterator<Item = (K, V)>,
+        K: Eq + Hash + Send,
+        V: Send,
+    {
+        let shards = shard_by(self, |(k, _)| k);
+        let maps: Vec<FxHashMap<K, V>> = shards
+            .into_par_iter()
+            .map(|shard| shard.into_iter().collect())
+            .collect();
+
+        let mut map = FxHashMap::default();
+        map.reserve(maps.iter().map(|m| m.len()).sum());
+        for m in maps {
+            map.extend(m);
+        }
+        map
+    }
+
+    /// Collects the items of this iterator into an [`FxHashSet`].
+    fn par_collect_fx_set<T>(self) -> FxHashSet<T>
+    where
+        Self: ParallelIterator<Item = T>,
+        T: Eq + Hash + Send,
+    {
+        let shards = shard_by(self, |t| t);
+        let sets: Vec<FxHashSet<T>> = shards
+            .into_par_iter()
+            .map(|shard| shard.into_iter().collect())
+            .collect();
+
+        let mut set = FxHashSet::default();
+        set.reserve(sets.iter().map(|s| s.len()).sum());
+        for s in sets {
+            set.extend(s);
+        }
+        set
+    }
+}
+
+impl<I: ParallelIterator> ParallelCollectFx for I {}
+
+/// Extends `map` with the key-value pairs of a parallel iterator, deduplicating
+/// in parallel like [`ParallelCollectFx::par_collect_fx`].
+pub fn par_extend_fx<K, V, I>(map: &mut FxHashMap<K, V>, iter: I)
+where
+    I: IntoParallelIterator<Item = (K, V)>,
+    K: Eq + Hash + Send,
+    V: Send,
+{
+    let other = iter.into_par_iter().par_collect_fx();
+    if map.is_empty() {
+        *map = other;
+    } else {
+        map.extend(other);
+    }
+}
+
+/// Merges many maps into one, combining the values of keys present in several
+/// maps with `merge`.
+///
+/// Every map is split into shards by hash on its own thread, and the shards
+/// covering the same part of the hash range are merged in parallel. The values
+/// of a key are merged in the order of the maps, as if folding them serially.
+///
+/// The merged shards become the shards of the returned [`FxShardedMap`], so no
+/// entry is moved again after merging. Use [`FxShardedMap::into_inner`] to get
+/// a single `FxHashMap`.
+///
+/// ```
+/// use rustc_hash::{merge_all, FxHashMap};
+///
+/// let maps: Vec<FxHashMap<u32, u32>> = (0..10)
+///     .map(|i| (0..100).map(|k| (k, i)).collect())
+///     .collect();
+/// let map = merge_all(maps, |a, b| *a += b);
+/// assert_eq!(map.len(), 100);
+/// assert_eq!(map.get(&7), Some(45));
+/// ```
+pub fn merge_all<K, V, I, F>(maps: I, merge: F) -> FxShardedMap<K, V>
+where
+    I: IntoParallelIterator<Item = FxHashMap<K, V>>,
+    K: Eq + Hash + Send,
+    V: Send,
+    F: Fn(&mut V, V) + Sync,
+{
+    let n = (rayon::current_num_threads() * 4).next_power_of_two();
+    let partitioned: Vec<Vec<FxHashMap<K, V>>> = maps
+        .into_par_iter()
+        .map(|map| map.partition_into(n))
+        .collect();
+
+    let mut columns: Vec<Vec<FxHashMap<K, V>>> = (0..n)
+        .map(|_| Vec::with_capacity(partitioned.len()))
+        .collect();
+    for shards in partitioned {
+        for (column, shard) in columns.iter_mut().zip(shards) {
+            column.push(shard);
+        }
+    }
+
+    let merged: Vec<FxHashMap<K, V>> = columns
+        .into_par_iter()
+        .map(|column| {
+            let mut column = column.into_iter();
+            let mut acc = column.next().unwrap_or_default();
+            for shard in column {
+                acc.reserve(shard.len());
+                for (k, v) in shard {
+                    match acc.entry(k) {
+                        Entry::Occupied(entry) => merge(entry.into_mut(), v),
+                        Entry::Vacant(entry) => {
+                            entry.insert(v);
+                        }
+                    }
+                }
+            }
+            acc
+        })
+        .collect();
+    FxShardedMap::from_shards(merged)
+}
+
+/// Encodes a map as independent chunks in parallel, for loading it with
+/// [`par_decode_fx`].
+///
+/// The entries are split into chunks by hash, so that every key is in exactly
+/// one chunk, and `encode` is called for every chunk on its own thread. The
+/// number of chunks depends on the number of threads, and some chunks can be
+/// empty.
+pub fn par_encode_chunks<K, V, T, E, F>(map: &FxHashMap<K, V>, encode: F) -> Result<Vec<T>, E>
+where
+    K: Hash + Sync,
+    V: Sync,
+    T: Send,
+    E: Send,
+    F: Fn(&[(&K, &V)]) -> Result<T, E> + Sync,
+{
+    shard_by(map.par_iter(), |(k, _)| *k)
+        .into_par_iter()
+        .map(|chunk| encode(&chunk))
+        .collect()
+}
+
+/// Decodes a map from chunks in parallel.
+///
+/// Every chunk is decoded into a map by `decode` on its own thread, and the
+/// maps are merged into an [`FxShardedMap`] like [`merge_all`] does, so
+/// loading a large snapshot scales with the number of threads instead of being
+/// limited by a single deserializer. Chunks written by [`par_encode_chunks`] hold
+/// disjoint keys; for other chunks, values from later chunks win over earlier
+/// ones. Decoding stops at the first error.
+///
+/// ```
+/// use rustc_hash::{par_decode_fx, par_encode_chunks, FxHashMap};
+///
+/// let config = bincode::config::standard();
+/// let map: FxHashMap<u64, String> = (0..10_000).map(|i| (i, i.to_string())).collect();
+///
+/// let chunks = par_encode_chunks(&map, |entries| bincode::encode_to_vec(entries, config))?;
+/// let back = par_decode_fx(chunks, |bytes| {
+///     let (entries, _): (Vec<(u64, String)>, _) = bincode::decode_from_slice(&bytes, config)?;
+///     Ok::<_, bincode::error::DecodeError>(entries.into_iter().collect())
+/// })?;
+/// assert_eq!(back.into_inner(), map);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn par_decode_fx<C, K, V, E, F>(chunks: C, decode: F) -> Result<FxShardedMap<K, V>, E>
+where
+    C: IntoParallelIterator,
+    K: Eq + Hash + Send,
+    V: Send,
+    E: Send,
+    F: Fn(C::Item) -> Result<FxHashMap<K, V>, E> + Sync,
+{
+    let maps: Vec<FxHashMap<K, V>> = chunks
+        .into_par_iter()
+        .map(&decode)
+        .collect::<Result<_, _>>()?;
+    Ok(merge_all(maps, |old, new| *old = new))
+}
+
+/// Distributes the items of `iter` over shards by the hash of their key,
+/// preserving the relative order of the items within each shard.
+fn shard_by<I, K, F>(iter: I, key: F) -> Vec<Vec<I::Item>>
+where
+    I: ParallelIterator,
+    K: Hash + ?Sized,
+    F: Fn(&I::Item) -> &K + Sync,
+{
+    let bits = (rayon::current_num_threads() * 4)
+        .next_power_of_two()
+        .trailing_zeros();
+    let empty = || (0..1_usize << bits).map(|_| Vec::new()).collect::<Vec<_>>();
+
+    iter.fold(empty, |mut shards, item| {
+        let shard = sketch::spread(FxBuildHasher.hash_one(key(&item))) >> (64 - bits);
+        shards[shard as usize].push(item);
+        shards
+    })
+    .reduce(empty, |mut a, b| {
+        for (a, b) in a.iter_mut().zip(b) {
+            a.extend(b);
+        }
+        a
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rayon::prelude::*;
+
+    use std::vec;
+    use std::vec::Vec;
+
+    use crate::{
+        merge_all, par_decode_fx, par_encode_chunks, par_extend_fx, FxHashMap, ParallelCollectFx,
+    };
+
+    #[test]
+    fn later_items_win() {
+        let map = (0..10_000_u32)
+            .into_par_iter()
+            .map(|i| (i % 100, i))
+            .par_collect_fx();
+
+        assert_eq!(map.len(), 100);
+        for (k, v) in map {
+            assert_eq!(v, 9900 + k);
+        }
+    }
+
+    #[test]
+    fn collect_set_and_extend() {
+        let set = (0..10_000_u32)
+            .into_par_iter()
+            .map(|i| i % 7)
+            .par_collect_fx_set();
+        assert_eq!(set.len(), 7);
+
+        let mut map = FxHashMap::default();
+        map.insert(1_u32, 0_u32);
+        map.insert(u32::MAX, 0);
+        par_extend_fx(&mut map, (0..100_u32).into_par_iter().map(|i| (i, i)));
+        assert_eq!(map.len(), 101);
+        assert_eq!(map[&1], 1);
+        assert_eq!(map[&u32::MAX], 0);
+    }
+
+    #[test]
+    fn merge_all_in_order() {
+        let maps: Vec<FxHashMap<u32, Vec<u32>>> = (0..50)
+            .map(|i| (i * 10..i * 10 + 500).map(|k| (k, vec![i])).collect())
+            .collect();
+        let map = merge_all(maps, |a, b| a.extend(b));
+        assert_eq!(map.len(), 990);
+        // Lookups find every key in the shard it was merged into.
+        for k in 0..990 {
+            let expected: Vec<u32> = (0..50)
+                .filter(|i| (i * 10..i * 10 + 500).contains(&k))
+                .collect();
+            assert_eq!(map.get(&k), Some(expected));
+        }
+        assert!(merge_all(Vec::<FxHashMap<u32, u32>>::new(), |_, _| ()).is_empty());
+    }
+
+    #[test]
+    fn chunked_round_trip() {
+        let map: FxHashMap<u32, u32> = (0..10_000).map(|i| (i, i * 2)).collect();
+        let chunks = par_encode_chunks(&map, |entries| {
+            Ok::<_, ()>(entries.iter().map(|(&k, &v)| (k, v)).collect::<Vec<_>>())
+        })
+        .unwrap();
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks.iter().map(Vec::len).sum::<usize>(), map.len());
+        let back = par_decode_fx(chunks, |chunk| Ok::<_, ()>(chunk.into_iter().collect())).unwrap();
+        assert_eq!(back.into_inner(), map);
+
+        // Later chunks win, and errors are passed through.
+        let chunks = vec![vec![(1, 1)], vec![(1, 2)], vec![(1, 3)]];
+        let back = par_decode_fx(chunks, |c| Ok::<_, ()>(c.into_iter().collect())).unwrap();
+        assert_eq!(back.get(&1), Some(3));
+        let failed = par_decode_fx(vec![0, 1], |i: u32| match i {
+            0 => Ok(FxHashMap::<u32, u32>::default()),
+            _ => Err("bad chunk"),
+        });
+        assert_eq!(failed.err(), Some("bad chunk"));
+    }
+}
@@ -0,0 +1,124 @@
+use alloc::vec::Vec;
+use core::hash::{BuildHasher, Hash};
+
+use crate::{sketch, FxBuildHasher, FxMapState};
+
+/// Returns the shard of `n` a hash belongs to, splitting the hash range into
+/// `n` equal parts.
+pub(crate) fn shard_of(hash: u64, n: usize) -> usize {
+    ((u128::from(sketch::spread(hash)) * n as u128) >> 64) as usize
+}
+
+/// Splitting an Fx-hashed map into disjoint shards by hash.
+///
+/// Every key goes into the shard covering its part of the hash range, so the
+/// same key always lands in the same shard of `n` and shards of different
+/// maps line up. The maps don't store hashes, so every key is hashed once;
+/// `FxPrehashedMap::partition_into` avoids even that.
+///
+/// ```
+/// use rustc_hash::{FxHashMap, FxPartitionExt};
+///
+/// let map: FxHashMap<u32, u32> = (0..1000).map(|i| (i, i)).collect();
+/// let shards = map.clone().partition_into(4);
+/// assert_eq!(shards.len(), 4);
+/// assert_eq!(shards.iter().map(|s| s.len()).sum::<usize>(), 1000);
+/// assert_eq!(map.partition_iter(1, 4).count(), shards[1].len());
+/// ```
+pub trait FxPartitionExt<K, V>: Sized {
+    /// Moves the entries into `n` maps by the hashes of their keys.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is 0.
+    fn partition_into(self, n: usize) -> Vec<Self>;
+
+    /// Returns an iterator over the entries that [`partition_into`] puts
+    /// into shard `shard` of `n`, in unspecified order.
+    ///
+    /// [`partition_into`]: FxPartitionExt::partition_into
+    fn partition_iter<'a>(&'a self, shard: usize, n: usize) -> impl Iterator<Item = (&'a K, &'a V)>
+    where
+        K: 'a,
+        V: 'a;
+}
+
+macro_rules! impl_partition {
+    ($map:ident) => {
+        impl<K: Eq + Hash, V> FxPartitionExt<K, V> for $map<K, V, FxMapState> {
+            fn partition_into(self, n: usize) -> Vec<Self> {
+                assert!(n > 0, "cannot partition a map into 0 shards");
+                let capacity = self.len().div_ceil(n);
+                let mut shards: Vec<Self> = (0..n)
+                    .map(|_| $map::with_capacity_and_hasher(capacity, Default::default()))
+                    .collect();
+                for (k, v) in self {
+                    shards[shard_of(FxBuildHasher.hash_one(&k), n)].insert(k, v);
+                }
+                shards
+            }
+
+            fn partition_iter<'a>(
+                &'a self,
+                shard: usize,
+                n: usize,
+            ) -> impl Iterator<Item = (&'a K, &'a V)>
+            where
+                K: 'a,
+                V: 'a,
+            {
+                self.iter()
+                    .filter(move |(k, _)| shard_of(FxBuildHasher.hash_one(k), n) == shard)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "std")]
+mod std_impls {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    impl_partition!(HashMap);
+}
+
+#[cfg(feature = "hashbrown")]
+mod hashbrown_impls {
+    use hashbrown::HashMap;
+
+    use super::*;
+
+    impl_partition!(HashMap);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FxPartitionExt, FxTable};
+
+    #[test]
+    fn shards_are_disjoint() {
+        let map: FxTable<u32, u32> = (0..10_000).map(|i| (i, i * 2)).collect();
+        let shards = map.clone().partition_into(7);
+        assert_eq!(shards.len(), 7);
+        for (i, shard) in shards.iter().enumerate() {
+            // The hash range split keeps the shards roughly balanced.
+            assert!(shard.len() > 1000);
+            assert!(map
+                .partition_iter(i, 7)
+                .all(|(k, v)| shard.get(k) == Some(v)));
+            assert_eq!(map.partition_iter(i, 7).count(), shard.len());
+        }
+        assert_eq!(map.clone().partition_into(1)[0], map);
+    }
+
+    #[test]
+    fn narrow_hashes_are_spread() {
+        // The hashes of 32-bit targets must not all land in the first shard.
+        let mut counts = [0; 4];
+        for hash in 0..1000_u64 {
+            counts[super::shard_of(hash << 16, 4)] += 1;
+        }
+        assert!(counts.iter().all(|&c| c > 200), "{counts:?}");
+    }
+}
@@ -0,0 +1,250 @@
+use core::fmt;
+use core::hash::{Hash, Hasher};
+
+use crate::FxHasher;
+
+/// The byte order marker of little-endian platforms.
+const LITTLE_ENDIAN: u8 = 0;
+
+/// The byte order marker of big-endian platforms.
+const BIG_ENDIAN: u8 = 1;
+
+/// The byte order marker of this platform.
+const NATIVE_ENDIAN: u8 = if cfg!(target_endian = "big") {
+    BIG_ENDIAN
+} else {
+    LITTLE_ENDIAN
+};
+
+/// An error returned when a [`PersistedHash`] cannot be decoded or was not
+/// produced by the algorithm and platform reading it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PersistError {
+    /// The bytes are not [`PersistedHash::ENCODED_LEN`] bytes long.
+    BadLength(usize),
+    /// The byte order marker or the reserved byte is invalid.
+    Corrupt,
+    /// The hash was produced by another version of the hashing algorithm.
+    AlgorithmMismatch {
+        /// The algorithm ID of the persisted hash.
+        found: u8,
+        /// The algorithm ID of this version of the crate.
+        expected: u8,
+    },
+    /// The hash was produced on a platform with a different pointer width,
+    /// which hashes differently.
+    WidthMismatch {
+        /// The pointer width in bytes of the platform that produced the hash.
+        found: u8,
+        /// The pointer width in bytes of this platform.
+        expected: u8,
+    },
+    /// The hash was produced on a platform with a different byte order, which
+    /// can hash differently.
+    ByteOrderMismatch,
+}
+
+impl fmt::Display for PersistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PersistError::BadLength(len) => {
+                write!(f, "persisted hash has {len} bytes instead of 12")
+            }
+            PersistError::Corrupt => f.write_str("persisted hash is corrupt"),
+            PersistError::AlgorithmMismatch { found, expected } => write!(
+                f,
+                "persisted hash was produced by algorithm {found}, not {expected}"
+            ),
+            PersistError::WidthMismatch { found, expected } => write!(
+                f,
+                "persisted hash was produced with {found}-byte pointers, not {expected}"
+            ),
+            PersistError::ByteOrderMismatch => {
+                f.write_str("persisted hash was produced with another byte order")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PersistError {}
+
+/// A raw Fx hash value tagged with the algorithm and platform that produced
+/// it, for storing hashes in files.
+///
+/// Fx hashes are not stable: the algorithm changed between versions 1 and 2 of
+/// this crate, and the same value hashes differently with a different pointer
+/// width. A stored hash is only meaningful to a reader that hashes the same
+/// way, so the tag lets [`verify_compatible`](Self::verify_compatible) detect
+/// hashes that would silently mismatch.
+///
+/// ```
+/// use rustc_hash::PersistedHash;
+///
+/// let bytes = PersistedHash::of("key").to_bytes();
+///
+/// let persisted = PersistedHash::from_bytes(&bytes).unwrap();
+/// assert_eq!(persisted.verify_compatible(), Ok(PersistedHash::of("key").hash()));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PersistedHash {
+    algorithm: u8,
+    width: u8,
+    endian: u8,
+    hash: u64,
+}
+
+impl PersistedHash {
+    /// The ID of the hashing algorithm of this version of the crate.
+    ///
+    /// It is 1 for the algorithm of versions 1.x, which was inherited from
+    /// Firefox, and 2 for the algorithm introduced in version 2.0.
+    pub const ALGORITHM: u8 = 2;
+
+    /// The size of an encoded `PersistedHash` in bytes.
+    pub const ENCODED_LEN: usize = 12;
+
+    /// Tags `hash`, which must have been produced by this version of the
+    /// crate on this platform, e.g. with [`FxHasher`].
+    pub const fn new(hash: u64) -> PersistedHash {
+        PersistedHash {
+            algorithm: Self::ALGORITHM,
+            width: core::mem::size_of::<usize>() as u8,
+            endian: NATIVE_ENDIAN,
+            hash,
+        }
+    }
+
+    /// Hashes `value` with an unseeded [`FxHasher`] and tags the hash.
+    pub fn of<T: Hash + ?Sized>(value: &T) -> PersistedHash {
+        let mut hasher = FxHasher::default();
+        value.hash(&mut hasher);
+        PersistedHash::new(hasher.finish())
+    }
+
+    /// Returns the hash value, whether or not it is compatible.
+    pub const fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Returns the ID of the algorithm that produced the hash.
+    pub const fn algorithm(&self) -> u8 {
+        self.algorithm
+    }
+
+    /// Returns the pointer width in bytes of the platform that produced the
+    /// hash.
+    pub const fn width(&self) -> u8 {
+        self.width
+    }
+
+    /// Returns the hash value if it was produced by the algorithm of this
+    /// version of the crate on a platform that hashes like this one.
+    pub fn verify_compatible(&self) -> Result<u64, PersistError> {
+        let expected = PersistedHash::new(self.hash);
+        if self.algorithm != expected.algorithm {
+            return Err(PersistError::AlgorithmMismatch {
+                found: self.algorithm,
+                expected: expected.algorithm,
+            });
+        }
+        if self.width != expected.width {
+            return Err(PersistError::WidthMismatch {
+                found: self.width,
+                expected: expected.width,
+            });
+        }
+        if self.endian != expected.endian {
+            return Err(PersistError::ByteOrderMismatch);
+        }
+        Ok(self.hash)
+    }
+
+    /// Encodes the tagged hash.
+    ///
+    /// | offset | size | content                                        |
+    /// |--------|------|------------------------------------------------|
+    /// | 0      | 1    | algorithm ID                                   |
+    /// | 1      | 1    | pointer width in bytes                         |
+    /// | 2      | 1    | byte order, 0 for little- and 1 for big-endian |
+    /// | 3      | 1    | reserved, zero                                 |
+    /// | 4      | 8    | hash, little-endian                            |
+    pub fn to_bytes(&self) -> [u8; Self::ENCODED_LEN] {
+        let mut out = [0; Self::ENCODED_LEN];
+        out[0] = self.algorithm;
+        out[1] = self.width;
+        out[2] = self.endian;
+        out[4..].copy_from_slice(&self.hash.to_le_bytes());
+        out
+    }
+
+    /// Decodes a tagged hash encoded with [`to_bytes`](Self::to_bytes).
+    ///
+    /// This only checks that the encoding is valid. Use
+    /// [`verify_compatible`](Self::verify_compatible) to check that the hash
+    /// can be compared with hashes produced now.
+    pub fn from_bytes(bytes: &[u8]) -> Result<PersistedHash, PersistError> {
+        let bytes: &[u8; Self::ENCODED_LEN] = bytes
+            .try_into()
+            .map_err(|_| PersistError::BadLength(bytes.len()))?;
+        if !matches!(bytes[2], LITTLE_ENDIAN | BIG_ENDIAN) || bytes[3] != 0 {
+            return Err(PersistError::Corrupt);
+        }
+        let mut hash = [0; 8];
+        hash.copy_from_slice(&bytes[4..]);
+        Ok(PersistedHash {
+            algorithm: bytes[0],
+            width: bytes[1],
+            endian: bytes[2],
+            hash: u64::from_le_bytes(hash),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{PersistError, PersistedHash};
+
+    #[test]
+    fn round_trip_and_mismatches() {
+        let persisted = PersistedHash::of(&42_u64);
+        let bytes = persisted.to_bytes();
+        assert_eq!(PersistedHash::from_bytes(&bytes), Ok(persisted));
+        assert_eq!(persisted.verify_compatible(), Ok(persisted.hash()));
+
+        assert_eq!(
+            PersistedHash::from_bytes(&bytes[..11]),
+            Err(PersistError::BadLength(11))
+        );
+        let mut bad = bytes;
+        bad[3] = 1;
+        assert_eq!(PersistedHash::from_bytes(&bad), Err(PersistError::Corrupt));
+
+        let mut old = bytes;
+        old[0] = 1;
+        assert_eq!(
+            PersistedHash::from_bytes(&old).unwrap().verify_compatible(),
+            Err(PersistError::AlgorithmMismatch {
+                found: 1,
+                expected: 2
+            })
+        );
+        let mut other = bytes;
+        other[1] ^= 12;
+        assert!(matches!(
+            PersistedHash::from_bytes(&other)
+                .unwrap()
+                .verify_compatible(),
+            Err(PersistError::WidthMismatch { .. })
+        ));
+        let mut other = bytes;
+        other[2] ^= 1;
+        assert_eq!(
+            PersistedHash::from_bytes(&other)
+                .unwrap()
+                .verify_compatible(),
+            Err(PersistError::ByteOrderMismatch)
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use crate::{FxBuildHasher, FxSeededState};
+
+/// Type alias for an [`im::HashMap`] that uses the Fx hashing algorithm.
+///
+/// ```
+/// use rustc_hash::FxImHashMap;
+///
+/// let a: FxImHashMap<u32, u32> = FxImHashMap::default();
+/// let b = a.update(22, 44);
+/// assert!(a.is_empty());
+/// assert_eq!(b[&22], 44);
+/// ```
+#[cfg(feature = "im")]
+pub type FxImHashMap<K, V> = im::HashMap<K, V, FxBuildHasher>;
+
+/// Type alias for an [`im::HashSet`] that uses the Fx hashing algorithm.
+#[cfg(feature = "im")]
+pub type FxImHashSet<T> = im::HashSet<T, FxBuildHasher>;
+
+/// Type alias for an [`im::HashMap`] using the `fx` hash algorithm with [`FxSeededState`].
+///
+/// The hasher is reference counted, so all versions of a map, as well as maps
+/// created from it with `new_from`, share the same seed.
+#[cfg(feature = "im")]
+pub type FxImHashMapSeed<K, V> = im::HashMap<K, V, FxSeededState>;
+
+/// Type alias for an [`im::HashSet`] using the `fx` hash algorithm with [`FxSeededState`].
+#[cfg(feature = "im")]
+pub type FxImHashSetSeed<T> = im::HashSet<T, FxSeededState>;
+
+/// Type alias for an [`im_rc::HashMap`] that uses the Fx hashing algorithm.
+#[cfg(feature = "im-rc")]
+pub type FxImRcHashMap<K, V> = im_rc::HashMap<K, V, FxBuildHasher>;
+
+/// Type alias for an [`im_rc::HashSet`] that uses the Fx hashing algorithm.
+#[cfg(feature = "im-rc")]
+pub type FxImRcHashSet<T> = im_rc::HashSet<T, FxBuildHasher>;
+
+/// Type alias for an [`im_rc::HashMap`] using the `fx` hash algorithm with [`FxSeededState`].
+#[cfg(feature = "im-rc")]
+pub type FxImRcHashMapSeed<K, V> = im_rc::HashMap<K, V, FxSeededState>;
+
+/// Type alias for an [`im_rc::HashSet`] using the `fx` hash algorithm with [`FxSeededState`].
+#[cfg(feature = "im-rc")]
+pub type FxImRcHashSetSeed<T> = im_rc::HashSet<T, FxSeededState>;
+
+#[cfg(test)]
+mod tests {
+    use crate::FxSeededState;
+
+    #[cfg(feature = "im")]
+    #[test]
+    fn im_versions_share_seed() {
+        use crate::FxImHashMapSeed;
+
+        let a: FxImHashMapSeed<u32, u32> =
+            FxImHashMapSeed::with_hasher(FxSeededState::with_seed(3));
+        let b = a.update(1, 2);
+        let c: FxImHashMapSeed<u32, u32> = b.new_from();
+
+        assert_eq!(b.hasher().seed(), 3);
+        assert_eq!(c.hasher().seed(), 3);
+        assert!(a.is_empty());
+        assert_eq!(b[&1], 2);
+    }
+
+    #[cfg(feature = "im-rc")]
+    #[test]
+    fn im_rc_versions_share_seed() {
+        use crate::{FxImRcHashMapSeed, FxImRcHashSet};
+
+        let a: FxImRcHashMapSeed<u32, u32> =
+            FxImRcHashMapSeed::with_hasher(FxSeededState::with_seed(3));
+        let b = a.update(1, 2);
+        assert_eq!(b.hasher().seed(), 3);
+
+        let set: FxImRcHashSet<u32> = [1_u32, 2, 2].into_iter().collect();
+        assert_eq!(set.len(), 2);
+    }
+}
@@ -0,0 +1,373 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+
+use crate::{sketch, FxSeededState};
+
+/// The bucket and the two slot hashes derived from the hash of a key.
+pub(crate) struct Split {
+    pub(crate) bucket: u32,
+    f1: u32,
+    f2: u32,
+}
+
+impl Split {
+    pub(crate) fn new(hash: u64) -> Split {
+        // Spread like `sketch::spread` twice, with different multipliers,
+        // before taking the high halves.
+        let a = sketch::spread(hash);
+        let b = hash.wrapping_mul(0xc2b2ae3d27d4eb4f);
+        Split {
+            bucket: (a >> 32) as u32,
+            f1: (b >> 32) as u32,
+            f2: a as u32 ^ b as u32,
+        }
+    }
+
+    /// Returns what determines the slot of the hash in an index with `buckets`
+    /// buckets: hashes with equal keys share their slot for every displacement.
+    #[cfg(any(feature = "std", feature = "hashbrown"))]
+    pub(crate) fn key(&self, buckets: usize) -> (usize, u32, u32) {
+        (self.bucket as usize % buckets, self.f1, self.f2)
+    }
+
+    pub(crate) fn slot(&self, (d1, d2): (u32, u32), len: u32) -> u32 {
+        self.f1
+            .wrapping_mul(d1)
+            .wrapping_add(self.f2)
+            .wrapping_add(d2)
+            % len
+    }
+}
+
+/// Returns the slot of a key in an index whose displacements are read by
+/// `displacement`, so indexes that are not stored in a `DisplacementIndex`
+/// can be used as well.
+pub(crate) fn slot_with<Q: Hash + ?Sized>(
+    seed: usize,
+    len: u32,
+    buckets: usize,
+    key: &Q,
+    displacement: impl FnOnce(usize) -> (u32, u32),
+) -> Option<usize> {
+    if len == 0 || buckets == 0 {
+        return None;
+    }
+    let split = Split::new(FxSeededState::with_seed(seed).hash_one(key));
+    let bucket = split.bucket as usize % buckets;
+    Some(split.slot(displacement(bucket), len) as usize)
+}
+
+/// A static hash map with a perfect hash function built on the Fx hasher.
+///
+/// The map is generated at build time by `FxPhfBuilder`, typically in a
+/// build script, and stored in `static` or `const` items. Every key maps to
+/// its own slot, so a lookup is a single hash, an index access and one key
+/// comparison, without probing. It does not need `alloc`.
+///
+/// Keys whose hashes collide for every seed are stored after the others, and
+/// looking up a key that misses its slot scans them. This is only the case for
+/// multi-word keys with adversarial contents.
+///
+/// The Fx hash depends on the pointer width, so the generated code only
+/// compiles for targets with the pointer width of the machine that ran the
+/// builder.
+///
+/// ```ignore
+/// static KEYWORDS: rustc_hash::FxPhfMap<&str, Keyword> =
+///     include!(concat!(env!("OUT_DIR"), "/keywords.rs"));
+///
+/// assert_eq!(KEYWORDS.get("let"), Some(&Keyword::Let));
+/// ```
+pub struct FxPhfMap<K: 'static, V: 'static> {
+    seed: usize,
+    displacements: &'static [(u32, u32)],
+    overflow: usize,
+    entries: &'static [(K, V)],
+}
+
+impl<K, V> FxPhfMap<K, V> {
+    /// Creates a map from the parts generated by `FxPhfBuilder`.
+    ///
+    /// This is only meant to be called by generated code: the map returns
+    /// wrong results if the parts do not come from the builder.
+    #[doc(hidden)]
+    pub const fn from_raw_parts(
+        seed: usize,
+        displacements: &'static [(u32, u32)],
+        overflow: usize,
+        entries: &'static [(K, V)],
+    ) -> FxPhfMap<K, V> {
+        FxPhfMap {
+            seed,
+            displacements,
+            overflow,
+            entries,
+        }
+    }
+
+    /// Returns the number of entries in the map.
+    pub const fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub const fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the entries, in unspecified order.
+    pub const fn entries(&self) -> &'static [(K, V)] {
+        self.entries
+    }
+
+    /// Returns an iterator over the keys and values, in unspecified order.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (&'static K, &'static V)> {
+        self.entries.iter().map(|(k, v)| (k, v))
+    }
+}
+
+impl<K: Eq + Hash, V> FxPhfMap<K, V> {
+    /// Returns the stored key and the value of `key`.
+    pub fn get_entry<Q>(&self, key: &Q) -> Option<(&'static K, &'static V)>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let indexed = self.entries.len() - self.overflow;
+        let slot = slot_with(
+            self.seed,
+            indexed as u32,
+            self.displacements.len(),
+            key,
+            |b| self.displacements[b],
+        );
+        if let Some((k, v)) = slot.map(|slot| &self.entries[slot]) {
+            if k.borrow() == key {
+                return Some((k, v));
+            }
+        }
+        self.entries[indexed..]
+            .iter()
+            .find(|(k, _)| k.borrow() == key)
+            .map(|(k, v)| (k, v))
+    }
+
+    /// Returns a reference to the value of `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<&'static V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.get_entry(key).map(|(_, v)| v)
+    }
+
+    /// Returns `true` if the map contains `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.get_entry(key).is_some()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for FxPhfMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Generates the source code of an [`FxPhfMap`], e.g. in a build script.
+///
+/// Keys are written with their `Debug` representation, which is valid Rust
+/// for string slices, byte strings, characters, integers and booleans. Values
+/// are given as Rust expressions. The output is a single expression of type
+/// `FxPhfMap<K, V>` that can be used as the initializer of a `static` or
+/// `const` item.
+///
+/// ```
+/// use rustc_hash::FxPhfBuilder;
+///
+/// let mut builder = FxPhfBuilder::new();
+/// builder.entry("fn", "Keyword::Fn").entry("let", "Keyword::Let");
+///
+/// let code = builder.build().unwrap().to_string();
+/// assert!(code.contains("(\"let\", Keyword::Let)"));
+/// # // std::fs::write(Path::new(&env::var("OUT_DIR")?).join("keywords.rs"), code)?;
+/// ```
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub struct FxPhfBuilder<K> {
+    entries: alloc::vec::Vec<(K, alloc::string::String)>,
+}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+impl<K: Eq + Hash + fmt::Debug> FxPhfBuilder<K> {
+    /// Creates an empty `FxPhfBuilder`.
+    pub fn new() -> FxPhfBuilder<K> {
+        FxPhfBuilder {
+            entries: alloc::vec::Vec::new(),
+        }
+    }
+
+    /// Adds `key` with a value given as the Rust expression `value`.
+    pub fn entry(&mut self, key: K, value: &str) -> &mut Self {
+        self.entries.push((key, value.into()));
+        self
+    }
+
+    /// Finds the perfect hash function and returns a value that displays the
+    /// source code of the map.
+    ///
+    /// Returns an error if no perfect hash function is found within the search
+    /// limits, see [`FrozenBuildError`](crate::FrozenBuildError).
+    ///
+    /// # Panics
+    ///
+    /// Panics if a key was added more than once.
+    pub fn build(&self) -> Result<DisplayFxPhfMap<'_, K>, crate::FrozenBuildError> {
+        let mut unique = crate::FxTable::default();
+        for (k, _) in &self.entries {
+            assert!(unique.insert(k, ()).is_none(), "duplicate key {k:?}");
+        }
+
+        let keys: alloc::vec::Vec<&K> = self.entries.iter().map(|(k, _)| k).collect();
+        let (index, slots) = crate::frozen_map::DisplacementIndex::build(&keys)?;
+        Ok(DisplayFxPhfMap {
+            builder: self,
+            index,
+            slots,
+        })
+    }
+}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+impl<K: Eq + Hash + fmt::Debug> Default for FxPhfBuilder<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The source code of an [`FxPhfMap`], returned by [`FxPhfBuilder::build`].
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub struct DisplayFxPhfMap<'a, K> {
+    builder: &'a FxPhfBuilder<K>,
+    index: crate::frozen_map::DisplacementIndex,
+    slots: alloc::vec::Vec<usize>,
+}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+impl<K: fmt::Debug> fmt::Display for DisplayFxPhfMap<'_, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bits = usize::BITS;
+        writeln!(f, "{{")?;
+        writeln!(
+            f,
+            "    const _: () = ::core::assert!(usize::BITS == {bits}, \"this map was generated for {bits}-bit targets\");"
+        )?;
+        writeln!(f, "    ::rustc_hash::FxPhfMap::from_raw_parts(")?;
+        writeln!(f, "        {},", self.index.seed)?;
+        writeln!(f, "        &[")?;
+        for (d1, d2) in self.index.displacements.iter() {
+            writeln!(f, "            ({d1}, {d2}),")?;
+        }
+        writeln!(f, "        ],")?;
+        writeln!(f, "        {},", self.slots.len() - self.index.len as usize)?;
+        writeln!(f, "        &[")?;
+        for &i in &self.slots {
+            let (k, v) = &self.builder.entries[i];
+            writeln!(f, "            ({k:?}, {v}),")?;
+        }
+        writeln!(f, "        ],")?;
+        writeln!(f, "    )")?;
+        write!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FxPhfMap;
+
+    // Generated by `FxPhfBuilder` on a 64-bit target for the keys and values below.
+    #[cfg(target_pointer_width = "64")]
+    static GENERATED: FxPhfMap<&str, u32> = FxPhfMap::from_raw_parts(
+        0,
+        &[(4, 1), (0, 0)],
+        0,
+        &[
+            ("match", 2),
+            ("mut", 3),
+            ("fn", 0),
+            ("let", 1),
+            ("struct", 4),
+        ],
+    );
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn generated_table() {
+        assert_eq!(GENERATED.len(), 5);
+        for (i, key) in ["fn", "let", "match", "mut", "struct"].iter().enumerate() {
+            assert_eq!(GENERATED.get(*key), Some(&(i as u32)));
+        }
+        assert!(!GENERATED.contains_key("enum"));
+    }
+
+    #[test]
+    #[cfg(any(feature = "std", feature = "hashbrown"))]
+    fn builds_a_valid_table() {
+        use alloc::string::ToString;
+        use alloc::vec::Vec;
+
+        let keys: Vec<u32> = (0..500).map(|i| i * 31).collect();
+        let mut builder = crate::FxPhfBuilder::new();
+        for k in &keys {
+            builder.entry(*k, "()");
+        }
+        let built = builder.build().unwrap();
+
+        // Every key must land in its own slot.
+        let mut seen = alloc::vec![false; keys.len()];
+        for k in &keys {
+            let slot = super::slot_with(
+                built.index.seed,
+                keys.len() as u32,
+                built.index.displacements.len(),
+                k,
+                |b| built.index.displacements[b],
+            )
+            .unwrap();
+            assert!(!seen[slot]);
+            seen[slot] = true;
+            assert_eq!(builder.entries[built.slots[slot]].0, *k);
+        }
+        assert!(built.to_string().starts_with('{'));
+    }
+
+    #[test]
+    #[cfg(any(feature = "std", feature = "hashbrown"))]
+    fn colliding_hashes() {
+        use alloc::boxed::Box;
+        use alloc::vec::Vec;
+
+        // The Fx hashes of these keys are equal for every seed.
+        let keys = [(0, crate::K), (1, 0), (2, 3)];
+        let mut builder = crate::FxPhfBuilder::new();
+        for k in &keys {
+            builder.entry(*k, "()");
+        }
+        let built = builder.build().unwrap();
+        assert_eq!(built.index.len, 2);
+
+        let entries: Vec<((usize, usize), ())> =
+            built.slots.iter().map(|&i| (keys[i], ())).collect();
+        let map = FxPhfMap::from_raw_parts(
+            built.index.seed,
+            Box::leak(built.index.displacements.clone()),
+            1,
+            Box::leak(entries.into_boxed_slice()),
+        );
+        assert!(keys.iter().all(|k| map.contains_key(k)));
+        assert!(!map.contains_key(&(0, 0)));
+    }
+}
@@ -0,0 +1,482 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+
+use hashbrown::HashTable;
+
+use crate::partition::shard_of;
+use crate::FxBuildHasher;
+
+/// The number of bits of the bucket index [`FxPrehashedMap::from_iter_bulk`]
+/// sorts by.
+const BULK_GROUP_BITS: u32 = 12;
+
+/// A hash map whose operations accept a precomputed hash, so a key probed
+/// against several maps is hashed only once.
+///
+/// The `*_with_hash` methods expect the hash of the key as computed by
+/// `FxBuildHasher.hash_one(key)`, which is also what the methods without a
+/// hash argument use. The hash of every entry is stored next to it, so growing
+/// the map never hashes keys again. Passing a different hash for the same key
+/// does not cause undefined behavior, but lookups may fail to find the entry.
+///
+/// ```
+/// use core::hash::BuildHasher;
+/// use rustc_hash::{FxBuildHasher, FxPrehashedMap};
+///
+/// let mut ids = FxPrehashedMap::new();
+/// let mut names = FxPrehashedMap::new();
+///
+/// let hash = FxBuildHasher.hash_one("fx");
+/// ids.insert_with_hash(hash, "fx", 1);
+/// names.insert_with_hash(hash, "fx", "Fx hasher");
+///
+/// assert_eq!(ids.get_with_hash(hash, "fx"), Some(&1));
+/// assert_eq!(names.get("fx"), Some(&"Fx hasher"));
+/// ```
+#[derive(Clone)]
+pub struct FxPrehashedMap<K, V> {
+    table: HashTable<(u64, K, V)>,
+}
+
+impl<K, V> FxPrehashedMap<K, V> {
+    /// Creates an empty `FxPrehashedMap`.
+    pub fn new() -> FxPrehashedMap<K, V> {
+        FxPrehashedMap {
+            table: HashTable::new(),
+        }
+    }
+
+    /// Creates an empty `FxPrehashedMap` with room for at least `capacity` entries.
+    pub fn with_capacity(capacity: usize) -> FxPrehashedMap<K, V> {
+        FxPrehashedMap {
+            table: HashTable::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    /// Removes all entries.
+    pub fn clear(&mut self) {
+        self.table.clear();
+    }
+
+    /// Returns an iterator over the keys and values, in unspecified order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.table.iter().map(|(_, k, v)| (k, v))
+    }
+
+    /// Returns an iterator over the keys and mutable values, in unspecified order.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut V)> {
+        self.table.iter_mut().map(|(_, k, v)| (&*k, v))
+    }
+
+    /// Returns an iterator over the stored hashes, keys and values, in unspecified order.
+    pub fn iter_with_hash(&self) -> impl Iterator<Item = (u64, &K, &V)> {
+        self.table.iter().map(|(h, k, v)| (*h, k, v))
+    }
+}
+
+impl<K: Eq + Hash, V> FxPrehashedMap<K, V> {
+    /// Builds a map from key-value pairs, inserting them in the order of the
+    /// buckets they land in.
+    ///
+    /// Inserting keys in iteration order writes to random buckets, which for
+    /// maps much larger than the cache misses the cache on almost every
+    /// insertion. This constructor first hashes all keys, sizes the table for
+    /// all pairs and groups the pairs by bucket, so the insertions sweep over
+    /// the table once. It needs memory for two copies of all pairs while
+    /// building.
+    /// Like `collect`, later pairs win over earlier pairs with an equal key.
+    ///
+    /// ```
+    /// use rustc_hash::FxPrehashedMap;
+    ///
+    /// let map = FxPrehashedMap::from_iter_bulk((0..10_000_u64).map(|i| (i, i * i)));
+    /// assert_eq!(map.get(&100), Some(&10_000));
+    /// ```
+    pub fn from_iter_bulk<I: IntoIterator<Item = (K, V)>>(iter: I) -> FxPrehashedMap<K, V> {
+        let pairs: Vec<(u64, K, V)> = iter
+            .into_iter()
+            .map(|(k, v)| (FxBuildHasher.hash_one(&k), k, v))
+            .collect();
+        let mut map = Self::with_capacity(pairs.len());
+
+        // The table takes the bucket from the low bits of the hash. Grouping
+        // the pairs by the top bits of their bucket is enough to make the
+        // insertions sweep over the table, and a counting sort into these
+        // groups is much faster than sorting by bucket. It is stable, so equal
+        // keys stay in iteration order.
+        let buckets = map.table.num_buckets().max(1);
+        let shift = buckets.trailing_zeros().saturating_sub(BULK_GROUP_BITS);
+        let group_of = |hash: u64| (hash as usize & (buckets - 1)) >> shift;
+        let mut sizes = vec![0; (buckets >> shift).max(1)];
+        for &(hash, _, _) in &pairs {
+            sizes[group_of(hash)] += 1;
+        }
+        let mut groups: Vec<Vec<(u64, K, V)>> = sizes.into_iter().map(Vec::with_capacity).collect();
+        for pair in pairs {
+            groups[group_of(pair.0)].push(pair);
+        }
+
+        for (hash, k, v) in groups.into_iter().flatten() {
+            map.insert_with_hash(hash, k, v);
+        }
+        map
+    }
+
+    /// Moves all entries of `other` into `self`, replacing the values of keys
+    /// present in both maps.
+    ///
+    /// Unlike `extend`, this reuses the hashes stored in `other` instead of
+    /// hashing its keys again.
+    ///
+    /// ```
+    /// use rustc_hash::FxPrehashedMap;
+    ///
+    /// let mut a: FxPrehashedMap<&str, u32> = [("x", 1), ("y", 2)].into_iter().collect();
+    /// let b: FxPrehashedMap<&str, u32> = [("y", 20), ("z", 30)].into_iter().collect();
+    /// a.merge_from(b);
+    /// assert_eq!(a.len(), 3);
+    /// assert_eq!(a.get("y"), Some(&20));
+    /// ```
+    pub fn merge_from(&mut self, other: FxPrehashedMap<K, V>) {
+        self.table.reserve(other.len(), |&(h, _, _)| h);
+        for (hash, k, v) in other.table {
+            self.insert_with_hash(hash, k, v);
+        }
+    }
+
+    /// Moves the entries into `n` maps by their stored hashes, without
+    /// hashing any key.
+    ///
+    /// The shards match those of `FxPartitionExt::partition_into`, so a key
+    /// lands in the same shard of `n` in both.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is 0.
+    ///
+    /// ```
+    /// use rustc_hash::FxPrehashedMap;
+    ///
+    /// let map: FxPrehashedMap<u32, u32> = (0..1000).map(|i| (i, i)).collect();
+    /// let shards = map.partition_into(4);
+    /// assert_eq!(shards.iter().map(|s| s.len()).sum::<usize>(), 1000);
+    /// ```
+    pub fn partition_into(self, n: usize) -> Vec<FxPrehashedMap<K, V>> {
+        assert!(n > 0, "cannot partition a map into 0 shards");
+        let capacity = self.len().div_ceil(n);
+        let mut shards: Vec<Self> = (0..n).map(|_| Self::with_capacity(capacity)).collect();
+        for entry in self.table {
+            // The keys are distinct, so no shard needs to look them up.
+            shards[shard_of(entry.0, n)]
+                .table
+                .insert_unique(entry.0, entry, |&(h, _, _)| h);
+        }
+        shards
+    }
+
+    /// Returns an iterator over the entries that
+    /// [`partition_into`](Self::partition_into) puts into shard `shard` of
+    /// `n`, in unspecified order.
+    pub fn partition_iter(&self, shard: usize, n: usize) -> impl Iterator<Item = (&K, &V)> {
+        self.table
+            .iter()
+            .filter(move |(h, _, _)| shard_of(*h, n) == shard)
+            .map(|(_, k, v)| (k, v))
+    }
+
+    /// Returns a reference to the value of `key`, given its hash.
+    pub fn get_with_hash<Q>(&self, hash: u64, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        let (_, _, v) = self.table.find(hash, |(_, k, _)| k.borrow() == key)?;
+        Some(v)
+    }
+
+    /// Returns a mutable reference to the value of `key`, given its hash.
+    pub fn get_mut_with_hash<Q>(&mut self, hash: u64, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        let (_, _, v) = self.table.find_mut(hash, |(_, k, _)| k.borrow() == key)?;
+        Some(v)
+    }
+
+    /// Returns `true` if the map contains `key`, given its hash.
+    pub fn contains_key_with_hash<Q>(&self, hash: u64, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        self.get_with_hash(hash, key).is_some()
+    }
+
+    /// Inserts a key-value pair given the hash of the key, returning the
+    /// previous value of the key.
+    pub fn insert_with_hash(&mut self, hash: u64, key: K, value: V) -> Option<V> {
+        if let Some(v) = self.get_mut_with_hash(hash, &key) {
+            return Some(core::mem::replace(v, value));
+        }
+        self.table
+            .insert_unique(hash, (hash, key, value), |&(h, _, _)| h);
+        None
+    }
+
+    /// Removes `key` given its hash, returning its value.
+    pub fn remove_with_hash<Q>(&mut self, hash: u64, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        let entry = self
+            .table
+            .find_entry(hash, |(_, k, _)| k.borrow() == key)
+            .ok()?;
+        let ((_, _, v), _) = entry.remove();
+        Some(v)
+    }
+
+    /// Returns a reference to the value of `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.get_with_hash(FxBuildHasher.hash_one(key), key)
+    }
+
+    /// Looks up a batch of keys, returning their values in the order of `keys`.
+    ///
+    /// All keys are hashed first and the home buckets of the hashes are
+    /// prefetched before any key is probed, so the cache misses of the batch
+    /// overlap instead of being paid one after another as with
+    /// [`get`](Self::get).
+    ///
+    /// ```
+    /// use rustc_hash::FxPrehashedMap;
+    ///
+    /// let map: FxPrehashedMap<u32, u32> = (0..100).map(|i| (i, i * 2)).collect();
+    /// assert_eq!(map.get_many(&[&3, &300, &7]), [Some(&6), None, Some(&14)]);
+    /// ```
+    pub fn get_many<Q>(&self, keys: &[&Q]) -> Vec<Option<&V>>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        if self.table.is_empty() {
+            return vec![None; keys.len()];
+        }
+
+        let hashes: Vec<u64> = keys.iter().map(|key| FxBuildHasher.hash_one(key)).collect();
+        // The table takes the home bucket from the low bits of the hash.
+        let mask = self.table.num_buckets() - 1;
+        for &hash in &hashes {
+            // Reading the control byte of the bucket loads its group, and the
+            // entry of an occupied bucket shares its cache line with the
+            // entries probed next.
+            if let Some(entry) = self.table.get_bucket(hash as usize & mask) {
+                prefetch(entry);
+            }
+        }
+
+        keys.iter()
+            .zip(hashes)
+            .map(|(key, hash)| self.get_with_hash(hash, key))
+            .collect()
+    }
+
+    /// Returns a mutable reference to the value of `key`.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.get_mut_with_hash(FxBuildHasher.hash_one(key), key)
+    }
+
+    /// Returns `true` if the map contains `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Inserts a key-value pair, returning the previous value of the key.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.insert_with_hash(FxBuildHasher.hash_one(&key), key, value)
+    }
+
+    /// Removes `key`, returning its value.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.remove_with_hash(FxBuildHasher.hash_one(key), key)
+    }
+}
+
+impl<K, V> Default for FxPrehashedMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for FxPrehashedMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Eq + Hash, V> Extend<(K, V)> for FxPrehashedMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<K: Eq + Hash, V> FromIterator<(K, V)> for FxPrehashedMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+/// Hints the CPU to load the cache line holding `entry`.
+#[inline]
+fn prefetch<T>(entry: &T) {
+    #[cfg(target_arch = "x86_64")]
+    // SAFETY: Prefetching never faults and does not change memory, and `sse`
+    // is part of the x86_64 baseline.
+    unsafe {
+        use core::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+        _mm_prefetch::<_MM_HINT_T0>((entry as *const T).cast());
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    let _ = entry;
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use core::hash::{BuildHasher, Hash, Hasher};
+
+    use crate::{FxBuildHasher, FxPrehashedMap};
+
+    /// A key that counts how often it is hashed.
+    #[derive(PartialEq, Eq)]
+    struct Counted<'a>(u32, &'a core::cell::Cell<u32>);
+
+    impl Hash for Counted<'_> {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.1.set(self.1.get() + 1);
+            self.0.hash(state);
+        }
+    }
+
+    #[test]
+    fn hashes_once() {
+        let hashed = core::cell::Cell::new(0);
+        let mut map = FxPrehashedMap::new();
+        for i in 0..1000 {
+            let key = Counted(i, &hashed);
+            let hash = FxBuildHasher.hash_one(&key);
+            assert_eq!(map.insert_with_hash(hash, key, i), None);
+        }
+        assert_eq!(hashed.get(), 1000);
+        assert_eq!(map.len(), 1000);
+
+        let key = Counted(7, &hashed);
+        let hash = FxBuildHasher.hash_one(&key);
+        assert_eq!(map.insert_with_hash(hash, Counted(7, &hashed), 70), Some(7));
+        assert_eq!(map.get_with_hash(hash, &key), Some(&70));
+        assert_eq!(map.remove_with_hash(hash, &key), Some(70));
+        assert!(!map.contains_key_with_hash(hash, &key));
+        assert_eq!(hashed.get(), 1001);
+    }
+
+    #[test]
+    fn get_many() {
+        let map: FxPrehashedMap<u32, u32> = (0..1000).map(|i| (i, i + 1)).collect();
+        let keys: Vec<u32> = (0..100).map(|i| i * 17).collect();
+        let refs: Vec<&u32> = keys.iter().collect();
+        let values = map.get_many(&refs);
+        assert_eq!(values.len(), 100);
+        assert!(keys.iter().zip(values).all(|(k, v)| v == map.get(k)));
+        assert!(map.get_many::<u32>(&[]).is_empty());
+        assert_eq!(
+            FxPrehashedMap::<u32, u32>::new().get_many(&[&1, &2]),
+            [None, None]
+        );
+    }
+
+    #[test]
+    fn from_iter_bulk() {
+        let pairs = (0..5000_u32).map(|i| (i % 3000, i));
+        let bulk = FxPrehashedMap::from_iter_bulk(pairs.clone());
+        let collected: FxPrehashedMap<u32, u32> = pairs.collect();
+        assert_eq!(bulk.len(), 3000);
+        assert!(collected.iter().all(|(k, v)| bulk.get(k) == Some(v)));
+        assert!(FxPrehashedMap::<u32, u32>::from_iter_bulk([]).is_empty());
+    }
+
+    #[test]
+    fn merge_from_reuses_hashes() {
+        let hashed = core::cell::Cell::new(0);
+        let mut a: FxPrehashedMap<_, _> = (0..100).map(|i| (Counted(i, &hashed), i)).collect();
+        let b: FxPrehashedMap<_, _> = (50..200).map(|i| (Counted(i, &hashed), i * 10)).collect();
+        hashed.set(0);
+        a.merge_from(b);
+        assert_eq!(hashed.get(), 0);
+        assert_eq!(a.len(), 200);
+        assert_eq!(a.get(&Counted(60, &hashed)), Some(&600));
+        assert_eq!(a.get(&Counted(10, &hashed)), Some(&10));
+    }
+
+    #[test]
+    fn partition_reuses_hashes() {
+        let hashed = core::cell::Cell::new(0);
+        let map: FxPrehashedMap<_, _> = (0..1000).map(|i| (Counted(i, &hashed), i)).collect();
+        hashed.set(0);
+        let counts: Vec<usize> = (0..5).map(|i| map.partition_iter(i, 5).count()).collect();
+        let shards = map.partition_into(5);
+        assert_eq!(hashed.get(), 0);
+        assert_eq!(shards.iter().map(|s| s.len()).collect::<Vec<_>>(), counts);
+        assert_eq!(shards[2].get(&Counted(17, &hashed)).is_some(), {
+            let hash = FxBuildHasher.hash_one(Counted(17, &hashed));
+            crate::partition::shard_of(hash, 5) == 2
+        });
+    }
+
+    #[test]
+    fn without_hash() {
+        let mut map: FxPrehashedMap<&str, u32> = [("a", 1), ("b", 2)].into_iter().collect();
+        *map.get_mut("a").unwrap() += 10;
+        assert_eq!(map.get("a"), Some(&11));
+        assert_eq!(map.remove("b"), Some(2));
+        assert!(!map.contains_key("b"));
+        assert_eq!(
+            map.iter_with_hash().next(),
+            Some((FxBuildHasher.hash_one("a"), &"a", &11))
+        );
+    }
+}
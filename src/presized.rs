@@ -0,0 +1,98 @@
+/// The most memory a [`FxPresized`] collection reserves up front, whatever
+/// the length the input claims.
+const MAX_PREALLOC_BYTES: usize = 1 << 30;
+
+/// A wrapper deserializing a hash map or set with room for all of its
+/// elements reserved up front.
+///
+/// Deserializing a hash map usually reserves little or no room up front, so
+/// that a malicious length cannot make it allocate much memory. A large
+/// collection then grows, and rehashes all elements, many times while being
+/// deserialized, which dominates the cost with a hasher as cheap as Fx.
+/// It is much worse for a collection that was serialized from an Fx-hashed
+/// collection: its elements arrive in the order of their hashes, and a table
+/// too small for them ends up with long probe sequences.
+/// This wrapper reserves room for as many elements as the input announces,
+/// up to 1 GiB worth of them, and serializes like the collection it wraps.
+///
+/// It implements the traits of the serialization frameworks enabled by the
+/// `serde`, `speedy`, `musli` and `bincode` features. With serde, formats like JSON don't announce
+/// the number of elements, so this only helps with formats that do, like
+/// bincode.
+///
+/// Only use it for input whose lengths you trust to be reasonable.
+#[cfg_attr(
+    feature = "serde",
+    doc = r#"
+With serde, `deserialize_presized` can be used with
+`#[serde(deserialize_with)]` instead of the wrapper.
+
+```
+use rustc_hash::{FxHashSet, FxPresized};
+
+let config = bincode::config::standard();
+let bytes = bincode::serde::encode_to_vec((0..1000).collect::<Vec<u32>>(), config).unwrap();
+let (FxPresized(set), _): (FxPresized<FxHashSet<u32>>, _) =
+    bincode::serde::decode_from_slice(&bytes, config).unwrap();
+assert_eq!(set.len(), 1000);
+```
+"#
+)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FxPresized<C>(pub C);
+
+impl<C> FxPresized<C> {
+    /// Returns the wrapped collection.
+    pub fn into_inner(self) -> C {
+        self.0
+    }
+}
+
+/// Returns how many elements of type `T` to reserve room for, given the
+/// announced length.
+pub(crate) fn prealloc<T>(hint: Option<usize>) -> usize {
+    let max = MAX_PREALLOC_BYTES / core::mem::size_of::<T>().max(1);
+    hint.unwrap_or(0).min(max)
+}
+
+/// A wrapper (de)serializing a hash map or set together with the seed of its
+/// [`FxSeededState`](crate::FxSeededState).
+///
+/// A hash map serializes only its entries, so deserializing it uses a default
+/// hasher. This wrapper serializes the seed and then the entries, and
+/// deserializes into a collection with the same seed, presized like
+/// [`FxPresized`] does. In formats with named fields, the seed has to come
+/// before the entries.
+///
+/// It implements the traits of the serialization frameworks enabled by the
+/// `serde` and `bincode` features.
+#[cfg_attr(
+    feature = "serde",
+    doc = r#"
+```
+use std::collections::HashMap;
+use rustc_hash::{FxSeededState, FxWithSeed};
+
+let mut map = HashMap::with_hasher(FxSeededState::with_seed(42));
+map.insert(1_u32, 10_u32);
+
+let config = bincode::config::standard();
+let bytes = bincode::serde::encode_to_vec(FxWithSeed(&map), config).unwrap();
+let (FxWithSeed(back), _): (FxWithSeed<HashMap<u32, u32, FxSeededState>>, _) =
+    bincode::serde::decode_from_slice(&bytes, config).unwrap();
+assert_eq!(back.hasher().seed(), 42);
+assert_eq!(back, map);
+```
+"#
+)]
+#[cfg(any(feature = "serde", feature = "bincode"))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FxWithSeed<C>(pub C);
+
+#[cfg(any(feature = "serde", feature = "bincode"))]
+impl<C> FxWithSeed<C> {
+    /// Returns the wrapped collection.
+    pub fn into_inner(self) -> C {
+        self.0
+    }
+}
@@ -0,0 +1,57 @@
+use core::hash::Hash;
+
+use crate::FxBuildHasher;
+
+/// Type alias for a [`priority_queue::PriorityQueue`] that uses the Fx hashing algorithm.
+///
+/// ```
+/// use rustc_hash::FxPriorityQueue;
+///
+/// let mut queue: FxPriorityQueue<&str, u32> = FxPriorityQueue::default();
+/// queue.push("a", 1);
+/// queue.push("b", 2);
+/// queue.change_priority("a", 3);
+/// assert_eq!(queue.pop(), Some(("a", 3)));
+/// ```
+pub type FxPriorityQueue<I, P> = priority_queue::PriorityQueue<I, P, FxBuildHasher>;
+
+/// Type alias for a [`priority_queue::DoublePriorityQueue`] that uses the Fx hashing algorithm.
+pub type FxDoublePriorityQueue<I, P> = priority_queue::DoublePriorityQueue<I, P, FxBuildHasher>;
+
+/// Creates an empty [`FxPriorityQueue`] with room for at least `capacity` items.
+pub fn priority_queue_with_capacity<I: Hash + Eq, P: Ord>(
+    capacity: usize,
+) -> FxPriorityQueue<I, P> {
+    FxPriorityQueue::with_capacity_and_hasher(capacity, FxBuildHasher)
+}
+
+/// Creates an empty [`FxDoublePriorityQueue`] with room for at least `capacity` items.
+pub fn double_priority_queue_with_capacity<I: Hash + Eq, P: Ord>(
+    capacity: usize,
+) -> FxDoublePriorityQueue<I, P> {
+    FxDoublePriorityQueue::with_capacity_and_hasher(capacity, FxBuildHasher)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{double_priority_queue_with_capacity, priority_queue_with_capacity};
+
+    #[test]
+    fn decrease_key() {
+        let mut queue = priority_queue_with_capacity(8);
+        assert!(queue.capacity() >= 8);
+        for i in 0..8_u32 {
+            queue.push(i, core::cmp::Reverse(i * 10 + 10));
+        }
+        queue.change_priority(&7, core::cmp::Reverse(0));
+        assert_eq!(queue.pop(), Some((7, core::cmp::Reverse(0))));
+        assert_eq!(queue.pop(), Some((0, core::cmp::Reverse(10))));
+
+        let mut queue = double_priority_queue_with_capacity(8);
+        queue.push('a', 1_u32);
+        queue.push('b', 2);
+        queue.push('c', 3);
+        assert_eq!(queue.pop_min(), Some(('a', 1)));
+        assert_eq!(queue.pop_max(), Some(('c', 3)));
+    }
+}
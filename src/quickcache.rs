@@ -0,0 +1,94 @@
+use core::hash::Hash;
+
+use quick_cache::sync::DefaultLifecycle;
+use quick_cache::{UnitWeighter, Weighter};
+
+use crate::FxBuildHasher;
+
+/// Type alias for a [`quick_cache::sync::Cache`] that uses the Fx hashing algorithm.
+///
+/// ```
+/// use rustc_hash::quick_cache_with_capacity;
+///
+/// let cache = quick_cache_with_capacity(100);
+/// cache.insert(22, 44);
+/// assert_eq!(cache.get(&22), Some(44));
+/// ```
+pub type FxQuickCache<K, V> = quick_cache::sync::Cache<K, V, UnitWeighter, FxBuildHasher>;
+
+/// Type alias for a weighted [`quick_cache::sync::Cache`] that uses the Fx hashing algorithm.
+pub type FxQuickCacheWeighted<K, V, We> = quick_cache::sync::Cache<K, V, We, FxBuildHasher>;
+
+/// Type alias for a [`quick_cache::unsync::Cache`] that uses the Fx hashing algorithm.
+pub type FxQuickUnsyncCache<K, V> = quick_cache::unsync::Cache<K, V, UnitWeighter, FxBuildHasher>;
+
+/// Creates an [`FxQuickCache`] that holds up to approximately `capacity` items.
+pub fn quick_cache_with_capacity<K: Eq + Hash, V: Clone>(capacity: usize) -> FxQuickCache<K, V> {
+    FxQuickCache::with(
+        capacity,
+        capacity as u64,
+        UnitWeighter,
+        FxBuildHasher,
+        DefaultLifecycle::default(),
+    )
+}
+
+/// Creates an [`FxQuickCacheWeighted`] that holds items up to a total weight of
+/// `weight_capacity`, expecting about `estimated_items_capacity` items.
+pub fn quick_cache_with_weighter<K, V, We>(
+    estimated_items_capacity: usize,
+    weight_capacity: u64,
+    weighter: We,
+) -> FxQuickCacheWeighted<K, V, We>
+where
+    K: Eq + Hash,
+    V: Clone,
+    We: Weighter<K, V> + Clone,
+{
+    FxQuickCacheWeighted::with(
+        estimated_items_capacity,
+        weight_capacity,
+        weighter,
+        FxBuildHasher,
+        DefaultLifecycle::default(),
+    )
+}
+
+/// Creates an [`FxQuickUnsyncCache`] that holds up to approximately `capacity` items.
+pub fn quick_unsync_cache_with_capacity<K: Eq + Hash, V>(
+    capacity: usize,
+) -> FxQuickUnsyncCache<K, V> {
+    FxQuickUnsyncCache::with(
+        capacity,
+        capacity as u64,
+        UnitWeighter,
+        FxBuildHasher,
+        quick_cache::unsync::DefaultLifecycle::default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{quick_cache_with_weighter, quick_unsync_cache_with_capacity};
+
+    #[derive(Clone)]
+    struct LenWeighter;
+
+    impl quick_cache::Weighter<u32, &'static str> for LenWeighter {
+        fn weight(&self, _key: &u32, val: &&'static str) -> u64 {
+            val.len() as u64
+        }
+    }
+
+    #[test]
+    fn constructors() {
+        let cache = quick_cache_with_weighter(4, 100, LenWeighter);
+        cache.insert(1, "uwu");
+        assert_eq!(cache.get(&1), Some("uwu"));
+        assert_eq!(cache.weight(), 3);
+
+        let mut cache = quick_unsync_cache_with_capacity(4);
+        cache.insert(1_u32, 2_u32);
+        assert_eq!(cache.get(&1), Some(&2));
+    }
+}
@@ -13,6 +13,11 @@ pub type FxHashSetRand<V> = HashSet<V, FxRandomState>;
 /// A particular instance `FxRandomState` will create the same instances of
 /// [`Hasher`], but the hashers created by two different `FxRandomState`
 /// instances are unlikely to produce the same result for the same values.
+///
+/// With the `env-seed` feature enabled, the `FX_HASH_SEED` environment variable
+/// can be set to a decimal or `0x`-prefixed hexadecimal number to replace the
+/// random base seed. States are then created deterministically, as long as the
+/// program creates them in the same order.
 #[derive(Clone)]
 pub struct FxRandomState {
     seed: usize,
@@ -21,26 +26,109 @@ pub struct FxRandomState {
 impl FxRandomState {
     /// Constructs a new `FxRandomState` that is initialized with random seed.
     pub fn new() -> FxRandomState {
-        use rand::Rng;
+        use rand_core::{OsRng, TryRngCore};
         use std::{cell::Cell, thread_local};
 
+        // A seed from the environment is shared by all threads, so we count
+        // globally to keep states distinct across threads.
+        #[cfg(feature = "env-seed")]
+        if let Some(base) = env_seed() {
+            use core::sync::atomic::{AtomicUsize, Ordering};
+
+            static COUNTER: AtomicUsize = AtomicUsize::new(0);
+            return Self::derive(base, COUNTER.fetch_add(1, Ordering::Relaxed));
+        }
+
         // This mirrors what `std::collections::hash_map::RandomState` does, as of 2024-01-14.
         //
         // Basically
         // 1. Cache result of the rng in a thread local, so repeatedly
         //    creating maps is cheaper
-        // 2. Change the cached result on every creation, so maps created
+        // 2. Bump a counter on every creation, so maps created
         //    on the same thread don't have the same iteration order
-        thread_local!(static SEED: Cell<usize> = {
-            Cell::new(rand::thread_rng().gen())
+        //
+        // Unlike `RandomState` we don't hand out the incremented value as-is,
+        // since seeds that only differ in their low bits produce closely
+        // related hashers. Instead the counter is hashed with the base seed,
+        // which is a bijection, so seeds on one thread remain distinct.
+        thread_local!(static BASE: (usize, Cell<usize>) = {
+            let seed = OsRng.try_next_u64().expect("failed to read a seed from the OS RNG");
+            (seed as usize, Cell::new(0))
         });
 
-        SEED.with(|seed| {
-            let s = seed.get();
-            seed.set(s.wrapping_add(1));
-            FxRandomState { seed: s }
+        BASE.with(|(base, counter)| {
+            let n = counter.get();
+            counter.set(n.wrapping_add(1));
+            Self::derive(*base, n)
         })
     }
+
+    fn derive(base: usize, n: usize) -> FxRandomState {
+        use core::hash::Hasher;
+
+        let mut hasher = FxHasher::with_seed(base);
+        hasher.write_usize(n);
+        FxRandomState {
+            seed: hasher.finish() as usize,
+        }
+    }
+
+    /// Constructs a new `FxRandomState` with a seed drawn from `rng`.
+    ///
+    /// Drawing seeds from a deterministically seeded RNG makes runs reproducible.
+    pub fn from_rng<R: rand_core::RngCore + ?Sized>(rng: &mut R) -> FxRandomState {
+        FxRandomState {
+            seed: rng.next_u64() as usize,
+        }
+    }
+
+    /// Constructs a new `FxRandomState` with a seed taken from raw entropy bytes.
+    pub const fn from_entropy(entropy: [u8; core::mem::size_of::<usize>()]) -> FxRandomState {
+        FxRandomState {
+            seed: usize::from_le_bytes(entropy),
+        }
+    }
+
+    /// Constructs a `FxRandomState` with a fixed `seed`.
+    ///
+    /// Together with [`FxRandomState::seed`] this allows replaying a run that
+    /// used a randomly seeded state.
+    pub const fn with_seed(seed: usize) -> FxRandomState {
+        FxRandomState { seed }
+    }
+
+    /// Returns the seed used by this `FxRandomState`.
+    pub const fn seed(&self) -> usize {
+        self.seed
+    }
+}
+
+/// Returns the seed set through `FX_HASH_SEED`, reading it only once per process.
+#[cfg(feature = "env-seed")]
+fn env_seed() -> Option<usize> {
+    use std::sync::OnceLock;
+
+    static SEED: OnceLock<Option<usize>> = OnceLock::new();
+
+    *SEED.get_or_init(|| {
+        let value = std::env::var("FX_HASH_SEED").ok()?;
+        match parse_seed(&value) {
+            Some(seed) => Some(seed),
+            None => panic!("invalid FX_HASH_SEED: {value:?}"),
+        }
+    })
+}
+
+#[cfg(feature = "env-seed")]
+fn parse_seed(value: &str) -> Option<usize> {
+    let value = value.trim();
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
 }
 
 impl core::hash::BuildHasher for FxRandomState {
@@ -59,9 +147,10 @@ impl Default for FxRandomState {
 
 #[cfg(test)]
 mod tests {
+    use core::hash::BuildHasher;
     use std::thread;
 
-    use crate::FxHashMapRand;
+    use crate::{FxHashMapRand, FxRandomState};
 
     #[test]
     fn cloned_random_states_are_equal() {
@@ -78,8 +167,8 @@ mod tests {
 
         // That's the whole point of them being random!
         //
-        // N.B.: `FxRandomState` uses a thread-local set to a random value and then incremented,
-        //       which means that this is *guaranteed* to pass :>
+        // N.B.: `FxRandomState` hashes a thread-local counter into a random base seed, which is
+        //       a bijection on the counter, so this is *guaranteed* to pass :>
         assert_ne!(a.hasher().seed, b.hasher().seed);
     }
 
@@ -92,10 +181,56 @@ mod tests {
         // `1 / 2.pow(bit_size_of::<usize>())`. Or 1/1.7e19 for 64 bit platforms or 1/4294967295
         // for 32 bit platforms. I suppose this is acceptable.
         let a = FxHashMapRand::<&str, u32>::default();
-        let b = thread::spawn(|| FxHashMapRand::<&str, u32>::default())
+        let b = thread::spawn(FxHashMapRand::<&str, u32>::default)
             .join()
             .unwrap();
 
         assert_ne!(a.hasher().seed, b.hasher().seed);
     }
+
+    #[test]
+    fn from_rng_is_deterministic() {
+        struct Counter(u64);
+        impl rand_core::RngCore for Counter {
+            fn next_u32(&mut self) -> u32 {
+                self.next_u64() as u32
+            }
+            fn next_u64(&mut self) -> u64 {
+                self.0 += 1;
+                self.0
+            }
+            fn fill_bytes(&mut self, dst: &mut [u8]) {
+                rand_core::impls::fill_bytes_via_next(self, dst)
+            }
+        }
+
+        let a = FxRandomState::from_rng(&mut Counter(0));
+        let b = FxRandomState::from_rng(&mut Counter(0));
+        assert_eq!(a.seed(), b.seed());
+        assert_eq!(a.seed(), 1);
+
+        let c = FxRandomState::from_entropy(1_usize.to_le_bytes());
+        assert_eq!(c.seed(), 1);
+    }
+
+    #[test]
+    fn with_seed_reproduces_random_state() {
+        let a = FxRandomState::new();
+        let b = FxRandomState::with_seed(a.seed());
+
+        assert_eq!(a.seed(), b.seed());
+        assert_eq!(a.hash_one("uwu"), b.hash_one("uwu"));
+    }
+
+    #[cfg(feature = "env-seed")]
+    #[test]
+    fn parse_env_seed() {
+        use super::parse_seed;
+
+        assert_eq!(parse_seed("42"), Some(42));
+        assert_eq!(parse_seed(" 0x2a\n"), Some(42));
+        assert_eq!(parse_seed("0X2A"), Some(42));
+        assert_eq!(parse_seed("uwu"), None);
+        assert_eq!(parse_seed(""), None);
+    }
 }
@@ -0,0 +1,363 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+
+use crate::{sketch, FxBuildHasher};
+
+/// The number of slots in a group, which share a probe position.
+const GROUP: usize = 8;
+
+/// The tag of a slot that was never used.
+const EMPTY: u8 = 0;
+
+/// The tag of a slot whose entry was removed.
+const DELETED: u8 = 1;
+
+/// A group of slots with their tags stored next to each other, so probing a
+/// group usually touches a single cache line.
+#[derive(Clone)]
+struct Group<K, V> {
+    tags: [u8; GROUP],
+    slots: [Option<(K, V)>; GROUP],
+}
+
+impl<K, V> Group<K, V> {
+    fn new() -> Group<K, V> {
+        Group {
+            tags: [EMPTY; GROUP],
+            slots: core::array::from_fn(|_| None),
+        }
+    }
+}
+
+/// The group and tag derived from the hash of a key.
+#[derive(Clone, Copy)]
+struct Probe {
+    group: usize,
+    tag: u8,
+}
+
+/// An open-addressing hash map designed around the Fx hash.
+///
+/// The Fx hash is fast but its output has weak bits, especially for integer
+/// keys. Instead of taking the bucket from the low bits and a tag from the top
+/// bits of the hash like SwissTable, `FxRawMap` multiplies the hash by a large
+/// odd constant and takes both the group and a one-byte tag from the top of the
+/// product, which depend on all bits of the hash. Slots are arranged in groups
+/// of eight with their tags in front; a lookup compares the tags of a group and
+/// only then the keys, and continues with groups chosen by quadratic probing
+/// until a group with a never-used slot is found.
+///
+/// ```
+/// use rustc_hash::FxRawMap;
+///
+/// let mut map = FxRawMap::new();
+/// for i in 0..1000_u64 {
+///     map.insert(i << 32, i);
+/// }
+/// assert_eq!(map.get(&(7 << 32)), Some(&7));
+/// assert_eq!(map.remove(&(7 << 32)), Some(7));
+/// assert_eq!(map.len(), 999);
+/// ```
+#[derive(Clone)]
+pub struct FxRawMap<K, V> {
+    groups: Box<[Group<K, V>]>,
+    len: usize,
+    deleted: usize,
+    /// The number of bits of the product used for the group index.
+    bits: u32,
+}
+
+impl<K, V> FxRawMap<K, V> {
+    /// Creates an empty `FxRawMap`.
+    pub fn new() -> FxRawMap<K, V> {
+        FxRawMap {
+            groups: Box::new([]),
+            len: 0,
+            deleted: 0,
+            bits: 0,
+        }
+    }
+
+    /// Creates an empty `FxRawMap` with room for at least `capacity` entries.
+    pub fn with_capacity(capacity: usize) -> FxRawMap<K, V> {
+        let mut map = Self::new();
+        if capacity > 0 {
+            map.allocate(capacity);
+        }
+        map
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of entries the map can hold without growing.
+    pub fn capacity(&self) -> usize {
+        Self::max_load(self.groups.len())
+    }
+
+    /// Removes all entries, keeping the allocated memory.
+    pub fn clear(&mut self) {
+        for group in self.groups.iter_mut() {
+            *group = Group::new();
+        }
+        self.len = 0;
+        self.deleted = 0;
+    }
+
+    /// Returns an iterator over the keys and values, in unspecified order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.groups
+            .iter()
+            .flat_map(|g| g.slots.iter().flatten())
+            .map(|(k, v)| (k, v))
+    }
+
+    /// Returns an iterator over the keys and mutable values, in unspecified order.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut V)> {
+        self.groups
+            .iter_mut()
+            .flat_map(|g| g.slots.iter_mut().flatten())
+            .map(|(k, v)| (&*k, v))
+    }
+
+    /// Returns an iterator over the keys, in unspecified order.
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    /// Returns an iterator over the values, in unspecified order.
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, v)| v)
+    }
+
+    /// Returns the number of entries that fit into `groups` groups, keeping
+    /// an eighth of the slots free so that probe sequences stay short.
+    fn max_load(groups: usize) -> usize {
+        groups * GROUP / 8 * 7
+    }
+
+    /// Replaces the groups with enough empty groups for `capacity` entries.
+    fn allocate(&mut self, capacity: usize) {
+        let groups = capacity.div_ceil(7).max(1).next_power_of_two();
+        self.groups = (0..groups).map(|_| Group::new()).collect();
+        self.bits = groups.trailing_zeros();
+        self.len = 0;
+        self.deleted = 0;
+    }
+
+    fn probe<Q: Hash + ?Sized>(&self, key: &Q) -> Probe {
+        let product = sketch::spread(FxBuildHasher.hash_one(key));
+        let group = product.checked_shr(64 - self.bits).unwrap_or(0) as usize;
+        let tag = ((product << self.bits) >> 56) as u8;
+        Probe {
+            group,
+            tag: tag.max(DELETED + 1),
+        }
+    }
+
+    /// Returns the groups visited for `probe` in order. Triangular steps visit
+    /// every group exactly once, since the number of groups is a power of two.
+    fn groups_for(&self, probe: Probe) -> impl Iterator<Item = usize> {
+        let mask = self.groups.len().wrapping_sub(1);
+        (0..self.groups.len()).map(move |i| (probe.group + i * (i + 1) / 2) & mask)
+    }
+}
+
+impl<K: Eq + Hash, V> FxRawMap<K, V> {
+    /// Returns the group and slot of `key`.
+    fn find<Q>(&self, key: &Q) -> Option<(usize, usize)>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        if self.len == 0 {
+            return None;
+        }
+        let probe = self.probe(key);
+        for g in self.groups_for(probe) {
+            let group = &self.groups[g];
+            for (i, &tag) in group.tags.iter().enumerate() {
+                if tag == probe.tag {
+                    if let Some((k, _)) = &group.slots[i] {
+                        if k.borrow() == key {
+                            return Some((g, i));
+                        }
+                    }
+                }
+            }
+            if group.tags.contains(&EMPTY) {
+                return None;
+            }
+        }
+        None
+    }
+
+    /// Returns a reference to the value of `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let (g, i) = self.find(key)?;
+        self.groups[g].slots[i].as_ref().map(|(_, v)| v)
+    }
+
+    /// Returns a mutable reference to the value of `key`.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let (g, i) = self.find(key)?;
+        self.groups[g].slots[i].as_mut().map(|(_, v)| v)
+    }
+
+    /// Returns `true` if the map contains `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    /// Inserts a key-value pair, returning the previous value of the key.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(v) = self.get_mut(&key) {
+            return Some(core::mem::replace(v, value));
+        }
+
+        if self.len + self.deleted >= self.capacity() {
+            self.resize();
+        }
+        let probe = self.probe(&key);
+        for g in self.groups_for(probe) {
+            let group = &mut self.groups[g];
+            if let Some(i) = group.tags.iter().position(|&t| t == EMPTY || t == DELETED) {
+                if group.tags[i] == DELETED {
+                    self.deleted -= 1;
+                }
+                group.tags[i] = probe.tag;
+                group.slots[i] = Some((key, value));
+                self.len += 1;
+                return None;
+            }
+        }
+        unreachable!("a resized map always has a free slot")
+    }
+
+    /// Removes `key`, returning its value.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let (g, i) = self.find(key)?;
+        let group = &mut self.groups[g];
+        // Lookups stop at a group with an empty slot, so no probe sequence
+        // continues past this group and the slot can become empty again.
+        group.tags[i] = if group.tags.contains(&EMPTY) {
+            EMPTY
+        } else {
+            self.deleted += 1;
+            DELETED
+        };
+        self.len -= 1;
+        group.slots[i].take().map(|(_, v)| v)
+    }
+
+    /// Rehashes all entries into a table with room for twice as many entries,
+    /// or into a table of the same size if mostly removed entries fill it.
+    fn resize(&mut self) {
+        let capacity = if self.len >= self.capacity() / 2 {
+            (self.len * 2).max(GROUP)
+        } else {
+            self.capacity()
+        };
+        let old = core::mem::replace(self, Self::with_capacity(capacity));
+        for group in Vec::from(old.groups) {
+            for (k, v) in group.slots.into_iter().flatten() {
+                self.insert(k, v);
+            }
+        }
+    }
+}
+
+impl<K, V> Default for FxRawMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for FxRawMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Eq + Hash, V> Extend<(K, V)> for FxRawMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<K: Eq + Hash, V> FromIterator<(K, V)> for FxRawMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FxRawMap, FxTable};
+
+    #[test]
+    fn matches_hash_map() {
+        let mut map = FxRawMap::new();
+        let mut reference = FxTable::default();
+        let mut state = 1_u64;
+        for _ in 0..100_000 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+            let key = (state >> 40) as u32 % 5000;
+            if state >> 63 == 0 {
+                assert_eq!(map.insert(key, state), reference.insert(key, state));
+            } else {
+                assert_eq!(map.remove(&key), reference.remove(&key));
+            }
+            assert_eq!(map.len(), reference.len());
+        }
+        assert!(reference.iter().all(|(k, v)| map.get(k) == Some(v)));
+        assert_eq!(map.iter().count(), reference.len());
+    }
+
+    #[test]
+    fn capacity_and_clear() {
+        let mut map = FxRawMap::with_capacity(100);
+        let capacity = map.capacity();
+        assert!(capacity >= 100);
+        map.extend((0..capacity as u32).map(|i| (i, i)));
+        assert_eq!(map.capacity(), capacity);
+
+        *map.get_mut(&3).unwrap() = 30;
+        assert_eq!(map.get(&3), Some(&30));
+
+        map.clear();
+        assert!(map.is_empty());
+        assert!(!map.contains_key(&3));
+        assert_eq!(map.capacity(), capacity);
+    }
+}
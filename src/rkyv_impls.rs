@@ -0,0 +1,151 @@
+use rkyv::collections::swiss_table::{ArchivedHashMap, ArchivedHashSet};
+use rkyv::rancor::Fallible;
+use rkyv::{Archive, Archived, Deserialize, Place, Serialize};
+
+use crate::{FxBuildHasher, FxSeededState};
+
+// `FxHashMap`, `FxHashSet` and their `hashbrown` counterparts are archived by
+// the impls rkyv provides for all hash maps and sets, which this feature
+// enables. Archived maps use rkyv's own hasher, so the hasher state of a map
+// is not part of its archive. rkyv can only deserialize maps whose hasher
+// implements `Default`, so a map with an `FxSeededState` has to be rebuilt
+// from its archived entries and a separately archived state.
+
+/// An archived `FxHashMap<K, V>`, which can be queried in place.
+///
+/// The archive is a SwissTable whose layout rkyv pins, hashed with its
+/// `FxHasher64`: a port of the Fx hashing algorithm that hashes the same on
+/// every platform. [`get`](ArchivedHashMap::get) and the other lookups hash the
+/// key with it again and probe the archived buckets directly, so a map can be
+/// used without being deserialized.
+///
+/// ```
+/// use rkyv::rancor::Error;
+/// use rustc_hash::{ArchivedFxHashMap, FxHashMap};
+///
+/// let map: FxHashMap<String, u32> = [("a".to_string(), 1), ("b".to_string(), 2)]
+///     .into_iter()
+///     .collect();
+/// let bytes = rkyv::to_bytes::<Error>(&map).unwrap();
+///
+/// let archived = rkyv::access::<ArchivedFxHashMap<String, u32>, Error>(&bytes).unwrap();
+/// assert_eq!(archived.get("b").map(|v| v.to_native()), Some(2));
+/// assert!(archived.get("c").is_none());
+/// ```
+pub type ArchivedFxHashMap<K, V> = ArchivedHashMap<Archived<K>, Archived<V>>;
+
+/// An archived `FxHashSet<T>`, which can be queried in place like an
+/// [`ArchivedFxHashMap`].
+pub type ArchivedFxHashSet<T> = ArchivedHashSet<Archived<T>>;
+
+impl Archive for FxBuildHasher {
+    type Archived = ();
+    type Resolver = ();
+
+    fn resolve(&self, _: (), _: Place<()>) {}
+}
+
+impl<S: Fallible + ?Sized> Serialize<S> for FxBuildHasher {
+    fn serialize(&self, _: &mut S) -> Result<(), S::Error> {
+        Ok(())
+    }
+}
+
+impl<D: Fallible + ?Sized> Deserialize<FxBuildHasher, D> for () {
+    fn deserialize(&self, _: &mut D) -> Result<FxBuildHasher, D::Error> {
+        Ok(FxBuildHasher)
+    }
+}
+
+/// An archived [`FxSeededState`].
+///
+/// The seed is archived as a `u64` so that archives are portable between
+/// platforms. A seed that does not fit the `usize` of a 32-bit platform is
+/// truncated when deserialized there.
+#[derive(rkyv::Portable, rkyv::bytecheck::CheckBytes)]
+#[rkyv(crate = rkyv)]
+#[bytecheck(crate = rkyv::bytecheck)]
+#[repr(transparent)]
+pub struct ArchivedFxSeededState {
+    seed: Archived<u64>,
+}
+
+impl ArchivedFxSeededState {
+    /// Returns the archived seed.
+    pub fn seed(&self) -> u64 {
+        self.seed.to_native()
+    }
+}
+
+impl Archive for FxSeededState {
+    type Archived = ArchivedFxSeededState;
+    type Resolver = ();
+
+    fn resolve(&self, _: (), out: Place<ArchivedFxSeededState>) {
+        rkyv::munge::munge!(let ArchivedFxSeededState { seed } = out);
+        (self.seed() as u64).resolve((), seed);
+    }
+}
+
+impl<S: Fallible + ?Sized> Serialize<S> for FxSeededState {
+    fn serialize(&self, _: &mut S) -> Result<(), S::Error> {
+        Ok(())
+    }
+}
+
+impl<D: Fallible + ?Sized> Deserialize<FxSeededState, D> for ArchivedFxSeededState {
+    fn deserialize(&self, _: &mut D) -> Result<FxSeededState, D::Error> {
+        Ok(FxSeededState::with_seed(self.seed() as usize))
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use alloc::string::{String, ToString};
+
+    use rkyv::rancor::Error;
+
+    use crate::{
+        ArchivedFxHashMap, ArchivedFxHashSet, ArchivedFxSeededState, FxHashMap, FxHashSet,
+        FxSeededState,
+    };
+
+    #[test]
+    fn round_trip() {
+        let map: FxHashMap<u32, u64> = (0..1000).map(|i| (i, u64::from(i) * 3)).collect();
+        let bytes = rkyv::to_bytes::<Error>(&map).unwrap();
+        let archived = rkyv::access::<rkyv::Archived<FxHashMap<u32, u64>>, Error>(&bytes).unwrap();
+        assert_eq!(archived.len(), 1000);
+        let back: FxHashMap<u32, u64> = rkyv::deserialize::<_, Error>(archived).unwrap();
+        assert_eq!(back, map);
+
+        let set: FxHashSet<u8> = (0..=255).collect();
+        let bytes = rkyv::to_bytes::<Error>(&set).unwrap();
+        let back: FxHashSet<u8> = rkyv::from_bytes::<_, Error>(&bytes).unwrap();
+        assert_eq!(back, set);
+
+        let state = FxSeededState::with_seed(0xdead_beef);
+        let bytes = rkyv::to_bytes::<Error>(&state).unwrap();
+        let archived = rkyv::access::<ArchivedFxSeededState, Error>(&bytes).unwrap();
+        assert_eq!(archived.seed(), 0xdead_beef);
+        let back: FxSeededState = rkyv::from_bytes::<_, Error>(&bytes).unwrap();
+        assert_eq!(back.seed(), 0xdead_beef);
+    }
+
+    #[test]
+    fn probe_in_place() {
+        let map: FxHashMap<String, u32> = (0..1000).map(|i| (i.to_string(), i)).collect();
+        let bytes = rkyv::to_bytes::<Error>(&map).unwrap();
+        let archived = rkyv::access::<ArchivedFxHashMap<String, u32>, Error>(&bytes).unwrap();
+        for (k, v) in &map {
+            assert_eq!(archived.get(k.as_str()).unwrap().to_native(), *v);
+        }
+        assert!(archived.get("1000").is_none());
+
+        let set: FxHashSet<u64> = (0..1000).map(|i| i * 7).collect();
+        let bytes = rkyv::to_bytes::<Error>(&set).unwrap();
+        let archived = rkyv::access::<ArchivedFxHashSet<u64>, Error>(&bytes).unwrap();
+        assert!(archived.contains(&rkyv::rend::u64_le::from_native(21)));
+        assert!(!archived.contains(&rkyv::rend::u64_le::from_native(22)));
+    }
+}
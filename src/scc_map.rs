@@ -0,0 +1,51 @@
+use crate::FxBuildHasher;
+
+/// Type alias for an [`scc::HashMap`] that uses the Fx hashing algorithm.
+///
+/// ```
+/// use rustc_hash::FxSccHashMap;
+///
+/// let map: FxSccHashMap<u32, u32> = FxSccHashMap::default();
+/// assert!(map.insert(22, 44).is_ok());
+/// assert_eq!(map.read(&22, |_, v| *v), Some(44));
+/// ```
+pub type FxSccHashMap<K, V> = scc::HashMap<K, V, FxBuildHasher>;
+
+/// Type alias for an [`scc::HashSet`] that uses the Fx hashing algorithm.
+pub type FxSccHashSet<T> = scc::HashSet<T, FxBuildHasher>;
+
+/// Type alias for an [`scc::HashIndex`] that uses the Fx hashing algorithm.
+pub type FxSccHashIndex<K, V> = scc::HashIndex<K, V, FxBuildHasher>;
+
+/// Creates an empty [`FxSccHashMap`] with room for at least `capacity` elements.
+pub fn scc_hash_map_with_capacity<K, V>(capacity: usize) -> FxSccHashMap<K, V> {
+    FxSccHashMap::with_capacity_and_hasher(capacity, FxBuildHasher)
+}
+
+/// Creates an empty [`FxSccHashIndex`] with room for at least `capacity` elements.
+pub fn scc_hash_index_with_capacity<K, V>(capacity: usize) -> FxSccHashIndex<K, V>
+where
+    K: 'static + Clone + Eq + core::hash::Hash,
+    V: 'static + Clone,
+{
+    FxSccHashIndex::with_capacity_and_hasher(capacity, FxBuildHasher)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{scc_hash_index_with_capacity, scc_hash_map_with_capacity};
+
+    #[test]
+    fn with_capacity() {
+        let map = scc_hash_map_with_capacity(100);
+        assert!(map.capacity() >= 100);
+        assert!(map.insert(1_u32, 2_u32).is_ok());
+        assert_eq!(map.insert(1, 3), Err((1, 3)));
+        assert_eq!(map.read(&1, |_, v| *v), Some(2));
+
+        let index = scc_hash_index_with_capacity(100);
+        assert!(index.capacity() >= 100);
+        assert!(index.insert(1_u32, 2_u32).is_ok());
+        assert_eq!(index.peek_with(&1, |_, v| *v), Some(2));
+    }
+}
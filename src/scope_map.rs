@@ -0,0 +1,191 @@
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::Hash;
+
+use crate::FxTable;
+
+/// A hash map with nested scopes using the Fx hashing algorithm, as used for
+/// symbol tables.
+///
+/// Inserting a key inside a scope shadows its value from outer scopes until
+/// the scope is popped. Lookups see the innermost value and cost a single hash
+/// table lookup regardless of the nesting depth: the table only holds the
+/// visible values, and every insertion inside a scope logs the value it
+/// shadowed. Popping a scope restores these values, which takes time
+/// proportional to the insertions made in the scope and nothing more.
+///
+/// Entries inserted outside of all scopes are never removed by
+/// [`pop_scope`](Self::pop_scope).
+///
+/// ```
+/// use rustc_hash::FxScopeMap;
+///
+/// let mut vars = FxScopeMap::new();
+/// vars.insert("x", 1);
+///
+/// vars.push_scope();
+/// vars.insert("x", 2);
+/// vars.insert("y", 3);
+/// assert_eq!(vars.get("x"), Some(&2));
+/// vars.pop_scope();
+///
+/// assert_eq!(vars.get("x"), Some(&1));
+/// assert_eq!(vars.get("y"), None);
+/// ```
+#[derive(Clone)]
+pub struct FxScopeMap<K, V> {
+    map: FxTable<K, V>,
+    /// Every key inserted inside a scope, with the value it shadowed.
+    shadowed: Vec<(K, Option<V>)>,
+    /// The length of `shadowed` when each open scope was pushed.
+    scopes: Vec<usize>,
+}
+
+impl<K, V> FxScopeMap<K, V> {
+    /// Creates an empty `FxScopeMap` without open scopes.
+    pub fn new() -> FxScopeMap<K, V> {
+        FxScopeMap {
+            map: FxTable::default(),
+            shadowed: Vec::new(),
+            scopes: Vec::new(),
+        }
+    }
+
+    /// Returns the number of visible entries.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if no entry is visible.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns the number of open scopes.
+    pub fn depth(&self) -> usize {
+        self.scopes.len()
+    }
+
+    /// Returns an iterator over the visible keys and values, in unspecified order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.map.iter()
+    }
+
+    /// Opens a new innermost scope.
+    pub fn push_scope(&mut self) {
+        self.scopes.push(self.shadowed.len());
+    }
+}
+
+impl<K: Eq + Hash, V> FxScopeMap<K, V> {
+    /// Closes the innermost scope, removing its entries and making the values
+    /// they shadowed visible again.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no scope is open.
+    pub fn pop_scope(&mut self) {
+        let start = self.scopes.pop().expect("no scope to pop");
+        for (k, v) in self.shadowed.drain(start..).rev() {
+            match v {
+                Some(v) => {
+                    self.map.insert(k, v);
+                }
+                None => {
+                    self.map.remove(&k);
+                }
+            }
+        }
+    }
+
+    /// Returns a reference to the innermost value of `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.map.get(key)
+    }
+
+    /// Returns a mutable reference to the innermost value of `key`.
+    ///
+    /// Changes to the value are lost when the scope it was inserted in is
+    /// popped.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.map.get_mut(key)
+    }
+
+    /// Returns `true` if `key` is visible.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.map.contains_key(key)
+    }
+}
+
+impl<K: Clone + Eq + Hash, V> FxScopeMap<K, V> {
+    /// Inserts a key-value pair into the innermost scope, shadowing the value
+    /// of the key until the scope is popped.
+    ///
+    /// Outside of all scopes, this replaces the value of the key for good.
+    pub fn insert(&mut self, key: K, value: V) {
+        if self.scopes.is_empty() {
+            self.map.insert(key, value);
+            return;
+        }
+        let shadowed = self.map.insert(key.clone(), value);
+        self.shadowed.push((key, shadowed));
+    }
+}
+
+impl<K, V> Default for FxScopeMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for FxScopeMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.map.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FxScopeMap;
+
+    #[test]
+    fn shadowing() {
+        let mut map = FxScopeMap::new();
+        map.insert("a", 0);
+        map.push_scope();
+        map.insert("a", 1);
+        map.insert("b", 1);
+        map.push_scope();
+        map.insert("a", 2);
+        map.insert("a", 3);
+        assert_eq!(map.depth(), 2);
+        assert_eq!(map.get("a"), Some(&3));
+
+        map.pop_scope();
+        assert_eq!(map.get("a"), Some(&1));
+        assert_eq!(map.get("b"), Some(&1));
+        map.pop_scope();
+        assert_eq!(map.get("a"), Some(&0));
+        assert!(!map.contains_key("b"));
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn pop_without_scope() {
+        FxScopeMap::<u32, u32>::new().pop_scope();
+    }
+}
@@ -1,3 +1,5 @@
+use core::hash::Hasher;
+
 use crate::FxHasher;
 
 /// Type alias for a hashmap using the `fx` hash algorithm with [`FxSeededState`].
@@ -28,6 +30,44 @@ impl FxSeededState {
     pub const fn with_seed(seed: usize) -> FxSeededState {
         Self { seed }
     }
+
+    /// Returns the seed used by this `FxSeededState`.
+    pub const fn seed(&self) -> usize {
+        self.seed
+    }
+
+    /// Constructs a new `FxSeededState` whose seed is derived from `domain`.
+    ///
+    /// See [`derive_seed`] for details.
+    pub fn for_domain(domain: &str) -> FxSeededState {
+        Self::for_domain_with_seed(0, domain)
+    }
+
+    /// Constructs a new `FxSeededState` whose seed is derived from `seed` and `domain`.
+    ///
+    /// See [`derive_seed`] for details.
+    pub fn for_domain_with_seed(seed: usize, domain: &str) -> FxSeededState {
+        Self::with_seed(derive_seed(seed, domain))
+    }
+}
+
+/// Derives a new seed from a base `seed` and a `domain` tag.
+///
+/// This is useful to decorrelate the hash functions of several structures that
+/// are indexed by the same keys: giving each one its own domain results in
+/// different seeds, while the same base seed and domain always yield the same
+/// derived seed.
+///
+/// ```
+/// use rustc_hash::derive_seed;
+///
+/// assert_eq!(derive_seed(7, "names"), derive_seed(7, "names"));
+/// assert_ne!(derive_seed(7, "names"), derive_seed(7, "types"));
+/// ```
+pub fn derive_seed(seed: usize, domain: &str) -> usize {
+    let mut hasher = FxHasher::with_seed(seed);
+    hasher.write(domain.as_bytes());
+    hasher.finish() as usize
 }
 
 impl core::hash::BuildHasher for FxSeededState {
@@ -42,7 +82,7 @@ impl core::hash::BuildHasher for FxSeededState {
 mod tests {
     use core::hash::BuildHasher;
 
-    use crate::FxSeededState;
+    use crate::{derive_seed, FxSeededState};
 
     #[test]
     fn cloned_seeded_states_are_equal() {
@@ -73,4 +113,14 @@ mod tests {
 
         assert_ne!(a.build_hasher().hash, b.build_hasher().hash);
     }
+
+    #[test]
+    fn domains_are_different() {
+        let a = FxSeededState::for_domain("a");
+        let b = FxSeededState::for_domain("b");
+
+        assert_ne!(a.seed, b.seed);
+        assert_eq!(a.seed, FxSeededState::for_domain("a").seed);
+        assert_ne!(derive_seed(1, "a"), derive_seed(2, "a"));
+    }
 }
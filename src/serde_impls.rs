@@ -0,0 +1,1538 @@
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use core::marker::PhantomData;
+
+use serde::de::{self, Deserialize, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use crate::presized::prealloc;
+use crate::{FxPresized, FxSeededState, FxWithSeed};
+
+impl<C: Serialize> Serialize for FxPresized<C> {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+/// Deserializes a hash map or set like [`FxPresized`] does, for use with
+/// `#[serde(deserialize_with = "rustc_hash::deserialize_presized")]`.
+pub fn deserialize_presized<'de, D, C>(deserializer: D) -> Result<C, D::Error>
+where
+    D: Deserializer<'de>,
+    FxPresized<C>: Deserialize<'de>,
+{
+    FxPresized::deserialize(deserializer).map(FxPresized::into_inner)
+}
+
+/// A `DeserializeSeed` refilling an existing hash map or set with the
+/// deserialized elements.
+///
+/// The collection is cleared and then filled, reusing its allocation instead
+/// of allocating a new table every time, like [`FxPresized`] reserves room
+/// for all elements up front if it doesn't have enough. If deserializing
+/// fails, the collection holds the elements deserialized until then.
+///
+/// ```
+/// use serde::de::DeserializeSeed;
+/// use rustc_hash::{FxHashMap, FxRefill};
+///
+/// let config = bincode::config::standard();
+/// let mut map = FxHashMap::default();
+/// for round in 0..3_u32 {
+///     let snapshot: FxHashMap<u32, u32> = (0..1000).map(|i| (i, i * round)).collect();
+///     let bytes = bincode::serde::encode_to_vec(&snapshot, config).unwrap();
+///     bincode::serde::seed_decode_from_slice(FxRefill(&mut map), &bytes, config).unwrap();
+///     assert_eq!(map, snapshot);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct FxRefill<'a, C>(pub &'a mut C);
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use loader::FxMapLoader;
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod loader {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    /// A `DeserializeSeed` passing the entries of a serialized map to a sink in
+    /// batches, without building the map.
+    ///
+    /// Deserializing a map materializes all of its entries at once. The loader
+    /// instead collects up to `batch_size` entries in a buffer and calls the sink
+    /// with it whenever it is full and once more at the end, so the caller can
+    /// insert, filter or spill the entries while memory use stays bounded by the
+    /// batch size. The sink is expected to empty the buffer, which is reused for
+    /// the next batch; entries left in it are dropped. The sink can stop loading
+    /// by returning an error, which becomes a deserialization error.
+    ///
+    /// Deserializing with the loader returns the number of entries read.
+    ///
+    /// ```
+    /// use rustc_hash::{FxHashMap, FxMapLoader};
+    ///
+    /// let config = bincode::config::standard();
+    /// let map: FxHashMap<u32, u32> = (0..10_000).map(|i| (i, i)).collect();
+    /// let bytes = bincode::serde::encode_to_vec(&map, config).unwrap();
+    ///
+    /// let mut even = FxHashMap::default();
+    /// let loader = FxMapLoader::new(1024, |batch: &mut Vec<(u32, u32)>| {
+    ///     even.extend(batch.drain(..).filter(|(k, _)| k % 2 == 0));
+    ///     Ok::<_, &str>(())
+    /// });
+    /// let (read, _) = bincode::serde::seed_decode_from_slice(loader, &bytes, config).unwrap();
+    /// assert_eq!(read, 10_000);
+    /// assert_eq!(even.len(), 5_000);
+    /// ```
+    pub struct FxMapLoader<K, V, F> {
+        batch_size: usize,
+        sink: F,
+        marker: PhantomData<fn() -> (K, V)>,
+    }
+
+    impl<K, V, F> FxMapLoader<K, V, F> {
+        /// Creates a loader calling `sink` with batches of up to `batch_size`
+        /// entries.
+        ///
+        /// # Panics
+        ///
+        /// Panics if `batch_size` is 0.
+        pub fn new(batch_size: usize, sink: F) -> FxMapLoader<K, V, F> {
+            assert!(batch_size > 0, "the batch size must not be 0");
+            FxMapLoader {
+                batch_size,
+                sink,
+                marker: PhantomData,
+            }
+        }
+    }
+
+    impl<'de, K, V, F, E> Visitor<'de> for FxMapLoader<K, V, F>
+    where
+        K: Deserialize<'de>,
+        V: Deserialize<'de>,
+        F: FnMut(&mut Vec<(K, V)>) -> Result<(), E>,
+        E: fmt::Display,
+    {
+        type Value = usize;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a map")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(mut self, mut access: A) -> Result<usize, A::Error> {
+            let capacity = prealloc::<(K, V)>(access.size_hint()).min(self.batch_size);
+            let mut batch = Vec::with_capacity(capacity);
+            let mut read = 0;
+            while let Some(entry) = access.next_entry()? {
+                batch.push(entry);
+                read += 1;
+                if batch.len() == self.batch_size {
+                    (self.sink)(&mut batch).map_err(de::Error::custom)?;
+                    batch.clear();
+                }
+            }
+            if !batch.is_empty() {
+                (self.sink)(&mut batch).map_err(de::Error::custom)?;
+            }
+            Ok(read)
+        }
+    }
+
+    impl<'de, K, V, F, E> DeserializeSeed<'de> for FxMapLoader<K, V, F>
+    where
+        K: Deserialize<'de>,
+        V: Deserialize<'de>,
+        F: FnMut(&mut Vec<(K, V)>) -> Result<(), E>,
+        E: fmt::Display,
+    {
+        type Value = usize;
+
+        fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<usize, D::Error> {
+            deserializer.deserialize_map(self)
+        }
+    }
+}
+
+/// A wrapper serializing a hash map or set in key order.
+///
+/// The iteration order of a hash collection depends on the hasher, the
+/// platform and the insertion history, so serializing the same entries can
+/// produce different bytes. `SortedSer` serializes them sorted by key, which
+/// makes the output reproducible, e.g. for content-addressed caches. The
+/// sorted output deserializes like the collection itself.
+///
+/// ```
+/// use rustc_hash::{FxHashMap, SortedSer};
+///
+/// let a: FxHashMap<u32, u32> = (0..100).map(|i| (i, i)).collect();
+/// let b: FxHashMap<u32, u32> = (0..100).rev().map(|i| (i, i)).collect();
+///
+/// let config = bincode::config::standard();
+/// assert_eq!(
+///     bincode::serde::encode_to_vec(SortedSer(&a), config).unwrap(),
+///     bincode::serde::encode_to_vec(SortedSer(&b), config).unwrap(),
+/// );
+/// ```
+#[derive(Debug)]
+pub struct SortedSer<'a, M: ?Sized>(pub &'a M);
+
+impl Serialize for FxSeededState {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        // The seed is stored as a `u64` so that it reads back on any platform.
+        serializer.serialize_newtype_struct("FxSeededState", &(self.seed() as u64))
+    }
+}
+
+impl<'de> Deserialize<'de> for FxSeededState {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct SeedVisitor;
+
+        impl<'de> Visitor<'de> for SeedVisitor {
+            type Value = FxSeededState;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a seed")
+            }
+
+            fn visit_newtype_struct<D: Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> Result<Self::Value, D::Error> {
+                let seed = u64::deserialize(deserializer)?;
+                let seed = usize::try_from(seed).map_err(|_| {
+                    de::Error::invalid_value(
+                        de::Unexpected::Unsigned(seed),
+                        &"a seed fitting usize",
+                    )
+                })?;
+                Ok(FxSeededState::with_seed(seed))
+            }
+        }
+
+        deserializer.deserialize_newtype_struct("FxSeededState", SeedVisitor)
+    }
+}
+
+const WITH_SEED_FIELDS: &[&str] = &["seed", "entries"];
+
+/// Serializes the seed and the entries of a collection.
+fn serialize_with_seed<Ser: Serializer, C: Serialize + ?Sized>(
+    serializer: Ser,
+    state: &FxSeededState,
+    entries: &C,
+) -> Result<Ser::Ok, Ser::Error> {
+    let mut s = serializer.serialize_struct("FxWithSeed", 2)?;
+    s.serialize_field("seed", state)?;
+    s.serialize_field("entries", entries)?;
+    s.end()
+}
+
+/// Serializes the entries of a hash map, which `hashbrown` maps only
+/// implement `Serialize` for with its `serde` feature.
+struct MapEntries<'a, M>(&'a M);
+
+/// Serializes the elements of a hash set.
+struct SetEntries<'a, S>(&'a S);
+
+/// A field of a serialized [`FxWithSeed`].
+enum WithSeedField {
+    Seed,
+    Entries,
+}
+
+impl<'de> Deserialize<'de> for WithSeedField {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FieldVisitor;
+
+        impl Visitor<'_> for FieldVisitor {
+            type Value = WithSeedField;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("`seed` or `entries`")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<WithSeedField, E> {
+                match v {
+                    "seed" => Ok(WithSeedField::Seed),
+                    "entries" => Ok(WithSeedField::Entries),
+                    _ => Err(E::unknown_field(v, WITH_SEED_FIELDS)),
+                }
+            }
+        }
+
+        deserializer.deserialize_identifier(FieldVisitor)
+    }
+}
+
+/// Deserializes an [`FxWithSeed`], passing the seed to `entries` to
+/// deserialize the collection with.
+struct WithSeedVisitor<F>(F);
+
+impl<'de, F, D> Visitor<'de> for WithSeedVisitor<F>
+where
+    F: FnOnce(FxSeededState) -> D,
+    D: DeserializeSeed<'de>,
+{
+    type Value = FxWithSeed<D::Value>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a seed followed by entries")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+        let state = access
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let entries = access
+            .next_element_seed((self.0)(state))?
+            .ok_or_else(|| de::Error::invalid_length(1, &"a seed followed by entries"))?;
+        Ok(FxWithSeed(entries))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+        let state = match access.next_key()? {
+            Some(WithSeedField::Seed) => access.next_value()?,
+            Some(WithSeedField::Entries) => {
+                return Err(de::Error::custom("the seed must come before the entries"))
+            }
+            None => return Err(de::Error::missing_field("seed")),
+        };
+        let entries = match access.next_key()? {
+            Some(WithSeedField::Entries) => access.next_value_seed((self.0)(state))?,
+            Some(WithSeedField::Seed) => return Err(de::Error::duplicate_field("seed")),
+            None => return Err(de::Error::missing_field("entries")),
+        };
+        Ok(FxWithSeed(entries))
+    }
+}
+
+/// Deserializes a hash map with the given hasher.
+struct MapVisitor<C, S> {
+    hasher: S,
+    marker: PhantomData<fn() -> C>,
+}
+
+/// Deserializes a hash set with the given hasher.
+struct SetVisitor<C, S> {
+    hasher: S,
+    marker: PhantomData<fn() -> C>,
+}
+
+impl<C, S> MapVisitor<C, S> {
+    fn new(hasher: S) -> Self {
+        MapVisitor {
+            hasher,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<C, S> SetVisitor<C, S> {
+    fn new(hasher: S) -> Self {
+        SetVisitor {
+            hasher,
+            marker: PhantomData,
+        }
+    }
+}
+
+macro_rules! impl_presized {
+    ($map:ident, $set:ident) => {
+        impl<'de, K, V, S> Visitor<'de> for MapVisitor<$map<K, V, S>, S>
+        where
+            K: Deserialize<'de> + Eq + Hash,
+            V: Deserialize<'de>,
+            S: BuildHasher,
+        {
+            type Value = $map<K, V, S>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a map")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+                let capacity = prealloc::<(K, V)>(access.size_hint());
+                let mut map = $map::with_capacity_and_hasher(capacity, self.hasher);
+                while let Some((k, v)) = access.next_entry()? {
+                    map.insert(k, v);
+                }
+                Ok(map)
+            }
+        }
+
+        impl<'de, K, V, S> Deserialize<'de> for FxPresized<$map<K, V, S>>
+        where
+            K: Deserialize<'de> + Eq + Hash,
+            V: Deserialize<'de>,
+            S: BuildHasher + Default,
+        {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                deserializer
+                    .deserialize_map(MapVisitor::<$map<K, V, S>, S>::new(S::default()))
+                    .map(FxPresized)
+            }
+        }
+
+        impl<'de, T, S> Visitor<'de> for SetVisitor<$set<T, S>, S>
+        where
+            T: Deserialize<'de> + Eq + Hash,
+            S: BuildHasher,
+        {
+            type Value = $set<T, S>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a sequence")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+                let capacity = prealloc::<T>(access.size_hint());
+                let mut set = $set::with_capacity_and_hasher(capacity, self.hasher);
+                while let Some(t) = access.next_element()? {
+                    set.insert(t);
+                }
+                Ok(set)
+            }
+        }
+
+        impl<'de, K, V, S> DeserializeSeed<'de> for MapVisitor<$map<K, V, S>, S>
+        where
+            K: Deserialize<'de> + Eq + Hash,
+            V: Deserialize<'de>,
+            S: BuildHasher,
+        {
+            type Value = $map<K, V, S>;
+
+            fn deserialize<D: Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> Result<Self::Value, D::Error> {
+                deserializer.deserialize_map(self)
+            }
+        }
+
+        impl<'de, T, S> DeserializeSeed<'de> for SetVisitor<$set<T, S>, S>
+        where
+            T: Deserialize<'de> + Eq + Hash,
+            S: BuildHasher,
+        {
+            type Value = $set<T, S>;
+
+            fn deserialize<D: Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> Result<Self::Value, D::Error> {
+                deserializer.deserialize_seq(self)
+            }
+        }
+
+        impl<K: Serialize, V: Serialize, S> Serialize for MapEntries<'_, $map<K, V, S>> {
+            fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+                serializer.collect_map(self.0)
+            }
+        }
+
+        impl<T: Serialize, S> Serialize for SetEntries<'_, $set<T, S>> {
+            fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+                serializer.collect_seq(self.0)
+            }
+        }
+
+        impl<K: Serialize, V: Serialize> Serialize for FxWithSeed<&$map<K, V, FxSeededState>> {
+            fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+                serialize_with_seed(serializer, self.0.hasher(), &MapEntries(self.0))
+            }
+        }
+
+        impl<K: Serialize, V: Serialize> Serialize for FxWithSeed<$map<K, V, FxSeededState>> {
+            fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+                FxWithSeed(&self.0).serialize(serializer)
+            }
+        }
+
+        impl<'de, K, V> Deserialize<'de> for FxWithSeed<$map<K, V, FxSeededState>>
+        where
+            K: Deserialize<'de> + Eq + Hash,
+            V: Deserialize<'de>,
+        {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let visitor = WithSeedVisitor(MapVisitor::<$map<K, V, FxSeededState>, _>::new);
+                deserializer.deserialize_struct("FxWithSeed", WITH_SEED_FIELDS, visitor)
+            }
+        }
+
+        impl<K: Ord + Serialize, V: Serialize, S> Serialize for SortedSer<'_, $map<K, V, S>> {
+            fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+                serializer.collect_map(self.0.to_sorted_vec())
+            }
+        }
+
+        impl<T: Ord + Serialize, S> Serialize for SortedSer<'_, $set<T, S>> {
+            fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+                serializer.collect_seq(self.0.to_sorted_vec())
+            }
+        }
+
+        impl<T: Serialize> Serialize for FxWithSeed<&$set<T, FxSeededState>> {
+            fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+                serialize_with_seed(serializer, self.0.hasher(), &SetEntries(self.0))
+            }
+        }
+
+        impl<T: Serialize> Serialize for FxWithSeed<$set<T, FxSeededState>> {
+            fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+                FxWithSeed(&self.0).serialize(serializer)
+            }
+        }
+
+        impl<'de, T> Deserialize<'de> for FxWithSeed<$set<T, FxSeededState>>
+        where
+            T: Deserialize<'de> + Eq + Hash,
+        {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let visitor = WithSeedVisitor(SetVisitor::<$set<T, FxSeededState>, _>::new);
+                deserializer.deserialize_struct("FxWithSeed", WITH_SEED_FIELDS, visitor)
+            }
+        }
+
+        impl<'de, T, S> Deserialize<'de> for FxPresized<$set<T, S>>
+        where
+            T: Deserialize<'de> + Eq + Hash,
+            S: BuildHasher + Default,
+        {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                deserializer
+                    .deserialize_seq(SetVisitor::<$set<T, S>, S>::new(S::default()))
+                    .map(FxPresized)
+            }
+        }
+
+        impl<'de, K, V, S> Visitor<'de> for FxRefill<'_, $map<K, V, S>>
+        where
+            K: Deserialize<'de> + Eq + Hash,
+            V: Deserialize<'de>,
+            S: BuildHasher,
+        {
+            type Value = ();
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a map")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<(), A::Error> {
+                self.0.clear();
+                self.0.reserve(prealloc::<(K, V)>(access.size_hint()));
+                while let Some((k, v)) = access.next_entry()? {
+                    self.0.insert(k, v);
+                }
+                Ok(())
+            }
+        }
+
+        impl<'de, K, V, S> DeserializeSeed<'de> for FxRefill<'_, $map<K, V, S>>
+        where
+            K: Deserialize<'de> + Eq + Hash,
+            V: Deserialize<'de>,
+            S: BuildHasher,
+        {
+            type Value = ();
+
+            fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+                deserializer.deserialize_map(self)
+            }
+        }
+
+        impl<'de, T, S> Visitor<'de> for FxRefill<'_, $set<T, S>>
+        where
+            T: Deserialize<'de> + Eq + Hash,
+            S: BuildHasher,
+        {
+            type Value = ();
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a sequence")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut access: A) -> Result<(), A::Error> {
+                self.0.clear();
+                self.0.reserve(prealloc::<T>(access.size_hint()));
+                while let Some(t) = access.next_element()? {
+                    self.0.insert(t);
+                }
+                Ok(())
+            }
+        }
+
+        impl<'de, T, S> DeserializeSeed<'de> for FxRefill<'_, $set<T, S>>
+        where
+            T: Deserialize<'de> + Eq + Hash,
+            S: BuildHasher,
+        {
+            type Value = ();
+
+            fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+                deserializer.deserialize_seq(self)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "std")]
+mod std_impls {
+    use std::collections::{HashMap, HashSet};
+
+    use super::*;
+    use crate::FxSortedExt;
+
+    impl_presized!(HashMap, HashSet);
+}
+
+#[cfg(feature = "hashbrown")]
+mod hashbrown_impls {
+    use hashbrown::{HashMap, HashSet};
+
+    use super::*;
+    use crate::FxSortedExt;
+
+    impl_presized!(HashMap, HashSet);
+}
+
+// A `FrozenFxMap` is serialized as its seed, its displacements, the number of
+// keys left out of its index and its keys and values in slot order, so that deserializing it fills the flat arrays
+// directly instead of building a hash map and freezing it. A `FrozenFxSet` is
+// serialized like a `FrozenFxMap` with `()` values.
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod frozen_impls {
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::frozen_map::DisplacementIndex;
+    use crate::{FrozenFxMap, FrozenFxSet};
+
+    const FROZEN_FIELDS: &[&str] = &["seed", "displacements", "overflow", "keys", "values"];
+
+    impl<K: Serialize, V: Serialize> Serialize for FrozenFxMap<K, V> {
+        fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+            let mut s = serializer.serialize_struct("FrozenFxMap", 5)?;
+            s.serialize_field("seed", &(self.index.seed as u64))?;
+            s.serialize_field("displacements", &*self.index.displacements)?;
+            s.serialize_field("overflow", &(self.keys.len() as u32 - self.index.len))?;
+            s.serialize_field("keys", &*self.keys)?;
+            s.serialize_field("values", &*self.values)?;
+            s.end()
+        }
+    }
+
+    impl<'de, K, V> Deserialize<'de> for FrozenFxMap<K, V>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        V: Deserialize<'de>,
+    {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let visitor = FrozenVisitor(PhantomData);
+            deserializer.deserialize_struct("FrozenFxMap", FROZEN_FIELDS, visitor)
+        }
+    }
+
+    impl<T: Serialize> Serialize for FrozenFxSet<T> {
+        fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+            self.map.serialize(serializer)
+        }
+    }
+
+    impl<'de, T: Deserialize<'de> + Eq + Hash> Deserialize<'de> for FrozenFxSet<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let map = FrozenFxMap::deserialize(deserializer)?;
+            Ok(FrozenFxSet { map })
+        }
+    }
+
+    /// A field of a serialized [`FrozenFxMap`].
+    enum FrozenField {
+        Seed,
+        Displacements,
+        Overflow,
+        Keys,
+        Values,
+    }
+
+    impl<'de> Deserialize<'de> for FrozenField {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct FieldVisitor;
+
+            impl Visitor<'_> for FieldVisitor {
+                type Value = FrozenField;
+
+                fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    f.write_str("a frozen map field")
+                }
+
+                fn visit_str<E: de::Error>(self, v: &str) -> Result<FrozenField, E> {
+                    match v {
+                        "seed" => Ok(FrozenField::Seed),
+                        "displacements" => Ok(FrozenField::Displacements),
+                        "overflow" => Ok(FrozenField::Overflow),
+                        "keys" => Ok(FrozenField::Keys),
+                        "values" => Ok(FrozenField::Values),
+                        _ => Err(de::Error::unknown_field(v, FROZEN_FIELDS)),
+                    }
+                }
+            }
+
+            deserializer.deserialize_identifier(FieldVisitor)
+        }
+    }
+
+    struct FrozenVisitor<K, V>(PhantomData<fn() -> (K, V)>);
+
+    impl<'de, K, V> Visitor<'de> for FrozenVisitor<K, V>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        V: Deserialize<'de>,
+    {
+        type Value = FrozenFxMap<K, V>;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a frozen map")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+            let missing = |i| de::Error::invalid_length(i, &"a frozen map with 5 fields");
+            let seed = access.next_element()?.ok_or_else(|| missing(0))?;
+            let displacements = access.next_element()?.ok_or_else(|| missing(1))?;
+            let overflow = access.next_element()?.ok_or_else(|| missing(2))?;
+            let keys = access.next_element()?.ok_or_else(|| missing(3))?;
+            let values = access.next_element()?.ok_or_else(|| missing(4))?;
+            from_parts(seed, displacements, overflow, keys, values)
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+            let mut seed = None;
+            let mut displacements = None;
+            let mut overflow = None;
+            let mut keys = None;
+            let mut values = None;
+            while let Some(field) = access.next_key()? {
+                match field {
+                    FrozenField::Seed => set_once(&mut seed, "seed", &mut access)?,
+                    FrozenField::Displacements => {
+                        set_once(&mut displacements, "displacements", &mut access)?
+                    }
+                    FrozenField::Overflow => set_once(&mut overflow, "overflow", &mut access)?,
+                    FrozenField::Keys => set_once(&mut keys, "keys", &mut access)?,
+                    FrozenField::Values => set_once(&mut values, "values", &mut access)?,
+                }
+            }
+            from_parts(
+                seed.ok_or_else(|| de::Error::missing_field("seed"))?,
+                displacements.ok_or_else(|| de::Error::missing_field("displacements"))?,
+                overflow.ok_or_else(|| de::Error::missing_field("overflow"))?,
+                keys.ok_or_else(|| de::Error::missing_field("keys"))?,
+                values.ok_or_else(|| de::Error::missing_field("values"))?,
+            )
+        }
+    }
+
+    /// Deserializes the value of the field `name` into `slot`, which must be
+    /// empty.
+    fn set_once<'de, T: Deserialize<'de>, A: MapAccess<'de>>(
+        slot: &mut Option<T>,
+        name: &'static str,
+        access: &mut A,
+    ) -> Result<(), A::Error> {
+        if slot.is_some() {
+            return Err(de::Error::duplicate_field(name));
+        }
+        *slot = Some(access.next_value()?);
+        Ok(())
+    }
+
+    /// Assembles a map from its deserialized parts, checking that every indexed
+    /// key is in its slot.
+    ///
+    /// The check hashes every key once, which catches corrupt input as well as
+    /// maps serialized on a platform that hashes differently.
+    fn from_parts<K: Eq + Hash, V, E: de::Error>(
+        seed: u64,
+        displacements: Vec<(u32, u32)>,
+        overflow: u32,
+        keys: Vec<K>,
+        values: Vec<V>,
+    ) -> Result<FrozenFxMap<K, V>, E> {
+        let seed = usize::try_from(seed).map_err(|_| {
+            de::Error::invalid_value(de::Unexpected::Unsigned(seed), &"a seed fitting usize")
+        })?;
+        if values.len() != keys.len() {
+            return Err(de::Error::invalid_length(
+                values.len(),
+                &"one value per key",
+            ));
+        }
+        let len = u32::try_from(keys.len())
+            .map_err(|_| de::Error::invalid_length(keys.len(), &"at most u32::MAX keys"))?;
+        let len = len.checked_sub(overflow).ok_or_else(|| {
+            de::Error::invalid_value(
+                de::Unexpected::Unsigned(overflow.into()),
+                &"at most one overflow key per key",
+            )
+        })?;
+        let index = DisplacementIndex {
+            seed,
+            displacements: displacements.into_boxed_slice(),
+            len,
+        };
+        if !keys[..len as usize]
+            .iter()
+            .enumerate()
+            .all(|(i, k)| index.slot(k) == Some(i))
+        {
+            return Err(de::Error::custom(
+                "the keys do not match the frozen map index",
+            ));
+        }
+        Ok(FrozenFxMap {
+            index,
+            keys: keys.into_boxed_slice(),
+            values: values.into_boxed_slice(),
+        })
+    }
+}
+
+// Sketches are serialized as structs whose first fields are the version of
+// their format and the pointer width they were built with, followed by their
+// parameters and their contents, so that a sketch can only be restored, and
+// merged, with the hash functions it was built with.
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod sketch_impls {
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::counting_bloom::counter_bytes;
+    use crate::cuckoo::{slot_words, BUCKET_SIZE};
+    use crate::hyperloglog::PRECISIONS;
+    use crate::{
+        CounterWidth, FingerprintWidth, FxBinaryFuseFilter, FxBloomFilter, FxCountMinSketch,
+        FxCountingBloomFilter, FxCuckooFilter, FxHyperLogLog, MinHashSignature,
+    };
+
+    /// Implements `Serialize` and `Deserialize` for a sketch with the given
+    /// fields.
+    ///
+    /// Serializing writes every field from its expression. Deserializing reads
+    /// the fields, from either a sequence or a map, and passes them to the
+    /// validating constructor.
+    macro_rules! impl_sketch_serde {
+        (
+            impl[$($gen:tt)*] $ty:ty as $name:literal {
+                $($field:ident: $fty:ty = $value:expr,)*
+            }
+            |$this:ident| => $from_parts:expr;
+        ) => {
+            impl<$($gen)*> Serialize for $ty {
+                fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+                    let $this = self;
+                    let fields = [$(stringify!($field)),*];
+                    let mut s = serializer.serialize_struct($name, fields.len())?;
+                    $(s.serialize_field(stringify!($field), &$value)?;)*
+                    s.end()
+                }
+            }
+
+            impl<'de, $($gen)*> Deserialize<'de> for $ty {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    const FIELDS: &[&str] = &[$(stringify!($field)),*];
+
+                    struct PartsVisitor;
+
+                    impl<'de> Visitor<'de> for PartsVisitor {
+                        type Value = ($($fty,)*);
+
+                        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                            write!(f, "a serialized {}", $name)
+                        }
+
+                        fn visit_seq<A: SeqAccess<'de>>(
+                            self,
+                            mut access: A,
+                        ) -> Result<Self::Value, A::Error> {
+                            let mut read = 0;
+                            $(
+                                let $field = access
+                                    .next_element()?
+                                    .ok_or_else(|| de::Error::invalid_length(read, &self))?;
+                                read += 1;
+                            )*
+                            let _ = read;
+                            Ok(($($field,)*))
+                        }
+
+                        fn visit_map<A: MapAccess<'de>>(
+                            self,
+                            mut access: A,
+                        ) -> Result<Self::Value, A::Error> {
+                            $(let mut $field = None;)*
+                            while let Some(i) = access.next_key_seed(FieldSeed(FIELDS))? {
+                                $(
+                                    if FIELDS[i] == stringify!($field) {
+                                        if $field.is_some() {
+                                            return Err(de::Error::duplicate_field(FIELDS[i]));
+                                        }
+                                        $field = Some(access.next_value()?);
+                                    }
+                                )*
+                            }
+                            Ok(($(
+                                $field.ok_or_else(|| de::Error::missing_field(stringify!($field)))?,
+                            )*))
+                        }
+                    }
+
+                    let ($($field,)*) = deserializer.deserialize_struct($name, FIELDS, PartsVisitor)?;
+                    $from_parts($($field),*).map_err(de::Error::custom)
+                }
+            }
+        };
+    }
+
+    /// Deserializes a field name or index into its index in the fields.
+    struct FieldSeed(&'static [&'static str]);
+
+    impl<'de> DeserializeSeed<'de> for FieldSeed {
+        type Value = usize;
+
+        fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<usize, D::Error> {
+            deserializer.deserialize_identifier(self)
+        }
+    }
+
+    impl Visitor<'_> for FieldSeed {
+        type Value = usize;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a sketch field")
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<usize, E> {
+            match usize::try_from(v) {
+                Ok(i) if i < self.0.len() => Ok(i),
+                _ => Err(de::Error::invalid_value(de::Unexpected::Unsigned(v), &self)),
+            }
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<usize, E> {
+            self.0
+                .iter()
+                .position(|&f| f == v)
+                .ok_or_else(|| de::Error::unknown_field(v, self.0))
+        }
+    }
+
+    /// The pointer width in bytes of this platform, which the Fx hashes of
+    /// sketch items depend on.
+    const POINTER_WIDTH: u8 = core::mem::size_of::<usize>() as u8;
+
+    /// Checks the format version and the pointer width of a deserialized
+    /// sketch.
+    fn check_header(version: u32, expected: u32, pointer_width: u8) -> Result<(), &'static str> {
+        if version != expected {
+            Err("unsupported sketch format version")
+        } else if pointer_width != POINTER_WIDTH {
+            Err("the sketch was built on a platform with a different pointer width")
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Converts deserialized seeds, which must fit `usize` and differ.
+    fn to_seeds([s1, s2]: [u64; 2]) -> Result<(usize, usize), &'static str> {
+        match (usize::try_from(s1), usize::try_from(s2)) {
+            (Ok(s1), Ok(s2)) if s1 != s2 => Ok((s1, s2)),
+            _ => Err("invalid sketch seeds"),
+        }
+    }
+
+    const BLOOM_VERSION: u32 = 1;
+
+    impl_sketch_serde! {
+        impl[T: ?Sized] FxBloomFilter<T> as "FxBloomFilter" {
+            version: u32 = BLOOM_VERSION,
+            pointer_width: u8 = POINTER_WIDTH,
+            num_bits: u64 = this.num_bits,
+            num_hashes: u32 = this.num_hashes,
+            seeds: [u64; 2] = [this.seeds.0 as u64, this.seeds.1 as u64],
+            words: Vec<u64> = this.words,
+        }
+        |this| => bloom_from_parts;
+    }
+
+    fn bloom_from_parts<T: ?Sized>(
+        version: u32,
+        pointer_width: u8,
+        num_bits: u64,
+        num_hashes: u32,
+        seeds: [u64; 2],
+        words: Vec<u64>,
+    ) -> Result<FxBloomFilter<T>, &'static str> {
+        check_header(version, BLOOM_VERSION, pointer_width)?;
+        if num_bits == 0 || num_hashes == 0 {
+            return Err("a Bloom filter needs at least one bit and one hash");
+        }
+        // The bits past `num_bits` in the last word must be clear.
+        let tail = num_bits % 64;
+        if words.len() as u64 != num_bits.div_ceil(64)
+            || (tail != 0 && words.last().is_some_and(|w| w >> tail != 0))
+        {
+            return Err("the bits do not match the Bloom filter size");
+        }
+        Ok(FxBloomFilter {
+            words,
+            num_bits,
+            num_hashes,
+            seeds: to_seeds(seeds)?,
+            marker: PhantomData,
+        })
+    }
+
+    const COUNTING_BLOOM_VERSION: u32 = 1;
+
+    impl_sketch_serde! {
+        impl[T: ?Sized] FxCountingBloomFilter<T> as "FxCountingBloomFilter" {
+            version: u32 = COUNTING_BLOOM_VERSION,
+            pointer_width: u8 = POINTER_WIDTH,
+            num_counters: u64 = this.num_counters,
+            num_hashes: u32 = this.num_hashes,
+            counter_bits: u32 = this.width.bits(),
+            seeds: [u64; 2] = [this.seeds.0 as u64, this.seeds.1 as u64],
+            counters: Vec<u8> = this.counters,
+        }
+        |this| => counting_bloom_from_parts;
+    }
+
+    fn counting_bloom_from_parts<T: ?Sized>(
+        version: u32,
+        pointer_width: u8,
+        num_counters: u64,
+        num_hashes: u32,
+        counter_bits: u32,
+        seeds: [u64; 2],
+        counters: Vec<u8>,
+    ) -> Result<FxCountingBloomFilter<T>, &'static str> {
+        check_header(version, COUNTING_BLOOM_VERSION, pointer_width)?;
+        if num_counters == 0 || num_hashes == 0 {
+            return Err("a Bloom filter needs at least one counter and one hash");
+        }
+        let width = match counter_bits {
+            4 => CounterWidth::Four,
+            8 => CounterWidth::Eight,
+            _ => return Err("counters must be 4 or 8 bits wide"),
+        };
+        // The unused half of the last byte of 4-bit counters must be clear.
+        let odd = width == CounterWidth::Four && num_counters % 2 == 1;
+        if counters.len() as u64 != counter_bytes(num_counters, width)
+            || (odd && counters.last().is_some_and(|c| c >> 4 != 0))
+        {
+            return Err("the counters do not match the Bloom filter size");
+        }
+        Ok(FxCountingBloomFilter {
+            counters,
+            num_counters,
+            num_hashes,
+            width,
+            seeds: to_seeds(seeds)?,
+            marker: PhantomData,
+        })
+    }
+
+    const CUCKOO_VERSION: u32 = 1;
+
+    impl_sketch_serde! {
+        impl[T: ?Sized] FxCuckooFilter<T> as "FxCuckooFilter" {
+            version: u32 = CUCKOO_VERSION,
+            pointer_width: u8 = POINTER_WIDTH,
+            num_buckets: u64 = this.num_buckets,
+            fingerprint_bits: u32 = this.fingerprint_bits,
+            seeds: [u64; 2] = [this.seeds.0 as u64, this.seeds.1 as u64],
+            len: u64 = this.len,
+            victim: Option<(u64, u32)> = this.victim,
+            words: Vec<u64> = this.words,
+        }
+        |this| => cuckoo_from_parts;
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn cuckoo_from_parts<T: ?Sized>(
+        version: u32,
+        pointer_width: u8,
+        num_buckets: u64,
+        fingerprint_bits: u32,
+        seeds: [u64; 2],
+        len: u64,
+        victim: Option<(u64, u32)>,
+        words: Vec<u64>,
+    ) -> Result<FxCuckooFilter<T>, &'static str> {
+        check_header(version, CUCKOO_VERSION, pointer_width)?;
+        if !num_buckets.is_power_of_two() || !(1..=32).contains(&fingerprint_bits) {
+            return Err("invalid cuckoo filter parameters");
+        }
+        // The bits past the last fingerprint must be clear.
+        let tail = (num_buckets * BUCKET_SIZE * u64::from(fingerprint_bits)) % 64;
+        if words.len() as u64 != slot_words(num_buckets, fingerprint_bits)
+            || (tail != 0 && words.last().is_some_and(|w| w >> tail != 0))
+        {
+            return Err("the fingerprints do not match the cuckoo filter size");
+        }
+        if victim.is_some_and(|(i, f)| {
+            i >= num_buckets || f == 0 || u64::from(f) >> fingerprint_bits != 0
+        }) {
+            return Err("invalid cuckoo filter victim");
+        }
+        let filter = FxCuckooFilter {
+            words,
+            num_buckets,
+            fingerprint_bits,
+            len,
+            victim,
+            seeds: to_seeds(seeds)?,
+            marker: PhantomData,
+        };
+        if filter.stored() != len {
+            return Err("the cuckoo filter length does not match its fingerprints");
+        }
+        Ok(filter)
+    }
+
+    const FUSE_VERSION: u32 = 1;
+
+    impl_sketch_serde! {
+        impl[T: ?Sized] FxBinaryFuseFilter<T> as "FxBinaryFuseFilter" {
+            version: u32 = FUSE_VERSION,
+            pointer_width: u8 = POINTER_WIDTH,
+            fingerprint_bits: u32 = this.width.bits(),
+            segment_length: u64 = this.segment_length,
+            segment_count_length: u64 = this.segment_count_length,
+            len: u64 = this.len as u64,
+            attempt: u32 = this.attempt,
+            seeds: [u64; 2] = [this.seeds.0 as u64, this.seeds.1 as u64],
+            fingerprints: Vec<u8> = this.fingerprints,
+        }
+        |this| => fuse_from_parts;
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn fuse_from_parts<T: ?Sized>(
+        version: u32,
+        pointer_width: u8,
+        fingerprint_bits: u32,
+        segment_length: u64,
+        segment_count_length: u64,
+        len: u64,
+        attempt: u32,
+        seeds: [u64; 2],
+        fingerprints: Vec<u8>,
+    ) -> Result<FxBinaryFuseFilter<T>, &'static str> {
+        check_header(version, FUSE_VERSION, pointer_width)?;
+        let width = match fingerprint_bits {
+            8 => FingerprintWidth::Eight,
+            16 => FingerprintWidth::Sixteen,
+            _ => return Err("fingerprints must be 8 or 16 bits wide"),
+        };
+        if !segment_length.is_power_of_two()
+            || segment_count_length == 0
+            || !segment_count_length.is_multiple_of(segment_length)
+        {
+            return Err("invalid binary fuse filter segments");
+        }
+        let slots = segment_count_length.checked_add(2 * segment_length);
+        if slots.and_then(|s| s.checked_mul(width.bytes() as u64))
+            != Some(fingerprints.len() as u64)
+        {
+            return Err("the fingerprints do not match the binary fuse filter size");
+        }
+        Ok(FxBinaryFuseFilter {
+            fingerprints,
+            width,
+            segment_length,
+            segment_count_length,
+            len: usize::try_from(len).map_err(|_| "too many keys")?,
+            attempt,
+            seeds: to_seeds(seeds)?,
+            marker: PhantomData,
+        })
+    }
+
+    const HYPERLOGLOG_VERSION: u32 = 1;
+
+    impl_sketch_serde! {
+        impl[T: ?Sized] FxHyperLogLog<T> as "FxHyperLogLog" {
+            version: u32 = HYPERLOGLOG_VERSION,
+            pointer_width: u8 = POINTER_WIDTH,
+            precision: u8 = this.precision,
+            seeds: [u64; 2] = [this.seeds.0 as u64, this.seeds.1 as u64],
+            registers: Vec<u8> = this.registers,
+        }
+        |this| => hyperloglog_from_parts;
+    }
+
+    fn hyperloglog_from_parts<T: ?Sized>(
+        version: u32,
+        pointer_width: u8,
+        precision: u8,
+        seeds: [u64; 2],
+        registers: Vec<u8>,
+    ) -> Result<FxHyperLogLog<T>, &'static str> {
+        check_header(version, HYPERLOGLOG_VERSION, pointer_width)?;
+        if !PRECISIONS.contains(&precision) {
+            return Err("the precision must be between 4 and 18");
+        }
+        if registers.len() != 1 << precision || registers.iter().any(|&r| r > 64) {
+            return Err("the registers do not match the HyperLogLog precision");
+        }
+        Ok(FxHyperLogLog {
+            registers,
+            precision,
+            seeds: to_seeds(seeds)?,
+            marker: PhantomData,
+        })
+    }
+
+    const COUNT_MIN_VERSION: u32 = 1;
+
+    impl_sketch_serde! {
+        impl[T: ?Sized] FxCountMinSketch<T> as "FxCountMinSketch" {
+            version: u32 = COUNT_MIN_VERSION,
+            pointer_width: u8 = POINTER_WIDTH,
+            width: u64 = this.width,
+            depth: u32 = this.depth,
+            seed: u64 = this.seed as u64,
+            conservative: bool = this.conservative,
+            total: u64 = this.total,
+            counters: Vec<u64> = this.counters,
+        }
+        |this| => count_min_from_parts;
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn count_min_from_parts<T: ?Sized>(
+        version: u32,
+        pointer_width: u8,
+        width: u64,
+        depth: u32,
+        seed: u64,
+        conservative: bool,
+        total: u64,
+        counters: Vec<u64>,
+    ) -> Result<FxCountMinSketch<T>, &'static str> {
+        check_header(version, COUNT_MIN_VERSION, pointer_width)?;
+        if width == 0 || depth == 0 {
+            return Err("a Count-Min sketch needs at least one row and one column");
+        }
+        if width.checked_mul(u64::from(depth)) != Some(counters.len() as u64) {
+            return Err("the counters do not match the Count-Min sketch size");
+        }
+        Ok(FxCountMinSketch {
+            counters,
+            width,
+            depth,
+            seed: usize::try_from(seed).map_err(|_| "invalid sketch seed")?,
+            conservative,
+            total,
+            marker: PhantomData,
+        })
+    }
+
+    const MINHASH_VERSION: u32 = 1;
+
+    impl_sketch_serde! {
+        impl[] MinHashSignature as "MinHashSignature" {
+            version: u32 = MINHASH_VERSION,
+            pointer_width: u8 = POINTER_WIDTH,
+            seed: u64 = this.seed as u64,
+            mins: Vec<u64> = this.mins,
+        }
+        |this| => minhash_from_parts;
+    }
+
+    fn minhash_from_parts(
+        version: u32,
+        pointer_width: u8,
+        seed: u64,
+        mins: Vec<u64>,
+    ) -> Result<MinHashSignature, &'static str> {
+        check_header(version, MINHASH_VERSION, pointer_width)?;
+        if mins.is_empty() {
+            return Err("a MinHash signature needs at least one permutation");
+        }
+        Ok(MinHashSignature {
+            mins,
+            seed: usize::try_from(seed).map_err(|_| "invalid sketch seed")?,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::string::{String, ToString};
+    use std::vec::Vec;
+
+    use crate::{
+        deserialize_presized, FrozenFxMap, FrozenFxSet, FxBinaryFuseFilter, FxBloomFilter,
+        FxCountMinSketch, FxCountingBloomFilter, FxCuckooFilter, FxHashMap, FxHashMapSeed,
+        FxHashSet, FxHashSetSeed, FxHyperLogLog, FxMapLoader, FxMinHash, FxPresized, FxRefill,
+        FxSeededState, FxWithSeed, MinHashSignature, SortedSer,
+    };
+
+    #[test]
+    fn round_trip() {
+        let config = bincode::config::standard();
+
+        let map: FxHashMap<u32, String> = (0..100).map(|i| (i, i.to_string())).collect();
+        let bytes = bincode::serde::encode_to_vec(FxPresized(&map), config).unwrap();
+        let (FxPresized(back), _): (FxPresized<FxHashMap<u32, String>>, _) =
+            bincode::serde::decode_from_slice(&bytes, config).unwrap();
+        assert_eq!(back, map);
+
+        // bincode announces the length of the set, so it is reserved up front.
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct Doc {
+            #[serde(deserialize_with = "deserialize_presized")]
+            ids: FxHashSet<u64>,
+        }
+        let doc = Doc {
+            ids: (0..10_000).collect(),
+        };
+        let bytes = bincode::serde::encode_to_vec(&doc, config).unwrap();
+        let (back, _): (Doc, _) = bincode::serde::decode_from_slice(&bytes, config).unwrap();
+        assert_eq!(back.ids, doc.ids);
+        assert!(back.ids.capacity() < 2 * doc.ids.len());
+    }
+
+    #[test]
+    fn round_trip_with_seed() {
+        let config = bincode::config::standard();
+
+        let mut map = FxHashMapSeed::with_hasher(FxSeededState::with_seed(7));
+        map.extend((0..100_u32).map(|i| (i, i * 2)));
+        let bytes = bincode::serde::encode_to_vec(FxWithSeed(&map), config).unwrap();
+        let (FxWithSeed(back), _): (FxWithSeed<FxHashMapSeed<u32, u32>>, _) =
+            bincode::serde::decode_from_slice(&bytes, config).unwrap();
+        assert_eq!(back.hasher().seed(), 7);
+        assert_eq!(back, map);
+
+        let mut set = FxHashSetSeed::with_hasher(FxSeededState::with_seed(usize::MAX));
+        set.extend(["a", "b"].map(String::from));
+        let bytes = bincode::serde::encode_to_vec(FxWithSeed(set.clone()), config).unwrap();
+        let (FxWithSeed(back), _): (FxWithSeed<FxHashSetSeed<String>>, _) =
+            bincode::serde::decode_from_slice(&bytes, config).unwrap();
+        assert_eq!(back.hasher().seed(), usize::MAX);
+        assert_eq!(back, set);
+    }
+    #[test]
+    fn refill_reuses_allocation() {
+        let config = bincode::config::standard();
+
+        let mut map: FxHashMap<u32, u32> = (0..10_000).map(|i| (i, i)).collect();
+        let capacity = map.capacity();
+        let snapshot: FxHashMap<u32, u32> = (5..105).map(|i| (i, i * 3)).collect();
+        let bytes = bincode::serde::encode_to_vec(&snapshot, config).unwrap();
+        bincode::serde::seed_decode_from_slice(FxRefill(&mut map), &bytes, config).unwrap();
+        assert_eq!(map, snapshot);
+        assert_eq!(map.capacity(), capacity);
+
+        let mut set = FxHashSet::default();
+        let ids: FxHashSet<u64> = (0..10_000).collect();
+        let bytes = bincode::serde::encode_to_vec(&ids, config).unwrap();
+        bincode::serde::seed_decode_from_slice(FxRefill(&mut set), &bytes, config).unwrap();
+        assert_eq!(set, ids);
+        assert!(set.capacity() < 2 * ids.len());
+    }
+    #[test]
+    fn frozen_round_trip() {
+        let config = bincode::config::standard();
+
+        let map: FrozenFxMap<String, u32> = (0..1000).map(|i| (i.to_string(), i)).collect();
+        let bytes = bincode::serde::encode_to_vec(&map, config).unwrap();
+        let (back, _): (FrozenFxMap<String, u32>, _) =
+            bincode::serde::decode_from_slice(&bytes, config).unwrap();
+        assert_eq!(back.keys(), map.keys());
+        assert!((0..1000).all(|i| back.get(&i.to_string()) == Some(&i)));
+
+        let set: FrozenFxSet<u64> = (0..100).collect();
+        let bytes = bincode::serde::encode_to_vec(&set, config).unwrap();
+        let (back, _): (FrozenFxSet<u64>, _) =
+            bincode::serde::decode_from_slice(&bytes, config).unwrap();
+        assert!((0..100).all(|i| back.contains(&i)));
+
+        // Keys whose hashes always collide are kept after the indexed keys.
+        let k = crate::K;
+        let set: FrozenFxSet<(usize, usize)> = [(0, k), (1, 0)].into_iter().collect();
+        let bytes = bincode::serde::encode_to_vec(&set, config).unwrap();
+        let (back, _): (FrozenFxSet<(usize, usize)>, _) =
+            bincode::serde::decode_from_slice(&bytes, config).unwrap();
+        assert!(back.contains(&(0, k)) && back.contains(&(1, 0)));
+
+        // Keys that are not in their slots are rejected.
+        let mut keys = map.keys().to_vec();
+        keys.swap(0, 1);
+        let parts = (0_u64, &*map.index.displacements, 0_u32, keys, map.values());
+        let bytes = bincode::serde::encode_to_vec(parts, config).unwrap();
+        let result: Result<(FrozenFxMap<String, u32>, _), _> =
+            bincode::serde::decode_from_slice(&bytes, config);
+        assert!(result.is_err());
+    }
+    #[test]
+    fn sorted_is_reproducible() {
+        let config = bincode::config::standard();
+
+        let a: FxHashSet<String> = (0..1000).map(|i| i.to_string()).collect();
+        let mut b = FxHashSet::default();
+        b.extend((0..1000).rev().map(|i| i.to_string()));
+        let bytes = bincode::serde::encode_to_vec(SortedSer(&a), config).unwrap();
+        assert_eq!(
+            bytes,
+            bincode::serde::encode_to_vec(SortedSer(&b), config).unwrap()
+        );
+        let (back, _): (FxHashSet<String>, _) =
+            bincode::serde::decode_from_slice(&bytes, config).unwrap();
+        assert_eq!(back, a);
+    }
+    #[test]
+    fn loader_batches() {
+        let config = bincode::config::standard();
+        let map: FxHashMap<u32, String> = (0..1000).map(|i| (i, i.to_string())).collect();
+        let bytes = bincode::serde::encode_to_vec(&map, config).unwrap();
+
+        let mut batches = Vec::new();
+        let loader = FxMapLoader::new(300, |batch: &mut Vec<(u32, String)>| {
+            batches.push(batch.len());
+            Ok::<_, &str>(())
+        });
+        let (read, _) = bincode::serde::seed_decode_from_slice(loader, &bytes, config).unwrap();
+        assert_eq!(read, 1000);
+        assert_eq!(batches, [300, 300, 300, 100]);
+
+        let loader = FxMapLoader::new(10, |_: &mut Vec<(u32, String)>| Err("disk full"));
+        assert!(bincode::serde::seed_decode_from_slice(loader, &bytes, config).is_err());
+    }
+    #[test]
+    fn bloom_round_trip() {
+        let config = bincode::config::standard();
+        let filter: FxBloomFilter<u32> = (0..1000).collect();
+        let bytes = bincode::serde::encode_to_vec(&filter, config).unwrap();
+        let (back, _): (FxBloomFilter<u32>, _) =
+            bincode::serde::decode_from_slice(&bytes, config).unwrap();
+        assert_eq!(back, filter);
+        assert!((0..1000).all(|i| back.contains(&i)));
+
+        // Bits past the end of the filter are rejected.
+        let mut words = filter.words.clone();
+        *words.last_mut().unwrap() |= 1 << 63;
+        let width = core::mem::size_of::<usize>() as u8;
+        let corrupt = (
+            1_u32,
+            width,
+            filter.num_bits,
+            filter.num_hashes,
+            [0x243f6a88_u64, 0x85a308d3],
+            words,
+        );
+        let bytes = bincode::serde::encode_to_vec(corrupt, config).unwrap();
+        assert!(
+            bincode::serde::decode_from_slice::<FxBloomFilter<u32>, _>(&bytes, config).is_err()
+        );
+
+        // Filters built with another pointer width hash items differently.
+        let other = (
+            1_u32,
+            12 - width,
+            filter.num_bits,
+            filter.num_hashes,
+            [0x243f6a88_u64, 0x85a308d3],
+            filter.words.clone(),
+        );
+        let bytes = bincode::serde::encode_to_vec(other, config).unwrap();
+        assert!(
+            bincode::serde::decode_from_slice::<FxBloomFilter<u32>, _>(&bytes, config).is_err()
+        );
+    }
+    #[test]
+    fn counting_bloom_round_trip() {
+        let config = bincode::config::standard();
+        let mut filter = FxCountingBloomFilter::new(1001, 3);
+        filter.extend(0..100_u32);
+        filter.remove(&7);
+        let bytes = bincode::serde::encode_to_vec(&filter, config).unwrap();
+        let (back, _): (FxCountingBloomFilter<u32>, _) =
+            bincode::serde::decode_from_slice(&bytes, config).unwrap();
+        assert_eq!(back, filter);
+    }
+    #[test]
+    fn cuckoo_round_trip() {
+        let config = bincode::config::standard();
+        let mut filter = FxCuckooFilter::new(1000, 13);
+        for i in 0..900_u32 {
+            filter.insert(&i).unwrap();
+        }
+        filter.remove(&7);
+        let bytes = bincode::serde::encode_to_vec(&filter, config).unwrap();
+        let (back, _): (FxCuckooFilter<u32>, _) =
+            bincode::serde::decode_from_slice(&bytes, config).unwrap();
+        assert_eq!(back, filter);
+
+        // A length that does not match the fingerprints is rejected.
+        let corrupt = (
+            1_u32,
+            core::mem::size_of::<usize>() as u8,
+            filter.num_buckets,
+            filter.fingerprint_bits,
+            [0x243f6a88_u64, 0x85a308d3],
+            filter.len + 1,
+            filter.victim,
+            filter.words.clone(),
+        );
+        let bytes = bincode::serde::encode_to_vec(corrupt, config).unwrap();
+        assert!(
+            bincode::serde::decode_from_slice::<FxCuckooFilter<u32>, _>(&bytes, config).is_err()
+        );
+    }
+    #[test]
+    fn fuse_round_trip() {
+        let config = bincode::config::standard();
+        let filter: FxBinaryFuseFilter<u32> = (0..1000).collect();
+        let bytes = bincode::serde::encode_to_vec(&filter, config).unwrap();
+        let (back, _): (FxBinaryFuseFilter<u32>, _) =
+            bincode::serde::decode_from_slice(&bytes, config).unwrap();
+        assert_eq!(back, filter);
+        assert!((0..1000).all(|i| back.contains(&i)));
+    }
+    #[test]
+    fn hyperloglog_round_trip() {
+        let config = bincode::config::standard();
+        let sketch: FxHyperLogLog<u32> = (0..1000).collect();
+        let bytes = bincode::serde::encode_to_vec(&sketch, config).unwrap();
+        let (back, _): (FxHyperLogLog<u32>, _) =
+            bincode::serde::decode_from_slice(&bytes, config).unwrap();
+        assert_eq!(back, sketch);
+    }
+    #[test]
+    fn count_min_round_trip() {
+        let config = bincode::config::standard();
+        let mut sketch = FxCountMinSketch::new(100, 3);
+        sketch.set_conservative_update(true);
+        sketch.extend((0..1000_u32).map(|i| i % 30));
+        let bytes = bincode::serde::encode_to_vec(&sketch, config).unwrap();
+        let (back, _): (FxCountMinSketch<u32>, _) =
+            bincode::serde::decode_from_slice(&bytes, config).unwrap();
+        assert_eq!(back, sketch);
+    }
+    #[test]
+    fn minhash_round_trip() {
+        let config = bincode::config::standard();
+        let signature = FxMinHash::new(64).signature(0..100_u32);
+        let bytes = bincode::serde::encode_to_vec(&signature, config).unwrap();
+        let (back, _): (MinHashSignature, _) =
+            bincode::serde::decode_from_slice(&bytes, config).unwrap();
+        assert_eq!(back, signature);
+    }
+}
@@ -0,0 +1,142 @@
+/// Set operations collecting into a new set with a fitting capacity.
+///
+/// Collecting `a.union(&b).cloned()` starts from an empty set and grows it
+/// several times. These methods reserve room for the result up front, and
+/// `union_collect` and `difference_collect` start from a clone of one input
+/// where that is cheaper: cloning a hash set copies its table, so the cloned
+/// elements are not hashed again.
+///
+/// ```
+/// use rustc_hash::{FxHashSet, FxSetOpsExt};
+///
+/// let a: FxHashSet<u32> = (0..10).collect();
+/// let b: FxHashSet<u32> = (5..15).collect();
+/// assert_eq!(FxHashSet::union_collect(&a, &b).len(), 15);
+/// assert_eq!(a.intersection_collect(&b).len(), 5);
+/// assert_eq!(a.difference_collect(&b).len(), 5);
+/// assert_eq!(a.symmetric_difference_collect(&b).len(), 10);
+/// ```
+pub trait FxSetOpsExt {
+    /// Returns the elements in `self`, `other` or both.
+    fn union_collect(&self, other: &Self) -> Self;
+
+    /// Returns the elements in both `self` and `other`.
+    fn intersection_collect(&self, other: &Self) -> Self;
+
+    /// Returns the elements in `self` but not in `other`.
+    fn difference_collect(&self, other: &Self) -> Self;
+
+    /// Returns the elements in exactly one of `self` and `other`.
+    fn symmetric_difference_collect(&self, other: &Self) -> Self;
+}
+
+macro_rules! impl_set_ops {
+    ($set:ident) => {
+        impl<T: Clone + Eq + Hash, S: BuildHasher + Clone> FxSetOpsExt for $set<T, S> {
+            fn union_collect(&self, other: &Self) -> Self {
+                let (large, small) = if self.len() >= other.len() {
+                    (self, other)
+                } else {
+                    (other, self)
+                };
+                let mut set = large.clone();
+                set.reserve(small.len());
+                set.extend(small.iter().filter(|t| !large.contains(*t)).cloned());
+                set
+            }
+
+            fn intersection_collect(&self, other: &Self) -> Self {
+                let (large, small) = if self.len() >= other.len() {
+                    (self, other)
+                } else {
+                    (other, self)
+                };
+                let mut set = $set::with_capacity_and_hasher(small.len(), self.hasher().clone());
+                set.extend(small.iter().filter(|t| large.contains(*t)).cloned());
+                set
+            }
+
+            fn difference_collect(&self, other: &Self) -> Self {
+                // Removing a few elements from a clone beats inserting almost
+                // all elements into a new set.
+                if other.len() < self.len() / 2 {
+                    let mut set = self.clone();
+                    for t in other {
+                        set.remove(t);
+                    }
+                    return set;
+                }
+                let mut set = $set::with_capacity_and_hasher(self.len(), self.hasher().clone());
+                set.extend(self.iter().filter(|t| !other.contains(*t)).cloned());
+                set
+            }
+
+            fn symmetric_difference_collect(&self, other: &Self) -> Self {
+                let mut set =
+                    $set::with_capacity_and_hasher(self.len() + other.len(), self.hasher().clone());
+                set.extend(self.iter().filter(|t| !other.contains(*t)).cloned());
+                set.extend(other.iter().filter(|t| !self.contains(*t)).cloned());
+                set
+            }
+        }
+    };
+}
+
+#[cfg(feature = "std")]
+mod std_impls {
+    use core::hash::{BuildHasher, Hash};
+    use std::collections::HashSet;
+
+    use super::FxSetOpsExt;
+
+    impl_set_ops!(HashSet);
+}
+
+#[cfg(feature = "hashbrown")]
+mod hashbrown_impls {
+    use core::hash::{BuildHasher, Hash};
+
+    use hashbrown::HashSet;
+
+    use super::FxSetOpsExt;
+
+    impl_set_ops!(HashSet);
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))]
+    use hashbrown::HashSet;
+    #[cfg(feature = "std")]
+    use std::collections::HashSet;
+
+    use crate::{FxBuildHasher, FxSetOpsExt};
+
+    #[test]
+    fn matches_iterators() {
+        type Set = HashSet<u32, FxBuildHasher>;
+        let sets: [Set; 4] = [
+            (0..100).collect(),
+            (50..60).collect(),
+            (90..300).collect(),
+            Set::default(),
+        ];
+        for a in &sets {
+            for b in &sets {
+                assert_eq!(a.union_collect(b), a.union(b).copied().collect::<Set>());
+                assert_eq!(
+                    a.intersection_collect(b),
+                    a.intersection(b).copied().collect::<Set>()
+                );
+                assert_eq!(
+                    a.difference_collect(b),
+                    a.difference(b).copied().collect::<Set>()
+                );
+                assert_eq!(
+                    a.symmetric_difference_collect(b),
+                    a.symmetric_difference(b).copied().collect::<Set>()
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,336 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use std::boxed::Box;
+use std::collections::hash_map::Entry;
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::vec::Vec;
+
+use crate::{sketch, FxBuildHasher, FxHashMap};
+
+/// A concurrent hash map made of a fixed number of `RwLock<FxHashMap>` shards.
+///
+/// Every key is assigned to a shard by its Fx hash, so operations on keys in
+/// different shards do not contend. Methods that span all shards, like
+/// [`len`](Self::len) and [`snapshot`](Self::snapshot), lock the shards one at
+/// a time and therefore do not observe a single consistent state while other
+/// threads are writing.
+///
+/// Lock poisoning is ignored: the shards are always left in a valid state, so a
+/// panic in another thread does not make the map unusable.
+///
+/// ```
+/// use rustc_hash::FxShardedMap;
+/// use std::thread;
+///
+/// let map = FxShardedMap::new();
+/// thread::scope(|s| {
+///     for t in 0..4_u32 {
+///         let map = &map;
+///         s.spawn(move || {
+///             for i in 0..100 {
+///                 map.insert(t * 100 + i, i);
+///             }
+///         });
+///     }
+/// });
+/// assert_eq!(map.len(), 400);
+/// assert_eq!(map.get(&123), Some(23));
+/// ```
+pub struct FxShardedMap<K, V> {
+    shards: Box<[RwLock<FxHashMap<K, V>>]>,
+    shift: u32,
+}
+
+impl<K, V> FxShardedMap<K, V> {
+    /// Creates an empty `FxShardedMap` with a shard count based on the
+    /// available parallelism.
+    pub fn new() -> FxShardedMap<K, V> {
+        Self::with_capacity(0)
+    }
+
+    /// Creates an empty `FxShardedMap` with room for at least `capacity`
+    /// entries spread evenly over the shards.
+    pub fn with_capacity(capacity: usize) -> FxShardedMap<K, V> {
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        Self::with_capacity_and_shards(capacity, threads * 4)
+    }
+
+    /// Creates an empty `FxShardedMap` with `shards` shards, rounded up to a
+    /// power of two.
+    pub fn with_shards(shards: usize) -> FxShardedMap<K, V> {
+        Self::with_capacity_and_shards(0, shards)
+    }
+
+    /// Creates an empty `FxShardedMap` with room for at least `capacity`
+    /// entries and `shards` shards, rounded up to a power of two.
+    pub fn with_capacity_and_shards(capacity: usize, shards: usize) -> FxShardedMap<K, V> {
+        let shards = shards.max(1).next_power_of_two();
+        let per_shard = capacity.div_ceil(shards);
+        FxShardedMap {
+            shards: (0..shards)
+                .map(|_| {
+                    RwLock::new(FxHashMap::with_capacity_and_hasher(
+                        per_shard,
+                        Default::default(),
+                    ))
+                })
+                .collect(),
+            shift: 64 - shards.trailing_zeros(),
+        }
+    }
+
+    /// Creates a map from a power-of-two number of shards whose keys were
+    /// assigned with `partition::shard_of`, which picks the same shards.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn from_shards(shards: Vec<FxHashMap<K, V>>) -> FxShardedMap<K, V> {
+        debug_assert!(shards.len().is_power_of_two());
+        FxShardedMap {
+            shift: 64 - shards.len().trailing_zeros(),
+            shards: shards.into_iter().map(RwLock::new).collect(),
+        }
+    }
+
+    /// Returns the number of shards.
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        (0..self.shards.len()).map(|i| self.read(i).len()).sum()
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        (0..self.shards.len()).all(|i| self.read(i).is_empty())
+    }
+
+    /// Removes all entries.
+    pub fn clear(&self) {
+        for i in 0..self.shards.len() {
+            self.write(i).clear();
+        }
+    }
+
+    /// Removes all entries and returns them, taken one shard at a time.
+    pub fn drain(&self) -> Vec<(K, V)> {
+        let mut entries = Vec::new();
+        for i in 0..self.shards.len() {
+            entries.extend(self.write(i).drain());
+        }
+        entries
+    }
+
+    /// Calls `f` for every entry, holding the read lock of one shard at a time.
+    pub fn for_each(&self, mut f: impl FnMut(&K, &V)) {
+        for i in 0..self.shards.len() {
+            for (k, v) in self.read(i).iter() {
+                f(k, v);
+            }
+        }
+    }
+
+    /// Returns a copy of all entries, taken one shard at a time.
+    pub fn snapshot(&self) -> Vec<(K, V)>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        let mut entries = Vec::new();
+        self.for_each(|k, v| entries.push((k.clone(), v.clone())));
+        entries
+    }
+
+    /// Consumes the map and returns its shards merged into a single `FxHashMap`.
+    pub fn into_inner(self) -> FxHashMap<K, V>
+    where
+        K: Eq + Hash,
+    {
+        let mut shards: Vec<_> = self
+            .shards
+            .into_vec()
+            .into_iter()
+            .map(|s| s.into_inner().unwrap_or_else(PoisonError::into_inner))
+            .collect();
+        let mut map = shards.pop().unwrap_or_default();
+        map.reserve(shards.iter().map(|s| s.len()).sum());
+        for shard in shards {
+            map.extend(shard);
+        }
+        map
+    }
+
+    fn read(&self, shard: usize) -> RwLockReadGuard<'_, FxHashMap<K, V>> {
+        self.shards[shard]
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self, shard: usize) -> RwLockWriteGuard<'_, FxHashMap<K, V>> {
+        self.shards[shard]
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn shard_of<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        let hash = sketch::spread(FxBuildHasher.hash_one(key));
+        hash.checked_shr(self.shift).unwrap_or(0) as usize
+    }
+}
+
+impl<K: Eq + Hash, V> FxShardedMap<K, V> {
+    /// Returns a clone of the value for `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Clone,
+    {
+        self.get_with(key, V::clone)
+    }
+
+    /// Calls `f` with the value for `key` under the shard's read lock.
+    pub fn get_with<Q, R>(&self, key: &Q, f: impl FnOnce(&V) -> R) -> Option<R>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.read(self.shard_of(key)).get(key).map(f)
+    }
+
+    /// Returns `true` if the map contains `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.read(self.shard_of(key)).contains_key(key)
+    }
+
+    /// Inserts a key-value pair, returning the previous value of `key`.
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        self.write(self.shard_of(&key)).insert(key, value)
+    }
+
+    /// Removes `key`, returning its value.
+    pub fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.write(self.shard_of(key)).remove(key)
+    }
+
+    /// Calls `f` with a mutable reference to the value for `key` under the
+    /// shard's write lock.
+    pub fn update<Q, R>(&self, key: &Q, f: impl FnOnce(&mut V) -> R) -> Option<R>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.write(self.shard_of(key)).get_mut(key).map(f)
+    }
+
+    /// Calls `f` with the [`Entry`] for `key` under the shard's write lock.
+    ///
+    /// ```
+    /// use rustc_hash::FxShardedMap;
+    ///
+    /// let map = FxShardedMap::new();
+    /// for word in ["a", "b", "a"] {
+    ///     map.entry(word, |e| *e.or_insert(0) += 1);
+    /// }
+    /// assert_eq!(map.get("a"), Some(2));
+    /// ```
+    pub fn entry<R>(&self, key: K, f: impl FnOnce(Entry<'_, K, V>) -> R) -> R {
+        f(self.write(self.shard_of(&key)).entry(key))
+    }
+
+    /// Returns a clone of the value for `key`, inserting the result of `default`
+    /// first if the key is missing.
+    pub fn get_or_insert_with(&self, key: K, default: impl FnOnce() -> V) -> V
+    where
+        V: Clone,
+    {
+        self.entry(key, |e| e.or_insert_with(default).clone())
+    }
+}
+
+impl<K, V> Default for FxShardedMap<K, V> {
+    fn default() -> FxShardedMap<K, V> {
+        Self::new()
+    }
+}
+
+impl<K: Eq + Hash, V> FromIterator<(K, V)> for FxShardedMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> FxShardedMap<K, V> {
+        let map = Self::new();
+        for (k, v) in iter {
+            map.insert(k, v);
+        }
+        map
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for FxShardedMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut map = f.debug_map();
+        self.for_each(|k, v| {
+            map.entry(k, v);
+        });
+        map.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use crate::FxShardedMap;
+
+    #[test]
+    fn shards() {
+        assert_eq!(FxShardedMap::<u32, u32>::with_shards(0).shard_count(), 1);
+        assert_eq!(FxShardedMap::<u32, u32>::with_shards(5).shard_count(), 8);
+
+        let map = FxShardedMap::with_shards(1);
+        map.insert(1_u32, 1_u32);
+        assert_eq!(map.get(&1), Some(1));
+
+        let map: FxShardedMap<u32, u32> = (0..1000).map(|i| (i, i)).collect();
+        let mut snapshot = map.snapshot();
+        snapshot.sort_unstable();
+        assert_eq!(snapshot.len(), 1000);
+        assert!(snapshot.iter().all(|&(k, v)| k == v));
+        assert_eq!(map.into_inner().len(), 1000);
+    }
+
+    #[test]
+    fn concurrent_updates() {
+        let map = FxShardedMap::with_shards(4);
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for i in 0..1000_u32 {
+                        map.entry(i % 10, |e| *e.or_insert(0_u32) += 1);
+                    }
+                });
+            }
+        });
+        assert_eq!(map.len(), 10);
+        assert_eq!(map.get(&3), Some(400));
+
+        assert_eq!(map.update(&3, core::mem::take), Some(400));
+        assert_eq!(map.get_or_insert_with(3, || 7), 0);
+        assert_eq!(map.get_or_insert_with(10, || 7), 7);
+        assert_eq!(map.remove(&10), Some(7));
+        assert!(!map.contains_key(&10));
+        assert_eq!(map.drain().len(), 10);
+        assert!(map.is_empty());
+        map.insert(1, 1);
+
+        map.clear();
+        assert!(map.is_empty());
+    }
+}
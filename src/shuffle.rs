@@ -0,0 +1,91 @@
+use core::hash::{BuildHasher, Hasher};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::FxHasher;
+
+/// The state of `FxHashMap`, `FxHashSet` and their `hashbrown` counterparts in
+/// debug builds with the `shuffle-debug` feature.
+///
+/// Every map gets its own seed, drawn when the map is constructed, so two maps
+/// holding the same keys iterate them in different orders, and so does the
+/// same map across runs. This shakes out code that depends on the iteration
+/// order of Fx maps. `FxBuildHasher` itself stays unseeded, so hashes computed
+/// with it directly remain stable.
+///
+/// Since the maps no longer use `FxBuildHasher`, they have to be constructed
+/// with `new`, `default` or `Default::default()` as their hasher rather than
+/// with `FxBuildHasher`.
+#[derive(Clone)]
+pub struct FxShuffledState {
+    seed: usize,
+}
+
+impl Default for FxShuffledState {
+    fn default() -> FxShuffledState {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+
+        let mut hasher = FxHasher::with_seed(run_seed());
+        hasher.write_usize(n);
+        FxShuffledState {
+            // The seed must not be 0, which would leave the hashes unchanged.
+            seed: hasher.finish() as usize | 1,
+        }
+    }
+}
+
+impl BuildHasher for FxShuffledState {
+    type Hasher = FxHasher;
+
+    fn build_hasher(&self) -> FxHasher {
+        FxHasher::with_seed(self.seed)
+    }
+}
+
+/// Returns a seed that differs between runs, which the seeds of the maps are
+/// derived from.
+#[cfg(feature = "std")]
+fn run_seed() -> usize {
+    use std::hash::RandomState;
+    use std::sync::OnceLock;
+
+    static SEED: OnceLock<usize> = OnceLock::new();
+    *SEED.get_or_init(|| RandomState::new().hash_one(0_u8) as usize)
+}
+
+/// Returns a seed that differs between runs, which the seeds of the maps are
+/// derived from.
+///
+/// Without `std` there is no source of randomness, so the seed is taken from
+/// the address of a static, which changes between runs under address space
+/// layout randomization.
+#[cfg(not(feature = "std"))]
+fn run_seed() -> usize {
+    static ANCHOR: u8 = 0;
+
+    &ANCHOR as *const u8 as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use core::hash::BuildHasher;
+
+    use super::FxShuffledState;
+    use crate::{FxBuildHasher, FxTable};
+
+    #[test]
+    fn maps_are_perturbed_but_fx_build_hasher_is_not() {
+        // Unseeded, Fx hashes 0 to 0.
+        assert_eq!(FxBuildHasher.hash_one(0_u64), 0);
+
+        let state = FxShuffledState::default();
+        assert_ne!(state.hash_one(0_u64), 0);
+        assert_eq!(state.hash_one(12345_u64), state.hash_one(12345_u64));
+        assert_ne!(FxShuffledState::default().seed, state.seed);
+
+        let map: FxTable<u32, u32> = (0..1000).map(|i| (i, i)).collect();
+        assert!((0..1000).all(|i| map.get(&i) == Some(&i)));
+        assert_ne!(map.hasher().hash_one(0_u64), 0);
+    }
+}
@@ -0,0 +1,123 @@
+use core::hash::{BuildHasher, Hash};
+
+use crate::sketch::{self, DEFAULT_SEEDS};
+use crate::FxSeededState;
+
+/// Computes SimHash fingerprints using the Fx hashing algorithm.
+///
+/// Every feature of a document, such as a word or shingle, is hashed to 64
+/// bits, and every bit adds the weight of the feature to a sum if it is set
+/// or subtracts it otherwise. Bit `i` of the fingerprint is set if sum `i`
+/// is positive. Similar documents get fingerprints with a small
+/// [`hamming_distance`], as most of their features, and thus most sums,
+/// agree.
+///
+/// ```
+/// use rustc_hash::{hamming_distance, FxSimHash};
+///
+/// let fingerprint = |text: &str| {
+///     let mut simhash = FxSimHash::new();
+///     simhash.extend(text.split(' '));
+///     simhash.finish()
+/// };
+/// let a = fingerprint("the quick brown fox jumps over the lazy dog near the river bank");
+/// let b = fingerprint("the quick brown fox jumps over the lazy cat near the river bank");
+/// let c = fingerprint("an entirely unrelated sentence about compilers and hash tables");
+/// assert!(hamming_distance(a, b) < hamming_distance(a, c));
+/// ```
+#[derive(Clone, Debug)]
+pub struct FxSimHash {
+    sums: [i64; 64],
+    seed: usize,
+}
+
+impl FxSimHash {
+    /// Creates a SimHash with no features.
+    pub fn new() -> FxSimHash {
+        Self::with_seed(DEFAULT_SEEDS.0)
+    }
+
+    /// Creates a SimHash with no features, hashing them with `seed`.
+    pub fn with_seed(seed: usize) -> FxSimHash {
+        FxSimHash {
+            sums: [0; 64],
+            seed,
+        }
+    }
+
+    /// Returns the seed the features are hashed with.
+    pub fn seed(&self) -> usize {
+        self.seed
+    }
+
+    /// Adds a feature with weight 1.
+    pub fn add<T: Hash + ?Sized>(&mut self, feature: &T) {
+        self.add_weighted(feature, 1);
+    }
+
+    /// Adds a feature with the given weight, e.g. its frequency in the
+    /// document.
+    pub fn add_weighted<T: Hash + ?Sized>(&mut self, feature: &T, weight: i64) {
+        // Every bit is used, so the hash is mixed rather than spread.
+        let hash = sketch::mix(FxSeededState::with_seed(self.seed).hash_one(feature));
+        for (i, sum) in self.sums.iter_mut().enumerate() {
+            if hash >> i & 1 == 1 {
+                *sum = sum.saturating_add(weight);
+            } else {
+                *sum = sum.saturating_sub(weight);
+            }
+        }
+    }
+
+    /// Returns the fingerprint of the features added so far.
+    pub fn finish(&self) -> u64 {
+        (self.sums.iter().enumerate())
+            .filter(|(_, &sum)| sum > 0)
+            .fold(0, |fingerprint, (i, _)| fingerprint | 1 << i)
+    }
+}
+
+impl Default for FxSimHash {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Hash> Extend<T> for FxSimHash {
+    /// Adds every feature with weight 1.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for feature in iter {
+            self.add(&feature);
+        }
+    }
+}
+
+/// Returns the number of bits in which two fingerprints differ.
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{hamming_distance, FxSimHash};
+
+    #[test]
+    fn similar_features_are_close() {
+        let fingerprint = |features: core::ops::Range<u32>| {
+            let mut simhash = FxSimHash::with_seed(1);
+            simhash.extend(features);
+            simhash.finish()
+        };
+        let base = fingerprint(0..1000);
+        assert_eq!(base, fingerprint(0..1000));
+        assert!(hamming_distance(base, fingerprint(10..1010)) < 12);
+        assert!(hamming_distance(base, fingerprint(5000..6000)) > 16);
+
+        let mut weighted = FxSimHash::with_seed(1);
+        weighted.add_weighted(&7_u32, 3);
+        weighted.add(&8_u32);
+        let mut plain = FxSimHash::with_seed(1);
+        plain.add(&7_u32);
+        assert_eq!(weighted.finish(), plain.finish());
+    }
+}
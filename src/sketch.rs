@@ -0,0 +1,145 @@
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+
+use crate::FxSeededState;
+
+/// The false positive rate of Bloom filters collected from iterators.
+pub(crate) const DEFAULT_FALSE_POSITIVE_RATE: f64 = 0.01;
+
+/// The seeds of the two hash functions of sketches created without explicit
+/// seeds.
+pub(crate) const DEFAULT_SEEDS: (usize, usize) = (0x243f6a88, 0x85a308d3);
+
+/// An error returned when combining two sketches whose parameters differ.
+///
+/// Sketches can only be merged when they have the same size and the same hash
+/// functions, as otherwise the same item is recorded in different places.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SketchMismatch;
+
+impl fmt::Display for SketchMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the sketches have different parameters")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SketchMismatch {}
+
+/// Hashes `item` with the two seeds, for deriving any number of hashes by
+/// double hashing.
+///
+/// The second hash is odd, so that its multiples cycle through all residues of
+/// a power of two.
+pub(crate) fn hash_pair<T: Hash + ?Sized>((s1, s2): (usize, usize), item: &T) -> (u64, u64) {
+    let h1 = spread(FxSeededState::with_seed(s1).hash_one(item));
+    // Spread like `spread`, with another multiplier.
+    let h2 = FxSeededState::with_seed(s2)
+        .hash_one(item)
+        .wrapping_mul(0xc2b2ae3d27d4eb4f);
+    (h1, h2 | 1)
+}
+
+/// Returns the `i`-th of the hashes derived from `(h1, h2)`.
+pub(crate) fn nth_hash((h1, h2): (u64, u64), i: u32) -> u64 {
+    h1.wrapping_add(u64::from(i).wrapping_mul(h2))
+}
+
+/// Spreads the bits of an Fx hash towards the high bits of the result, by
+/// multiplying it with 2<sup>64</sup> divided by the golden ratio.
+///
+/// The Fx hash is only 32 bits wide on 32-bit targets, and the hash maps of
+/// this crate take their bucket index from its low bits. Taking the high bits
+/// of the spread hash instead, e.g. for picking a shard, uses every bit of
+/// the hash and keeps the choice independent of the bucket index. Only the
+/// high bits are well mixed; use [`mix`] where every bit counts.
+#[inline]
+pub(crate) fn spread(hash: u64) -> u64 {
+    hash.wrapping_mul(0x9e3779b97f4a7c15)
+}
+
+/// Mixes all bits of `h` into all bits of the result, like the finalizer of
+/// MurmurHash3.
+///
+/// The hashes of [`hash_pair`] are only good modulo a number; sketches that
+/// take some of their bits, rather than the remainder, mix them first.
+pub(crate) fn mix(mut h: u64) -> u64 {
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xff51afd7ed558ccd);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xc4ceb9fe1a85ec53);
+    h ^ (h >> 33)
+}
+
+/// Returns the number of bits and hashes of a Bloom filter holding `items` items
+/// with a false positive rate of `rate`.
+pub(crate) fn bloom_parameters(items: usize, rate: f64) -> (u64, u32) {
+    assert!(
+        rate > 0.0 && rate < 1.0,
+        "the false positive rate must be between 0 and 1"
+    );
+    let hashes = -log2(rate);
+    let bits = ceil(items.max(1) as f64 * hashes * core::f64::consts::LOG2_E);
+    // Round the number of hashes to the nearest integer.
+    let num_hashes = (hashes + 0.5) as u32;
+    (bits.max(64), num_hashes.max(1))
+}
+
+/// Returns the binary logarithm of a positive, finite `x`.
+///
+/// `core` has no floating point logarithms, so this computes the fraction bit
+/// by bit by squaring the mantissa, which is exact up to rounding.
+pub(crate) fn log2(x: f64) -> f64 {
+    debug_assert!(x > 0.0 && x.is_finite());
+    if x < f64::MIN_POSITIVE {
+        // Normalize subnormal numbers first.
+        return log2(x * (1_u64 << 60) as f64) - 60.0;
+    }
+    let bits = x.to_bits();
+    let exponent = ((bits >> 52) & 0x7ff) as i32 - 1023;
+    let mut mantissa = f64::from_bits(bits & ((1 << 52) - 1) | (1023 << 52));
+    let mut log = f64::from(exponent);
+    let mut bit = 1.0;
+    for _ in 0..52 {
+        mantissa *= mantissa;
+        bit /= 2.0;
+        if mantissa >= 2.0 {
+            mantissa /= 2.0;
+            log += bit;
+        }
+    }
+    log
+}
+
+/// Returns the smallest integer not less than a non-negative `x`, saturating
+/// at `u64::MAX`.
+pub(crate) fn ceil(x: f64) -> u64 {
+    let n = x as u64;
+    if (n as f64) < x {
+        n.saturating_add(1)
+    } else {
+        n
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ceil, log2};
+
+    #[test]
+    fn float_helpers() {
+        for (x, expected) in [
+            (1.0, 0.0),
+            (2.0, 1.0),
+            (0.125, -3.0),
+            (1e-3, -9.965784284662087),
+        ] {
+            assert!((log2(x) - expected).abs() < 1e-12, "log2({x})");
+        }
+        assert!((log2(f64::MIN_POSITIVE / 4.0) + 1024.0).abs() < 1e-9);
+        assert_eq!(ceil(0.0), 0);
+        assert_eq!(ceil(2.0), 2);
+        assert_eq!(ceil(2.1), 3);
+        assert_eq!(ceil(1e30), u64::MAX);
+    }
+}
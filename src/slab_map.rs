@@ -0,0 +1,338 @@
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::Hash;
+
+use crate::FxTable;
+
+/// The number of slots in the first chunk of an [`FxSlabMap`]. Every further
+/// chunk is twice as large as the one before.
+pub(crate) const FIRST_CHUNK_LEN: usize = 16;
+
+/// A handle to an entry of an [`FxSlabMap`].
+///
+/// A handle stays valid until its entry is removed. Every slot counts how
+/// often it was emptied, so the handle of a removed entry does not find the
+/// entry that reuses its slot, unless the slot was reused 2<sup>32</sup> times.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FxSlabKey {
+    slot: usize,
+    generation: u32,
+}
+
+/// A hash map using the Fx hashing algorithm whose values never move.
+///
+/// The entries live in a slab of chunks that are allocated with their final
+/// size and never grow, and an index maps every key to its slot. Inserting
+/// more entries allocates new chunks instead of moving the existing ones, so
+/// the address of a value stays the same until it is removed, and slots of
+/// removed entries are reused. Every entry also has an [`FxSlabKey`] handle
+/// that looks it up without hashing.
+///
+/// Inserting takes `&mut self`, so the borrow checker does not let a
+/// reference into the map live across an insertion. The stability is only
+/// observable through handles and raw pointers, e.g. for values that are
+/// registered by address elsewhere. For references that stay usable while
+/// values are added, use [`FxTypedInterner`](crate::FxTypedInterner), which
+/// inserts through a shared reference.
+///
+/// ```
+/// use rustc_hash::FxSlabMap;
+///
+/// let mut map = FxSlabMap::new();
+/// let (handle, _) = map.insert("x", 1);
+/// let address: *const i32 = map.get("x").unwrap();
+///
+/// for i in 0..1000 {
+///     map.insert(if i % 2 == 0 { "even" } else { "odd" }, i);
+/// }
+/// assert_eq!(map.get_by_key(handle), Some(&1));
+/// assert!(core::ptr::eq(address, map.get("x").unwrap()));
+/// ```
+pub struct FxSlabMap<K, V> {
+    index: FxTable<K, FxSlabKey>,
+    chunks: Vec<Vec<Slot<K, V>>>,
+    /// The number of slots in use or free, all in `chunks`.
+    slots: usize,
+    free: Vec<usize>,
+}
+
+#[derive(Clone)]
+struct Slot<K, V> {
+    generation: u32,
+    entry: Option<(K, V)>,
+}
+
+/// Returns the chunk and the offset within it of `slot`.
+pub(crate) fn locate(slot: usize) -> (usize, usize) {
+    let n = slot / FIRST_CHUNK_LEN + 1;
+    let chunk = (usize::BITS - 1 - n.leading_zeros()) as usize;
+    (chunk, slot - FIRST_CHUNK_LEN * ((1 << chunk) - 1))
+}
+
+impl<K, V> FxSlabMap<K, V> {
+    /// Creates an empty `FxSlabMap`.
+    pub fn new() -> FxSlabMap<K, V> {
+        FxSlabMap {
+            index: FxTable::default(),
+            chunks: Vec::new(),
+            slots: 0,
+            free: Vec::new(),
+        }
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Returns a reference to the value of the entry `key`.
+    pub fn get_by_key(&self, key: FxSlabKey) -> Option<&V> {
+        let (_, v) = self.slot(key)?.entry.as_ref()?;
+        Some(v)
+    }
+
+    /// Returns a mutable reference to the value of the entry `key`.
+    pub fn get_by_key_mut(&mut self, key: FxSlabKey) -> Option<&mut V> {
+        let (_, v) = self.slot_mut(key)?.entry.as_mut()?;
+        Some(v)
+    }
+
+    /// Returns an iterator over the handles, keys and values, in slot order.
+    pub fn iter(&self) -> impl Iterator<Item = (FxSlabKey, &K, &V)> {
+        self.chunks
+            .iter()
+            .flatten()
+            .enumerate()
+            .filter_map(|(slot, s)| {
+                let (k, v) = s.entry.as_ref()?;
+                let key = FxSlabKey {
+                    slot,
+                    generation: s.generation,
+                };
+                Some((key, k, v))
+            })
+    }
+
+    fn slot(&self, key: FxSlabKey) -> Option<&Slot<K, V>> {
+        let (chunk, offset) = locate(key.slot);
+        let slot = self.chunks.get(chunk)?.get(offset)?;
+        (slot.generation == key.generation).then_some(slot)
+    }
+
+    fn slot_mut(&mut self, key: FxSlabKey) -> Option<&mut Slot<K, V>> {
+        let (chunk, offset) = locate(key.slot);
+        let slot = self.chunks.get_mut(chunk)?.get_mut(offset)?;
+        (slot.generation == key.generation).then_some(slot)
+    }
+
+    /// Stores an entry in a free slot, returning its handle.
+    fn alloc(&mut self, k: K, v: V) -> FxSlabKey {
+        if let Some(slot) = self.free.pop() {
+            let (chunk, offset) = locate(slot);
+            let s = &mut self.chunks[chunk][offset];
+            s.entry = Some((k, v));
+            return FxSlabKey {
+                slot,
+                generation: s.generation,
+            };
+        }
+        let slot = self.slots;
+        let (chunk, _) = locate(slot);
+        if chunk == self.chunks.len() {
+            self.chunks
+                .push(Vec::with_capacity(FIRST_CHUNK_LEN << chunk));
+        }
+        // The chunk was allocated with its final length, so this never
+        // moves the values in it.
+        self.chunks[chunk].push(Slot {
+            generation: 0,
+            entry: Some((k, v)),
+        });
+        self.slots += 1;
+        FxSlabKey {
+            slot,
+            generation: 0,
+        }
+    }
+}
+
+impl<K: Eq + Hash, V> FxSlabMap<K, V> {
+    /// Returns the handle of `key`.
+    pub fn key<Q>(&self, key: &Q) -> Option<FxSlabKey>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.index.get(key).copied()
+    }
+
+    /// Returns a reference to the value of `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.get_by_key(self.key(key)?)
+    }
+
+    /// Returns a mutable reference to the value of `key`.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.get_by_key_mut(self.key(key)?)
+    }
+
+    /// Returns `true` if the map contains `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.index.contains_key(key)
+    }
+
+    /// Removes `key`, returning its value.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let handle = self.index.remove(key)?;
+        let (_, v) = self.release(handle);
+        Some(v)
+    }
+
+    /// Removes the entry `key`, returning its key and value.
+    pub fn remove_by_key(&mut self, key: FxSlabKey) -> Option<(K, V)> {
+        self.slot(key)?.entry.as_ref()?;
+        let (k, v) = self.release(key);
+        self.index.remove(&k);
+        Some((k, v))
+    }
+
+    /// Empties the slot of a live entry, invalidating its handle.
+    fn release(&mut self, key: FxSlabKey) -> (K, V) {
+        let slot = self.slot_mut(key).expect("the index points to a live slot");
+        slot.generation = slot.generation.wrapping_add(1);
+        let entry = slot.entry.take().expect("the index points to a live slot");
+        self.free.push(key.slot);
+        entry
+    }
+}
+
+impl<K: Clone + Eq + Hash, V> FxSlabMap<K, V> {
+    /// Inserts a key-value pair, returning the handle of the entry and the
+    /// previous value of the key.
+    ///
+    /// An existing value is replaced in place, so its address and handle stay
+    /// the same.
+    pub fn insert(&mut self, key: K, value: V) -> (FxSlabKey, Option<V>) {
+        if let Some(handle) = self.key(&key) {
+            let v = self
+                .get_by_key_mut(handle)
+                .expect("the index points to a live slot");
+            return (handle, Some(core::mem::replace(v, value)));
+        }
+        let handle = self.alloc(key.clone(), value);
+        self.index.insert(key, handle);
+        (handle, None)
+    }
+}
+
+impl<K: Clone, V: Clone> Clone for FxSlabMap<K, V> {
+    fn clone(&self) -> Self {
+        // Cloned chunks must keep their final capacity, otherwise filling
+        // the last one would move its values.
+        let chunks = self
+            .chunks
+            .iter()
+            .enumerate()
+            .map(|(i, chunk)| {
+                let mut clone = Vec::with_capacity(FIRST_CHUNK_LEN << i);
+                clone.extend(chunk.iter().cloned());
+                clone
+            })
+            .collect();
+        FxSlabMap {
+            index: self.index.clone(),
+            chunks,
+            slots: self.slots,
+            free: self.free.clone(),
+        }
+    }
+}
+
+impl<K, V> Default for FxSlabMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for FxSlabMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.iter().map(|(_, k, v)| (k, v)))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::locate;
+    use crate::FxSlabMap;
+
+    #[test]
+    fn slot_locations() {
+        assert_eq!(locate(0), (0, 0));
+        assert_eq!(locate(15), (0, 15));
+        assert_eq!(locate(16), (1, 0));
+        assert_eq!(locate(47), (1, 31));
+        assert_eq!(locate(48), (2, 0));
+    }
+
+    #[test]
+    fn addresses_and_handles_are_stable() {
+        let mut map = FxSlabMap::new();
+        let mut addresses = Vec::new();
+        for i in 0..1000_u32 {
+            let (handle, old) = map.insert(i, i);
+            assert_eq!(old, None);
+            addresses.push((handle, map.get_by_key(handle).unwrap() as *const u32));
+        }
+        for (i, &(handle, address)) in addresses.iter().enumerate() {
+            assert!(core::ptr::eq(map.get(&(i as u32)).unwrap(), address));
+            assert_eq!(map.key(&(i as u32)), Some(handle));
+        }
+
+        let (handle, _) = addresses[10];
+        assert_eq!(map.remove_by_key(handle), Some((10, 10)));
+        assert_eq!(map.get_by_key(handle), None);
+        assert_eq!(map.remove(&11), Some(11));
+
+        // The freed slots are reused, but old handles stay invalid.
+        let (new_handle, _) = map.insert(5000, 0);
+        assert_ne!(new_handle, handle);
+        assert_eq!(map.get_by_key(handle), None);
+        assert_eq!(map.insert(5000, 1), (new_handle, Some(0)));
+        assert_eq!(map.len(), 999);
+        assert_eq!(map.iter().count(), 999);
+
+        // Clones keep whole chunks, so their values don't move either.
+        let mut clone = map.clone();
+        let address: *const u32 = clone.get(&999).unwrap();
+        for i in 0..1000 {
+            clone.insert(10_000 + i, i);
+        }
+        assert!(core::ptr::eq(clone.get(&999).unwrap(), address));
+    }
+}
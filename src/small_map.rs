@@ -0,0 +1,380 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::Hash;
+
+use crate::FxTable;
+
+/// A hash map that stores up to `N` entries inline and spills to a hash table
+/// using the Fx hashing algorithm beyond that.
+///
+/// While inline, lookups are a linear scan comparing keys without hashing them,
+/// which beats a hash table for a handful of entries with cheap equality. Once
+/// the map spilled it stays a hash table, even if entries are removed again.
+///
+/// ```
+/// use rustc_hash::FxSmallMap;
+///
+/// let mut map = FxSmallMap::<u32, &str, 2>::new();
+/// map.insert(1, "a");
+/// map.insert(2, "b");
+/// assert!(!map.is_spilled());
+///
+/// map.insert(3, "c");
+/// assert!(map.is_spilled());
+/// assert_eq!(map.get(&2), Some(&"b"));
+/// ```
+#[derive(Clone)]
+pub struct FxSmallMap<K, V, const N: usize> {
+    inline: [Option<(K, V)>; N],
+    len: usize,
+    spilled: Option<FxTable<K, V>>,
+}
+
+impl<K, V, const N: usize> FxSmallMap<K, V, N> {
+    /// Creates an empty `FxSmallMap`.
+    pub fn new() -> FxSmallMap<K, V, N> {
+        FxSmallMap {
+            inline: core::array::from_fn(|_| None),
+            len: 0,
+            spilled: None,
+        }
+    }
+
+    /// Creates an empty `FxSmallMap` with room for at least `capacity` entries.
+    ///
+    /// The map starts out spilled if `capacity` is larger than `N`.
+    pub fn with_capacity(capacity: usize) -> FxSmallMap<K, V, N> {
+        let mut map = Self::new();
+        if capacity > N {
+            map.spilled = Some(FxTable::with_capacity_and_hasher(
+                capacity,
+                Default::default(),
+            ));
+        }
+        map
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.spilled.as_ref().map_or(self.len, |m| m.len())
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the entries are stored in a hash table instead of inline.
+    pub fn is_spilled(&self) -> bool {
+        self.spilled.is_some()
+    }
+
+    /// Removes all entries, keeping the hash table if the map spilled.
+    pub fn clear(&mut self) {
+        self.inline[..self.len].iter_mut().for_each(|e| *e = None);
+        self.len = 0;
+        if let Some(map) = &mut self.spilled {
+            map.clear();
+        }
+    }
+
+    /// Returns an iterator over the keys and values, in unspecified order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.inline[..self.len]
+            .iter()
+            .flatten()
+            .map(|(k, v)| (k, v))
+            .chain(self.spilled.iter().flatten())
+    }
+
+    /// Returns an iterator over the keys and mutable values, in unspecified order.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut V)> {
+        self.inline[..self.len]
+            .iter_mut()
+            .flatten()
+            .map(|(k, v)| (&*k, v))
+            .chain(self.spilled.iter_mut().flatten())
+    }
+
+    /// Returns an iterator over the keys, in unspecified order.
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    /// Returns an iterator over the values, in unspecified order.
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, v)| v)
+    }
+
+    fn position<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        self.inline[..self.len]
+            .iter()
+            .position(|e| e.as_ref().is_some_and(|(k, _)| k.borrow() == key))
+    }
+}
+
+impl<K: Eq + Hash, V, const N: usize> FxSmallMap<K, V, N> {
+    /// Returns a reference to the value of `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        match &self.spilled {
+            Some(map) => map.get(key),
+            None => self.inline[self.position(key)?].as_ref().map(|(_, v)| v),
+        }
+    }
+
+    /// Returns a mutable reference to the value of `key`.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        if self.spilled.is_none() {
+            let i = self.position(key)?;
+            return self.inline[i].as_mut().map(|(_, v)| v);
+        }
+        self.spilled.as_mut()?.get_mut(key)
+    }
+
+    /// Returns `true` if the map contains `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Inserts a key-value pair, returning the previous value of the key.
+    ///
+    /// Inserting a new key into a full inline map moves all entries into a hash table.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(map) = &mut self.spilled {
+            return map.insert(key, value);
+        }
+
+        if let Some(i) = self.position(&key) {
+            let (_, v) = self.inline[i].as_mut()?;
+            return Some(core::mem::replace(v, value));
+        }
+
+        if self.len < N {
+            self.inline[self.len] = Some((key, value));
+            self.len += 1;
+        } else {
+            let mut map = FxTable::with_capacity_and_hasher(N * 2 + 1, Default::default());
+            map.extend(self.inline.iter_mut().filter_map(Option::take));
+            map.insert(key, value);
+            self.len = 0;
+            self.spilled = Some(map);
+        }
+        None
+    }
+
+    /// Removes `key`, returning its value.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        if let Some(map) = &mut self.spilled {
+            return map.remove(key);
+        }
+
+        // Keep the inline entries contiguous by moving the last one into the hole.
+        let i = self.position(key)?;
+        self.len -= 1;
+        self.inline.swap(i, self.len);
+        self.inline[self.len].take().map(|(_, v)| v)
+    }
+}
+
+impl<K, V, const N: usize> Default for FxSmallMap<K, V, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, const N: usize> fmt::Debug for FxSmallMap<K, V, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Eq + Hash, V, const N: usize> Extend<(K, V)> for FxSmallMap<K, V, N> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<K: Eq + Hash, V, const N: usize> FromIterator<(K, V)> for FxSmallMap<K, V, N> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+/// A hash set that stores up to `N` elements inline and spills to a hash table
+/// using the Fx hashing algorithm beyond that.
+///
+/// See [`FxSmallMap`] for details.
+///
+/// ```
+/// use rustc_hash::FxSmallSet;
+///
+/// let mut set = FxSmallSet::<u32, 4>::new();
+/// assert!(set.insert(1));
+/// assert!(!set.insert(1));
+/// assert!(set.contains(&1));
+/// ```
+#[derive(Clone)]
+pub struct FxSmallSet<T, const N: usize> {
+    map: FxSmallMap<T, (), N>,
+}
+
+impl<T, const N: usize> FxSmallSet<T, N> {
+    /// Creates an empty `FxSmallSet`.
+    pub fn new() -> FxSmallSet<T, N> {
+        FxSmallSet {
+            map: FxSmallMap::new(),
+        }
+    }
+
+    /// Creates an empty `FxSmallSet` with room for at least `capacity` elements.
+    pub fn with_capacity(capacity: usize) -> FxSmallSet<T, N> {
+        FxSmallSet {
+            map: FxSmallMap::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the number of elements in the set.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the set contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns `true` if the elements are stored in a hash table instead of inline.
+    pub fn is_spilled(&self) -> bool {
+        self.map.is_spilled()
+    }
+
+    /// Removes all elements.
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    /// Returns an iterator over the elements, in unspecified order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.map.keys()
+    }
+}
+
+impl<T: Eq + Hash, const N: usize> FxSmallSet<T, N> {
+    /// Adds `value` to the set, returning `true` if it was not present.
+    pub fn insert(&mut self, value: T) -> bool {
+        self.map.insert(value, ()).is_none()
+    }
+
+    /// Returns `true` if the set contains `value`.
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.map.contains_key(value)
+    }
+
+    /// Removes `value`, returning `true` if it was present.
+    pub fn remove<Q>(&mut self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.map.remove(value).is_some()
+    }
+}
+
+impl<T, const N: usize> Default for FxSmallSet<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for FxSmallSet<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Eq + Hash, const N: usize> Extend<T> for FxSmallSet<T, N> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for t in iter {
+            self.insert(t);
+        }
+    }
+}
+
+impl<T: Eq + Hash, const N: usize> FromIterator<T> for FxSmallSet<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FxSmallMap, FxSmallSet};
+
+    #[test]
+    fn inline_and_spilled() {
+        let mut map = FxSmallMap::<u32, u32, 4>::new();
+        for i in 0..4 {
+            assert_eq!(map.insert(i, i), None);
+        }
+        assert!(!map.is_spilled());
+        assert_eq!(map.insert(2, 20), Some(2));
+        assert_eq!(map.remove(&0), Some(0));
+        assert_eq!(map.remove(&0), None);
+        assert_eq!(map.get(&3), Some(&3));
+        *map.get_mut(&3).unwrap() = 30;
+
+        map.extend([(4, 4), (5, 5)]);
+        assert!(map.is_spilled());
+        assert_eq!(map.len(), 5);
+        assert_eq!(map.get(&2), Some(&20));
+        assert_eq!(map.get(&3), Some(&30));
+        assert_eq!(map.iter().count(), 5);
+
+        map.clear();
+        assert!(map.is_empty());
+        assert!(map.is_spilled());
+        assert!(FxSmallMap::<u32, u32, 4>::with_capacity(5).is_spilled());
+    }
+
+    #[test]
+    fn zero_inline_capacity() {
+        let mut set = FxSmallSet::<u32, 0>::new();
+        assert!(set.insert(1));
+        assert!(set.is_spilled());
+
+        let set: FxSmallSet<u32, 8> = [1, 2, 2, 3].into_iter().collect();
+        assert_eq!(set.len(), 3);
+        assert!(set.contains(&2));
+        assert!(!set.is_spilled());
+    }
+}
@@ -0,0 +1,181 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::Hash;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use arc_swap::ArcSwap;
+
+use crate::FxHashMap;
+
+/// A read-mostly concurrent map that publishes immutable [`FxHashMap`] snapshots.
+///
+/// Readers load the current snapshot without locking. Writers clone the
+/// current map, modify the clone and publish it, so every write costs a full
+/// clone. Use [`update`](Self::update) or [`insert_many`](Self::insert_many) to
+/// pay that cost once for a batch of changes. Writers are serialized, so no
+/// write is lost, while readers never wait for them.
+///
+/// ```
+/// use rustc_hash::FxSnapshotMap;
+///
+/// let routes = FxSnapshotMap::new();
+/// routes.insert_many([("/", 1), ("/about", 2)]);
+///
+/// let snapshot = routes.load();
+/// routes.update(|map| {
+///     map.remove("/about");
+///     map.insert("/contact", 3);
+/// });
+///
+/// assert_eq!(snapshot.get("/about"), Some(&2));
+/// assert_eq!(routes.get("/about"), None);
+/// assert_eq!(routes.get("/contact"), Some(3));
+/// ```
+pub struct FxSnapshotMap<K, V> {
+    current: ArcSwap<FxHashMap<K, V>>,
+    writer: Mutex<()>,
+}
+
+impl<K, V> FxSnapshotMap<K, V> {
+    /// Creates an empty `FxSnapshotMap`.
+    pub fn new() -> FxSnapshotMap<K, V> {
+        Self::from_map(FxHashMap::default())
+    }
+
+    /// Creates an `FxSnapshotMap` with `map` as the first snapshot.
+    pub fn from_map(map: FxHashMap<K, V>) -> FxSnapshotMap<K, V> {
+        FxSnapshotMap {
+            current: ArcSwap::from_pointee(map),
+            writer: Mutex::new(()),
+        }
+    }
+
+    /// Returns the current snapshot.
+    ///
+    /// The snapshot is not affected by later writes.
+    pub fn load(&self) -> Arc<FxHashMap<K, V>> {
+        self.current.load_full()
+    }
+
+    /// Returns the number of entries in the current snapshot.
+    pub fn len(&self) -> usize {
+        self.current.load().len()
+    }
+
+    /// Returns `true` if the current snapshot contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.current.load().is_empty()
+    }
+
+    /// Publishes `map` as the new snapshot, returning the previous one.
+    pub fn store(&self, map: FxHashMap<K, V>) -> Arc<FxHashMap<K, V>> {
+        let _writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        self.current.swap(Arc::new(map))
+    }
+}
+
+impl<K: Eq + Hash, V> FxSnapshotMap<K, V> {
+    /// Returns a clone of the value of `key` in the current snapshot.
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+        V: Clone,
+    {
+        self.current.load().get(key).cloned()
+    }
+
+    /// Returns `true` if the current snapshot contains `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.current.load().contains_key(key)
+    }
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> FxSnapshotMap<K, V> {
+    /// Applies `f` to a copy of the current map and publishes the result as a
+    /// single new snapshot.
+    pub fn update<R>(&self, f: impl FnOnce(&mut FxHashMap<K, V>) -> R) -> R {
+        let _writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut map = FxHashMap::clone(&self.current.load());
+        let result = f(&mut map);
+        self.current.store(Arc::new(map));
+        result
+    }
+
+    /// Inserts a key-value pair, returning the previous value of the key.
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        self.update(|map| map.insert(key, value))
+    }
+
+    /// Inserts all key-value pairs of `iter` as a single new snapshot.
+    pub fn insert_many(&self, iter: impl IntoIterator<Item = (K, V)>) {
+        self.update(|map| map.extend(iter));
+    }
+
+    /// Removes `key`, returning its value.
+    pub fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        if !self.contains_key(key) {
+            return None;
+        }
+        self.update(|map| map.remove(key))
+    }
+}
+
+impl<K, V> Default for FxSnapshotMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> From<FxHashMap<K, V>> for FxSnapshotMap<K, V> {
+    fn from(map: FxHashMap<K, V>) -> Self {
+        Self::from_map(map)
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for FxSnapshotMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.current.load().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use crate::FxSnapshotMap;
+
+    #[test]
+    fn concurrent_writers() {
+        let map = FxSnapshotMap::new();
+        thread::scope(|s| {
+            for t in 0..4_u32 {
+                let map = &map;
+                s.spawn(move || {
+                    for i in 0..50 {
+                        map.insert(t * 50 + i, i);
+                    }
+                });
+            }
+        });
+        assert_eq!(map.len(), 200);
+
+        let before = map.load();
+        assert_eq!(map.remove(&0), Some(0));
+        assert_eq!(map.remove(&0), None);
+        assert_eq!(before.len(), 200);
+        assert_eq!(map.len(), 199);
+
+        let old = map.store(Default::default());
+        assert_eq!(old.len(), 199);
+        assert!(map.is_empty());
+    }
+}
@@ -0,0 +1,126 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+/// A wrapper formatting a hash map or set with `Debug` in key order.
+///
+/// The iteration order of a hash collection depends on the hasher, the
+/// platform and the insertion history, which makes its `Debug` output unfit
+/// for snapshot tests. `SortedDebug` formats the same entries sorted by key,
+/// both with `{:?}` and `{:#?}`.
+///
+/// ```
+/// use rustc_hash::{FxHashMap, SortedDebug};
+///
+/// let map: FxHashMap<u32, &str> = [(3, "c"), (1, "a"), (2, "b")].into_iter().collect();
+/// assert_eq!(format!("{:?}", SortedDebug(&map)), r#"{1: "a", 2: "b", 3: "c"}"#);
+/// ```
+pub struct SortedDebug<'a, M: ?Sized>(pub &'a M);
+
+/// Sorted snapshots of the entries of a hash map or set.
+///
+/// ```
+/// use rustc_hash::{FxHashSet, FxSortedExt};
+///
+/// let set: FxHashSet<u32> = [3, 1, 2].into_iter().collect();
+/// assert_eq!(set.to_sorted_vec(), [&1, &2, &3]);
+/// assert_eq!(format!("{:?}", set.sorted_debug()), "{1, 2, 3}");
+/// ```
+pub trait FxSortedExt {
+    /// A borrowed entry: `(&K, &V)` for maps and `&T` for sets.
+    type Entry<'a>
+    where
+        Self: 'a;
+
+    /// Returns the entries sorted by key.
+    fn to_sorted_vec(&self) -> Vec<Self::Entry<'_>>;
+
+    /// Returns a wrapper formatting the collection with `Debug` in key order.
+    fn sorted_debug(&self) -> SortedDebug<'_, Self> {
+        SortedDebug(self)
+    }
+
+    /// Returns a wrapper serializing the collection in key order.
+    #[cfg(feature = "serde")]
+    fn sorted_ser(&self) -> crate::SortedSer<'_, Self> {
+        crate::SortedSer(self)
+    }
+}
+
+macro_rules! impl_sorted {
+    ($map:ident, $set:ident) => {
+        impl<K: Ord, V, S> FxSortedExt for $map<K, V, S> {
+            type Entry<'a>
+                = (&'a K, &'a V)
+            where
+                Self: 'a;
+
+            fn to_sorted_vec(&self) -> Vec<(&K, &V)> {
+                let mut entries: Vec<_> = self.iter().collect();
+                entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+                entries
+            }
+        }
+
+        impl<T: Ord, S> FxSortedExt for $set<T, S> {
+            type Entry<'a>
+                = &'a T
+            where
+                Self: 'a;
+
+            fn to_sorted_vec(&self) -> Vec<&T> {
+                let mut entries: Vec<_> = self.iter().collect();
+                entries.sort_unstable();
+                entries
+            }
+        }
+
+        impl<K: Ord + fmt::Debug, V: fmt::Debug, S> fmt::Debug for SortedDebug<'_, $map<K, V, S>> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_map().entries(self.0.to_sorted_vec()).finish()
+            }
+        }
+
+        impl<T: Ord + fmt::Debug, S> fmt::Debug for SortedDebug<'_, $set<T, S>> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_set().entries(self.0.to_sorted_vec()).finish()
+            }
+        }
+    };
+}
+
+#[cfg(feature = "std")]
+mod std_impls {
+    use std::collections::{HashMap, HashSet};
+
+    use super::*;
+
+    impl_sorted!(HashMap, HashSet);
+}
+
+#[cfg(feature = "hashbrown")]
+mod hashbrown_impls {
+    use hashbrown::{HashMap, HashSet};
+
+    use super::*;
+
+    impl_sorted!(HashMap, HashSet);
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+
+    use crate::{FxSortedExt, FxTable, SortedDebug};
+
+    #[test]
+    fn stable_output() {
+        let a: FxTable<i32, i32> = (0..50).map(|i| (i, -i)).collect();
+        let b: FxTable<i32, i32> = (0..50).rev().map(|i| (i, -i)).collect();
+        assert_eq!(
+            format!("{:#?}", SortedDebug(&a)),
+            format!("{:#?}", b.sorted_debug())
+        );
+        let sorted = a.to_sorted_vec();
+        assert!(sorted.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+}
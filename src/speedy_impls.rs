@@ -0,0 +1,168 @@
+use core::hash::{BuildHasher, Hash};
+
+use speedy::{Context, Readable, Reader, Writable, Writer};
+
+use crate::presized::prealloc;
+use crate::{FxPresized, FxSeededState};
+
+// speedy's own impls write a hash map or set as its length followed by its
+// elements, and read it back without reserving any room up front. The impls
+// for `FxPresized` write the same format and reserve room for all elements
+// before reading them, so both can read what the other wrote.
+
+impl<C: Context> Writable<C> for FxSeededState {
+    fn write_to<W: ?Sized + Writer<C>>(&self, writer: &mut W) -> Result<(), C::Error> {
+        // The seed is stored as a `u64` so that it reads back on any platform.
+        writer.write_u64(self.seed() as u64)
+    }
+
+    fn bytes_needed(&self) -> Result<usize, C::Error> {
+        Ok(8)
+    }
+}
+
+impl<'a, C: Context> Readable<'a, C> for FxSeededState {
+    fn read_from<R: Reader<'a, C>>(reader: &mut R) -> Result<Self, C::Error> {
+        let seed = reader.read_u64()?;
+        let seed = usize::try_from(seed)
+            .map_err(|_| speedy::Error::custom("the seed does not fit usize"))?;
+        Ok(FxSeededState::with_seed(seed))
+    }
+
+    fn minimum_bytes_needed() -> usize {
+        8
+    }
+}
+
+macro_rules! impl_presized {
+    ($map:ident, $set:ident) => {
+        impl<C, K, V, S> Writable<C> for FxPresized<$map<K, V, S>>
+        where
+            C: Context,
+            K: Writable<C>,
+            V: Writable<C>,
+        {
+            fn write_to<W: ?Sized + Writer<C>>(&self, writer: &mut W) -> Result<(), C::Error> {
+                write_len(self.0.len(), writer)?;
+                self.0.iter().try_for_each(|(k, v)| {
+                    writer.write_value(k)?;
+                    writer.write_value(v)
+                })
+            }
+        }
+
+        impl<'a, C, K, V, S> Readable<'a, C> for FxPresized<$map<K, V, S>>
+        where
+            C: Context,
+            K: Readable<'a, C> + Eq + Hash,
+            V: Readable<'a, C>,
+            S: BuildHasher + Default,
+        {
+            fn read_from<R: Reader<'a, C>>(reader: &mut R) -> Result<Self, C::Error> {
+                let len = reader.read_u32()? as usize;
+                let capacity = prealloc::<(K, V)>(Some(len));
+                let mut map = $map::with_capacity_and_hasher(capacity, S::default());
+                for _ in 0..len {
+                    let k = reader.read_value()?;
+                    let v = reader.read_value()?;
+                    map.insert(k, v);
+                }
+                Ok(FxPresized(map))
+            }
+
+            fn minimum_bytes_needed() -> usize {
+                4
+            }
+        }
+
+        impl<C, T, S> Writable<C> for FxPresized<$set<T, S>>
+        where
+            C: Context,
+            T: Writable<C>,
+        {
+            fn write_to<W: ?Sized + Writer<C>>(&self, writer: &mut W) -> Result<(), C::Error> {
+                write_len(self.0.len(), writer)?;
+                self.0.iter().try_for_each(|t| writer.write_value(t))
+            }
+        }
+
+        impl<'a, C, T, S> Readable<'a, C> for FxPresized<$set<T, S>>
+        where
+            C: Context,
+            T: Readable<'a, C> + Eq + Hash,
+            S: BuildHasher + Default,
+        {
+            fn read_from<R: Reader<'a, C>>(reader: &mut R) -> Result<Self, C::Error> {
+                let len = reader.read_u32()? as usize;
+                let capacity = prealloc::<T>(Some(len));
+                let mut set = $set::with_capacity_and_hasher(capacity, S::default());
+                for _ in 0..len {
+                    set.insert(reader.read_value()?);
+                }
+                Ok(FxPresized(set))
+            }
+
+            fn minimum_bytes_needed() -> usize {
+                4
+            }
+        }
+    };
+}
+
+/// Writes the length of a collection the way speedy does.
+fn write_len<C: Context, W: ?Sized + Writer<C>>(
+    len: usize,
+    writer: &mut W,
+) -> Result<(), C::Error> {
+    let len =
+        u32::try_from(len).map_err(|_| speedy::Error::custom("the collection is too long"))?;
+    writer.write_u32(len)
+}
+
+#[cfg(feature = "std")]
+mod std_impls {
+    use std::collections::{HashMap, HashSet};
+
+    use super::*;
+
+    impl_presized!(HashMap, HashSet);
+}
+
+#[cfg(feature = "hashbrown")]
+mod hashbrown_impls {
+    use hashbrown::{HashMap, HashSet};
+
+    use super::*;
+
+    impl_presized!(HashMap, HashSet);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::string::{String, ToString};
+    use std::vec::Vec;
+
+    use speedy::{Readable, Writable};
+
+    use crate::{FxHashMap, FxHashSet, FxPresized, FxSeededState};
+
+    #[test]
+    fn round_trip() {
+        let map: FxHashMap<u32, String> = (0..1000).map(|i| (i, i.to_string())).collect();
+        let bytes = FxPresized(map.clone()).write_to_vec().unwrap();
+        assert_eq!(bytes, map.write_to_vec().unwrap());
+        let back = FxPresized::<FxHashMap<u32, String>>::read_from_buffer(&bytes).unwrap();
+        assert_eq!(back.0, map);
+        assert!(back.0.capacity() < 2 * map.len());
+
+        let set: FxHashSet<u64> = (0..1000).collect();
+        let bytes = set.write_to_vec().unwrap();
+        let back = FxPresized::<FxHashSet<u64>>::read_from_buffer(&bytes).unwrap();
+        assert_eq!(back.0, set);
+
+        let state = FxSeededState::with_seed(0xdead_beef);
+        let bytes: Vec<u8> = state.write_to_vec().unwrap();
+        let back = FxSeededState::read_from_buffer(&bytes).unwrap();
+        assert_eq!(back.seed(), 0xdead_beef);
+    }
+}
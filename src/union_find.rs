@@ -0,0 +1,225 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::Hash;
+
+use crate::FxTable;
+
+/// A union-find (disjoint set) structure over hashable elements, using the Fx
+/// hashing algorithm to map elements to their IDs.
+///
+/// Every element is interned on first use and gets a dense ID, starting at 0 in
+/// insertion order. The classes are kept as a forest with union by size and
+/// path compression, so [`find`](Self::find) and [`union`](Self::union) take
+/// nearly constant amortized time after hashing the elements.
+///
+/// ```
+/// use rustc_hash::FxUnionFind;
+///
+/// let mut uf = FxUnionFind::new();
+/// uf.union("a", "b");
+/// uf.union("c", "d");
+/// uf.union("b", "c");
+/// uf.insert("e");
+///
+/// assert!(uf.same_class("a", "d"));
+/// assert!(!uf.same_class("a", "e"));
+/// assert_eq!(uf.classes().len(), 2);
+/// ```
+#[derive(Clone)]
+pub struct FxUnionFind<T> {
+    ids: FxTable<T, usize>,
+    elements: Vec<T>,
+    /// The parent ID of every element, pointing to itself for roots.
+    parents: Vec<usize>,
+    /// The size of the class of every root.
+    sizes: Vec<usize>,
+}
+
+impl<T> FxUnionFind<T> {
+    /// Creates an empty `FxUnionFind`.
+    pub fn new() -> FxUnionFind<T> {
+        FxUnionFind {
+            ids: FxTable::default(),
+            elements: Vec::new(),
+            parents: Vec::new(),
+            sizes: Vec::new(),
+        }
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    /// Returns `true` if there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// Returns the element with ID `id`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not the ID of an element.
+    pub fn element(&self, id: usize) -> &T {
+        &self.elements[id]
+    }
+
+    /// Returns the ID of the representative of the class of `id`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not the ID of an element.
+    pub fn find_id(&mut self, id: usize) -> usize {
+        let root = self.root(id);
+        let mut id = id;
+        while self.parents[id] != root {
+            id = core::mem::replace(&mut self.parents[id], root);
+        }
+        root
+    }
+
+    /// Merges the classes of `a` and `b`, returning `false` if they were
+    /// already the same.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `a` or `b` is not the ID of an element.
+    pub fn union_ids(&mut self, a: usize, b: usize) -> bool {
+        let (mut a, mut b) = (self.find_id(a), self.find_id(b));
+        if a == b {
+            return false;
+        }
+        if self.sizes[a] < self.sizes[b] {
+            core::mem::swap(&mut a, &mut b);
+        }
+        self.parents[b] = a;
+        self.sizes[a] += self.sizes[b];
+        true
+    }
+
+    /// Returns the classes as lists of their elements, each in insertion
+    /// order, ordered by their first element.
+    pub fn classes(&self) -> Vec<Vec<&T>> {
+        let mut class_of_root = vec![usize::MAX; self.len()];
+        let mut classes: Vec<Vec<&T>> = Vec::new();
+        for (id, t) in self.elements.iter().enumerate() {
+            let root = self.root(id);
+            if class_of_root[root] == usize::MAX {
+                class_of_root[root] = classes.len();
+                classes.push(Vec::with_capacity(self.sizes[root]));
+            }
+            classes[class_of_root[root]].push(t);
+        }
+        classes
+    }
+
+    /// Returns the root of `id` without compressing paths.
+    fn root(&self, mut id: usize) -> usize {
+        while self.parents[id] != id {
+            id = self.parents[id];
+        }
+        id
+    }
+}
+
+impl<T: Eq + Hash> FxUnionFind<T> {
+    /// Returns the ID of `t`, if it is an element.
+    pub fn id<Q>(&self, t: &Q) -> Option<usize>
+    where
+        T: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.ids.get(t).copied()
+    }
+
+    /// Returns the ID of the representative of the class of `t`, if it is an
+    /// element.
+    pub fn find<Q>(&mut self, t: &Q) -> Option<usize>
+    where
+        T: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let id = self.id(t)?;
+        Some(self.find_id(id))
+    }
+
+    /// Returns `true` if `a` and `b` are elements of the same class.
+    pub fn same_class<Q>(&mut self, a: &Q, b: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        match (self.find(a), self.find(b)) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl<T: Clone + Eq + Hash> FxUnionFind<T> {
+    /// Returns the ID of `t`, adding it in a class of its own if it is not an
+    /// element yet.
+    pub fn insert(&mut self, t: T) -> usize {
+        if let Some(id) = self.id(&t) {
+            return id;
+        }
+        let id = self.elements.len();
+        self.ids.insert(t.clone(), id);
+        self.elements.push(t);
+        self.parents.push(id);
+        self.sizes.push(1);
+        id
+    }
+
+    /// Merges the classes of `a` and `b`, adding them first if they are not
+    /// elements yet. Returns `false` if they were already in the same class.
+    pub fn union(&mut self, a: T, b: T) -> bool {
+        let (a, b) = (self.insert(a), self.insert(b));
+        self.union_ids(a, b)
+    }
+}
+
+impl<T> Default for FxUnionFind<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for FxUnionFind<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.classes()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::FxUnionFind;
+
+    #[test]
+    fn classes_by_remainder() {
+        let mut uf = FxUnionFind::new();
+        for i in 0..100_u32 {
+            assert_eq!(uf.insert(i), i as usize);
+        }
+        for i in 0..100 {
+            uf.union(i, (i + 5) % 100);
+        }
+        assert!(!uf.union(3, 8));
+        assert_eq!(uf.len(), 100);
+        assert_eq!(uf.find(&13), uf.find(&98));
+        assert_eq!(uf.find(&100), None);
+
+        let classes = uf.classes();
+        assert_eq!(classes.len(), 5);
+        for (r, class) in classes.iter().enumerate() {
+            let expected: Vec<u32> = (0..100).filter(|i| i % 5 == r as u32).collect();
+            assert!(class.iter().copied().eq(&expected));
+        }
+        assert_eq!(*uf.element(uf.id(&42).unwrap()), 42);
+    }
+}
@@ -0,0 +1,280 @@
+use core::hash::Hash;
+
+use crate::sketch;
+use crate::{combine_all, stable_hash};
+
+/// Hashes a collection independently of the order of its items, using the
+/// Fx hashing algorithm.
+///
+/// This is meant for sets, multisets and maps, whose iteration order depends
+/// on their insertion history. Every item is hashed with Fx and mixed, then
+/// the hashes are accumulated with wrapping addition and multiplication,
+/// which both commute. Unlike XOR, neither cancels repeated items: a
+/// multiset with an item twice hashes differently from one without it.
+///
+/// ```
+/// use rustc_hash::FxUnorderedHasher;
+///
+/// let mut a = FxUnorderedHasher::new();
+/// a.extend(["x", "y", "z"]);
+/// let mut b = FxUnorderedHasher::new();
+/// b.extend(["z", "x", "y"]);
+/// assert_eq!(a.finish(), b.finish());
+///
+/// b.add("x");
+/// assert_ne!(a.finish(), b.finish());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FxUnorderedHasher {
+    sum: u64,
+    product: u64,
+    len: u64,
+}
+
+impl FxUnorderedHasher {
+    /// Creates a hasher with no items.
+    pub fn new() -> FxUnorderedHasher {
+        FxUnorderedHasher {
+            sum: 0,
+            product: 1,
+            len: 0,
+        }
+    }
+
+    /// Returns the number of items added.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if no items were added.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Adds an item.
+    pub fn add<T: Hash + ?Sized>(&mut self, item: &T) {
+        self.add_hash(stable_hash(item));
+    }
+
+    /// Adds an item by its hash, e.g. to combine the hashes of the keys and
+    /// values of a map entry first.
+    pub fn add_hash(&mut self, hash: u64) {
+        let mixed = sketch::mix(hash);
+        self.sum = self.sum.wrapping_add(mixed);
+        // Odd factors keep the product from ever becoming 0.
+        self.product = self.product.wrapping_mul(sketch::mix(!hash) | 1);
+        self.len = self.len.wrapping_add(1);
+    }
+
+    /// Returns the hash of the items added so far.
+    pub fn finish(&self) -> u64 {
+        combine_all([self.sum, self.product, self.len])
+    }
+}
+
+impl Default for FxUnorderedHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Hash> Extend<T> for FxUnorderedHasher {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.add(&item);
+        }
+    }
+}
+
+/// Maintains a fingerprint of a changing multiset of keys, using the Fx
+/// hashing algorithm.
+///
+/// Adding or removing a key takes constant time, as both accumulations of
+/// [`FxUnorderedHasher`] can be undone: the sum by subtraction and the
+/// product, of odd factors only, by multiplication with the inverse. The
+/// fingerprint is always equal to [`hash_unordered`] of the keys currently
+/// in the multiset.
+///
+/// Fingerprints are 64-bit hashes, so different multisets collide with a
+/// probability of about `2^-64` per pair, but the Fx hash is not keyed: an
+/// adversary choosing the keys can construct collisions. Removing a key that
+/// was never added is not detected and leaves a fingerprint that matches no
+/// multiset until the key is added back.
+///
+/// ```
+/// use rustc_hash::{hash_unordered, FxIncrementalFingerprint};
+///
+/// let mut fingerprint = FxIncrementalFingerprint::new();
+/// fingerprint.extend(["a", "b", "c"]);
+/// fingerprint.remove("b");
+/// fingerprint.add("d");
+/// assert_eq!(fingerprint.finish(), hash_unordered(["c", "a", "d"]));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FxIncrementalFingerprint {
+    hasher: FxUnorderedHasher,
+}
+
+impl FxIncrementalFingerprint {
+    /// Creates the fingerprint of the empty multiset.
+    pub fn new() -> FxIncrementalFingerprint {
+        FxIncrementalFingerprint {
+            hasher: FxUnorderedHasher::new(),
+        }
+    }
+
+    /// Returns the number of keys, counting repeated keys repeatedly.
+    ///
+    /// This wraps around below 0 if more keys were removed than added.
+    pub fn len(&self) -> u64 {
+        self.hasher.len
+    }
+
+    /// Returns `true` if the multiset is empty.
+    pub fn is_empty(&self) -> bool {
+        self.hasher.len == 0
+    }
+
+    /// Adds a key.
+    pub fn add<T: Hash + ?Sized>(&mut self, key: &T) {
+        self.hasher.add(key);
+    }
+
+    /// Adds a key by its hash.
+    pub fn add_hash(&mut self, hash: u64) {
+        self.hasher.add_hash(hash);
+    }
+
+    /// Removes a key, which must have been added before.
+    pub fn remove<T: Hash + ?Sized>(&mut self, key: &T) {
+        self.remove_hash(stable_hash(key));
+    }
+
+    /// Removes a key by its hash, which must have been added before.
+    pub fn remove_hash(&mut self, hash: u64) {
+        let hasher = &mut self.hasher;
+        hasher.sum = hasher.sum.wrapping_sub(sketch::mix(hash));
+        hasher.product = hasher.product.wrapping_mul(inverse(sketch::mix(!hash) | 1));
+        hasher.len = hasher.len.wrapping_sub(1);
+    }
+
+    /// Returns the fingerprint of the current keys.
+    pub fn finish(&self) -> u64 {
+        self.hasher.finish()
+    }
+}
+
+impl<T: Hash> Extend<T> for FxIncrementalFingerprint {
+    /// Adds every key.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.hasher.extend(iter);
+    }
+}
+
+/// Returns the multiplicative inverse of an odd number modulo `2^64`.
+fn inverse(odd: u64) -> u64 {
+    // Every Newton step doubles the number of correct low bits, starting
+    // from 3 as every odd number is its own inverse modulo 8.
+    let mut x = odd;
+    for _ in 0..5 {
+        x = x.wrapping_mul(2_u64.wrapping_sub(odd.wrapping_mul(x)));
+    }
+    x
+}
+
+/// Hashes the items independently of their order with an
+/// [`FxUnorderedHasher`].
+///
+/// ```
+/// use rustc_hash::{hash_unordered, FxHashSet};
+///
+/// let a: FxHashSet<u32> = (0..100).collect();
+/// let b: FxHashSet<u32> = (0..100).rev().collect();
+/// assert_eq!(hash_unordered(&a), hash_unordered(&b));
+/// ```
+pub fn hash_unordered<I>(items: I) -> u64
+where
+    I: IntoIterator,
+    I::Item: Hash,
+{
+    let mut hasher = FxUnorderedHasher::new();
+    hasher.extend(items);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use core::hash::{Hash, Hasher};
+
+    use crate::{hash_unordered, FxHasher, FxIncrementalFingerprint, FxTable, FxUnorderedHasher};
+
+    #[test]
+    fn ignores_order_but_not_repetition() {
+        assert_eq!(hash_unordered([1, 2, 3]), hash_unordered([3, 1, 2]));
+        assert_ne!(hash_unordered([1, 2]), hash_unordered([0, 3]));
+        assert_ne!(hash_unordered([1, 1]), hash_unordered([0; 0]));
+        assert_ne!(hash_unordered([1, 1, 2]), hash_unordered([1, 2, 2]));
+        assert_ne!(hash_unordered([0; 0]), hash_unordered([0]));
+
+        let mut a = FxUnorderedHasher::new();
+        a.add_hash(5);
+        a.add(&7_u32);
+        let mut b = FxUnorderedHasher::default();
+        b.add(&7_u32);
+        b.add_hash(5);
+        assert_eq!(a.len(), 2);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn incremental_fingerprint_matches_recomputation() {
+        let mut fingerprint = FxIncrementalFingerprint::new();
+        fingerprint.extend(0..1000_u32);
+        for key in (0..1000_u32).step_by(3) {
+            fingerprint.remove(&key);
+        }
+        fingerprint.add(&5_u32);
+        let keys = (0..1000_u32).filter(|k| k % 3 != 0).chain([5]);
+        assert_eq!(fingerprint.finish(), hash_unordered(keys));
+        assert_eq!(fingerprint.len(), 667);
+
+        fingerprint.remove(&2000_u32);
+        fingerprint.add(&2000_u32);
+        assert_eq!(fingerprint.len(), 667);
+
+        for key in (0..1000_u32).filter(|k| k % 3 != 0).chain([5]) {
+            fingerprint.remove(&key);
+        }
+        assert_eq!(fingerprint, FxIncrementalFingerprint::new());
+        assert!(fingerprint.is_empty());
+    }
+
+    #[test]
+    fn fingerprint_uses_unseeded_hashes() {
+        let unseeded = |key: u32| {
+            let mut hasher = FxHasher::default();
+            key.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        // The maps are seeded per instance with `shuffle-debug`, so they may
+        // iterate their keys in different orders.
+        let a: FxTable<u32, ()> = (0..100).map(|k| (k, ())).collect();
+        let b: FxTable<u32, ()> = (0..100).rev().map(|k| (k, ())).collect();
+
+        let mut fingerprint = FxIncrementalFingerprint::new();
+        fingerprint.extend(a.keys());
+        let mut expected = FxIncrementalFingerprint::new();
+        for &key in b.keys() {
+            expected.add_hash(unseeded(key));
+        }
+        assert_eq!(fingerprint, expected);
+
+        for key in 0..50 {
+            fingerprint.remove(&key);
+            expected.remove_hash(unseeded(key));
+        }
+        assert_eq!(fingerprint, expected);
+        assert_eq!(fingerprint.finish(), hash_unordered(50..100_u32));
+    }
+}
@@ -0,0 +1,51 @@
+use crate::FxBuildHasher;
+
+/// Type alias for a [`weak_table::WeakKeyHashMap`] that uses the Fx hashing algorithm.
+pub type FxWeakKeyHashMap<K, V> = weak_table::WeakKeyHashMap<K, V, FxBuildHasher>;
+
+/// Type alias for a [`weak_table::WeakValueHashMap`] that uses the Fx hashing algorithm.
+///
+/// ```
+/// use std::sync::{Arc, Weak};
+/// use rustc_hash::FxWeakValueHashMap;
+///
+/// let mut map: FxWeakValueHashMap<u32, Weak<str>> = FxWeakValueHashMap::default();
+/// let value: Arc<str> = Arc::from("uwu");
+/// map.insert(1, value.clone());
+/// assert_eq!(map.get(&1).as_deref(), Some("uwu"));
+///
+/// drop(value);
+/// assert_eq!(map.get(&1), None);
+/// ```
+pub type FxWeakValueHashMap<K, V> = weak_table::WeakValueHashMap<K, V, FxBuildHasher>;
+
+/// Type alias for a [`weak_table::WeakWeakHashMap`] that uses the Fx hashing algorithm.
+pub type FxWeakWeakHashMap<K, V> = weak_table::WeakWeakHashMap<K, V, FxBuildHasher>;
+
+/// Type alias for a [`weak_table::WeakHashSet`] that uses the Fx hashing algorithm.
+pub type FxWeakHashSet<T> = weak_table::WeakHashSet<T, FxBuildHasher>;
+
+#[cfg(test)]
+mod tests {
+    use std::rc::{Rc, Weak};
+
+    use crate::{FxBuildHasher, FxWeakHashSet, FxWeakKeyHashMap};
+
+    #[test]
+    fn entries_expire() {
+        let mut map: FxWeakKeyHashMap<Weak<u32>, u32> = FxWeakKeyHashMap::default();
+        let key = Rc::new(1);
+        map.insert(key.clone(), 2);
+        assert_eq!(map.get(&1), Some(&2));
+        drop(key);
+        assert_eq!(map.get(&1), None);
+
+        let mut set: FxWeakHashSet<Weak<u32>> =
+            FxWeakHashSet::with_capacity_and_hasher(8, FxBuildHasher);
+        let value = Rc::new(3);
+        set.insert(value.clone());
+        assert!(set.contains(&3));
+        drop(value);
+        assert!(!set.contains(&3));
+    }
+}